@@ -0,0 +1,141 @@
+//! Measures the throughput of the data store, with concurrent clients on
+//! separate threads, as the keyspace is split into more shards. The
+//! throughput of a server is measured too, as its writes also take the write
+//! locks of the replication stream.
+//!
+//! Run with `cargo bench --bench sharding`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use redust::cli::command_frame;
+use redust::server::{self, Listeners};
+use redust::{Config, Connection, ConnectionBase, DataType, SharedStore, SharedStoreBase};
+use std::sync::Arc;
+use std::thread;
+use tokio::net::{TcpListener, TcpStream};
+
+const THREADS: usize = 8;
+const OPERATIONS_PER_THREAD: usize = 10_000;
+const SERVER_OPERATIONS_PER_CLIENT: usize = 1_000;
+
+/// Run `THREADS` threads, each alternating SET and GET on its own keys
+fn run_clients(store: &Arc<SharedStore>) {
+    let handles: Vec<_> = (0..THREADS)
+        .map(|thread_id| {
+            let store = store.clone();
+
+            thread::spawn(move || {
+                for i in 0..OPERATIONS_PER_THREAD {
+                    let key = format!("Key:{}:{}", thread_id, i % 1000);
+
+                    if i % 2 == 0 {
+                        let value = DataType::String(i.to_string());
+                        let _ = store.set(key, value, None, false, false);
+                    } else {
+                        let _ = store.get(key);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+/// Run `THREADS` clients of the server at `port`, each alternating SET and
+/// GET on its own keys, through the connection handlers
+async fn run_server_clients(port: u16) {
+    let clients: Vec<_> = (0..THREADS)
+        .map(|client_id| {
+            tokio::spawn(async move {
+                let socket = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+                let mut cnxn = Connection::new(socket);
+
+                for i in 0..SERVER_OPERATIONS_PER_CLIENT {
+                    let key = format!("Key:{}:{}", client_id, i % 1000);
+
+                    let args = if i % 2 == 0 {
+                        vec!["SET".to_string(), key, i.to_string()]
+                    } else {
+                        vec!["GET".to_string(), key]
+                    };
+                    cnxn.write_frame(&command_frame(&args)).await.unwrap();
+                    cnxn.read_frame().await.unwrap();
+                }
+            })
+        })
+        .collect();
+
+    for client in clients {
+        client.await.unwrap();
+    }
+}
+
+/// Start a server with `num_shards` shards, and return its port
+async fn start_server(num_shards: usize) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let listeners = Listeners {
+        tcp: vec![listener],
+        ..Listeners::default()
+    };
+    let config = Config {
+        shards: num_shards,
+        ..Config::default()
+    };
+    tokio::spawn(async move {
+        server::run_with_listeners(listeners, config)
+            .await
+            .map_err(|err| err.to_string())
+    });
+
+    port
+}
+
+fn bench_sharding(c: &mut Criterion) {
+    // The expiry tasks of the shards are spawned on the runtime
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let _guard = runtime.enter();
+
+    let mut group = c.benchmark_group("set_get");
+    group.throughput(Throughput::Elements(
+        (THREADS * OPERATIONS_PER_THREAD) as u64,
+    ));
+
+    for num_shards in [1, 2, 4, 8, 16, 32] {
+        let store = Arc::new(SharedStore::with_shards(num_shards));
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(num_shards),
+            &store,
+            |b, store| b.iter(|| run_clients(store)),
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_server_sharding(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut group = c.benchmark_group("server_set_get");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(
+        (THREADS * SERVER_OPERATIONS_PER_CLIENT) as u64,
+    ));
+
+    for num_shards in [1, 4, 16] {
+        let port = runtime.block_on(start_server(num_shards));
+
+        group.bench_with_input(BenchmarkId::from_parameter(num_shards), &port, |b, port| {
+            b.iter(|| runtime.block_on(run_server_clients(*port)))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_sharding, bench_server_sharding);
+criterion_main!(benches);
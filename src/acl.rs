@@ -0,0 +1,581 @@
+use crate::glob::glob_match;
+use crate::ACLLOG_MAX_LEN;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// The user which new connections are authenticated as, if it needs no password
+pub const DEFAULT_USER: &str = "default";
+
+/// The ACL categories of each command, as in Redis
+pub const COMMAND_CATEGORIES: &[(&str, &[&str])] = &[
+    ("ping", &["fast", "connection"]),
+    ("echo", &["fast", "connection"]),
+    ("auth", &["fast", "connection"]),
+    ("asking", &["fast", "connection"]),
+    ("set", &["write", "string", "slow"]),
+    ("get", &["read", "string", "fast"]),
+    ("incr", &["write", "string", "fast"]),
+    ("decr", &["write", "string", "fast"]),
+    ("exists", &["keyspace", "read", "fast"]),
+    ("del", &["keyspace", "write", "slow"]),
+    ("object", &["keyspace", "read", "slow"]),
+    ("lpush", &["write", "list", "fast"]),
+    ("rpush", &["write", "list", "fast"]),
+    ("lrange", &["read", "list", "slow"]),
+    ("setbit", &["write", "bitmap", "slow"]),
+    ("getbit", &["read", "bitmap", "fast"]),
+    ("bitcount", &["read", "bitmap", "slow"]),
+    ("bitpos", &["read", "bitmap", "slow"]),
+    ("bitop", &["write", "bitmap", "slow"]),
+    ("bitfield", &["write", "bitmap", "slow"]),
+    ("bitfield_ro", &["read", "bitmap", "fast"]),
+    ("geoadd", &["write", "geo", "slow"]),
+    ("geopos", &["read", "geo", "slow"]),
+    ("geodist", &["read", "geo", "slow"]),
+    ("geohash", &["read", "geo", "slow"]),
+    ("geosearch", &["read", "geo", "slow"]),
+    ("geosearchstore", &["write", "geo", "slow"]),
+    ("memory", &["read", "slow"]),
+    ("info", &["slow", "dangerous"]),
+    ("role", &["admin", "fast", "dangerous"]),
+    ("replicaof", &["admin", "slow", "dangerous"]),
+    ("replconf", &["admin", "slow", "dangerous"]),
+    ("psync", &["admin", "slow", "dangerous"]),
+    ("client", &["admin", "slow", "dangerous", "connection"]),
+    ("cluster", &["admin", "slow"]),
+    ("acl", &["admin", "slow", "dangerous"]),
+    ("config", &["admin", "slow", "dangerous"]),
+    ("latency", &["admin", "slow", "dangerous"]),
+    ("slowlog", &["admin", "slow", "dangerous"]),
+    ("monitor", &["admin", "slow", "dangerous"]),
+    ("shutdown", &["admin", "slow", "dangerous"]),
+];
+
+/// The names of the ACL categories, in alphabetical order
+pub fn categories() -> Vec<&'static str> {
+    let categories: BTreeSet<&'static str> = COMMAND_CATEGORIES
+        .iter()
+        .flat_map(|(_, categories)| categories.iter().copied())
+        .collect();
+
+    categories.into_iter().collect()
+}
+
+/// The names of the commands of a `category`, or `None` if it doesn't exist
+pub fn commands_in_category(category: &str) -> Option<Vec<&'static str>> {
+    if category == "all" {
+        return Some(COMMAND_CATEGORIES.iter().map(|(name, _)| *name).collect());
+    }
+
+    let commands: Vec<&'static str> = COMMAND_CATEGORIES
+        .iter()
+        .filter(|(_, categories)| categories.contains(&category))
+        .map(|(name, _)| *name)
+        .collect();
+
+    if commands.is_empty() {
+        None
+    } else {
+        Some(commands)
+    }
+}
+
+/// Hash a password as it is stored by the ACL, i.e. SHA-256 in lowercase hexadecimal
+pub fn hash_password(password: &str) -> String {
+    Sha256::digest(password.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// A user of the ACL, with its passwords and permissions
+#[derive(Debug, Clone, PartialEq)]
+pub struct User {
+    pub name: String,
+    pub enabled: bool,
+
+    /// Any password authenticates the user
+    pub nopass: bool,
+
+    // The SHA-256 hashes of the passwords
+    passwords: BTreeSet<String>,
+
+    // The commands the user may run, and the rules which allowed them
+    commands: BTreeSet<&'static str>,
+    command_rules: Vec<String>,
+
+    key_patterns: Vec<String>,
+    channel_patterns: Vec<String>,
+}
+
+impl User {
+    /// Create a new user, which is disabled and has no permissions
+    pub fn new(name: &str) -> User {
+        User {
+            name: name.to_string(),
+            enabled: false,
+            nopass: false,
+            passwords: BTreeSet::new(),
+            commands: BTreeSet::new(),
+            command_rules: vec!["-@all".to_string()],
+            key_patterns: Vec::new(),
+            channel_patterns: Vec::new(),
+        }
+    }
+
+    /// Apply a rule of ACL SETUSER to the user.
+    ///
+    /// Returns the reason why the rule is invalid, if it is.
+    pub fn apply_rule(&mut self, rule: &str) -> Result<(), String> {
+        let lowercase = rule.to_lowercase();
+
+        match lowercase.as_str() {
+            "on" => self.enabled = true,
+            "off" => self.enabled = false,
+            "nopass" => {
+                self.passwords.clear();
+                self.nopass = true;
+            }
+            "resetpass" => {
+                self.passwords.clear();
+                self.nopass = false;
+            }
+            "allkeys" => self.key_patterns = vec!["*".to_string()],
+            "resetkeys" => self.key_patterns.clear(),
+            "allchannels" => self.channel_patterns = vec!["*".to_string()],
+            "resetchannels" => self.channel_patterns.clear(),
+            "allcommands" => self.apply_rule("+@all")?,
+            "nocommands" => self.apply_rule("-@all")?,
+            "reset" => *self = User::new(&self.name),
+            _ => {
+                let (prefix, arg) = rule.split_at(rule.chars().next().map_or(0, char::len_utf8));
+
+                match prefix {
+                    ">" => {
+                        self.passwords.insert(hash_password(arg));
+                        self.nopass = false;
+                    }
+                    "<" => {
+                        if !self.passwords.remove(&hash_password(arg)) {
+                            return Err(User::missing_password());
+                        }
+                    }
+                    "#" => {
+                        User::validate_hash(arg)?;
+                        self.passwords.insert(arg.to_string());
+                        self.nopass = false;
+                    }
+                    "!" => {
+                        User::validate_hash(arg)?;
+                        if !self.passwords.remove(arg) {
+                            return Err(User::missing_password());
+                        }
+                    }
+                    "~" => {
+                        if !self.key_patterns.iter().any(|pattern| pattern == arg) {
+                            self.key_patterns.push(arg.to_string());
+                        }
+                    }
+                    "&" => {
+                        if !self.channel_patterns.iter().any(|pattern| pattern == arg) {
+                            self.channel_patterns.push(arg.to_string());
+                        }
+                    }
+                    "+" | "-" => self.apply_command_rule(prefix == "+", &arg.to_lowercase())?,
+                    _ => return Err("Syntax error".to_string()),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Allow or disallow a command, or all the commands of a `@category`
+    fn apply_command_rule(&mut self, allow: bool, arg: &str) -> Result<(), String> {
+        let commands = match arg.strip_prefix('@') {
+            Some(category) => commands_in_category(category),
+            None => COMMAND_CATEGORIES
+                .iter()
+                .find(|(name, _)| *name == arg)
+                .map(|(name, _)| vec![*name]),
+        };
+
+        let commands =
+            commands.ok_or_else(|| "Unknown command or category name in ACL".to_string())?;
+
+        for command in commands {
+            if allow {
+                self.commands.insert(command);
+            } else {
+                self.commands.remove(command);
+            }
+        }
+
+        // Allowing or disallowing everything overrides the previous rules
+        let rule = format!("{}{}", if allow { "+" } else { "-" }, arg);
+        if arg == "@all" {
+            self.command_rules.clear();
+        }
+
+        self.command_rules.push(rule);
+
+        Ok(())
+    }
+
+    fn validate_hash(hash: &str) -> Result<(), String> {
+        if hash.len() == 64 && hash.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')) {
+            Ok(())
+        } else {
+            Err("The password hash must be exactly 64 characters and contain only lowercase hexadecimal characters".to_string())
+        }
+    }
+
+    fn missing_password() -> String {
+        "The password you are trying to remove from the user does not exist".to_string()
+    }
+
+    /// Whether the `password` authenticates the user
+    pub fn check_password(&self, password: &str) -> bool {
+        self.enabled && (self.nopass || self.passwords.contains(&hash_password(password)))
+    }
+
+    /// Whether the user may run the `command`
+    pub fn can_run(&self, command: &str) -> bool {
+        self.commands.contains(command)
+    }
+
+    /// Whether the user may access the `key`
+    pub fn can_access_key(&self, key: &str) -> bool {
+        self.key_patterns
+            .iter()
+            .any(|pattern| glob_match(pattern, key))
+    }
+
+    /// Whether the user may access the Pub/Sub `channel`
+    pub fn can_access_channel(&self, channel: &str) -> bool {
+        self.channel_patterns
+            .iter()
+            .any(|pattern| glob_match(pattern, channel))
+    }
+
+    pub fn flags(&self) -> Vec<&'static str> {
+        let mut flags = vec![if self.enabled { "on" } else { "off" }];
+        if self.nopass {
+            flags.push("nopass");
+        }
+
+        flags
+    }
+
+    /// The hashes of the passwords
+    pub fn passwords(&self) -> Vec<String> {
+        self.passwords.iter().cloned().collect()
+    }
+
+    pub fn commands_description(&self) -> String {
+        self.command_rules.join(" ")
+    }
+
+    pub fn keys_description(&self) -> String {
+        self.key_patterns
+            .iter()
+            .map(|pattern| format!("~{}", pattern))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    pub fn channels_description(&self) -> String {
+        self.channel_patterns
+            .iter()
+            .map(|pattern| format!("&{}", pattern))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// Describe the user with the rules which recreate it, as in ACL LIST
+    pub fn describe(&self) -> String {
+        let mut rules: Vec<String> = vec!["user".to_string(), self.name.clone()];
+        rules.extend(self.flags().into_iter().map(String::from));
+        rules.extend(self.passwords.iter().map(|hash| format!("#{}", hash)));
+
+        if !self.key_patterns.is_empty() {
+            rules.push(self.keys_description());
+        }
+
+        if self.channel_patterns.is_empty() {
+            rules.push("resetchannels".to_string());
+        } else {
+            rules.push(self.channels_description());
+        }
+
+        rules.push(self.commands_description());
+        rules.join(" ")
+    }
+}
+
+/// The reasons why the ACL denies a command
+#[derive(Debug, Clone, PartialEq)]
+pub enum AclError {
+    /// The connection didn't authenticate yet
+    NoAuth,
+
+    /// The user isn't allowed to run the command
+    Command { user: String, command: String },
+
+    /// The user isn't allowed to access one of the keys of the command
+    Key(String),
+
+    /// The user of the connection was deleted
+    NoUser,
+}
+
+impl std::error::Error for AclError {}
+
+impl fmt::Display for AclError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AclError::NoAuth => "NOAUTH Authentication required.".fmt(fmt),
+            AclError::Command { user, command } => format!(
+                "NOPERM User {} has no permissions to run the '{}' command",
+                user, command
+            )
+            .fmt(fmt),
+            AclError::Key(_) => "NOPERM No permissions to access a key".fmt(fmt),
+            AclError::NoUser => "ERR The user of this connection no longer exists".fmt(fmt),
+        }
+    }
+}
+
+/// An entry of the ACL LOG, which groups the identical denials
+#[derive(Debug, Clone, PartialEq)]
+pub struct AclLogEntry {
+    pub count: u64,
+
+    /// Either "command", "key" or "auth"
+    pub reason: String,
+
+    /// The command, key or AUTH, which was denied
+    pub object: String,
+    pub username: String,
+    pub client_info: String,
+    pub created: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+struct AclState {
+    users: BTreeMap<String, User>,
+
+    // The most recent entries first
+    log: VecDeque<AclLogEntry>,
+}
+
+/// The access control list, shared by every connection.
+///
+/// Internally, this is an `Arc`, so a clone only increments the reference count.
+#[derive(Debug, Clone)]
+pub struct Acl {
+    shared: Arc<Mutex<AclState>>,
+}
+
+impl Acl {
+    /// Create an ACL with only the default user, which needs no password and
+    /// may run every command, as in Redis.
+    pub fn new() -> Acl {
+        let mut default = User::new(DEFAULT_USER);
+        for rule in ["on", "nopass", "allkeys", "allchannels", "allcommands"] {
+            default.apply_rule(rule).unwrap();
+        }
+
+        let mut users = BTreeMap::new();
+        users.insert(DEFAULT_USER.to_string(), default);
+
+        Acl {
+            shared: Arc::new(Mutex::new(AclState {
+                users,
+                log: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Set the password of the default user, as with the `requirepass` directive.
+    /// Without a password, the default user needs none.
+    pub fn set_requirepass(&self, password: Option<&str>) {
+        let mut state = self.shared.lock().unwrap();
+        let default = state
+            .users
+            .entry(DEFAULT_USER.to_string())
+            .or_insert_with(|| User::new(DEFAULT_USER));
+
+        let _ = default.apply_rule("resetpass");
+        let _ = match password {
+            Some(password) => default.apply_rule(&format!(">{}", password)),
+            None => default.apply_rule("nopass"),
+        };
+    }
+
+    /// Whether new connections are authenticated as the default user, i.e.
+    /// whether it is enabled and needs no password
+    pub fn default_user_needs_no_auth(&self) -> bool {
+        let state = self.shared.lock().unwrap();
+
+        state
+            .users
+            .get(DEFAULT_USER)
+            .is_some_and(|user| user.enabled && user.nopass)
+    }
+
+    /// Whether the `password` authenticates the user `username`
+    pub fn authenticate(&self, username: &str, password: &str) -> bool {
+        let state = self.shared.lock().unwrap();
+
+        state
+            .users
+            .get(username)
+            .is_some_and(|user| user.check_password(password))
+    }
+
+    /// Check whether the user `username` may run the `command` on the `keys`.
+    ///
+    /// Denials are added to the ACL LOG, with the `client_info` of the connection.
+    pub fn check(
+        &self,
+        username: &str,
+        client_info: &str,
+        command: &str,
+        keys: &[&str],
+    ) -> Result<(), AclError> {
+        let denial = {
+            let state = self.shared.lock().unwrap();
+            let user = match state.users.get(username) {
+                Some(user) => user,
+                None => return Err(AclError::NoUser),
+            };
+
+            if !user.can_run(command) {
+                Some((
+                    "command",
+                    command.to_string(),
+                    AclError::Command {
+                        user: username.to_string(),
+                        command: command.to_string(),
+                    },
+                ))
+            } else {
+                keys.iter()
+                    .find(|key| !user.can_access_key(key))
+                    .map(|key| ("key", key.to_string(), AclError::Key(key.to_string())))
+            }
+        };
+
+        match denial {
+            Some((reason, object, err)) => {
+                self.log(reason, &object, username, client_info);
+                Err(err)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Add a denial to the ACL LOG. Denials of the same reason, object and
+    /// user are grouped into the same entry.
+    pub fn log(&self, reason: &str, object: &str, username: &str, client_info: &str) {
+        let mut state = self.shared.lock().unwrap();
+        let now = Utc::now();
+
+        let existing = state.log.iter().position(|entry| {
+            entry.reason == reason && entry.object == object && entry.username == username
+        });
+
+        let entry = match existing.and_then(|index| state.log.remove(index)) {
+            Some(mut entry) => {
+                entry.count += 1;
+                entry.client_info = client_info.to_string();
+                entry.updated = now;
+                entry
+            }
+            None => AclLogEntry {
+                count: 1,
+                reason: reason.to_string(),
+                object: object.to_string(),
+                username: username.to_string(),
+                client_info: client_info.to_string(),
+                created: now,
+                updated: now,
+            },
+        };
+
+        state.log.push_front(entry);
+        state.log.truncate(ACLLOG_MAX_LEN);
+    }
+
+    /// The `count` most recent entries of the ACL LOG
+    pub fn log_entries(&self, count: usize) -> Vec<AclLogEntry> {
+        let state = self.shared.lock().unwrap();
+        state.log.iter().take(count).cloned().collect()
+    }
+
+    pub fn reset_log(&self) {
+        self.shared.lock().unwrap().log.clear();
+    }
+
+    /// Create or modify the user `name` with the `rules` of ACL SETUSER.
+    ///
+    /// The rules are applied in order. If one of them is invalid, the user is left unchanged.
+    pub fn set_user(&self, name: &str, rules: &[String]) -> Result<(), String> {
+        let mut state = self.shared.lock().unwrap();
+
+        let mut user = match state.users.get(name) {
+            Some(user) => user.clone(),
+            None => User::new(name),
+        };
+
+        for rule in rules {
+            user.apply_rule(rule).map_err(|reason| {
+                format!("ERR Error in ACL SETUSER modifier '{}': {}", rule, reason)
+            })?;
+        }
+
+        state.users.insert(name.to_string(), user);
+
+        Ok(())
+    }
+
+    pub fn get_user(&self, name: &str) -> Option<User> {
+        self.shared.lock().unwrap().users.get(name).cloned()
+    }
+
+    /// Delete the users, and return how many of them existed
+    pub fn del_users(&self, names: &[String]) -> Result<usize, String> {
+        if names.iter().any(|name| name == DEFAULT_USER) {
+            return Err("ERR The 'default' user cannot be removed".to_string());
+        }
+
+        let mut state = self.shared.lock().unwrap();
+
+        Ok(names
+            .iter()
+            .filter(|name| state.users.remove(name.as_str()).is_some())
+            .count())
+    }
+
+    /// All the users, in alphabetical order
+    pub fn users(&self) -> Vec<User> {
+        self.shared
+            .lock()
+            .unwrap()
+            .users
+            .values()
+            .cloned()
+            .collect()
+    }
+}
+
+impl Default for Acl {
+    fn default() -> Self {
+        Acl::new()
+    }
+}
@@ -0,0 +1,280 @@
+use redust::cli::{format_reply, split_args, ArgsError, CliConnection};
+use redust::{DEFAULT_HOST, DEFAULT_PORT};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::io::{self, Read};
+use std::process::exit;
+use std::thread::sleep;
+use std::time::Duration;
+
+const USAGE: &str = "Usage: redust-cli [OPTIONS] [cmd [arg [arg ...]]]
+  -h <hostname>      Server hostname (default: 127.0.0.1).
+  -p <port>          Server port (default: 6666).
+  -r <repeat>        Execute specified command N times, or forever if negative.
+  -i <interval>      When -r is used, waits <interval> seconds per command.
+                     It is possible to specify sub-second times like -i 0.1.
+  --pipe             Transfer raw Redust protocol, or one command per line,
+                     from stdin to server.
+  --help             Output this help and exit.
+
+Examples:
+  redust-cli
+  redust-cli -r 100 -i 0.1 INCR counter
+  cat commands.txt | redust-cli --pipe
+
+When no command is given, redust-cli starts in interactive mode.
+Type \"quit\" or \"exit\" to leave it.";
+
+/// The name of the history file, in the home directory
+const HISTORY_FILE: &str = ".redustcli_history";
+
+#[derive(Debug)]
+struct Options {
+    host: String,
+    port: u16,
+    repeat: i64,
+    interval: Duration,
+    pipe: bool,
+    command: Vec<String>,
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        host: DEFAULT_HOST.to_string(),
+        port: DEFAULT_PORT,
+        repeat: 1,
+        interval: Duration::ZERO,
+        pipe: false,
+        command: Vec::new(),
+    };
+
+    let mut args = std::env::args().skip(1);
+
+    let invalid = |message: String| -> ! {
+        eprintln!("{}\n\n{}", message, USAGE);
+        exit(1);
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| match args.next() {
+            Some(value) => value,
+            None => invalid(format!("Missing value for option {}", name)),
+        };
+
+        match arg.as_str() {
+            "-h" => options.host = value("-h"),
+            "-p" => {
+                let port = value("-p");
+                options.port = port
+                    .parse()
+                    .unwrap_or_else(|_| invalid(format!("Invalid port '{}'", port)));
+            }
+            "-r" => {
+                let repeat = value("-r");
+                options.repeat = repeat
+                    .parse()
+                    .unwrap_or_else(|_| invalid(format!("Invalid repeat count '{}'", repeat)));
+            }
+            "-i" => {
+                let interval = value("-i");
+                let seconds: f64 = interval
+                    .parse()
+                    .ok()
+                    .filter(|seconds: &f64| *seconds >= 0.0)
+                    .unwrap_or_else(|| invalid(format!("Invalid interval '{}'", interval)));
+                options.interval = Duration::from_secs_f64(seconds);
+            }
+            "--pipe" => options.pipe = true,
+            "--help" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            _ => {
+                // The rest of the arguments are the command
+                options.command.push(arg);
+                options.command.extend(args.by_ref());
+            }
+        }
+    }
+
+    options
+}
+
+fn connect(options: &Options) -> io::Result<CliConnection> {
+    CliConnection::connect(&options.host, options.port).map_err(|err| {
+        eprintln!(
+            "Could not connect to Redust at {}:{}: {}",
+            options.host, options.port, err
+        );
+        err
+    })
+}
+
+/// Run the `args` command `repeat` times, or forever if negative, and print each reply
+fn run_command(
+    cnxn: &mut CliConnection,
+    args: &[String],
+    repeat: i64,
+    interval: Duration,
+) -> io::Result<()> {
+    let mut count: i64 = 0;
+
+    while repeat < 0 || count < repeat {
+        let reply = cnxn.request(args)?;
+        println!("{}", format_reply(&reply));
+
+        count += 1;
+        if !interval.is_zero() {
+            sleep(interval);
+        }
+    }
+
+    Ok(())
+}
+
+fn run_pipe(options: &Options) -> io::Result<()> {
+    let mut input: Vec<u8> = Vec::new();
+    io::stdin().read_to_end(&mut input)?;
+
+    let mut cnxn = connect(options)?;
+
+    let summary = cnxn.pipe(&input)?;
+    eprintln!("Last reply received from server.");
+    println!("errors: {}, replies: {}", summary.errors, summary.replies);
+
+    if summary.errors > 0 {
+        exit(1);
+    }
+
+    Ok(())
+}
+
+fn run_repl(options: &Options) -> rustyline::Result<()> {
+    let mut editor = DefaultEditor::new()?;
+
+    let history = std::env::var("HOME")
+        .map(|home| format!("{}/{}", home, HISTORY_FILE))
+        .ok();
+    if let Some(history) = &history {
+        let _ = editor.load_history(history);
+    }
+
+    let address = format!("{}:{}", options.host, options.port);
+    let mut cnxn = connect(options).ok();
+
+    // The lines of a command, whose quotes aren't closed yet
+    let mut pending: Option<String> = None;
+
+    loop {
+        let prompt = match (&pending, &cnxn) {
+            (Some(_), _) => "...> ".to_string(),
+            (None, Some(_)) => format!("{}> ", address),
+            (None, None) => "not connected> ".to_string(),
+        };
+
+        let line = match editor.readline(&prompt) {
+            Ok(line) => line,
+            // Ctrl-C discards the current command, and Ctrl-D leaves
+            Err(ReadlineError::Interrupted) => {
+                pending = None;
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err),
+        };
+
+        let line = match pending.take() {
+            Some(previous) => format!("{}\n{}", previous, line),
+            None => line,
+        };
+
+        let mut args = match split_args(&line) {
+            Ok(args) => args,
+            Err(ArgsError::Incomplete) => {
+                pending = Some(line);
+                continue;
+            }
+            Err(err) => {
+                println!("(error) {}", err);
+                continue;
+            }
+        };
+
+        if args.is_empty() {
+            continue;
+        }
+
+        let _ = editor.add_history_entry(line.as_str());
+
+        match args[0].to_lowercase().as_str() {
+            "quit" | "exit" => break,
+            "help" => {
+                println!("{}", USAGE);
+                continue;
+            }
+            _ => {}
+        }
+
+        // A leading number repeats the command, as in "5 INCR counter"
+        let mut repeat = options.repeat;
+        if args.len() > 1 {
+            if let Ok(count) = args[0].parse::<i64>() {
+                repeat = count;
+                args.remove(0);
+            }
+        }
+
+        // Reconnect, if the server was down or closed the connection
+        if cnxn.is_none() {
+            cnxn = connect(options).ok();
+        }
+
+        if let Some(connection) = cnxn.as_mut() {
+            if let Err(err) = run_command(connection, &args, repeat, options.interval) {
+                eprintln!("Error: {}", err);
+                cnxn = None;
+            }
+        }
+    }
+
+    if let Some(history) = &history {
+        let _ = editor.save_history(history);
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let options = parse_options();
+
+    if options.pipe {
+        if let Err(err) = run_pipe(&options) {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+        return;
+    }
+
+    if !options.command.is_empty() {
+        let mut cnxn = match connect(&options) {
+            Ok(cnxn) => cnxn,
+            Err(_) => exit(1),
+        };
+
+        if let Err(err) = run_command(
+            &mut cnxn,
+            &options.command,
+            options.repeat,
+            options.interval,
+        ) {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+        return;
+    }
+
+    if let Err(err) = run_repl(&options) {
+        eprintln!("Error: {}", err);
+        exit(1);
+    }
+}
@@ -1,13 +1,155 @@
-use redust::{server, DEFAULT_HOST, DEFAULT_PORT};
+use redust::server::{self, Listeners};
+use redust::Config;
+use std::path::Path;
 use tokio::net::TcpListener;
 
+const USAGE: &str =
+    "Usage: server [/path/to/redust.conf] [--config <file>] [--<directive> <value>...]...
+
+Examples:
+  server --port 7777
+  server --bind 127.0.0.1 ::1 --requirepass secret
+  server /etc/redust/redust.conf --port 7777 --protected-mode no
+  server --config redust.conf --rename-command info \"\"
+  server --port 7777 --metrics-port 9121
+
+The directives of the command line are applied after those of the configuration file.";
+
+/// Parse the configuration file and directives of the command line, as in
+/// redis-server, e.g. `redust.conf --port 6380 --requirepass secret`.
+fn parse_args(args: &[String]) -> Result<Config, String> {
+    let mut config = Config::default();
+    let mut args = args.iter().peekable();
+
+    // The configuration file may be given first, without a flag
+    let mut files: Vec<String> = args
+        .next_if(|arg| !arg.starts_with("--"))
+        .cloned()
+        .into_iter()
+        .collect();
+    let mut directives: Vec<(&str, Vec<String>)> = Vec::new();
+
+    while let Some(flag) = args.next() {
+        let name = match flag.strip_prefix("--") {
+            Some("help") | None => return Err(USAGE.to_string()),
+            Some(name) => name,
+        };
+
+        // The arguments of a directive run until the next flag
+        let mut values = Vec::new();
+        while let Some(value) = args.next_if(|arg| !arg.starts_with("--")) {
+            values.push(value.clone());
+        }
+
+        match (name, values.as_slice()) {
+            ("config", [file]) => files.push(file.clone()),
+            ("config", _) => return Err(USAGE.to_string()),
+            _ => directives.push((name, values)),
+        }
+    }
+
+    // CONFIG REWRITE updates the last configuration file
+    for file in files {
+        config.load_file(Path::new(&file))?;
+        config.config_file = Some(file.into());
+    }
+
+    for (name, values) in directives {
+        config
+            .set(name, &values)
+            .map_err(|err| format!("Invalid option --{}: {}", name, err))?;
+    }
+
+    Ok(config)
+}
+
+/// Listen on `port` of each of the `bind` addresses
+async fn bind_all(bind: &[String], port: u16, kind: &str) -> std::io::Result<Vec<TcpListener>> {
+    let mut listeners = Vec::new();
+
+    for address in bind {
+        let listener = TcpListener::bind((address.as_str(), port)).await?;
+        println!("Server listening{} on {}", kind, listener.local_addr()?);
+        listeners.push(listener);
+    }
+
+    Ok(listeners)
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let listener: TcpListener =
-        TcpListener::bind(&format!("{}:{}", DEFAULT_HOST, DEFAULT_PORT)).await?;
-    println!("Server listening on {}:{}", DEFAULT_HOST, DEFAULT_PORT);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let config = match parse_args(&args) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    // Plain TCP clients are accepted unless the port is 0
+    let tcp = match config.port {
+        0 => Vec::new(),
+        port => bind_all(&config.bind, port, "").await?,
+    };
+
+    // TLS clients are accepted on their own port, alongside the plain ones
+    let tls = match config.tls_port {
+        Some(port) => bind_all(&config.bind, port, " for TLS").await?,
+        None => Vec::new(),
+    };
+
+    let unix = match &config.unixsocket {
+        Some(path) => {
+            let listener = server::bind_unix(path, config.unixsocketperm)?;
+            println!("Server listening on {}", path.display());
+            Some(listener)
+        }
+        None => None,
+    };
+
+    // Prometheus scrapes the metrics over HTTP, on a port of their own
+    let metrics = match config.metrics_port {
+        Some(port) => bind_all(&config.bind, port, " for metrics").await?,
+        None => Vec::new(),
+    };
+
+    // The server shuts down gracefully on SIGINT and SIGTERM
+    let listeners = Listeners {
+        tcp,
+        tls,
+        unix,
+        metrics,
+        signals: true,
+    };
+    let unixsocket = config.unixsocket.clone();
+
+    let result = if config.cluster_enabled {
+        // The cluster bus listens on the first bind address, by default on
+        // the port of the clients plus 10000
+        let bus_port = match config.cluster_port {
+            Some(port) => port,
+            None => config
+                .port
+                .checked_add(10000)
+                .ok_or("The port is too high for a cluster bus port, set cluster-port")?,
+        };
+        let mut bus = bind_all(&config.bind[..1], bus_port, " for the cluster bus").await?;
+
+        server::run_cluster_with_listeners(listeners, bus.remove(0), config).await
+    } else {
+        server::run_with_listeners(listeners, config).await
+    };
+
+    if let Err(err) = result {
+        println!("Server Error | {}", err);
+    }
 
-    let _ = server::run(listener).await;
+    // The socket file is removed once the server is stopped, as in Redis
+    if let Some(path) = unixsocket {
+        let _ = std::fs::remove_file(path);
+    }
 
+    println!("Server stopped");
     Ok(())
 }
@@ -0,0 +1,352 @@
+use crate::{deserialize_buffer, protocol_handler::BulkStringData, serialize_data, RESPType};
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    net::TcpStream,
+    thread,
+};
+
+/// The errors of splitting a command line into arguments
+#[derive(Debug, PartialEq)]
+pub enum ArgsError {
+    /// A quoted argument isn't closed yet, so the line continues on the next one
+    Incomplete,
+
+    /// A closing quote isn't followed by a space, an escape is malformed, or
+    /// the escaped bytes aren't valid UTF-8
+    Invalid,
+}
+
+impl std::error::Error for ArgsError {}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgsError::Incomplete => "Unbalanced quotes in request".fmt(fmt),
+            ArgsError::Invalid => "Invalid argument(s)".fmt(fmt),
+        }
+    }
+}
+
+/// Split a command line into its arguments, following the quoting rules of redis-cli.
+///
+/// Arguments are separated by whitespace. Within double quotes, the escapes
+/// `\n`, `\r`, `\t`, `\b`, `\a`, `\\`, `\"` and `\xHH` are supported. Within single
+/// quotes, only `\'` is an escape. A closing quote must be followed by whitespace,
+/// or the end of the line.
+///
+/// The `\xHH` escapes are raw bytes, so `"\xc3\xa9"` is `é`. As the arguments are
+/// sent as UTF-8 strings, those which aren't valid UTF-8 are rejected.
+pub fn split_args(line: &str) -> Result<Vec<String>, ArgsError> {
+    let mut args: Vec<String> = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    loop {
+        // Skip the whitespace between arguments
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+
+        if chars.peek().is_none() {
+            return Ok(args);
+        }
+
+        let mut arg: Vec<u8> = Vec::new();
+        let push = |arg: &mut Vec<u8>, c: char| {
+            arg.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes())
+        };
+
+        loop {
+            match chars.next() {
+                None => break,
+                Some(c) if c.is_whitespace() => break,
+                Some('"') => {
+                    loop {
+                        match chars.next() {
+                            None => return Err(ArgsError::Incomplete),
+                            Some('"') => break,
+                            Some('\\') => match chars.next() {
+                                None => return Err(ArgsError::Incomplete),
+                                Some('n') => push(&mut arg, '\n'),
+                                Some('r') => push(&mut arg, '\r'),
+                                Some('t') => push(&mut arg, '\t'),
+                                Some('b') => push(&mut arg, '\u{8}'),
+                                Some('a') => push(&mut arg, '\u{7}'),
+                                Some('x') => {
+                                    let hex: String = chars.by_ref().take(2).collect();
+                                    match u8::from_str_radix(&hex, 16) {
+                                        Ok(byte) if hex.len() == 2 => arg.push(byte),
+                                        _ => return Err(ArgsError::Invalid),
+                                    }
+                                }
+                                Some(c) => push(&mut arg, c),
+                            },
+                            Some(c) => push(&mut arg, c),
+                        }
+                    }
+
+                    if chars.peek().is_some_and(|c| !c.is_whitespace()) {
+                        return Err(ArgsError::Invalid);
+                    }
+                }
+                Some('\'') => {
+                    loop {
+                        match chars.next() {
+                            None => return Err(ArgsError::Incomplete),
+                            Some('\'') => break,
+                            Some('\\') if chars.peek() == Some(&'\'') => {
+                                chars.next();
+                                push(&mut arg, '\'');
+                            }
+                            Some(c) => push(&mut arg, c),
+                        }
+                    }
+
+                    if chars.peek().is_some_and(|c| !c.is_whitespace()) {
+                        return Err(ArgsError::Invalid);
+                    }
+                }
+                Some(c) => push(&mut arg, c),
+            }
+        }
+
+        match String::from_utf8(arg) {
+            Ok(arg) => args.push(arg),
+            Err(_) => return Err(ArgsError::Invalid),
+        }
+    }
+}
+
+/// Build the array frame of a command, from its arguments
+pub fn command_frame(args: &[String]) -> RESPType {
+    RESPType::Array(
+        args.iter()
+            .map(|arg| {
+                RESPType::BulkString(Some(BulkStringData {
+                    text: arg.clone(),
+                    prefix_length: arg.len(),
+                }))
+            })
+            .collect(),
+    )
+}
+
+/// Quote a bulk string, escaping the characters which aren't printable
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+
+    for c in text.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\u{7}' => quoted.push_str("\\a"),
+            '\u{8}' => quoted.push_str("\\b"),
+            c if c.is_control() => quoted.push_str(&format!("\\x{:02x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// Quote a bulk string which isn't valid UTF-8, escaping each byte which
+/// isn't printable ASCII
+fn quote_bytes(bytes: &[u8]) -> String {
+    let mut quoted = String::from("\"");
+
+    for &byte in bytes {
+        match byte {
+            b'\\' => quoted.push_str("\\\\"),
+            b'"' => quoted.push_str("\\\""),
+            b'\n' => quoted.push_str("\\n"),
+            b'\r' => quoted.push_str("\\r"),
+            b'\t' => quoted.push_str("\\t"),
+            0x07 => quoted.push_str("\\a"),
+            0x08 => quoted.push_str("\\b"),
+            b' '..=b'~' => quoted.push(byte as char),
+            byte => quoted.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// Render a reply for humans, in the style of redis-cli.
+///
+/// Multi-line bulk strings, e.g. the reply of INFO, are printed raw, and
+/// the others are quoted. Each element of an array is numbered, and the
+/// elements of nested arrays are aligned under their parent's number.
+pub fn format_reply(reply: &RESPType) -> String {
+    match reply {
+        RESPType::SimpleString(text) => text.clone(),
+        RESPType::Error(text) => format!("(error) {}", text),
+        RESPType::Integer(value) => format!("(integer) {}", value),
+        RESPType::BulkString(None) => "(nil)".to_string(),
+        RESPType::BulkString(Some(data)) if data.text.contains('\n') => data.text.clone(),
+        RESPType::BulkString(Some(data)) => quote(&data.text),
+        RESPType::BulkBytes(bytes) => quote_bytes(bytes),
+        RESPType::Array(elements) if elements.is_empty() => "(empty array)".to_string(),
+        RESPType::Array(elements) => {
+            let width = elements.len().to_string().len();
+            let mut lines: Vec<String> = Vec::new();
+
+            for (i, element) in elements.iter().enumerate() {
+                let prefix = format!("{:>width$}) ", i + 1, width = width);
+                let indent = " ".repeat(prefix.len());
+
+                for (j, line) in format_reply(element).lines().enumerate() {
+                    if j == 0 {
+                        lines.push(format!("{}{}", prefix, line));
+                    } else {
+                        lines.push(format!("{}{}", indent, line));
+                    }
+                }
+            }
+
+            lines.join("\n")
+        }
+    }
+}
+
+/// A blocking connection to a server, for the command-line client
+#[derive(Debug)]
+pub struct CliConnection {
+    stream: TcpStream,
+
+    // The bytes read, which don't form a complete reply yet
+    buffer: Vec<u8>,
+}
+
+/// The outcome of `CliConnection::pipe`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PipeSummary {
+    pub replies: usize,
+    pub errors: usize,
+}
+
+impl CliConnection {
+    pub fn connect(host: &str, port: u16) -> io::Result<CliConnection> {
+        Ok(CliConnection {
+            stream: TcpStream::connect((host, port))?,
+            buffer: Vec::with_capacity(4 * 1024),
+        })
+    }
+
+    /// Send a command, without waiting for its reply
+    pub fn send(&mut self, frame: &RESPType) -> io::Result<()> {
+        let data = serialize_data(frame).unwrap();
+        self.stream.write_all(&data)
+    }
+
+    /// Read the next reply, waiting until it is complete
+    pub fn read_reply(&mut self) -> io::Result<RESPType> {
+        let mut chunk = [0u8; 4 * 1024];
+
+        loop {
+            let (frame, frame_size) = deserialize_buffer(&self.buffer);
+            if let Some(frame) = frame {
+                self.buffer.drain(0..frame_size);
+                return Ok(frame);
+            }
+
+            let read = self.stream.read(&mut chunk)?;
+            if read == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Server closed the connection",
+                ));
+            }
+
+            self.buffer.extend_from_slice(&chunk[..read]);
+        }
+    }
+
+    /// Send a command, and wait for its reply
+    pub fn request(&mut self, args: &[String]) -> io::Result<RESPType> {
+        self.send(&command_frame(args))?;
+        self.read_reply()
+    }
+
+    /// Send all the commands of the `input` for mass insertion, and count the replies.
+    ///
+    /// The input is either in the RESP format, if it starts with `*`, or one
+    /// command per line, with the same quoting rules as the prompt. The commands
+    /// are written from another thread, while the replies are read, so that
+    /// neither side blocks on a full socket buffer.
+    pub fn pipe(&mut self, input: &[u8]) -> io::Result<PipeSummary> {
+        let (data, commands) = CliConnection::pipe_data(input)?;
+
+        let mut writer = self.stream.try_clone()?;
+        let sender = thread::spawn(move || writer.write_all(&data));
+
+        let mut summary = PipeSummary {
+            replies: 0,
+            errors: 0,
+        };
+
+        while summary.replies < commands {
+            if let RESPType::Error(_) = self.read_reply()? {
+                summary.errors += 1;
+            }
+
+            summary.replies += 1;
+        }
+
+        sender
+            .join()
+            .map_err(|_| io::Error::other("Failed to send the data"))??;
+
+        Ok(summary)
+    }
+
+    /// Serialize the commands of the `input`, and count them
+    fn pipe_data(input: &[u8]) -> io::Result<(Vec<u8>, usize)> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        // Data in the RESP format is sent as is, once it is known to be complete
+        if input.first() == Some(&b'*') {
+            let mut offset = 0;
+            let mut commands = 0;
+
+            while offset < input.len() {
+                match deserialize_buffer(&input[offset..]) {
+                    // Empty arrays are skipped by the server, without a reply
+                    (Some(RESPType::Array(args)), size) if args.is_empty() => offset += size,
+                    (Some(_), size) => {
+                        offset += size;
+                        commands += 1;
+                    }
+                    (None, _) => {
+                        return Err(invalid(format!("Malformed protocol at byte {}", offset)))
+                    }
+                }
+            }
+
+            return Ok((input.to_vec(), commands));
+        }
+
+        let text = String::from_utf8(input.to_vec()).map_err(|err| invalid(err.to_string()))?;
+
+        let mut data: Vec<u8> = Vec::new();
+        let mut commands = 0;
+
+        for (number, line) in text.lines().enumerate() {
+            let args =
+                split_args(line).map_err(|err| invalid(format!("Line {}: {}", number + 1, err)))?;
+
+            if args.is_empty() {
+                continue;
+            }
+
+            data.extend(serialize_data(&command_frame(&args)).unwrap());
+            commands += 1;
+        }
+
+        Ok((data, commands))
+    }
+}
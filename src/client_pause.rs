@@ -0,0 +1,119 @@
+use std::sync::Arc;
+use tokio::{
+    sync::watch,
+    time::{sleep_until, Duration, Instant},
+};
+
+/// Which commands are held, while clients are paused.
+///
+/// The modes are ordered from the least to the most restrictive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PauseMode {
+    /// Only commands which modify the data store are held
+    Write,
+
+    /// All commands are held
+    All,
+}
+
+impl PauseMode {
+    pub fn parse(mode: &str) -> Option<PauseMode> {
+        match mode.to_lowercase().as_str() {
+            "write" => Some(PauseMode::Write),
+            "all" => Some(PauseMode::All),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Pause {
+    mode: PauseMode,
+    until: Instant,
+}
+
+impl Pause {
+    /// Whether a command is held by this pause
+    fn holds(&self, is_write: bool) -> bool {
+        Instant::now() < self.until && (self.mode == PauseMode::All || is_write)
+    }
+}
+
+/// The state of CLIENT PAUSE, shared across each `ConnectionHandler`
+/// and the key expiry task.
+///
+/// The pause is kept in a `watch` channel, so that the held commands are
+/// resumed as soon as the pause ends, or is lifted with CLIENT UNPAUSE.
+#[derive(Debug, Clone)]
+pub struct ClientPause {
+    state: Arc<watch::Sender<Option<Pause>>>,
+}
+
+impl ClientPause {
+    pub fn new() -> ClientPause {
+        let (state, _) = watch::channel(None);
+
+        ClientPause {
+            state: Arc::new(state),
+        }
+    }
+
+    /// Pause the clients for the `duration`, holding the commands of the `mode`.
+    ///
+    /// If clients are already paused, the longer duration and the more
+    /// restrictive mode are kept.
+    pub fn pause(&self, mode: PauseMode, duration: Duration) {
+        let until = Instant::now() + duration;
+
+        self.state.send_modify(|pause| {
+            *pause = match *pause {
+                Some(current) if current.until > Instant::now() => Some(Pause {
+                    mode: current.mode.max(mode),
+                    until: current.until.max(until),
+                }),
+                _ => Some(Pause { mode, until }),
+            };
+        });
+    }
+
+    /// Lift the pause, resuming all the held commands
+    pub fn unpause(&self) {
+        self.state.send_replace(None);
+    }
+
+    /// Whether a command, which modifies the data store if `is_write`,
+    /// would currently be held.
+    pub fn is_paused(&self, is_write: bool) -> bool {
+        match *self.state.borrow() {
+            Some(pause) => pause.holds(is_write),
+            None => false,
+        }
+    }
+
+    /// Wait until a command, which modifies the data store if `is_write`,
+    /// is no longer held.
+    pub async fn wait(&self, is_write: bool) {
+        let mut changes = self.state.subscribe();
+
+        loop {
+            let pause = *changes.borrow_and_update();
+
+            match pause {
+                Some(pause) if pause.holds(is_write) => {
+                    // Wake up when the pause ends, or is changed
+                    tokio::select! {
+                        _ = sleep_until(pause.until) => {}
+                        _ = changes.changed() => {}
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+}
+
+impl Default for ClientPause {
+    fn default() -> Self {
+        ClientPause::new()
+    }
+}
@@ -0,0 +1,330 @@
+// The registry of the connected clients, as reported by CLIENT LIST. Each
+// `ConnectionHandler` registers its connection, keeps its entry up to date,
+// and removes it once the connection is closed.
+use crate::DEFAULT_MAXCLIENTS;
+use std::collections::BTreeMap;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+use std::time::Instant;
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+
+/// The types of clients of CLIENT LIST and CLIENT KILL, as in Redis. There
+/// are no master or pubsub connections, but the types are accepted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClientType {
+    Normal,
+    Master,
+    Replica,
+    Pubsub,
+}
+
+impl ClientType {
+    pub fn parse(value: &str) -> Option<ClientType> {
+        match value.to_lowercase().as_str() {
+            "normal" => Some(ClientType::Normal),
+            "master" => Some(ClientType::Master),
+            "replica" | "slave" => Some(ClientType::Replica),
+            "pubsub" => Some(ClientType::Pubsub),
+            _ => None,
+        }
+    }
+}
+
+/// The state of a client which changes while it is connected
+#[derive(Debug, Clone)]
+struct ClientState {
+    name: String,
+    user: String,
+
+    /// The last command, and when it was received
+    last_command: String,
+    last_interaction: Instant,
+
+    /// The number of bytes in the read buffer, and its capacity
+    query_buffer: usize,
+    read_buffer_size: usize,
+
+    /// Whether the connection was turned into a replication link or a monitor
+    replica: bool,
+    monitor: bool,
+
+    /// Set by CLIENT NO-EVICT
+    no_evict: bool,
+}
+
+/// A connected client, shared by the registry and the `ConnectionHandler` of
+/// the connection
+#[derive(Debug)]
+pub struct ConnectedClient {
+    pub id: u64,
+
+    /// The address of the peer, and the local address it is connected to
+    pub addr: String,
+    pub laddr: String,
+
+    created_at: Instant,
+    state: Mutex<ClientState>,
+
+    /// Notified once the client is killed, to close its connection
+    killed: Notify,
+}
+
+impl ConnectedClient {
+    /// Record that the `command` was received, with the read buffer of the
+    /// connection holding `query_buffer` of its `read_buffer_size` bytes
+    pub fn command_received(&self, command: &str, query_buffer: usize, read_buffer_size: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.last_command = command.to_string();
+        state.last_interaction = Instant::now();
+        state.query_buffer = query_buffer;
+        state.read_buffer_size = read_buffer_size;
+    }
+
+    /// Update the `name` and `user` of the client, e.g. after CLIENT SETNAME or AUTH
+    pub fn set_identity(&self, name: &str, user: &str) {
+        let mut state = self.state.lock().unwrap();
+        if state.name != name {
+            state.name = name.to_string();
+        }
+        if state.user != user {
+            state.user = user.to_string();
+        }
+    }
+
+    pub fn set_replica(&self) {
+        self.state.lock().unwrap().replica = true;
+    }
+
+    pub fn set_monitor(&self) {
+        self.state.lock().unwrap().monitor = true;
+    }
+
+    pub fn set_no_evict(&self, no_evict: bool) {
+        self.state.lock().unwrap().no_evict = no_evict;
+    }
+
+    pub fn client_type(&self) -> ClientType {
+        let state = self.state.lock().unwrap();
+
+        // Monitors are reported as normal clients, as in Redis
+        if state.replica && !state.monitor {
+            ClientType::Replica
+        } else {
+            ClientType::Normal
+        }
+    }
+
+    pub fn user(&self) -> String {
+        self.state.lock().unwrap().user.clone()
+    }
+
+    /// Close the connection of the client, once it is waiting for its next command
+    pub fn kill(&self) {
+        self.killed.notify_one();
+    }
+
+    /// Wait until the client is killed
+    pub async fn killed(&self) {
+        self.killed.notified().await
+    }
+
+    /// Describe the client in a line of CLIENT LIST, e.g.
+    /// `id=3 addr=127.0.0.1:50188 laddr=127.0.0.1:6666 name= age=0 idle=0 flags=N ...`
+    pub fn describe(&self) -> String {
+        let state = self.state.lock().unwrap();
+
+        let mut flags = String::new();
+        if state.replica && !state.monitor {
+            flags.push('S');
+        }
+        if state.monitor {
+            flags.push('O');
+        }
+        if state.no_evict {
+            flags.push('e');
+        }
+        if flags.is_empty() {
+            flags.push('N');
+        }
+
+        format!(
+            "id={} addr={} laddr={} name={} age={} idle={} flags={} db=0 qbuf={} rbs={} cmd={} user={}",
+            self.id,
+            self.addr,
+            self.laddr,
+            state.name,
+            self.created_at.elapsed().as_secs(),
+            state.last_interaction.elapsed().as_secs(),
+            flags,
+            state.query_buffer,
+            state.read_buffer_size,
+            state.last_command,
+            state.user
+        )
+    }
+}
+
+/// The connected clients, by id. A clone only increments the reference count.
+#[derive(Debug, Clone)]
+pub struct Clients {
+    shared: Arc<Registry>,
+}
+
+#[derive(Debug)]
+struct Registry {
+    clients: Mutex<BTreeMap<u64, Arc<ConnectedClient>>>,
+
+    /// The id of the previous client. The ids are never reused.
+    last_id: AtomicU64,
+
+    /// The slots of the connections, one per client up to `maxclients`
+    slots: Arc<Semaphore>,
+    limit: Mutex<Limit>,
+}
+
+#[derive(Debug)]
+struct Limit {
+    maxclients: usize,
+
+    /// The number of slots to remove once they are released, after `maxclients`
+    /// was lowered under the number of connected clients
+    excess: usize,
+}
+
+/// The slot of a connection, which is released once it is dropped
+#[derive(Debug)]
+pub struct ClientSlot {
+    permit: Option<OwnedSemaphorePermit>,
+    registry: Arc<Registry>,
+}
+
+impl Drop for ClientSlot {
+    fn drop(&mut self) {
+        let mut limit = self.registry.limit.lock().unwrap();
+
+        if limit.excess > 0 {
+            limit.excess -= 1;
+            if let Some(permit) = self.permit.take() {
+                permit.forget();
+            }
+        }
+    }
+}
+
+impl Clients {
+    /// Create the registry, which accepts up to `maxclients` connections
+    pub fn new(maxclients: usize) -> Clients {
+        Clients {
+            shared: Arc::new(Registry {
+                clients: Mutex::new(BTreeMap::new()),
+                last_id: AtomicU64::new(0),
+                slots: Arc::new(Semaphore::new(maxclients)),
+                limit: Mutex::new(Limit {
+                    maxclients,
+                    excess: 0,
+                }),
+            }),
+        }
+    }
+
+    /// Take the slot of a new connection, or `None` if `maxclients` connections
+    /// are already open
+    pub fn try_reserve(&self) -> Option<ClientSlot> {
+        let permit = self.shared.slots.clone().try_acquire_owned().ok()?;
+
+        Some(ClientSlot {
+            permit: Some(permit),
+            registry: self.shared.clone(),
+        })
+    }
+
+    /// Change the maximum number of connections. When it is lowered, the clients
+    /// over the limit stay connected, but no other one is accepted until enough
+    /// of them disconnect.
+    pub fn set_maxclients(&self, maxclients: usize) {
+        let mut limit = self.shared.limit.lock().unwrap();
+
+        if maxclients > limit.maxclients {
+            // The slots which were still to remove are kept instead
+            let added = maxclients - limit.maxclients;
+            let kept = added.min(limit.excess);
+            limit.excess -= kept;
+            self.shared.slots.add_permits(added - kept);
+        } else {
+            // The slots which are taken are removed as they are released
+            let removed = limit.maxclients - maxclients;
+            limit.excess += removed - self.shared.slots.forget_permits(removed);
+        }
+
+        limit.maxclients = maxclients;
+    }
+
+    /// Register a new client, connected from `addr` to `laddr` as the `user`
+    pub fn register(&self, addr: &str, laddr: &str, user: &str) -> Arc<ConnectedClient> {
+        let id = self.shared.last_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let now = Instant::now();
+
+        let client = Arc::new(ConnectedClient {
+            id,
+            addr: addr.to_string(),
+            laddr: laddr.to_string(),
+            created_at: now,
+            state: Mutex::new(ClientState {
+                name: String::new(),
+                user: user.to_string(),
+                last_command: "NULL".to_string(),
+                last_interaction: now,
+                query_buffer: 0,
+                read_buffer_size: 0,
+                replica: false,
+                monitor: false,
+                no_evict: false,
+            }),
+            killed: Notify::new(),
+        });
+
+        self.shared
+            .clients
+            .lock()
+            .unwrap()
+            .insert(id, client.clone());
+
+        client
+    }
+
+    /// Remove the client with the `id`, once its connection is closed
+    pub fn unregister(&self, id: u64) {
+        self.shared.clients.lock().unwrap().remove(&id);
+    }
+
+    pub fn get(&self, id: u64) -> Option<Arc<ConnectedClient>> {
+        self.shared.clients.lock().unwrap().get(&id).cloned()
+    }
+
+    /// The connected clients, in the order of their ids
+    pub fn list(&self) -> Vec<Arc<ConnectedClient>> {
+        self.shared
+            .clients
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.clients.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for Clients {
+    fn default() -> Self {
+        Clients::new(DEFAULT_MAXCLIENTS)
+    }
+}
@@ -0,0 +1,1001 @@
+use crate::{
+    cmd::Command, protocol_handler::BulkStringData, Connection, ConnectionBase, RESPType,
+    CLUSTER_LINK_RETRY_MS, CLUSTER_NODE_TIMEOUT_MS, CLUSTER_PING_PERIOD_MS, CLUSTER_SLOTS,
+};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    time::{interval, sleep, Duration},
+};
+
+/// The errors of the cluster bus tasks, which must be `Send` to be held across
+/// the awaits of a spawned task.
+type BusError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Calculate the CRC16 (XMODEM) checksum of the `bytes`, as used by Redis Cluster.
+pub fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+
+    for byte in bytes {
+        crc ^= (*byte as u16) << 8;
+
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+
+    crc
+}
+
+/// Calculate the hash slot of the `key`.
+///
+/// If the key contains a non-empty `{hashtag}`, only the hashtag is hashed,
+/// so that related keys can be placed in the same slot.
+pub fn key_hash_slot(key: &str) -> u16 {
+    let bytes = key.as_bytes();
+
+    let hashed = match bytes.iter().position(|byte| *byte == b'{') {
+        Some(start) => match bytes[start + 1..].iter().position(|byte| *byte == b'}') {
+            Some(length) if length > 0 => &bytes[start + 1..start + 1 + length],
+            _ => bytes,
+        },
+        None => bytes,
+    };
+
+    crc16(hashed) % CLUSTER_SLOTS
+}
+
+/// A node of the cluster, as known by this node
+#[derive(Debug, Clone)]
+pub struct ClusterNode {
+    pub id: String,
+    pub ip: String,
+
+    /// The port for clients
+    pub port: u16,
+
+    /// The port of the cluster bus
+    pub bus_port: u16,
+
+    /// The epoch of the node's claim on its slots. A claim with a greater
+    /// epoch wins, when two nodes claim the same slot.
+    pub config_epoch: u64,
+
+    /// When a message was last received from the node
+    pub last_pong: Option<DateTime<Utc>>,
+
+    /// Whether the node was added with MEET, and its real ID isn't known yet
+    pub handshake: bool,
+}
+
+impl ClusterNode {
+    /// The address of the node, for the clients
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.ip, self.port)
+    }
+}
+
+/// The change of a slot, with CLUSTER SETSLOT
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlotState {
+    /// The slot is being moved from this node, to the node
+    Migrating(String),
+
+    /// The slot is being moved to this node, from the node
+    Importing(String),
+
+    /// The slot is owned by the node
+    Node(String),
+
+    /// Clear the migrating and importing state
+    Stable,
+}
+
+#[derive(Debug)]
+struct ClusterState {
+    /// The ID of this node
+    myself: String,
+
+    /// All the known nodes, including this node, by their ID
+    nodes: HashMap<String, ClusterNode>,
+
+    /// The ID of the node which owns each slot
+    slots: Vec<Option<String>>,
+
+    /// The slots being moved from this node, to the node with the ID
+    migrating: HashMap<u16, String>,
+
+    /// The slots being moved to this node, from the node with the ID
+    importing: HashMap<u16, String>,
+
+    /// The greatest epoch seen in the cluster
+    current_epoch: u64,
+
+    /// The nodes with an active outbound link
+    links: HashSet<String>,
+}
+
+impl ClusterState {
+    /// Forget the node with the `id`, and unassign the slots it owned, e.g.
+    /// after CLUSTER SETSLOT NODE with the ID of a handshake
+    fn remove_node(&mut self, id: &str) {
+        if id == self.myself || self.nodes.remove(id).is_none() {
+            return;
+        }
+
+        for owner in self.slots.iter_mut() {
+            if owner.as_deref() == Some(id) {
+                *owner = None;
+            }
+        }
+
+        self.migrating.retain(|_, target| target != id);
+        self.importing.retain(|_, source| source != id);
+    }
+}
+
+/// The state of cluster mode, shared across each `ConnectionHandler` and the
+/// cluster bus tasks.
+///
+/// Each key belongs to one of the `CLUSTER_SLOTS` hash slots, and each slot is
+/// served by one node. Commands for the slots of other nodes are redirected
+/// with MOVED, or with ASK while a slot is being migrated.
+///
+/// The nodes learn about each other, and about the owners of the slots, by
+/// exchanging PING and PONG messages over the cluster bus. The messages carry
+/// the slots claimed by the sender, and a gossip section about the other nodes
+/// it knows, so that a single CLUSTER MEET is enough to join a cluster.
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    shared: Option<Arc<Mutex<ClusterState>>>,
+}
+
+/// The kind of a message on the cluster bus
+#[derive(Debug, Clone, PartialEq)]
+enum MessageKind {
+    Meet,
+    Ping,
+    Pong,
+}
+
+/// A message on the cluster bus, describing the sender and the nodes it knows
+#[derive(Debug, Clone)]
+struct Message {
+    kind: MessageKind,
+    sender: ClusterNode,
+    current_epoch: u64,
+
+    /// The slot ranges claimed by the sender
+    slots: Vec<(u16, u16)>,
+
+    /// The (id, ip, port, bus port) of the other nodes known by the sender
+    gossip: Vec<(String, String, u16, u16)>,
+}
+
+impl Cluster {
+    /// Create the state of a node, which isn't part of a cluster
+    pub fn disabled() -> Cluster {
+        Cluster { shared: None }
+    }
+
+    /// Create the state of a cluster node, with no slots and no other nodes
+    pub fn new(ip: IpAddr, port: u16, bus_port: u16) -> Cluster {
+        let myself = Cluster::generate_id();
+
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            myself.clone(),
+            ClusterNode {
+                id: myself.clone(),
+                ip: ip.to_string(),
+                port,
+                bus_port,
+                config_epoch: 0,
+                last_pong: None,
+                handshake: false,
+            },
+        );
+
+        Cluster {
+            shared: Some(Arc::new(Mutex::new(ClusterState {
+                myself,
+                nodes,
+                slots: vec![None; CLUSTER_SLOTS as usize],
+                migrating: HashMap::new(),
+                importing: HashMap::new(),
+                current_epoch: 0,
+                links: HashSet::new(),
+            }))),
+        }
+    }
+
+    /// Generate a random node ID, of 40 hex characters
+    fn generate_id() -> String {
+        let mut rng = rand::thread_rng();
+
+        (0..40)
+            .map(|_| std::char::from_digit(rng.gen_range(0..16), 16).unwrap())
+            .collect()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.shared.is_some()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, ClusterState> {
+        self.shared
+            .as_ref()
+            .expect("cluster mode is disabled")
+            .lock()
+            .unwrap()
+    }
+
+    /// Check whether a command for the `keys` can be served by this node.
+    ///
+    /// With `asking`, a slot which is being imported is served. `key_exists` is
+    /// used to decide whether the keys of a migrating slot were already moved.
+    ///
+    /// Will return the error to reply with, i.e. CROSSSLOT, MOVED, ASK, TRYAGAIN
+    /// or CLUSTERDOWN.
+    pub fn route(
+        &self,
+        keys: &[&str],
+        asking: bool,
+        key_exists: impl Fn(&str) -> bool,
+    ) -> Result<(), String> {
+        let slot = match keys.first() {
+            Some(key) => key_hash_slot(key),
+            None => return Ok(()),
+        };
+
+        if keys.iter().any(|key| key_hash_slot(key) != slot) {
+            return Err("CROSSSLOT Keys in request don't hash to the same slot".to_string());
+        }
+
+        let state = self.state();
+        let importing = asking && state.importing.contains_key(&slot);
+
+        match &state.slots[slot as usize] {
+            Some(owner) if *owner == state.myself => match state.migrating.get(&slot) {
+                Some(target) => {
+                    let missing = keys.iter().filter(|key| !key_exists(key)).count();
+
+                    if missing == 0 {
+                        Ok(())
+                    } else if missing == keys.len() {
+                        Err(format!(
+                            "ASK {} {}",
+                            slot,
+                            Cluster::node_address(&state, target)
+                        ))
+                    } else {
+                        Err("TRYAGAIN Multiple keys request during rehashing of slot".to_string())
+                    }
+                }
+                None => Ok(()),
+            },
+            _ if importing => Ok(()),
+            Some(owner) => Err(format!(
+                "MOVED {} {}",
+                slot,
+                Cluster::node_address(&state, owner)
+            )),
+            None => Err("CLUSTERDOWN Hash slot not served".to_string()),
+        }
+    }
+
+    fn node_address(state: &ClusterState, id: &str) -> String {
+        state
+            .nodes
+            .get(id)
+            .map(|node| node.address())
+            .unwrap_or_default()
+    }
+
+    /// The ID of this node
+    pub fn myself_id(&self) -> String {
+        self.state().myself.clone()
+    }
+
+    /// All the known nodes, ordered by ID
+    pub fn nodes(&self) -> Vec<ClusterNode> {
+        let state = self.state();
+
+        let mut nodes: Vec<ClusterNode> = state.nodes.values().cloned().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+
+        nodes
+    }
+
+    /// The contiguous ranges of slots, with the node which owns them
+    pub fn slot_ranges(&self) -> Vec<(u16, u16, ClusterNode)> {
+        let state = self.state();
+
+        Cluster::ranges(&state.slots, |_| true)
+            .into_iter()
+            .filter_map(|(start, end, owner)| {
+                let node = state.nodes.get(&owner)?;
+                Some((start, end, node.clone()))
+            })
+            .collect()
+    }
+
+    /// Group the owned `slots` into contiguous ranges, for the owners accepted by `filter`
+    fn ranges(slots: &[Option<String>], filter: impl Fn(&str) -> bool) -> Vec<(u16, u16, String)> {
+        let mut ranges: Vec<(u16, u16, String)> = Vec::new();
+
+        for (slot, owner) in slots.iter().enumerate() {
+            let owner = match owner {
+                Some(owner) if filter(owner) => owner,
+                _ => continue,
+            };
+
+            match ranges.last_mut() {
+                Some((_, end, last)) if *end as usize + 1 == slot && last == owner => {
+                    *end = slot as u16;
+                }
+                _ => ranges.push((slot as u16, slot as u16, owner.clone())),
+            }
+        }
+
+        ranges
+    }
+
+    /// Assign the `slots` to this node
+    pub fn add_slots(&self, slots: &[u16]) -> Result<(), String> {
+        let mut state = self.state();
+
+        for slot in slots {
+            if state.slots[*slot as usize].is_some() {
+                return Err(format!("ERR Slot {} is already busy", slot));
+            }
+        }
+
+        let myself = state.myself.clone();
+        for slot in slots {
+            state.slots[*slot as usize] = Some(myself.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Remove the owner of the `slots`
+    pub fn del_slots(&self, slots: &[u16]) -> Result<(), String> {
+        let mut state = self.state();
+
+        for slot in slots {
+            if state.slots[*slot as usize].is_none() {
+                return Err(format!("ERR Slot {} is already unassigned", slot));
+            }
+        }
+
+        for slot in slots {
+            state.slots[*slot as usize] = None;
+            state.migrating.remove(slot);
+            state.importing.remove(slot);
+        }
+
+        Ok(())
+    }
+
+    /// Change the state of the `slot`, to migrate it between nodes
+    pub fn set_slot(&self, slot: u16, slot_state: SlotState) -> Result<(), String> {
+        let mut state = self.state();
+        let myself = state.myself.clone();
+        let owned = state.slots[slot as usize].as_ref() == Some(&myself);
+
+        match slot_state {
+            SlotState::Migrating(id) => {
+                if !owned {
+                    return Err(format!("ERR I'm not the owner of hash slot {}", slot));
+                }
+                if !state.nodes.contains_key(&id) {
+                    return Err(format!("ERR I don't know about node {}", id));
+                }
+
+                state.migrating.insert(slot, id);
+            }
+            SlotState::Importing(id) => {
+                if owned {
+                    return Err(format!("ERR I'm already the owner of hash slot {}", slot));
+                }
+                if !state.nodes.contains_key(&id) {
+                    return Err(format!("ERR I don't know about node {}", id));
+                }
+
+                state.importing.insert(slot, id);
+            }
+            SlotState::Node(id) => {
+                if !state.nodes.contains_key(&id) {
+                    return Err(format!("ERR I don't know about node {}", id));
+                }
+
+                state.migrating.remove(&slot);
+
+                // Claim the imported slot with a new epoch, so that the
+                // other nodes prefer this claim over the previous owner's.
+                if id == myself && state.importing.remove(&slot).is_some() {
+                    state.current_epoch += 1;
+                    let epoch = state.current_epoch;
+                    if let Some(node) = state.nodes.get_mut(&myself) {
+                        node.config_epoch = epoch;
+                    }
+                }
+
+                state.slots[slot as usize] = Some(id);
+            }
+            SlotState::Stable => {
+                state.migrating.remove(&slot);
+                state.importing.remove(&slot);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add the node at `ip`:`port` to the cluster. Its ID is learned once it
+    /// replies to the MEET message, sent over the cluster bus.
+    pub fn meet(&self, ip: String, port: u16, bus_port: u16) {
+        let mut state = self.state();
+
+        let known = state
+            .nodes
+            .values()
+            .any(|node| node.ip == ip && node.bus_port == bus_port);
+
+        if !known {
+            let id = Cluster::generate_id();
+
+            state.nodes.insert(
+                id.clone(),
+                ClusterNode {
+                    id,
+                    ip,
+                    port,
+                    bus_port,
+                    config_epoch: 0,
+                    last_pong: None,
+                    handshake: true,
+                },
+            );
+        }
+    }
+
+    /// The fields of CLUSTER INFO
+    pub fn info(&self) -> Vec<(String, String)> {
+        let state = self.state();
+
+        let assigned = state.slots.iter().filter(|owner| owner.is_some()).count();
+        let size = state
+            .slots
+            .iter()
+            .flatten()
+            .collect::<HashSet<&String>>()
+            .len();
+
+        vec![
+            (
+                "cluster_state".to_string(),
+                if assigned == CLUSTER_SLOTS as usize {
+                    "ok".to_string()
+                } else {
+                    "fail".to_string()
+                },
+            ),
+            ("cluster_slots_assigned".to_string(), assigned.to_string()),
+            (
+                "cluster_known_nodes".to_string(),
+                state.nodes.len().to_string(),
+            ),
+            ("cluster_size".to_string(), size.to_string()),
+            (
+                "cluster_current_epoch".to_string(),
+                state.current_epoch.to_string(),
+            ),
+            (
+                "cluster_my_epoch".to_string(),
+                state
+                    .nodes
+                    .get(&state.myself)
+                    .map(|node| node.config_epoch)
+                    .unwrap_or(0)
+                    .to_string(),
+            ),
+        ]
+    }
+
+    /// The description of the nodes, in the format of CLUSTER NODES
+    pub fn nodes_description(&self) -> String {
+        let state = self.state();
+        let now = Utc::now();
+
+        let mut nodes: Vec<(&String, &ClusterNode)> = state.nodes.iter().collect();
+        nodes.sort_by_key(|(id, _)| *id);
+
+        let mut text = String::new();
+
+        for (id, node) in nodes {
+            let is_myself = *id == state.myself;
+
+            let mut flags: Vec<&str> = Vec::new();
+            if is_myself {
+                flags.push("myself");
+            }
+            flags.push("master");
+            if node.handshake {
+                flags.push("handshake");
+            }
+            if !is_myself && Cluster::is_failing(node, now) {
+                flags.push("fail?");
+            }
+
+            let link = if is_myself || state.links.contains(id) {
+                "connected"
+            } else {
+                "disconnected"
+            };
+
+            text.push_str(&format!(
+                "{} {}@{} {} - 0 {} {} {}",
+                id,
+                node.address(),
+                node.bus_port,
+                flags.join(","),
+                node.last_pong
+                    .map(|pong| pong.timestamp_millis())
+                    .unwrap_or(0),
+                node.config_epoch,
+                link
+            ));
+
+            for (start, end, _) in Cluster::ranges(&state.slots, |owner| owner == id) {
+                if start == end {
+                    text.push_str(&format!(" {}", start));
+                } else {
+                    text.push_str(&format!(" {}-{}", start, end));
+                }
+            }
+
+            if is_myself {
+                let mut migrating: Vec<(&u16, &String)> = state.migrating.iter().collect();
+                migrating.sort();
+                for (slot, target) in migrating {
+                    text.push_str(&format!(" [{}->-{}]", slot, target));
+                }
+
+                let mut importing: Vec<(&u16, &String)> = state.importing.iter().collect();
+                importing.sort();
+                for (slot, source) in importing {
+                    text.push_str(&format!(" [{}-<-{}]", slot, source));
+                }
+            }
+
+            text.push('\n');
+        }
+
+        text
+    }
+
+    /// Whether no message was received from the `node`, within the node timeout
+    pub fn is_failing(node: &ClusterNode, now: DateTime<Utc>) -> bool {
+        match node.last_pong {
+            Some(pong) => (now - pong).num_milliseconds() > CLUSTER_NODE_TIMEOUT_MS as i64,
+            None => !node.handshake,
+        }
+    }
+
+    /// Build a message about this node, and the nodes it knows
+    fn build_message(&self, kind: MessageKind) -> Result<Message, BusError> {
+        let state = self.state();
+        let myself = state.myself.clone();
+
+        let slots = Cluster::ranges(&state.slots, |owner| *owner == myself)
+            .into_iter()
+            .map(|(start, end, _)| (start, end))
+            .collect();
+
+        let gossip = state
+            .nodes
+            .values()
+            .filter(|node| node.id != myself && !node.handshake)
+            .map(|node| (node.id.clone(), node.ip.clone(), node.port, node.bus_port))
+            .collect();
+
+        let sender = match state.nodes.get(&myself) {
+            Some(node) => node.clone(),
+            None => return Err("This node isn't known".into()),
+        };
+
+        Ok(Message {
+            kind,
+            sender,
+            current_epoch: state.current_epoch,
+            slots,
+            gossip,
+        })
+    }
+
+    /// Update the state of the cluster, from a `message` received from the
+    /// node at `peer_ip`.
+    fn process_message(&self, message: Message, peer_ip: IpAddr) {
+        let mut state = self.state();
+        let sender = message.sender;
+
+        if sender.id == state.myself {
+            return;
+        }
+
+        state.current_epoch = state
+            .current_epoch
+            .max(message.current_epoch)
+            .max(sender.config_epoch);
+
+        // The announced IP may be unspecified, e.g. when listening on 0.0.0.0
+        let ip = match sender.ip.parse::<IpAddr>() {
+            Ok(ip) if !ip.is_unspecified() => sender.ip.clone(),
+            _ => peer_ip.to_string(),
+        };
+
+        state.nodes.insert(
+            sender.id.clone(),
+            ClusterNode {
+                ip,
+                last_pong: Some(Utc::now()),
+                handshake: false,
+                ..sender.clone()
+            },
+        );
+
+        // Update the owners of the slots, from the claims of the sender
+        let mut claimed = vec![false; CLUSTER_SLOTS as usize];
+        for (start, end) in message.slots {
+            for slot in start..=end.min(CLUSTER_SLOTS - 1) {
+                claimed[slot as usize] = true;
+            }
+        }
+
+        for (slot, claimed) in claimed.into_iter().enumerate() {
+            let owner = state.slots[slot].clone();
+
+            if claimed {
+                let wins = match &owner {
+                    None => true,
+                    Some(owner) if *owner == sender.id => true,
+                    Some(owner) => state
+                        .nodes
+                        .get(owner)
+                        .map(|node| node.config_epoch < sender.config_epoch)
+                        .unwrap_or(true),
+                };
+
+                if wins {
+                    state.slots[slot] = Some(sender.id.clone());
+                    state.migrating.remove(&(slot as u16));
+                }
+            } else if owner.as_ref() == Some(&sender.id) {
+                state.slots[slot] = None;
+            }
+        }
+
+        // Learn about the nodes known by the sender
+        for (id, ip, port, bus_port) in message.gossip {
+            if id == state.myself || state.nodes.contains_key(&id) {
+                continue;
+            }
+
+            // A node added with MEET, which is now known by its real ID
+            let handshakes: Vec<String> = state
+                .nodes
+                .values()
+                .filter(|node| node.handshake && node.ip == ip && node.bus_port == bus_port)
+                .map(|node| node.id.clone())
+                .collect();
+            for handshake_id in handshakes {
+                state.remove_node(&handshake_id);
+            }
+
+            state.nodes.insert(
+                id.clone(),
+                ClusterNode {
+                    id,
+                    ip,
+                    port,
+                    bus_port,
+                    config_epoch: 0,
+                    last_pong: None,
+                    handshake: false,
+                },
+            );
+        }
+    }
+
+    /// Replace the node added with MEET as `handshake_id`, by its real `id`
+    fn complete_handshake(&self, handshake_id: &str, id: &str) {
+        let mut state = self.state();
+
+        if let Some(node) = state.nodes.get(handshake_id) {
+            if node.handshake {
+                state.remove_node(handshake_id);
+            }
+        }
+
+        if state.links.remove(handshake_id) {
+            state.links.insert(id.to_string());
+        }
+    }
+
+    /// Start the tasks of the cluster bus, which accept the links of the
+    /// other nodes on the `listener`, and keep a link to each known node.
+    pub fn start_bus(&self, listener: TcpListener) {
+        tokio::spawn(run_bus_listener(self.clone(), listener));
+        tokio::spawn(run_bus_cron(self.clone()));
+    }
+
+    /// Start a link to each known node, which doesn't have one yet
+    fn connect_links(&self) {
+        let mut state = self.state();
+        let myself = state.myself.clone();
+
+        let pending: Vec<ClusterNode> = state
+            .nodes
+            .values()
+            .filter(|node| node.id != myself && !state.links.contains(&node.id))
+            .cloned()
+            .collect();
+
+        for node in pending {
+            state.links.insert(node.id.clone());
+            tokio::spawn(run_link(self.clone(), node));
+        }
+    }
+
+    /// Ping the `node` over the cluster bus, until the link is broken
+    async fn ping_node(&self, node: &mut ClusterNode) -> Result<(), BusError> {
+        let socket = TcpStream::connect((node.ip.as_str(), node.bus_port)).await?;
+        let peer_ip = socket.peer_addr()?.ip();
+        let mut cnxn = Connection::new(socket);
+
+        loop {
+            let kind = if node.handshake {
+                MessageKind::Meet
+            } else {
+                MessageKind::Ping
+            };
+
+            cnxn.write_frame(&self.build_message(kind)?.to_frame())
+                .await?;
+
+            let reply = match cnxn.read_frame().await {
+                Ok(Some(frame)) => Message::from_frame(frame)?,
+                Ok(None) => return Err("Cluster bus link closed".into()),
+                Err(err) => return Err(err.to_string().into()),
+            };
+
+            if node.handshake {
+                self.complete_handshake(&node.id, &reply.sender.id);
+                node.handshake = false;
+            }
+            node.id = reply.sender.id.clone();
+
+            self.process_message(reply, peer_ip);
+
+            sleep(Duration::from_millis(CLUSTER_PING_PERIOD_MS)).await;
+        }
+    }
+
+    /// Reply to the messages of a node, which linked to this node
+    async fn handle_inbound(&self, socket: TcpStream) -> Result<(), BusError> {
+        let peer_ip = socket.peer_addr()?.ip();
+        let local_ip = socket.local_addr()?.ip();
+        let mut cnxn = Connection::new(socket);
+
+        // Learn the IP of this node, if it is listening on an unspecified address
+        {
+            let mut state = self.state();
+            let myself = state.myself.clone();
+            let node = state.nodes.get_mut(&myself).unwrap();
+
+            if node
+                .ip
+                .parse::<IpAddr>()
+                .map(|ip| ip.is_unspecified())
+                .unwrap_or(true)
+            {
+                node.ip = local_ip.to_string();
+            }
+        }
+
+        loop {
+            let message = match cnxn.read_frame().await {
+                Ok(Some(frame)) => Message::from_frame(frame)?,
+                Ok(None) => return Ok(()),
+                Err(err) => return Err(err.to_string().into()),
+            };
+
+            self.process_message(message, peer_ip);
+
+            cnxn.write_frame(&self.build_message(MessageKind::Pong)?.to_frame())
+                .await?;
+        }
+    }
+}
+
+impl Message {
+    fn bulk_string(text: String) -> RESPType {
+        RESPType::BulkString(Some(BulkStringData {
+            prefix_length: text.len(),
+            text,
+        }))
+    }
+
+    /// Serialize the message as an array frame:
+    /// [kind, id, ip, port, bus port, config epoch, current epoch, slots, [gossip...]]
+    fn to_frame(&self) -> RESPType {
+        let kind = match self.kind {
+            MessageKind::Meet => "MEET",
+            MessageKind::Ping => "PING",
+            MessageKind::Pong => "PONG",
+        };
+
+        let slots: Vec<String> = self
+            .slots
+            .iter()
+            .map(|(start, end)| format!("{}-{}", start, end))
+            .collect();
+
+        let gossip = self
+            .gossip
+            .iter()
+            .map(|(id, ip, port, bus_port)| {
+                RESPType::Array(vec![
+                    Message::bulk_string(id.clone()),
+                    Message::bulk_string(ip.clone()),
+                    RESPType::Integer(*port as i64),
+                    RESPType::Integer(*bus_port as i64),
+                ])
+            })
+            .collect();
+
+        RESPType::Array(vec![
+            Message::bulk_string(kind.to_string()),
+            Message::bulk_string(self.sender.id.clone()),
+            Message::bulk_string(self.sender.ip.clone()),
+            RESPType::Integer(self.sender.port as i64),
+            RESPType::Integer(self.sender.bus_port as i64),
+            RESPType::Integer(self.sender.config_epoch as i64),
+            RESPType::Integer(self.current_epoch as i64),
+            Message::bulk_string(slots.join(",")),
+            RESPType::Array(gossip),
+        ])
+    }
+
+    fn from_frame(frame: RESPType) -> Result<Message, BusError> {
+        let malformed = || -> BusError { "Malformed cluster bus message".into() };
+
+        let parts = match frame {
+            RESPType::Array(parts) if parts.len() == 9 => parts,
+            _ => return Err(malformed()),
+        };
+        let mut parts = parts.into_iter();
+
+        let mut next_string = || -> Result<String, BusError> {
+            Command::extract_string(parts.next().unwrap()).map_err(|err| err.to_string().into())
+        };
+
+        let kind = match next_string()?.as_str() {
+            "MEET" => MessageKind::Meet,
+            "PING" => MessageKind::Ping,
+            "PONG" => MessageKind::Pong,
+            _ => return Err(malformed()),
+        };
+        let id = next_string()?;
+        let ip = next_string()?;
+
+        let mut integers = [0i64; 4];
+        for integer in integers.iter_mut() {
+            *integer = match parts.next().unwrap() {
+                RESPType::Integer(value) => value,
+                _ => return Err(malformed()),
+            };
+        }
+
+        let slots_text = Command::extract_string(parts.next().unwrap())?;
+        let mut slots: Vec<(u16, u16)> = Vec::new();
+        for range in slots_text.split(',').filter(|range| !range.is_empty()) {
+            let (start, end) = range.split_once('-').ok_or_else(malformed)?;
+            slots.push((start.parse()?, end.parse()?));
+        }
+
+        let mut gossip = Vec::new();
+        if let Some(RESPType::Array(entries)) = parts.next() {
+            for entry in entries {
+                match entry {
+                    RESPType::Array(fields) if fields.len() == 4 => {
+                        let mut fields = fields.into_iter();
+                        let id = Command::extract_string(fields.next().unwrap())?;
+                        let ip = Command::extract_string(fields.next().unwrap())?;
+
+                        match (fields.next().unwrap(), fields.next().unwrap()) {
+                            (RESPType::Integer(port), RESPType::Integer(bus_port)) => {
+                                gossip.push((id, ip, port as u16, bus_port as u16))
+                            }
+                            _ => return Err(malformed()),
+                        }
+                    }
+                    _ => return Err(malformed()),
+                }
+            }
+        }
+
+        Ok(Message {
+            kind,
+            sender: ClusterNode {
+                id,
+                ip,
+                port: integers[0] as u16,
+                bus_port: integers[1] as u16,
+                config_epoch: integers[2] as u64,
+                last_pong: None,
+                handshake: false,
+            },
+            current_epoch: integers[3] as u64,
+            slots,
+            gossip,
+        })
+    }
+}
+
+/// Accept the links of the other nodes, on the cluster bus
+async fn run_bus_listener(cluster: Cluster, listener: TcpListener) {
+    loop {
+        let socket = match listener.accept().await {
+            Ok((socket, _)) => socket,
+            Err(err) => {
+                println!("Cluster Bus Error | {:?}", err);
+                sleep(Duration::from_millis(CLUSTER_LINK_RETRY_MS)).await;
+                continue;
+            }
+        };
+
+        let cluster = cluster.clone();
+        tokio::spawn(async move {
+            let _ = cluster.handle_inbound(socket).await;
+        });
+    }
+}
+
+/// Periodically start the links to the known nodes, including the nodes
+/// learned through gossip, and the nodes whose link was broken.
+async fn run_bus_cron(cluster: Cluster) {
+    let mut ticks = interval(Duration::from_millis(CLUSTER_PING_PERIOD_MS));
+
+    loop {
+        ticks.tick().await;
+        cluster.connect_links();
+    }
+}
+
+/// Keep a link to the `node`. Once it is broken, wait before it can be retried.
+async fn run_link(cluster: Cluster, mut node: ClusterNode) {
+    let _ = cluster.ping_node(&mut node).await;
+
+    sleep(Duration::from_millis(CLUSTER_LINK_RETRY_MS)).await;
+
+    cluster.state().links.remove(&node.id);
+}
+
+impl Default for Cluster {
+    fn default() -> Self {
+        Cluster::disabled()
+    }
+}
@@ -0,0 +1,206 @@
+use crate::acl;
+use crate::cmd::ParseError;
+use crate::context::ClientContext;
+use crate::protocol_handler::BulkStringData;
+use crate::{ConnectionBase, RESPType};
+
+/// The number of entries ACL LOG returns, by default
+const DEFAULT_LOG_COUNT: usize = 10;
+
+/// The sub-commands of ACL
+#[derive(Debug, Clone, PartialEq)]
+pub enum AclSubcommand {
+    Setuser { name: String, rules: Vec<String> },
+    Getuser(String),
+    Deluser(Vec<String>),
+    List,
+    Users,
+    Whoami,
+    Cat(Option<String>),
+    Log(usize),
+    LogReset,
+}
+
+/// The ACL operation in Redis
+#[derive(Debug)]
+pub struct Acl {
+    subcommand: AclSubcommand,
+}
+
+impl Acl {
+    /// Create a new `Acl` command
+    pub fn new(subcommand: AclSubcommand) -> Acl {
+        Acl { subcommand }
+    }
+
+    /// Parsing the necessary arguments for the `Acl` command
+    ///
+    /// Syntax:
+    /// ACL SETUSER username [rule [rule ...]]
+    /// ACL GETUSER username
+    /// ACL DELUSER username [username ...]
+    /// ACL <LIST | USERS | WHOAMI>
+    /// ACL CAT [category]
+    /// ACL LOG [count | RESET]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Acl, ParseError> {
+        let subcommand = match cmd_strings.get(1) {
+            Some(arg) => arg.to_lowercase(),
+            None => {
+                return Err(ParseError::SyntaxError(
+                    "ERR wrong number of arguments for 'acl' command".to_string(),
+                ))
+            }
+        };
+
+        let args = &cmd_strings[2..];
+
+        let subcommand = match subcommand.as_str() {
+            "setuser" if !args.is_empty() => AclSubcommand::Setuser {
+                name: args[0].clone(),
+                rules: args[1..].to_vec(),
+            },
+            "getuser" if args.len() == 1 => AclSubcommand::Getuser(args[0].clone()),
+            "deluser" if !args.is_empty() => AclSubcommand::Deluser(args.to_vec()),
+            "list" if args.is_empty() => AclSubcommand::List,
+            "users" if args.is_empty() => AclSubcommand::Users,
+            "whoami" if args.is_empty() => AclSubcommand::Whoami,
+            "cat" if args.len() <= 1 => {
+                AclSubcommand::Cat(args.first().map(|arg| arg.to_lowercase()))
+            }
+            "log" if args.is_empty() => AclSubcommand::Log(DEFAULT_LOG_COUNT),
+            "log" if args.len() == 1 => {
+                if args[0].eq_ignore_ascii_case("reset") {
+                    AclSubcommand::LogReset
+                } else {
+                    match args[0].parse::<usize>() {
+                        Ok(count) => AclSubcommand::Log(count),
+                        Err(_) => {
+                            return Err(ParseError::SyntaxError(
+                                "ERR value is out of range, must be positive".to_string(),
+                            ))
+                        }
+                    }
+                }
+            }
+            "setuser" | "getuser" | "deluser" | "list" | "users" | "whoami" | "cat" | "log" => {
+                return Err(ParseError::SyntaxError(format!(
+                    "ERR wrong number of arguments for 'acl|{}' command",
+                    subcommand
+                )))
+            }
+            _ => {
+                return Err(ParseError::UnrecognizedCmd(format!(
+                    "ERR unknown subcommand '{}'. Try ACL HELP.",
+                    cmd_strings[1]
+                )))
+            }
+        };
+
+        Ok(Acl::new(subcommand))
+    }
+
+    fn bulk_string(text: String) -> RESPType {
+        RESPType::BulkString(Some(BulkStringData {
+            prefix_length: text.len(),
+            text,
+        }))
+    }
+
+    fn bulk_strings<T: ToString>(items: impl IntoIterator<Item = T>) -> RESPType {
+        RESPType::Array(
+            items
+                .into_iter()
+                .map(|item| Acl::bulk_string(item.to_string()))
+                .collect(),
+        )
+    }
+
+    /// Execute the `Acl` command
+    pub async fn execute(
+        self,
+        acl: &acl::Acl,
+        client: &ClientContext,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let response = match self.subcommand {
+            AclSubcommand::Setuser { name, rules } => match acl.set_user(&name, &rules) {
+                Ok(()) => RESPType::SimpleString("OK".to_string()),
+                Err(err) => RESPType::Error(err),
+            },
+            AclSubcommand::Getuser(name) => match acl.get_user(&name) {
+                Some(user) => RESPType::Array(vec![
+                    Acl::bulk_string("flags".to_string()),
+                    Acl::bulk_strings(user.flags()),
+                    Acl::bulk_string("passwords".to_string()),
+                    Acl::bulk_strings(user.passwords()),
+                    Acl::bulk_string("commands".to_string()),
+                    Acl::bulk_string(user.commands_description()),
+                    Acl::bulk_string("keys".to_string()),
+                    Acl::bulk_string(user.keys_description()),
+                    Acl::bulk_string("channels".to_string()),
+                    Acl::bulk_string(user.channels_description()),
+                    Acl::bulk_string("selectors".to_string()),
+                    RESPType::Array(vec![]),
+                ]),
+                None => RESPType::BulkString(None),
+            },
+            AclSubcommand::Deluser(names) => match acl.del_users(&names) {
+                Ok(deleted) => RESPType::Integer(deleted as i64),
+                Err(err) => RESPType::Error(err),
+            },
+            AclSubcommand::List => {
+                Acl::bulk_strings(acl.users().iter().map(|user| user.describe()))
+            }
+            AclSubcommand::Users => {
+                Acl::bulk_strings(acl.users().into_iter().map(|user| user.name))
+            }
+            AclSubcommand::Whoami => Acl::bulk_string(client.user.clone()),
+            AclSubcommand::Cat(None) => Acl::bulk_strings(acl::categories()),
+            AclSubcommand::Cat(Some(category)) => match acl::commands_in_category(&category) {
+                Some(commands) => Acl::bulk_strings(commands),
+                None => RESPType::Error(format!("ERR Unknown category '{}'", category)),
+            },
+            AclSubcommand::Log(count) => {
+                let now = chrono::Utc::now();
+
+                RESPType::Array(
+                    acl.log_entries(count)
+                        .into_iter()
+                        .map(|entry| {
+                            let age = (now - entry.created).num_milliseconds() as f64 / 1000.0;
+
+                            RESPType::Array(vec![
+                                Acl::bulk_string("count".to_string()),
+                                RESPType::Integer(entry.count as i64),
+                                Acl::bulk_string("reason".to_string()),
+                                Acl::bulk_string(entry.reason),
+                                Acl::bulk_string("context".to_string()),
+                                Acl::bulk_string("toplevel".to_string()),
+                                Acl::bulk_string("object".to_string()),
+                                Acl::bulk_string(entry.object),
+                                Acl::bulk_string("username".to_string()),
+                                Acl::bulk_string(entry.username),
+                                Acl::bulk_string("age-seconds".to_string()),
+                                Acl::bulk_string(format!("{:.3}", age)),
+                                Acl::bulk_string("client-info".to_string()),
+                                Acl::bulk_string(entry.client_info),
+                                Acl::bulk_string("timestamp-created".to_string()),
+                                RESPType::Integer(entry.created.timestamp_millis()),
+                                Acl::bulk_string("timestamp-last-updated".to_string()),
+                                RESPType::Integer(entry.updated.timestamp_millis()),
+                            ])
+                        })
+                        .collect(),
+                )
+            }
+            AclSubcommand::LogReset => {
+                acl.reset_log();
+                RESPType::SimpleString("OK".to_string())
+            }
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
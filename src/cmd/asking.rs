@@ -0,0 +1,50 @@
+use crate::cmd::ParseError;
+use crate::{Cluster, ConnectionBase, RESPType};
+
+/// The ASKING operation in Redis
+///
+/// Allows the next command of the connection to access a slot which is being
+/// imported by this node, after an ASK redirect. The flag itself is kept by the
+/// `ConnectionHandler`.
+#[derive(Debug, Default)]
+pub struct Asking {}
+
+impl Asking {
+    /// Create a new `Asking` command
+    pub fn new() -> Asking {
+        Asking {}
+    }
+
+    /// Parsing the necessary arguments for the `Asking` command
+    ///
+    /// Syntax:
+    /// ASKING
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Asking, ParseError> {
+        if cmd_strings.len() != 1 {
+            return Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'asking' command".to_string(),
+            ));
+        }
+
+        Ok(Asking::new())
+    }
+
+    /// Execute the `Asking` command
+    ///
+    /// Returns OK, or an error if cluster mode is disabled.
+    pub async fn execute(
+        self,
+        cluster: &Cluster,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let response = if cluster.is_enabled() {
+            RESPType::SimpleString("OK".to_string())
+        } else {
+            RESPType::Error("ERR This instance has cluster support disabled".to_string())
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use crate::cmd::ParseError;
+use crate::context::ClientContext;
+use crate::{Acl, ConnectionBase, RESPType, DEFAULT_USER};
+
+/// The AUTH operation in Redis
+#[derive(Debug)]
+pub struct Auth {
+    username: Option<String>,
+    password: String,
+}
+
+impl Auth {
+    /// Create a new `Auth` command
+    pub fn new(username: Option<String>, password: String) -> Auth {
+        Auth { username, password }
+    }
+
+    /// Parsing the necessary arguments for the `Auth` command
+    ///
+    /// Syntax:
+    /// AUTH [username] password
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Auth, ParseError> {
+        let mut args = cmd_strings.into_iter().skip(1);
+
+        match (args.next(), args.next(), args.next()) {
+            (Some(password), None, None) => Ok(Auth::new(None, password)),
+            (Some(username), Some(password), None) => Ok(Auth::new(Some(username), password)),
+            _ => Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'auth' command".to_string(),
+            )),
+        }
+    }
+
+    /// Execute the `Auth` command
+    ///
+    /// Authenticates the connection as the user, or as the default user if
+    /// only a password is given. Failures are added to the ACL LOG.
+    pub async fn execute(
+        self,
+        acl: &Acl,
+        client: &mut ClientContext,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Without a username, AUTH is only meaningful once requirepass is set
+        if self.username.is_none() && acl.default_user_needs_no_auth() {
+            cnxn.write_frame(&RESPType::Error(
+                "ERR AUTH <password> called without any password configured for the default user. Are you sure your configuration is correct?".to_string(),
+            ))
+            .await?;
+
+            return Ok(());
+        }
+
+        let username = self.username.unwrap_or_else(|| DEFAULT_USER.to_string());
+
+        let response = if acl.authenticate(&username, &self.password) {
+            client.user = username;
+            client.authenticated = true;
+
+            RESPType::SimpleString("OK".to_string())
+        } else {
+            acl.log("auth", "AUTH", &username, &client.info());
+
+            RESPType::Error(
+                "WRONGPASS invalid username-password pair or user is disabled.".to_string(),
+            )
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
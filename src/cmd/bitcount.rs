@@ -0,0 +1,103 @@
+use crate::cmd::ParseError;
+use crate::{ConnectionBase, RESPType, SharedStoreBase};
+
+/// Whether the indices of a bitmap range refer to bytes or bits
+#[derive(Debug, Clone, PartialEq)]
+pub enum BitUnit {
+    Byte,
+    Bit,
+}
+
+impl BitUnit {
+    /// Parse the BYTE | BIT argument of a range
+    pub fn parse(arg: &str) -> Result<BitUnit, ParseError> {
+        match arg.to_uppercase().as_str() {
+            "BYTE" => Ok(BitUnit::Byte),
+            "BIT" => Ok(BitUnit::Bit),
+            _ => Err(ParseError::SyntaxError("ERR syntax error".to_string())),
+        }
+    }
+
+    /// Parse an index of a range, which must be an integer
+    pub fn parse_index(arg: &str) -> Result<i64, ParseError> {
+        arg.parse::<i64>().map_err(|_| {
+            ParseError::SyntaxError("ERR value is not an integer or out of range".to_string())
+        })
+    }
+}
+
+/// The BITCOUNT operation in Redis
+#[derive(Debug)]
+pub struct Bitcount {
+    // The key of the string to count the set bits of
+    key: String,
+
+    // The optional inclusive start and end indices
+    range: Option<(i64, i64)>,
+
+    // Whether the range refers to bytes or bits
+    unit: BitUnit,
+}
+
+impl Bitcount {
+    /// Create a new `BITCOUNT` command
+    pub fn new(key: String, range: Option<(i64, i64)>, unit: BitUnit) -> Bitcount {
+        Bitcount { key, range, unit }
+    }
+
+    /// Parsing the necessary arguments for the `BITCOUNT` command
+    ///
+    /// Syntax:
+    /// BITCOUNT key [start end [BYTE | BIT]]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Bitcount, ParseError> {
+        match cmd_strings.len() {
+            2 => Ok(Bitcount::new(cmd_strings[1].clone(), None, BitUnit::Byte)),
+            4 | 5 => {
+                let start = BitUnit::parse_index(&cmd_strings[2])?;
+                let end = BitUnit::parse_index(&cmd_strings[3])?;
+
+                let unit = match cmd_strings.get(4) {
+                    Some(arg) => BitUnit::parse(arg)?,
+                    None => BitUnit::Byte,
+                };
+
+                Ok(Bitcount::new(
+                    cmd_strings[1].clone(),
+                    Some((start, end)),
+                    unit,
+                ))
+            }
+            3 => Err(ParseError::SyntaxError("ERR syntax error".to_string())),
+            _ => Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'bitcount' command".to_string(),
+            )),
+        }
+    }
+
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    /// Execute the `Bitcount` command
+    ///
+    /// Returns an integer reply, for the number of
+    /// bits set to 1 in the range
+    pub async fn execute(
+        self,
+        shared_store: &dyn SharedStoreBase,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Count the bits in the shared store
+        let result = shared_store.bitcount(self.key, self.range, self.unit);
+
+        let response = match result {
+            Ok(val) => RESPType::Integer(val),
+            Err(err) => RESPType::Error(err.to_string()),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+use crate::cmd::{ParseError, MAX_BIT_OFFSET};
+use crate::{ConnectionBase, RESPType, SharedStoreBase};
+
+/// The integer type of a bitfield, such as `i8` or `u16`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitfieldEncoding {
+    // Whether the integer is signed
+    pub signed: bool,
+
+    // The width of the integer in bits
+    pub bits: u8,
+}
+
+/// How integer overflows are handled by SET and INCRBY
+#[derive(Debug, Clone, PartialEq)]
+pub enum BitfieldOverflow {
+    Wrap,
+    Sat,
+    Fail,
+}
+
+/// A single sub-command of BITFIELD
+#[derive(Debug, Clone, PartialEq)]
+pub enum BitfieldOperation {
+    Get {
+        encoding: BitfieldEncoding,
+        offset: u64,
+    },
+    Set {
+        encoding: BitfieldEncoding,
+        offset: u64,
+        value: i64,
+    },
+    Incrby {
+        encoding: BitfieldEncoding,
+        offset: u64,
+        increment: i64,
+    },
+    Overflow(BitfieldOverflow),
+}
+
+/// The BITFIELD and BITFIELD_RO operations in Redis
+#[derive(Debug)]
+pub struct Bitfield {
+    // The key of the string to operate on
+    key: String,
+
+    // The sub-commands, in the order they were provided
+    operations: Vec<BitfieldOperation>,
+
+    // Whether it was run as BITFIELD_RO, which the ACL tells apart
+    read_only: bool,
+}
+
+impl Bitfield {
+    /// Create a new `BITFIELD` command
+    pub fn new(key: String, operations: Vec<BitfieldOperation>) -> Bitfield {
+        Bitfield {
+            key,
+            operations,
+            read_only: false,
+        }
+    }
+
+    /// Parsing the necessary arguments for the `BITFIELD` and `BITFIELD_RO` commands
+    ///
+    /// Syntax:
+    /// BITFIELD key [GET encoding offset | [OVERFLOW <WRAP | SAT | FAIL>]
+    ///   <SET encoding offset value | INCRBY encoding offset increment>
+    ///   [GET encoding offset | [OVERFLOW <WRAP | SAT | FAIL>]
+    ///   <SET encoding offset value | INCRBY encoding offset increment>
+    ///   ...]]
+    ///
+    /// BITFIELD_RO key [GET encoding offset [GET encoding offset ...]]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Bitfield, ParseError> {
+        let read_only = cmd_strings[0].to_lowercase() == "bitfield_ro";
+
+        if cmd_strings.len() < 2 {
+            return Err(ParseError::SyntaxError(format!(
+                "ERR wrong number of arguments for '{}' command",
+                cmd_strings[0].to_lowercase()
+            )));
+        }
+
+        let mut operations: Vec<BitfieldOperation> = Vec::new();
+        let mut iterator = cmd_strings.iter().skip(2);
+
+        while let Some(sub_cmd) = iterator.next() {
+            let sub_cmd = sub_cmd.to_uppercase();
+
+            if read_only && sub_cmd != "GET" {
+                return Err(ParseError::SyntaxError(
+                    "ERR BITFIELD_RO only supports the GET subcommand".to_string(),
+                ));
+            }
+
+            let operation = match sub_cmd.as_str() {
+                "GET" => {
+                    let (encoding, offset) = Bitfield::parse_field(&mut iterator)?;
+                    BitfieldOperation::Get { encoding, offset }
+                }
+                "SET" => {
+                    let (encoding, offset) = Bitfield::parse_field(&mut iterator)?;
+                    let value = Bitfield::parse_integer(iterator.next())?;
+                    BitfieldOperation::Set {
+                        encoding,
+                        offset,
+                        value,
+                    }
+                }
+                "INCRBY" => {
+                    let (encoding, offset) = Bitfield::parse_field(&mut iterator)?;
+                    let increment = Bitfield::parse_integer(iterator.next())?;
+                    BitfieldOperation::Incrby {
+                        encoding,
+                        offset,
+                        increment,
+                    }
+                }
+                "OVERFLOW" => {
+                    let behavior = match iterator.next().map(|arg| arg.to_uppercase()) {
+                        Some(arg) if arg == "WRAP" => BitfieldOverflow::Wrap,
+                        Some(arg) if arg == "SAT" => BitfieldOverflow::Sat,
+                        Some(arg) if arg == "FAIL" => BitfieldOverflow::Fail,
+                        _ => {
+                            return Err(ParseError::SyntaxError(
+                                "ERR Invalid OVERFLOW type specified".to_string(),
+                            ))
+                        }
+                    };
+                    BitfieldOperation::Overflow(behavior)
+                }
+                _ => return Err(ParseError::SyntaxError("ERR syntax error".to_string())),
+            };
+
+            operations.push(operation);
+        }
+
+        Ok(Bitfield {
+            read_only,
+            ..Bitfield::new(cmd_strings[1].clone(), operations)
+        })
+    }
+
+    /// The name of the command, as it was run
+    pub fn name(&self) -> &'static str {
+        match self.read_only {
+            true => "bitfield_ro",
+            false => "bitfield",
+        }
+    }
+
+    /// Whether all the sub-commands only read from the string
+    pub fn is_read_only(&self) -> bool {
+        self.operations.iter().all(|operation| {
+            matches!(
+                operation,
+                BitfieldOperation::Get { .. } | BitfieldOperation::Overflow(_)
+            )
+        })
+    }
+
+    /// Helper method to parse the `encoding` and `offset` arguments of a sub-command
+    ///
+    /// The offset may be prefixed with `#`, in which case it is multiplied by the encoding width.
+    fn parse_field<'a>(
+        iterator: &mut impl Iterator<Item = &'a String>,
+    ) -> Result<(BitfieldEncoding, u64), ParseError> {
+        let encoding_err = || {
+            ParseError::SyntaxError(
+                "ERR Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.".to_string(),
+            )
+        };
+        let offset_err = || {
+            ParseError::SyntaxError("ERR bit offset is not an integer or out of range".to_string())
+        };
+
+        let encoding_arg = iterator
+            .next()
+            .ok_or_else(|| ParseError::SyntaxError("ERR syntax error".to_string()))?;
+        let offset_arg = iterator
+            .next()
+            .ok_or_else(|| ParseError::SyntaxError("ERR syntax error".to_string()))?;
+
+        let signed = match encoding_arg.chars().next() {
+            Some('i') | Some('I') => true,
+            Some('u') | Some('U') => false,
+            _ => return Err(encoding_err()),
+        };
+
+        let bits = encoding_arg[1..]
+            .parse::<u8>()
+            .map_err(|_| encoding_err())?;
+
+        if bits == 0 || (signed && bits > 64) || (!signed && bits > 63) {
+            return Err(encoding_err());
+        }
+
+        let offset = match offset_arg.strip_prefix('#') {
+            Some(index) => index
+                .parse::<u64>()
+                .ok()
+                .and_then(|index| index.checked_mul(bits as u64))
+                .ok_or_else(offset_err)?,
+            None => offset_arg.parse::<u64>().map_err(|_| offset_err())?,
+        };
+
+        // The offset is checked first, so that the last bit can't overflow
+        let last_bit = match offset <= MAX_BIT_OFFSET {
+            true => offset.checked_add(bits as u64 - 1),
+            false => None,
+        };
+        if last_bit.is_none_or(|last_bit| last_bit > MAX_BIT_OFFSET) {
+            return Err(offset_err());
+        }
+
+        Ok((BitfieldEncoding { signed, bits }, offset))
+    }
+
+    /// Helper method to parse the value or increment argument of a sub-command
+    fn parse_integer(arg: Option<&String>) -> Result<i64, ParseError> {
+        match arg.map(|arg| arg.parse::<i64>()) {
+            Some(Ok(val)) => Ok(val),
+            Some(Err(_)) => Err(ParseError::SyntaxError(
+                "ERR value is not an integer or out of range".to_string(),
+            )),
+            None => Err(ParseError::SyntaxError("ERR syntax error".to_string())),
+        }
+    }
+
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    /// Execute the `Bitfield` command
+    ///
+    /// Returns an array reply, with an integer for each GET, SET
+    /// and INCRBY sub-command, or nil if it failed due to an overflow
+    pub async fn execute(
+        self,
+        shared_store: &dyn SharedStoreBase,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Perform the operations in the shared store
+        let result = shared_store.bitfield(self.key, self.operations);
+
+        let response = match result {
+            Ok(values) => RESPType::Array(
+                values
+                    .into_iter()
+                    .map(|val| match val {
+                        Some(num) => RESPType::Integer(num),
+                        None => RESPType::BulkString(None),
+                    })
+                    .collect(),
+            ),
+            Err(err) => RESPType::Error(err.to_string()),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use crate::cmd::ParseError;
+use crate::{ConnectionBase, RESPType, SharedStoreBase};
+
+/// The bitwise operations supported by BITOP
+#[derive(Debug, Clone, PartialEq)]
+pub enum BitOperation {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+/// The BITOP operation in Redis
+#[derive(Debug)]
+pub struct Bitop {
+    // The bitwise operation to perform
+    operation: BitOperation,
+
+    // The key to store the result at
+    destkey: String,
+
+    // The keys of the source strings
+    keys: Vec<String>,
+}
+
+impl Bitop {
+    /// Create a new `BITOP` command
+    pub fn new(operation: BitOperation, destkey: String, keys: Vec<String>) -> Bitop {
+        Bitop {
+            operation,
+            destkey,
+            keys,
+        }
+    }
+
+    /// Parsing the necessary arguments for the `BITOP` command
+    ///
+    /// Syntax:
+    /// BITOP <AND | OR | XOR | NOT> destkey key [key ...]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Bitop, ParseError> {
+        if cmd_strings.len() < 4 {
+            return Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'bitop' command".to_string(),
+            ));
+        }
+
+        let operation = match cmd_strings[1].to_uppercase().as_str() {
+            "AND" => BitOperation::And,
+            "OR" => BitOperation::Or,
+            "XOR" => BitOperation::Xor,
+            "NOT" => BitOperation::Not,
+            _ => return Err(ParseError::SyntaxError("ERR syntax error".to_string())),
+        };
+
+        let keys = cmd_strings[3..].to_vec();
+
+        if operation == BitOperation::Not && keys.len() != 1 {
+            return Err(ParseError::SyntaxError(
+                "ERR BITOP NOT must be called with a single source key.".to_string(),
+            ));
+        }
+
+        Ok(Bitop::new(operation, cmd_strings[2].clone(), keys))
+    }
+
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys = vec![self.destkey.as_str()];
+        keys.extend(self.keys.iter().map(String::as_str));
+
+        keys
+    }
+
+    /// Execute the `Bitop` command
+    ///
+    /// Returns an integer reply, for the length of
+    /// the string stored at the destination key
+    pub async fn execute(
+        self,
+        shared_store: &dyn SharedStoreBase,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Perform the operation in the shared store
+        let result = shared_store.bitop(self.operation, self.destkey, self.keys);
+
+        let response = match result {
+            Ok(val) => RESPType::Integer(val),
+            Err(err) => RESPType::Error(err.to_string()),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use crate::cmd::{BitUnit, ParseError};
+use crate::{ConnectionBase, RESPType, SharedStoreBase};
+
+/// The BITPOS operation in Redis
+#[derive(Debug)]
+pub struct Bitpos {
+    // The key of the string to search
+    key: String,
+
+    // The bit value to search for, either 0 or 1
+    bit: u8,
+
+    // The optional inclusive start index
+    start: Option<i64>,
+
+    // The optional inclusive end index
+    end: Option<i64>,
+
+    // Whether the indices refer to bytes or bits
+    unit: BitUnit,
+}
+
+impl Bitpos {
+    /// Create a new `BITPOS` command
+    pub fn new(
+        key: String,
+        bit: u8,
+        start: Option<i64>,
+        end: Option<i64>,
+        unit: BitUnit,
+    ) -> Bitpos {
+        Bitpos {
+            key,
+            bit,
+            start,
+            end,
+            unit,
+        }
+    }
+
+    /// Parsing the necessary arguments for the `BITPOS` command
+    ///
+    /// Syntax:
+    /// BITPOS key bit [start [end [BYTE | BIT]]]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Bitpos, ParseError> {
+        if cmd_strings.len() < 3 || cmd_strings.len() > 6 {
+            return Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'bitpos' command".to_string(),
+            ));
+        }
+
+        let bit: u8 = match cmd_strings[2].as_str() {
+            "0" => 0,
+            "1" => 1,
+            _ => {
+                return Err(ParseError::SyntaxError(
+                    "ERR The bit argument must be 1 or 0.".to_string(),
+                ))
+            }
+        };
+
+        let start = match cmd_strings.get(3) {
+            Some(arg) => Some(BitUnit::parse_index(arg)?),
+            None => None,
+        };
+
+        let end = match cmd_strings.get(4) {
+            Some(arg) => Some(BitUnit::parse_index(arg)?),
+            None => None,
+        };
+
+        let unit = match cmd_strings.get(5) {
+            Some(arg) => BitUnit::parse(arg)?,
+            None => BitUnit::Byte,
+        };
+
+        Ok(Bitpos::new(cmd_strings[1].clone(), bit, start, end, unit))
+    }
+
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    /// Execute the `Bitpos` command
+    ///
+    /// Returns an integer reply, for the position of the
+    /// first bit matching the searched bit, or -1
+    pub async fn execute(
+        self,
+        shared_store: &dyn SharedStoreBase,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Search for the bit in the shared store
+        let result = shared_store.bitpos(self.key, self.bit, self.start, self.end, self.unit);
+
+        let response = match result {
+            Ok(val) => RESPType::Integer(val),
+            Err(err) => RESPType::Error(err.to_string()),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
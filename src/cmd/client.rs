@@ -0,0 +1,330 @@
+use crate::clients::{ClientType, ConnectedClient};
+use crate::cmd::ParseError;
+use crate::protocol_handler::BulkStringData;
+use crate::{ClientContext, ConnectionBase, PauseMode, RESPType, ServerContext};
+use tokio::time::Duration;
+
+/// The sub-commands of CLIENT
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientSubcommand {
+    // Hold the commands of the `mode`, for `timeout` milliseconds
+    Pause { timeout: u64, mode: PauseMode },
+    Unpause,
+    Id,
+    Setname(String),
+    Getname,
+    List(ClientFilter),
+    Info,
+    // The legacy form of KILL, with the address of a single client
+    KillAddr(String),
+    Kill { filter: ClientFilter, skip_me: bool },
+    NoEvict(bool),
+}
+
+/// The filters of CLIENT LIST and CLIENT KILL. A client matches when it
+/// matches all of the filters which are set.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientFilter {
+    pub ids: Vec<u64>,
+    pub addr: Option<String>,
+    pub laddr: Option<String>,
+    pub user: Option<String>,
+    pub client_type: Option<ClientType>,
+}
+
+impl ClientFilter {
+    pub fn matches(&self, client: &ConnectedClient) -> bool {
+        (self.ids.is_empty() || self.ids.contains(&client.id))
+            && self.addr.as_ref().is_none_or(|addr| *addr == client.addr)
+            && self
+                .laddr
+                .as_ref()
+                .is_none_or(|laddr| *laddr == client.laddr)
+            && self.user.as_ref().is_none_or(|user| *user == client.user())
+            && self
+                .client_type
+                .is_none_or(|client_type| client_type == client.client_type())
+    }
+}
+
+/// The CLIENT operation in Redis
+#[derive(Debug)]
+pub struct Client {
+    subcommand: ClientSubcommand,
+}
+
+impl Client {
+    /// Create a new `Client` command
+    pub fn new(subcommand: ClientSubcommand) -> Client {
+        Client { subcommand }
+    }
+
+    /// Parsing the necessary arguments for the `Client` command
+    ///
+    /// Syntax:
+    /// CLIENT PAUSE timeout [WRITE | ALL]
+    /// CLIENT UNPAUSE
+    /// CLIENT ID
+    /// CLIENT SETNAME connection-name
+    /// CLIENT GETNAME
+    /// CLIENT LIST [TYPE normal | master | replica | pubsub] [ID client-id [client-id ...]]
+    /// CLIENT INFO
+    /// CLIENT KILL ip:port
+    /// CLIENT KILL [ID client-id] [ADDR ip:port] [LADDR ip:port] [USER username]
+    ///     [TYPE normal | master | replica | pubsub] [SKIPME yes | no] ...
+    /// CLIENT NO-EVICT ON | OFF
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Client, ParseError> {
+        let subcommand = match cmd_strings.get(1) {
+            Some(arg) => arg.to_lowercase(),
+            None => {
+                return Err(ParseError::SyntaxError(
+                    "ERR wrong number of arguments for 'client' command".to_string(),
+                ))
+            }
+        };
+
+        let wrong_args = || {
+            ParseError::SyntaxError(format!(
+                "ERR wrong number of arguments for 'client|{}' command",
+                subcommand
+            ))
+        };
+
+        match subcommand.as_str() {
+            "pause" => {
+                if cmd_strings.len() < 3 || cmd_strings.len() > 4 {
+                    return Err(wrong_args());
+                }
+
+                let timeout = match cmd_strings[2].parse::<u64>() {
+                    Ok(timeout) => timeout,
+                    Err(_) => {
+                        return Err(ParseError::SyntaxError(
+                            "ERR timeout is not an integer or out of range".to_string(),
+                        ))
+                    }
+                };
+
+                // All commands are paused by default, as in Redis
+                let mode = match cmd_strings.get(3) {
+                    Some(mode) => match PauseMode::parse(mode) {
+                        Some(mode) => mode,
+                        None => {
+                            return Err(ParseError::SyntaxError("ERR syntax error".to_string()))
+                        }
+                    },
+                    None => PauseMode::All,
+                };
+
+                Ok(Client::new(ClientSubcommand::Pause { timeout, mode }))
+            }
+            "unpause" if cmd_strings.len() == 2 => Ok(Client::new(ClientSubcommand::Unpause)),
+            "id" if cmd_strings.len() == 2 => Ok(Client::new(ClientSubcommand::Id)),
+            "getname" if cmd_strings.len() == 2 => Ok(Client::new(ClientSubcommand::Getname)),
+            "info" if cmd_strings.len() == 2 => Ok(Client::new(ClientSubcommand::Info)),
+            "setname" if cmd_strings.len() == 3 => {
+                let name = &cmd_strings[2];
+
+                // Names are shown in CLIENT LIST, where they must be a single word
+                if name.chars().any(|c| !c.is_ascii_graphic()) {
+                    return Err(ParseError::SyntaxError(
+                        "ERR Client names cannot contain spaces, newlines or special characters."
+                            .to_string(),
+                    ));
+                }
+
+                Ok(Client::new(ClientSubcommand::Setname(name.to_string())))
+            }
+            "list" => {
+                let filter = Client::parse_filter(&cmd_strings[2..], false)?.0;
+                Ok(Client::new(ClientSubcommand::List(filter)))
+            }
+            "kill" if cmd_strings.len() == 3 => Ok(Client::new(ClientSubcommand::KillAddr(
+                cmd_strings[2].to_string(),
+            ))),
+            "kill" if cmd_strings.len() > 3 => {
+                let (filter, skip_me) = Client::parse_filter(&cmd_strings[2..], true)?;
+                Ok(Client::new(ClientSubcommand::Kill { filter, skip_me }))
+            }
+            "no-evict" if cmd_strings.len() == 3 => match cmd_strings[2].to_lowercase().as_str() {
+                "on" => Ok(Client::new(ClientSubcommand::NoEvict(true))),
+                "off" => Ok(Client::new(ClientSubcommand::NoEvict(false))),
+                _ => Err(ParseError::SyntaxError("ERR syntax error".to_string())),
+            },
+            "unpause" | "id" | "getname" | "info" | "setname" | "kill" | "no-evict" => {
+                Err(wrong_args())
+            }
+            _ => Err(ParseError::UnrecognizedCmd(format!(
+                "ERR unknown subcommand '{}'. Try CLIENT HELP.",
+                cmd_strings[1]
+            ))),
+        }
+    }
+
+    /// Parse the filters of CLIENT LIST, or of CLIENT KILL if `kill`, which
+    /// also accepts more of them, and SKIPME
+    fn parse_filter(args: &[String], kill: bool) -> Result<(ClientFilter, bool), ParseError> {
+        let syntax_error = || ParseError::SyntaxError("ERR syntax error".to_string());
+
+        let mut filter = ClientFilter::default();
+        let mut skip_me = true;
+        let mut i = 0;
+
+        while i < args.len() {
+            let option = args[i].to_lowercase();
+
+            // LIST takes any number of ids after ID, KILL a single one
+            if option == "id" && !kill {
+                if i + 1 >= args.len() {
+                    return Err(syntax_error());
+                }
+
+                for id in &args[i + 1..] {
+                    filter.ids.push(Client::parse_id(id)?);
+                }
+                break;
+            }
+
+            let value = args.get(i + 1).ok_or_else(syntax_error)?;
+            match option.as_str() {
+                "type" => match ClientType::parse(value) {
+                    Some(client_type) => filter.client_type = Some(client_type),
+                    None => {
+                        return Err(ParseError::SyntaxError(format!(
+                            "ERR Unknown client type '{}'",
+                            value
+                        )))
+                    }
+                },
+                "id" => filter.ids.push(Client::parse_id(value)?),
+                "addr" if kill => filter.addr = Some(value.to_string()),
+                "laddr" if kill => filter.laddr = Some(value.to_string()),
+                "user" if kill => filter.user = Some(value.to_string()),
+                "skipme" if kill => match value.to_lowercase().as_str() {
+                    "yes" => skip_me = true,
+                    "no" => skip_me = false,
+                    _ => return Err(syntax_error()),
+                },
+                _ => return Err(syntax_error()),
+            }
+
+            i += 2;
+        }
+
+        Ok((filter, skip_me))
+    }
+
+    fn parse_id(id: &str) -> Result<u64, ParseError> {
+        match id.parse::<u64>() {
+            Ok(id) if id > 0 => Ok(id),
+            _ => Err(ParseError::SyntaxError(
+                "ERR client-id should be greater than 0".to_string(),
+            )),
+        }
+    }
+
+    /// Execute the `Client` command
+    ///
+    /// PAUSE holds the commands of other clients until the timeout ends, while
+    /// UNPAUSE resumes them immediately. Both return OK.
+    ///
+    /// ID, GETNAME, LIST and INFO describe the connected clients, from the
+    /// registry of the server. KILL closes the connections of the matching
+    /// clients, and returns how many, or OK with the legacy form.
+    pub async fn execute(
+        self,
+        server: &ServerContext,
+        client: &mut ClientContext,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let ok = RESPType::SimpleString("OK".to_string());
+        let registration = server.clients.get(client.id);
+
+        let response = match self.subcommand {
+            ClientSubcommand::Pause { timeout, mode } => {
+                server
+                    .shared_store
+                    .client_pause()
+                    .pause(mode, Duration::from_millis(timeout));
+                ok
+            }
+            ClientSubcommand::Unpause => {
+                server.shared_store.client_pause().unpause();
+                ok
+            }
+            ClientSubcommand::Id => RESPType::Integer(client.id as i64),
+            ClientSubcommand::Setname(name) => {
+                client.name = name;
+                ok
+            }
+            ClientSubcommand::Getname => match client.name.is_empty() {
+                true => RESPType::BulkString(None),
+                false => bulk_string(&client.name),
+            },
+            ClientSubcommand::List(filter) => {
+                let list: String = server
+                    .clients
+                    .list()
+                    .iter()
+                    .filter(|other| filter.matches(other))
+                    .map(|other| other.describe() + "\n")
+                    .collect();
+                bulk_string(&list)
+            }
+            ClientSubcommand::Info => match registration {
+                Some(registration) => bulk_string(&(registration.describe() + "\n")),
+                None => RESPType::BulkString(None),
+            },
+            ClientSubcommand::KillAddr(addr) => {
+                match server
+                    .clients
+                    .list()
+                    .iter()
+                    .find(|other| other.addr == addr)
+                {
+                    Some(other) => {
+                        other.kill();
+                        ok
+                    }
+                    None => RESPType::Error("ERR No such client".to_string()),
+                }
+            }
+            ClientSubcommand::Kill { filter, skip_me } => {
+                if let Some(user) = filter.user.as_ref() {
+                    if server.acl.get_user(user).is_none() {
+                        let err = format!("ERR No such user '{}'", user);
+                        cnxn.write_frame(&RESPType::Error(err)).await?;
+                        return Ok(());
+                    }
+                }
+
+                let mut killed = 0;
+                for other in server.clients.list() {
+                    if filter.matches(&other) && !(skip_me && other.id == client.id) {
+                        other.kill();
+                        killed += 1;
+                    }
+                }
+
+                RESPType::Integer(killed)
+            }
+            ClientSubcommand::NoEvict(no_evict) => {
+                if let Some(registration) = registration {
+                    registration.set_no_evict(no_evict);
+                }
+                ok
+            }
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+fn bulk_string(text: &str) -> RESPType {
+    RESPType::BulkString(Some(BulkStringData {
+        prefix_length: text.len(),
+        text: text.to_string(),
+    }))
+}
@@ -0,0 +1,344 @@
+use crate::cluster::{self, SlotState};
+use crate::cmd::ParseError;
+use crate::protocol_handler::BulkStringData;
+use crate::{ConnectionBase, RESPType, SharedStore, CLUSTER_SLOTS};
+
+/// The sub-commands of CLUSTER
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClusterSubcommand {
+    Keyslot(String),
+    Countkeysinslot(u16),
+    Getkeysinslot {
+        slot: u16,
+        count: usize,
+    },
+    Slots,
+    Shards,
+    Nodes,
+    Info,
+    Myid,
+    Meet {
+        ip: String,
+        port: u16,
+        bus_port: u16,
+    },
+    Addslots(Vec<u16>),
+    Delslots(Vec<u16>),
+    Setslot {
+        slot: u16,
+        state: SlotState,
+    },
+}
+
+/// The CLUSTER operation in Redis
+#[derive(Debug)]
+pub struct Cluster {
+    subcommand: ClusterSubcommand,
+}
+
+impl Cluster {
+    /// Create a new `Cluster` command
+    pub fn new(subcommand: ClusterSubcommand) -> Cluster {
+        Cluster { subcommand }
+    }
+
+    /// Parse a hash slot, which must be below `CLUSTER_SLOTS`
+    fn parse_slot(arg: &str) -> Result<u16, ParseError> {
+        match arg.parse::<u16>() {
+            Ok(slot) if slot < CLUSTER_SLOTS => Ok(slot),
+            _ => Err(ParseError::SyntaxError(format!(
+                "ERR Invalid or out of range slot '{}'",
+                arg
+            ))),
+        }
+    }
+
+    /// Parsing the necessary arguments for the `Cluster` command
+    ///
+    /// Syntax:
+    /// CLUSTER KEYSLOT key
+    /// CLUSTER COUNTKEYSINSLOT slot
+    /// CLUSTER GETKEYSINSLOT slot count
+    /// CLUSTER <SLOTS | SHARDS | NODES | INFO | MYID>
+    /// CLUSTER MEET ip port [bus-port]
+    /// CLUSTER ADDSLOTS slot [slot ...]
+    /// CLUSTER ADDSLOTSRANGE start-slot end-slot [start-slot end-slot ...]
+    /// CLUSTER DELSLOTS slot [slot ...]
+    /// CLUSTER SETSLOT slot <IMPORTING node-id | MIGRATING node-id | NODE node-id | STABLE>
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Cluster, ParseError> {
+        let subcommand = match cmd_strings.get(1) {
+            Some(arg) => arg.to_lowercase(),
+            None => {
+                return Err(ParseError::SyntaxError(
+                    "ERR wrong number of arguments for 'cluster' command".to_string(),
+                ))
+            }
+        };
+
+        let args = &cmd_strings[2..];
+
+        let wrong_args = || {
+            ParseError::SyntaxError(format!(
+                "ERR wrong number of arguments for 'cluster|{}' command",
+                subcommand
+            ))
+        };
+
+        let subcommand = match subcommand.as_str() {
+            "keyslot" if args.len() == 1 => ClusterSubcommand::Keyslot(args[0].clone()),
+            "countkeysinslot" if args.len() == 1 => {
+                ClusterSubcommand::Countkeysinslot(Cluster::parse_slot(&args[0])?)
+            }
+            "getkeysinslot" if args.len() == 2 => {
+                let slot = Cluster::parse_slot(&args[0])?;
+                let count = match args[1].parse::<usize>() {
+                    Ok(count) => count,
+                    Err(_) => {
+                        return Err(ParseError::SyntaxError(
+                            "ERR Invalid number of keys".to_string(),
+                        ))
+                    }
+                };
+
+                ClusterSubcommand::Getkeysinslot { slot, count }
+            }
+            "slots" if args.is_empty() => ClusterSubcommand::Slots,
+            "shards" if args.is_empty() => ClusterSubcommand::Shards,
+            "nodes" if args.is_empty() => ClusterSubcommand::Nodes,
+            "info" if args.is_empty() => ClusterSubcommand::Info,
+            "myid" if args.is_empty() => ClusterSubcommand::Myid,
+            "meet" if args.len() == 2 || args.len() == 3 => {
+                let invalid_port = || {
+                    ParseError::SyntaxError(format!(
+                        "ERR Invalid node address specified: {}:{}",
+                        args[0], args[1]
+                    ))
+                };
+
+                let port = args[1].parse::<u16>().map_err(|_| invalid_port())?;
+
+                // The cluster bus listens on the client port + 10000, by default
+                let bus_port = match args.get(2) {
+                    Some(bus_port) => bus_port.parse::<u16>().map_err(|_| invalid_port())?,
+                    None => port.checked_add(10000).ok_or_else(invalid_port)?,
+                };
+
+                ClusterSubcommand::Meet {
+                    ip: args[0].clone(),
+                    port,
+                    bus_port,
+                }
+            }
+            "addslots" | "delslots" if !args.is_empty() => {
+                let slots = args
+                    .iter()
+                    .map(|arg| Cluster::parse_slot(arg))
+                    .collect::<Result<Vec<u16>, ParseError>>()?;
+
+                if subcommand == "addslots" {
+                    ClusterSubcommand::Addslots(slots)
+                } else {
+                    ClusterSubcommand::Delslots(slots)
+                }
+            }
+            "addslotsrange" if !args.is_empty() && args.chunks_exact(2).remainder().is_empty() => {
+                let mut slots = Vec::new();
+
+                for range in args.chunks_exact(2) {
+                    let start = Cluster::parse_slot(&range[0])?;
+                    let end = Cluster::parse_slot(&range[1])?;
+
+                    if start > end {
+                        return Err(ParseError::SyntaxError(format!(
+                            "ERR start slot number {} is greater than end slot number {}",
+                            start, end
+                        )));
+                    }
+
+                    slots.extend(start..=end);
+                }
+
+                ClusterSubcommand::Addslots(slots)
+            }
+            "setslot" if args.len() == 2 || args.len() == 3 => {
+                let slot = Cluster::parse_slot(&args[0])?;
+
+                let state = match (args[1].to_lowercase().as_str(), args.get(2)) {
+                    ("importing", Some(id)) => SlotState::Importing(id.clone()),
+                    ("migrating", Some(id)) => SlotState::Migrating(id.clone()),
+                    ("node", Some(id)) => SlotState::Node(id.clone()),
+                    ("stable", None) => SlotState::Stable,
+                    _ => {
+                        return Err(ParseError::SyntaxError(
+                            "ERR Invalid CLUSTER SETSLOT action or number of arguments. Try CLUSTER HELP".to_string(),
+                        ))
+                    }
+                };
+
+                ClusterSubcommand::Setslot { slot, state }
+            }
+            "keyslot" | "countkeysinslot" | "getkeysinslot" | "slots" | "shards" | "nodes"
+            | "info" | "myid" | "meet" | "addslots" | "delslots" | "addslotsrange" | "setslot" => {
+                return Err(wrong_args())
+            }
+            _ => {
+                return Err(ParseError::UnrecognizedCmd(format!(
+                    "ERR unknown subcommand '{}'. Try CLUSTER HELP.",
+                    cmd_strings[1]
+                )))
+            }
+        };
+
+        Ok(Cluster::new(subcommand))
+    }
+
+    fn bulk_string(text: String) -> RESPType {
+        RESPType::BulkString(Some(BulkStringData {
+            prefix_length: text.len(),
+            text,
+        }))
+    }
+
+    /// Execute the `Cluster` command
+    ///
+    /// Returns an error if cluster mode is disabled, except for KEYSLOT which
+    /// only hashes the key.
+    pub async fn execute(
+        self,
+        shared_store: &SharedStore,
+        cluster: &cluster::Cluster,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let ok = || RESPType::SimpleString("OK".to_string());
+
+        if let ClusterSubcommand::Keyslot(key) = &self.subcommand {
+            let slot = cluster::key_hash_slot(key);
+            cnxn.write_frame(&RESPType::Integer(slot as i64)).await?;
+
+            return Ok(());
+        }
+
+        if !cluster.is_enabled() {
+            cnxn.write_frame(&RESPType::Error(
+                "ERR This instance has cluster support disabled".to_string(),
+            ))
+            .await?;
+
+            return Ok(());
+        }
+
+        let response = match self.subcommand {
+            ClusterSubcommand::Keyslot(_) => unreachable!(),
+            ClusterSubcommand::Countkeysinslot(slot) => {
+                RESPType::Integer(shared_store.count_keys_in_slot(slot) as i64)
+            }
+            ClusterSubcommand::Getkeysinslot { slot, count } => RESPType::Array(
+                shared_store
+                    .keys_in_slot(slot, count)
+                    .into_iter()
+                    .map(Cluster::bulk_string)
+                    .collect(),
+            ),
+            ClusterSubcommand::Slots => RESPType::Array(
+                cluster
+                    .slot_ranges()
+                    .into_iter()
+                    .map(|(start, end, node)| {
+                        RESPType::Array(vec![
+                            RESPType::Integer(start as i64),
+                            RESPType::Integer(end as i64),
+                            RESPType::Array(vec![
+                                Cluster::bulk_string(node.ip),
+                                RESPType::Integer(node.port as i64),
+                                Cluster::bulk_string(node.id),
+                            ]),
+                        ])
+                    })
+                    .collect(),
+            ),
+            ClusterSubcommand::Shards => {
+                let ranges = cluster.slot_ranges();
+
+                let shards = cluster
+                    .nodes()
+                    .into_iter()
+                    .map(|node| {
+                        let slots = ranges
+                            .iter()
+                            .filter(|(_, _, owner)| owner.id == node.id)
+                            .flat_map(|(start, end, _)| {
+                                [
+                                    RESPType::Integer(*start as i64),
+                                    RESPType::Integer(*end as i64),
+                                ]
+                            })
+                            .collect();
+
+                        let health = if node.id == cluster.myself_id()
+                            || !cluster::Cluster::is_failing(&node, chrono::Utc::now())
+                        {
+                            "online"
+                        } else {
+                            "failed"
+                        };
+
+                        let details = vec![
+                            Cluster::bulk_string("id".to_string()),
+                            Cluster::bulk_string(node.id.clone()),
+                            Cluster::bulk_string("port".to_string()),
+                            RESPType::Integer(node.port as i64),
+                            Cluster::bulk_string("ip".to_string()),
+                            Cluster::bulk_string(node.ip.clone()),
+                            Cluster::bulk_string("endpoint".to_string()),
+                            Cluster::bulk_string(node.ip.clone()),
+                            Cluster::bulk_string("role".to_string()),
+                            Cluster::bulk_string("master".to_string()),
+                            Cluster::bulk_string("health".to_string()),
+                            Cluster::bulk_string(health.to_string()),
+                        ];
+
+                        RESPType::Array(vec![
+                            Cluster::bulk_string("slots".to_string()),
+                            RESPType::Array(slots),
+                            Cluster::bulk_string("nodes".to_string()),
+                            RESPType::Array(vec![RESPType::Array(details)]),
+                        ])
+                    })
+                    .collect();
+
+                RESPType::Array(shards)
+            }
+            ClusterSubcommand::Nodes => Cluster::bulk_string(cluster.nodes_description()),
+            ClusterSubcommand::Info => {
+                let text: String = cluster
+                    .info()
+                    .into_iter()
+                    .map(|(name, value)| format!("{}:{}\r\n", name, value))
+                    .collect();
+
+                Cluster::bulk_string(text)
+            }
+            ClusterSubcommand::Myid => Cluster::bulk_string(cluster.myself_id()),
+            ClusterSubcommand::Meet { ip, port, bus_port } => {
+                cluster.meet(ip, port, bus_port);
+                ok()
+            }
+            ClusterSubcommand::Addslots(slots) => match cluster.add_slots(&slots) {
+                Ok(()) => ok(),
+                Err(err) => RESPType::Error(err),
+            },
+            ClusterSubcommand::Delslots(slots) => match cluster.del_slots(&slots) {
+                Ok(()) => ok(),
+                Err(err) => RESPType::Error(err),
+            },
+            ClusterSubcommand::Setslot { slot, state } => match cluster.set_slot(slot, state) {
+                Ok(()) => ok(),
+                Err(err) => RESPType::Error(err),
+            },
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use crate::cmd::ParseError;
+use crate::config::{self, MUTABLE_PARAMETERS, PARAMETERS};
+use crate::context::ServerContext;
+use crate::glob::glob_match;
+use crate::protocol_handler::BulkStringData;
+use crate::{ConnectionBase, RESPType};
+use std::collections::HashSet;
+
+/// The sub-commands of CONFIG
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigSubcommand {
+    // The glob-style patterns of the parameters to get
+    Get(Vec<String>),
+
+    // The parameters to set, with their new values
+    Set(Vec<(String, String)>),
+    Resetstat,
+    Rewrite,
+}
+
+/// The CONFIG operation in Redis
+#[derive(Debug)]
+pub struct Config {
+    subcommand: ConfigSubcommand,
+}
+
+impl Config {
+    /// Create a new `Config` command
+    pub fn new(subcommand: ConfigSubcommand) -> Config {
+        Config { subcommand }
+    }
+
+    /// Parsing the necessary arguments for the `Config` command
+    ///
+    /// Syntax:
+    /// CONFIG GET parameter [parameter ...]
+    /// CONFIG SET parameter value [parameter value ...]
+    /// CONFIG RESETSTAT
+    /// CONFIG REWRITE
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Config, ParseError> {
+        let subcommand = match cmd_strings.get(1) {
+            Some(arg) => arg.to_lowercase(),
+            None => {
+                return Err(ParseError::SyntaxError(
+                    "ERR wrong number of arguments for 'config' command".to_string(),
+                ))
+            }
+        };
+
+        let args = &cmd_strings[2..];
+
+        let subcommand = match subcommand.as_str() {
+            "get" if !args.is_empty() => {
+                ConfigSubcommand::Get(args.iter().map(|arg| arg.to_lowercase()).collect())
+            }
+            "set" if !args.is_empty() && args.len().is_multiple_of(2) => ConfigSubcommand::Set(
+                args.chunks(2)
+                    .map(|pair| (pair[0].to_lowercase(), pair[1].clone()))
+                    .collect(),
+            ),
+            "resetstat" if args.is_empty() => ConfigSubcommand::Resetstat,
+            "rewrite" if args.is_empty() => ConfigSubcommand::Rewrite,
+            "get" | "set" | "resetstat" | "rewrite" => {
+                return Err(ParseError::SyntaxError(format!(
+                    "ERR wrong number of arguments for 'config|{}' command",
+                    subcommand
+                )))
+            }
+            _ => {
+                return Err(ParseError::UnrecognizedCmd(format!(
+                    "ERR unknown subcommand '{}'. Try CONFIG HELP.",
+                    cmd_strings[1]
+                )))
+            }
+        };
+
+        Ok(Config::new(subcommand))
+    }
+
+    fn bulk_string(text: String) -> RESPType {
+        RESPType::BulkString(Some(BulkStringData {
+            prefix_length: text.len(),
+            text,
+        }))
+    }
+
+    /// Set the `params` all at once, or none of them if one is invalid or
+    /// can't be changed while running. The new values take effect right away.
+    fn set(server: &ServerContext, params: &[(String, String)]) -> Result<(), String> {
+        let mut config = server.config.write().unwrap();
+        let mut updated = config.clone();
+        let mut names: HashSet<&str> = HashSet::new();
+
+        for (name, value) in params {
+            let name = config::canonical_name(name);
+            let failed = |err: &str| {
+                format!(
+                    "ERR CONFIG SET failed (possibly related to argument '{}') - {}",
+                    name, err
+                )
+            };
+
+            if !PARAMETERS.contains(&name) {
+                return Err(format!(
+                    "ERR Unknown option or number of arguments for CONFIG SET - '{}'",
+                    name
+                ));
+            }
+
+            if !MUTABLE_PARAMETERS.contains(&name) {
+                return Err(failed("can't set immutable config"));
+            }
+
+            if !names.insert(name) {
+                return Err(failed("duplicate parameter"));
+            }
+
+            updated
+                .set(name, std::slice::from_ref(value))
+                .map_err(|err| failed(&err))?;
+        }
+
+        // Apply the parameters held by the rest of the server state. Only the
+        // parameters which were set are applied, e.g. to not reset the passwords
+        // of the default user, which may have changed since.
+        for name in names {
+            match name {
+                "requirepass" => server.acl.set_requirepass(updated.requirepass.as_deref()),
+                "maxclients" => server.clients.set_maxclients(updated.maxclients),
+                "hz" | "active-expire-keys" => server
+                    .shared_store
+                    .set_active_expiry(updated.hz, updated.active_expire_keys),
+                "maxmemory" => server.shared_store.set_maxmemory(updated.maxmemory),
+                "maxmemory-policy" => server
+                    .shared_store
+                    .set_eviction_policy(updated.maxmemory_policy),
+                "replica-read-only" => server.replication.set_read_only(updated.replica_read_only),
+                _ => {}
+            }
+        }
+
+        *config = updated;
+
+        Ok(())
+    }
+
+    /// Execute the `Config` command
+    ///
+    /// GET returns the names and values of the parameters which match any of the
+    /// patterns. SET, RESETSTAT and REWRITE return OK, or an error.
+    pub async fn execute(
+        self,
+        server: &ServerContext,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let response = match self.subcommand {
+            ConfigSubcommand::Get(patterns) => {
+                let config = server.config.read().unwrap();
+
+                RESPType::Array(
+                    PARAMETERS
+                        .iter()
+                        .filter(|param| patterns.iter().any(|pattern| glob_match(pattern, param)))
+                        .flat_map(|param| {
+                            let value = config.get(param).unwrap_or_default();
+                            [
+                                Config::bulk_string(param.to_string()),
+                                Config::bulk_string(value),
+                            ]
+                        })
+                        .collect(),
+                )
+            }
+            ConfigSubcommand::Set(params) => match Config::set(server, &params) {
+                Ok(()) => RESPType::SimpleString("OK".to_string()),
+                Err(err) => RESPType::Error(err),
+            },
+            ConfigSubcommand::Resetstat => {
+                server.shared_store.reset_stats();
+                server.stats.reset();
+                RESPType::SimpleString("OK".to_string())
+            }
+            ConfigSubcommand::Rewrite => {
+                let result = server.config.read().unwrap().rewrite_file();
+
+                match result {
+                    Ok(()) => RESPType::SimpleString("OK".to_string()),
+                    Err(err) => RESPType::Error(format!("ERR {}", err)),
+                }
+            }
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -28,6 +28,11 @@ impl Decr {
         }
     }
 
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
     /// Execute the `Decr` command
     ///
     /// Returns an integer reply, for the number of keys
@@ -30,6 +30,11 @@ impl Exists {
         }
     }
 
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        self.keys.iter().map(String::as_str).collect()
+    }
+
     /// Execute the `Exists` command
     ///
     /// Returns an integer reply, for the number of keys
@@ -0,0 +1,146 @@
+use crate::cmd::ParseError;
+use crate::geo;
+use crate::{ConnectionBase, RESPType, SharedStoreBase};
+
+/// The GEOADD operation in Redis
+#[derive(Debug)]
+pub struct Geoadd {
+    // The key of the geospatial index
+    key: String,
+
+    // The (longitude, latitude, member) locations to add
+    locations: Vec<(f64, f64, String)>,
+
+    // The NX flag (Only add new members)
+    nx: bool,
+
+    // The XX flag (Only update existing members)
+    xx: bool,
+
+    // The CH flag (Count the changed members, rather than the added members)
+    ch: bool,
+}
+
+impl Geoadd {
+    /// Create a new `GEOADD` command
+    pub fn new(
+        key: String,
+        locations: Vec<(f64, f64, String)>,
+        nx: bool,
+        xx: bool,
+        ch: bool,
+    ) -> Geoadd {
+        Geoadd {
+            key,
+            locations,
+            nx,
+            xx,
+            ch,
+        }
+    }
+
+    /// Parsing the necessary arguments for the `GEOADD` command
+    ///
+    /// Syntax:
+    /// GEOADD key [NX | XX] [CH] longitude latitude member [longitude
+    ///   latitude member ...]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Geoadd, ParseError> {
+        let wrong_args = || {
+            ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'geoadd' command".to_string(),
+            )
+        };
+
+        if cmd_strings.len() < 5 {
+            return Err(wrong_args());
+        }
+
+        let mut nx_flag: bool = false;
+        let mut xx_flag: bool = false;
+        let mut ch_flag: bool = false;
+
+        // The options come before the locations
+        let mut index: usize = 2;
+        while let Some(arg) = cmd_strings.get(index) {
+            match arg.to_uppercase().as_str() {
+                "NX" => nx_flag = true,
+                "XX" => xx_flag = true,
+                "CH" => ch_flag = true,
+                _ => break,
+            }
+            index += 1;
+        }
+
+        if nx_flag && xx_flag {
+            return Err(ParseError::SyntaxError(
+                "ERR XX and NX options at the same time are not compatible".to_string(),
+            ));
+        }
+
+        let arguments = &cmd_strings[index..];
+        if arguments.is_empty() || !arguments.chunks_exact(3).remainder().is_empty() {
+            return Err(wrong_args());
+        }
+
+        let mut locations: Vec<(f64, f64, String)> = Vec::new();
+
+        for location in arguments.chunks(3) {
+            let longitude = Geoadd::parse_float(&location[0])?;
+            let latitude = Geoadd::parse_float(&location[1])?;
+
+            if !geo::is_valid_coordinate(longitude, latitude) {
+                return Err(ParseError::ConditionNotMet(format!(
+                    "ERR invalid longitude,latitude pair {:.6},{:.6}",
+                    longitude, latitude
+                )));
+            }
+
+            locations.push((longitude, latitude, location[2].clone()));
+        }
+
+        Ok(Geoadd::new(
+            cmd_strings[1].clone(),
+            locations,
+            nx_flag,
+            xx_flag,
+            ch_flag,
+        ))
+    }
+
+    /// Parse a coordinate or distance argument, which must be a float
+    pub fn parse_float(arg: &str) -> Result<f64, ParseError> {
+        match arg.parse::<f64>() {
+            Ok(val) if val.is_finite() => Ok(val),
+            _ => Err(ParseError::SyntaxError(
+                "ERR value is not a valid float".to_string(),
+            )),
+        }
+    }
+
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    /// Execute the `Geoadd` command
+    ///
+    /// Returns an integer reply, for the number of members added,
+    /// or the number of members changed if the CH flag is provided
+    pub async fn execute(
+        self,
+        shared_store: &dyn SharedStoreBase,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Add the locations in the shared store
+        let result = shared_store.geoadd(self.key, self.locations, self.nx, self.xx, self.ch);
+
+        let response = match result {
+            Ok(val) => RESPType::Integer(val),
+            Err(err) => RESPType::Error(err.to_string()),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use crate::cmd::{Geosearch, ParseError};
+use crate::geo::GeoUnit;
+use crate::{ConnectionBase, RESPType, SharedStoreBase};
+
+/// The GEODIST operation in Redis
+#[derive(Debug)]
+pub struct Geodist {
+    // The key of the geospatial index
+    key: String,
+
+    // The first member
+    member1: String,
+
+    // The second member
+    member2: String,
+
+    // The unit to return the distance in
+    unit: GeoUnit,
+}
+
+impl Geodist {
+    /// Create a new `GEODIST` command
+    pub fn new(key: String, member1: String, member2: String, unit: GeoUnit) -> Geodist {
+        Geodist {
+            key,
+            member1,
+            member2,
+            unit,
+        }
+    }
+
+    /// Parsing the necessary arguments for the `GEODIST` command
+    ///
+    /// Syntax:
+    /// GEODIST key member1 member2 [M | KM | FT | MI]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Geodist, ParseError> {
+        if cmd_strings.len() != 4 && cmd_strings.len() != 5 {
+            return Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'geodist' command".to_string(),
+            ));
+        }
+
+        let unit = match cmd_strings.get(4) {
+            Some(arg) => Geosearch::parse_unit(arg)?,
+            None => GeoUnit::Meters,
+        };
+
+        Ok(Geodist::new(
+            cmd_strings[1].clone(),
+            cmd_strings[2].clone(),
+            cmd_strings[3].clone(),
+            unit,
+        ))
+    }
+
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    /// Execute the `Geodist` command
+    ///
+    /// Returns a bulk string reply, of the distance in the
+    /// provided unit, or nil if either member doesn't exist
+    pub async fn execute(
+        self,
+        shared_store: &dyn SharedStoreBase,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Get the distance from the shared store
+        let result = shared_store.geodist(self.key, self.member1, self.member2);
+
+        let response = match result {
+            Ok(Some(meters)) => Geosearch::distance_reply(meters / self.unit.to_meters()),
+            Ok(None) => RESPType::BulkString(None),
+            Err(err) => RESPType::Error(err.to_string()),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use crate::cmd::ParseError;
+use crate::protocol_handler::BulkStringData;
+use crate::{ConnectionBase, RESPType, SharedStoreBase};
+
+/// The GEOHASH operation in Redis
+#[derive(Debug)]
+pub struct Geohash {
+    // The key of the geospatial index
+    key: String,
+
+    // The members to get the geohash strings of
+    members: Vec<String>,
+}
+
+impl Geohash {
+    /// Create a new `GEOHASH` command
+    pub fn new(key: String, members: Vec<String>) -> Geohash {
+        Geohash { key, members }
+    }
+
+    /// Parsing the necessary arguments for the `GEOHASH` command
+    ///
+    /// Syntax:
+    /// GEOHASH key [member [member ...]]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Geohash, ParseError> {
+        if cmd_strings.len() < 2 {
+            return Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'geohash' command".to_string(),
+            ));
+        }
+
+        Ok(Geohash::new(
+            cmd_strings[1].clone(),
+            cmd_strings[2..].to_vec(),
+        ))
+    }
+
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    /// Execute the `Geohash` command
+    ///
+    /// Returns an array reply, with the 11 character geohash string
+    /// of each member, or nil if the member doesn't exist
+    pub async fn execute(
+        self,
+        shared_store: &dyn SharedStoreBase,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Get the geohashes from the shared store
+        let result = shared_store.geohash(self.key, self.members);
+
+        let response = match result {
+            Ok(hashes) => RESPType::Array(
+                hashes
+                    .into_iter()
+                    .map(|hash| match hash {
+                        Some(text) => RESPType::BulkString(Some(BulkStringData {
+                            prefix_length: text.len(),
+                            text,
+                        })),
+                        None => RESPType::BulkString(None),
+                    })
+                    .collect(),
+            ),
+            Err(err) => RESPType::Error(err.to_string()),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use crate::cmd::{Geosearch, ParseError};
+use crate::{ConnectionBase, RESPType, SharedStoreBase};
+
+/// The GEOPOS operation in Redis
+#[derive(Debug)]
+pub struct Geopos {
+    // The key of the geospatial index
+    key: String,
+
+    // The members to get the positions of
+    members: Vec<String>,
+}
+
+impl Geopos {
+    /// Create a new `GEOPOS` command
+    pub fn new(key: String, members: Vec<String>) -> Geopos {
+        Geopos { key, members }
+    }
+
+    /// Parsing the necessary arguments for the `GEOPOS` command
+    ///
+    /// Syntax:
+    /// GEOPOS key [member [member ...]]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Geopos, ParseError> {
+        if cmd_strings.len() < 2 {
+            return Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'geopos' command".to_string(),
+            ));
+        }
+
+        Ok(Geopos::new(
+            cmd_strings[1].clone(),
+            cmd_strings[2..].to_vec(),
+        ))
+    }
+
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    /// Execute the `Geopos` command
+    ///
+    /// Returns an array reply, with the [longitude, latitude] of
+    /// each member, or nil if the member doesn't exist
+    pub async fn execute(
+        self,
+        shared_store: &dyn SharedStoreBase,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Get the positions from the shared store
+        let result = shared_store.geopos(self.key, self.members);
+
+        let response = match result {
+            Ok(positions) => RESPType::Array(
+                positions
+                    .into_iter()
+                    .map(|position| match position {
+                        Some((longitude, latitude)) => {
+                            Geosearch::coordinates_reply(longitude, latitude)
+                        }
+                        None => RESPType::BulkString(None),
+                    })
+                    .collect(),
+            ),
+            Err(err) => RESPType::Error(err.to_string()),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,435 @@
+use crate::cmd::{Geoadd, ParseError};
+use crate::geo::GeoUnit;
+use crate::protocol_handler::BulkStringData;
+use crate::{ConnectionBase, RESPType, SharedStoreBase};
+
+/// The center of a geospatial search
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoOrigin {
+    // The position of an existing member
+    Member(String),
+
+    // A (longitude, latitude) position
+    LonLat(f64, f64),
+}
+
+/// The area of a geospatial search, in meters
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoShape {
+    // A circle with the radius
+    Radius(f64),
+
+    // An axis-aligned box with the width and height
+    Box(f64, f64),
+}
+
+/// The order of the results, by distance from the center
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoOrder {
+    Asc,
+    Desc,
+}
+
+/// The query shared by the GEOSEARCH and GEOSEARCHSTORE commands
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoSearchQuery {
+    pub origin: GeoOrigin,
+    pub shape: GeoShape,
+    pub order: Option<GeoOrder>,
+
+    // The maximum number of results to return
+    pub count: Option<usize>,
+
+    // Return as soon as `count` results are found, rather than the closest ones
+    pub any: bool,
+}
+
+/// A member which matched a geospatial search
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoSearchMatch {
+    pub member: String,
+
+    // The distance from the center, in meters
+    pub distance: f64,
+
+    // The 52 bit geohash stored for the member
+    pub hash: u64,
+
+    pub longitude: f64,
+    pub latitude: f64,
+}
+
+/// The parsed arguments of GEOSEARCH and GEOSEARCHSTORE
+#[derive(Debug)]
+struct GeoSearchArgs {
+    query: GeoSearchQuery,
+    unit: GeoUnit,
+    withcoord: bool,
+    withdist: bool,
+    withhash: bool,
+    storedist: bool,
+}
+
+/// The GEOSEARCH operation in Redis
+#[derive(Debug)]
+pub struct Geosearch {
+    // The key of the geospatial index
+    key: String,
+
+    // The search to perform
+    query: GeoSearchQuery,
+
+    // The unit of the provided radius or box, which is also used for the returned distances
+    unit: GeoUnit,
+
+    // Return the longitude and latitude of each member
+    withcoord: bool,
+
+    // Return the distance of each member from the center
+    withdist: bool,
+
+    // Return the raw geohash of each member
+    withhash: bool,
+}
+
+impl Geosearch {
+    /// Create a new `GEOSEARCH` command
+    pub fn new(
+        key: String,
+        query: GeoSearchQuery,
+        unit: GeoUnit,
+        withcoord: bool,
+        withdist: bool,
+        withhash: bool,
+    ) -> Geosearch {
+        Geosearch {
+            key,
+            query,
+            unit,
+            withcoord,
+            withdist,
+            withhash,
+        }
+    }
+
+    /// Parsing the necessary arguments for the `GEOSEARCH` command
+    ///
+    /// Syntax:
+    /// GEOSEARCH key <FROMMEMBER member | FROMLONLAT longitude latitude>
+    ///   <BYRADIUS radius <M | KM | FT | MI> | BYBOX width height <M | KM |
+    ///   FT | MI>> [ASC | DESC] [COUNT count [ANY]] [WITHCOORD] [WITHDIST]
+    ///   [WITHHASH]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Geosearch, ParseError> {
+        if cmd_strings.len() < 2 {
+            return Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'geosearch' command".to_string(),
+            ));
+        }
+
+        let args = Geosearch::parse_search_args(&cmd_strings[2..], false)?;
+
+        Ok(Geosearch::new(
+            cmd_strings[1].clone(),
+            args.query,
+            args.unit,
+            args.withcoord,
+            args.withdist,
+            args.withhash,
+        ))
+    }
+
+    /// Helper method to parse the search arguments, shared with `GEOSEARCHSTORE`
+    ///
+    /// The WITH* flags are only accepted by GEOSEARCH, and STOREDIST is only
+    /// accepted by GEOSEARCHSTORE, based on `store`.
+    fn parse_search_args(args: &[String], store: bool) -> Result<GeoSearchArgs, ParseError> {
+        let syntax_err = || ParseError::SyntaxError("ERR syntax error".to_string());
+        let cmd_name = if store { "GEOSEARCHSTORE" } else { "GEOSEARCH" };
+
+        let mut origin: Option<GeoOrigin> = None;
+        let mut shape: Option<(GeoShape, GeoUnit)> = None;
+        let mut order: Option<GeoOrder> = None;
+        let mut count: Option<usize> = None;
+        let mut any: bool = false;
+        let mut withcoord: bool = false;
+        let mut withdist: bool = false;
+        let mut withhash: bool = false;
+        let mut storedist: bool = false;
+
+        let mut iterator = args.iter();
+
+        while let Some(arg) = iterator.next() {
+            match arg.to_uppercase().as_str() {
+                "FROMMEMBER" if origin.is_none() => {
+                    let member = iterator.next().ok_or_else(syntax_err)?;
+                    origin = Some(GeoOrigin::Member(member.clone()));
+                }
+                "FROMLONLAT" if origin.is_none() => {
+                    let longitude = Geoadd::parse_float(iterator.next().ok_or_else(syntax_err)?)?;
+                    let latitude = Geoadd::parse_float(iterator.next().ok_or_else(syntax_err)?)?;
+                    origin = Some(GeoOrigin::LonLat(longitude, latitude));
+                }
+                "BYRADIUS" if shape.is_none() => {
+                    let radius = Geoadd::parse_float(iterator.next().ok_or_else(syntax_err)?)?;
+                    let unit = Geosearch::parse_unit(iterator.next().ok_or_else(syntax_err)?)?;
+                    shape = Some((GeoShape::Radius(radius * unit.to_meters()), unit));
+                }
+                "BYBOX" if shape.is_none() => {
+                    let width = Geoadd::parse_float(iterator.next().ok_or_else(syntax_err)?)?;
+                    let height = Geoadd::parse_float(iterator.next().ok_or_else(syntax_err)?)?;
+                    let unit = Geosearch::parse_unit(iterator.next().ok_or_else(syntax_err)?)?;
+                    shape = Some((
+                        GeoShape::Box(width * unit.to_meters(), height * unit.to_meters()),
+                        unit,
+                    ));
+                }
+                "ASC" => order = Some(GeoOrder::Asc),
+                "DESC" => order = Some(GeoOrder::Desc),
+                "COUNT" => {
+                    let value = iterator.next().ok_or_else(syntax_err)?;
+                    match value.parse::<i64>() {
+                        Ok(val) if val > 0 => count = Some(val as usize),
+                        Ok(_) => {
+                            return Err(ParseError::SyntaxError(
+                                "ERR COUNT must be > 0".to_string(),
+                            ))
+                        }
+                        Err(_) => {
+                            return Err(ParseError::SyntaxError(
+                                "ERR value is not an integer or out of range".to_string(),
+                            ))
+                        }
+                    }
+                }
+                "ANY" => any = true,
+                "WITHCOORD" if !store => withcoord = true,
+                "WITHDIST" if !store => withdist = true,
+                "WITHHASH" if !store => withhash = true,
+                "STOREDIST" if store => storedist = true,
+                _ => return Err(syntax_err()),
+            }
+        }
+
+        let origin = origin.ok_or_else(|| {
+            ParseError::SyntaxError(format!(
+                "ERR exactly one of FROMMEMBER or FROMLONLAT can be specified for {}",
+                cmd_name
+            ))
+        })?;
+
+        let (shape, unit) = shape.ok_or_else(|| {
+            ParseError::SyntaxError(format!(
+                "ERR exactly one of BYRADIUS and BYBOX can be specified for {}",
+                cmd_name
+            ))
+        })?;
+
+        if any && count.is_none() {
+            return Err(ParseError::SyntaxError(
+                "ERR the ANY argument requires COUNT argument".to_string(),
+            ));
+        }
+
+        Ok(GeoSearchArgs {
+            query: GeoSearchQuery {
+                origin,
+                shape,
+                order,
+                count,
+                any,
+            },
+            unit,
+            withcoord,
+            withdist,
+            withhash,
+            storedist,
+        })
+    }
+
+    /// Parse the M | KM | FT | MI argument
+    pub fn parse_unit(arg: &str) -> Result<GeoUnit, ParseError> {
+        GeoUnit::parse(arg).ok_or_else(|| {
+            ParseError::SyntaxError(
+                "ERR unsupported unit provided. please use M, KM, FT, MI".to_string(),
+            )
+        })
+    }
+
+    /// The bulk string reply of a distance, rounded to 4 decimal places
+    pub fn distance_reply(distance: f64) -> RESPType {
+        let text = format!("{:.4}", distance);
+
+        RESPType::BulkString(Some(BulkStringData {
+            prefix_length: text.len(),
+            text,
+        }))
+    }
+
+    /// The array reply of a [longitude, latitude] pair
+    pub fn coordinates_reply(longitude: f64, latitude: f64) -> RESPType {
+        let coordinates = [longitude.to_string(), latitude.to_string()];
+
+        RESPType::Array(
+            coordinates
+                .into_iter()
+                .map(|text| {
+                    RESPType::BulkString(Some(BulkStringData {
+                        prefix_length: text.len(),
+                        text,
+                    }))
+                })
+                .collect(),
+        )
+    }
+
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    /// Execute the `Geosearch` command
+    ///
+    /// Returns an array reply, with the matching members. If any of
+    /// the WITH* flags are provided, each member is returned as an array
+    /// of the member, distance, hash and coordinates, in that order.
+    pub async fn execute(
+        self,
+        shared_store: &dyn SharedStoreBase,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Search the locations in the shared store
+        let result = shared_store.geosearch(self.key, self.query);
+
+        let response = match result {
+            Ok(matches) => RESPType::Array(
+                matches
+                    .into_iter()
+                    .map(|found| {
+                        let member = RESPType::BulkString(Some(BulkStringData {
+                            prefix_length: found.member.len(),
+                            text: found.member,
+                        }));
+
+                        if !self.withdist && !self.withhash && !self.withcoord {
+                            return member;
+                        }
+
+                        let mut entry: Vec<RESPType> = vec![member];
+
+                        if self.withdist {
+                            entry.push(Geosearch::distance_reply(
+                                found.distance / self.unit.to_meters(),
+                            ));
+                        }
+                        if self.withhash {
+                            entry.push(RESPType::Integer(found.hash as i64));
+                        }
+                        if self.withcoord {
+                            entry.push(Geosearch::coordinates_reply(
+                                found.longitude,
+                                found.latitude,
+                            ));
+                        }
+
+                        RESPType::Array(entry)
+                    })
+                    .collect(),
+            ),
+            Err(err) => RESPType::Error(err.to_string()),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+/// The GEOSEARCHSTORE operation in Redis
+#[derive(Debug)]
+pub struct Geosearchstore {
+    // The key to store the matching members at
+    destination: String,
+
+    // The key of the geospatial index to search
+    source: String,
+
+    // The search to perform
+    query: GeoSearchQuery,
+
+    // Store the distance in this unit as the score, rather than the geohash
+    storedist: Option<GeoUnit>,
+}
+
+impl Geosearchstore {
+    /// Create a new `GEOSEARCHSTORE` command
+    pub fn new(
+        destination: String,
+        source: String,
+        query: GeoSearchQuery,
+        storedist: Option<GeoUnit>,
+    ) -> Geosearchstore {
+        Geosearchstore {
+            destination,
+            source,
+            query,
+            storedist,
+        }
+    }
+
+    /// Parsing the necessary arguments for the `GEOSEARCHSTORE` command
+    ///
+    /// Syntax:
+    /// GEOSEARCHSTORE destination source <FROMMEMBER member |
+    ///   FROMLONLAT longitude latitude> <BYRADIUS radius <M | KM | FT | MI>
+    ///   | BYBOX width height <M | KM | FT | MI>> [ASC | DESC] [COUNT count
+    ///   [ANY]] [STOREDIST]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Geosearchstore, ParseError> {
+        if cmd_strings.len() < 3 {
+            return Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'geosearchstore' command".to_string(),
+            ));
+        }
+
+        let args = Geosearch::parse_search_args(&cmd_strings[3..], true)?;
+        let storedist = if args.storedist {
+            Some(args.unit)
+        } else {
+            None
+        };
+
+        Ok(Geosearchstore::new(
+            cmd_strings[1].clone(),
+            cmd_strings[2].clone(),
+            args.query,
+            storedist,
+        ))
+    }
+
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.destination, &self.source]
+    }
+
+    /// Execute the `Geosearchstore` command
+    ///
+    /// Returns an integer reply, for the number of
+    /// members stored at the destination
+    pub async fn execute(
+        self,
+        shared_store: &dyn SharedStoreBase,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Search and store the locations in the shared store
+        let result =
+            shared_store.geosearchstore(self.destination, self.source, self.query, self.storedist);
+
+        let response = match result {
+            Ok(val) => RESPType::Integer(val),
+            Err(err) => RESPType::Error(err.to_string()),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -31,6 +31,11 @@ impl Get {
         }
     }
 
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
     /// Execute the `Get` command
     ///
     /// Get the value of key. If the key does not exist then `None` is returned
@@ -43,7 +48,7 @@ impl Get {
         let result: Option<DataType> = shared_store.get(self.key);
 
         let response: RESPType = match result {
-            // Return a bulk string if there is a result of type String
+            // Return a bulk string if there is a result of type String, or Bytes
             Some(val) => match val {
                 DataType::String(s) => {
                     let text = s.clone();
@@ -54,6 +59,7 @@ impl Get {
                         prefix_length,
                     }))
                 }
+                DataType::Bytes(bytes) => RESPType::BulkBytes(bytes),
                 _ => RESPType::Error("ERR Wrong key type".to_string()),
             },
             // Return a nil bulk, if key isn't found
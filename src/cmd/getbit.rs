@@ -0,0 +1,62 @@
+use crate::cmd::{ParseError, Setbit};
+use crate::{ConnectionBase, RESPType, SharedStoreBase};
+
+/// The GETBIT operation in Redis
+#[derive(Debug)]
+pub struct Getbit {
+    // The key of the string to query
+    key: String,
+
+    // The bit offset within the string
+    offset: u64,
+}
+
+impl Getbit {
+    /// Create a new `GETBIT` command
+    pub fn new(key: String, offset: u64) -> Getbit {
+        Getbit { key, offset }
+    }
+
+    /// Parsing the necessary arguments for the `GETBIT` command
+    ///
+    /// Syntax:
+    /// GETBIT key offset
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Getbit, ParseError> {
+        if cmd_strings.len() != 3 {
+            return Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'getbit' command".to_string(),
+            ));
+        }
+
+        let offset = Setbit::parse_offset(&cmd_strings[2])?;
+
+        Ok(Getbit::new(cmd_strings[1].clone(), offset))
+    }
+
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    /// Execute the `Getbit` command
+    ///
+    /// Returns an integer reply, of the bit value
+    /// stored at the offset
+    pub async fn execute(
+        self,
+        shared_store: &dyn SharedStoreBase,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Get the bit from the shared store
+        let result = shared_store.getbit(self.key, self.offset);
+
+        let response = match result {
+            Ok(val) => RESPType::Integer(val as i64),
+            Err(err) => RESPType::Error(err.to_string()),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,304 @@
+use crate::cmd::ParseError;
+use crate::context::ServerContext;
+use crate::protocol_handler::BulkStringData;
+use crate::{ConnectionBase, RESPType, SharedStoreBase};
+
+/// The sections of INFO, in the order they are reported
+const SECTIONS: [&str; 9] = [
+    "server",
+    "clients",
+    "memory",
+    "stats",
+    "replication",
+    "commandstats",
+    "latencystats",
+    "cluster",
+    "keyspace",
+];
+
+/// The INFO operation in Redis
+#[derive(Debug)]
+pub struct Info {
+    // The requested sections, in lowercase
+    sections: Vec<String>,
+}
+
+impl Info {
+    /// Create a new `Info` command
+    pub fn new(sections: Vec<String>) -> Info {
+        Info { sections }
+    }
+
+    /// Parsing the necessary arguments for the `Info` command
+    ///
+    /// Syntax:
+    /// INFO [section [section ...]]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Info, ParseError> {
+        let sections = cmd_strings[1..]
+            .iter()
+            .map(|section| section.to_lowercase())
+            .collect();
+
+        Ok(Info::new(sections))
+    }
+
+    /// Whether the `section` was requested. The sections of each command are
+    /// left out of the default ones, as in Redis.
+    fn includes(&self, section: &str) -> bool {
+        let default = !matches!(section, "commandstats" | "latencystats");
+
+        (self.sections.is_empty() && default)
+            || self.sections.iter().any(|requested| {
+                requested == section
+                    || requested == "all"
+                    || requested == "everything"
+                    || (requested == "default" && default)
+            })
+    }
+
+    /// Format a number of bytes as in Redis, e.g. `1.50M`
+    fn human_bytes(bytes: usize) -> String {
+        const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+
+        if bytes < 1024 {
+            return format!("{}B", bytes);
+        }
+
+        let mut value = bytes as f64 / 1024.0;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        format!("{:.2}{}", value, UNITS[unit])
+    }
+
+    fn server(server: &ServerContext) -> Vec<(String, String)> {
+        let config = server.config.read().unwrap();
+        let uptime = server.stats.uptime_in_seconds();
+        let mode = if server.cluster.is_enabled() {
+            "cluster"
+        } else {
+            "standalone"
+        };
+
+        vec![
+            ("redust_version", env!("CARGO_PKG_VERSION").to_string()),
+            ("redust_mode", mode.to_string()),
+            (
+                "os",
+                format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            ),
+            ("arch_bits", usize::BITS.to_string()),
+            ("process_id", std::process::id().to_string()),
+            ("tcp_port", config.port.to_string()),
+            (
+                "server_time_usec",
+                chrono::Utc::now().timestamp_micros().to_string(),
+            ),
+            ("uptime_in_seconds", uptime.to_string()),
+            ("uptime_in_days", (uptime / 86400).to_string()),
+            ("hz", config.hz.to_string()),
+            (
+                "config_file",
+                match &config.config_file {
+                    Some(path) => path.display().to_string(),
+                    None => String::new(),
+                },
+            ),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect()
+    }
+
+    fn memory(server: &ServerContext) -> Vec<(String, String)> {
+        let stats = server.shared_store.memory_stats();
+
+        vec![
+            ("used_memory", stats.used_memory.to_string()),
+            ("used_memory_human", Info::human_bytes(stats.used_memory)),
+            ("used_memory_peak", stats.peak_memory.to_string()),
+            (
+                "used_memory_peak_human",
+                Info::human_bytes(stats.peak_memory),
+            ),
+            ("used_memory_dataset", stats.dataset.to_string()),
+            ("maxmemory", stats.maxmemory.to_string()),
+            ("maxmemory_human", Info::human_bytes(stats.maxmemory)),
+            ("maxmemory_policy", stats.eviction_policy.to_string()),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect()
+    }
+
+    fn stats(server: &ServerContext) -> Vec<(String, String)> {
+        let keyspace = server.shared_store.keyspace_stats();
+
+        vec![
+            (
+                "total_connections_received",
+                server.stats.total_connections_received(),
+            ),
+            ("rejected_connections", server.stats.rejected_connections()),
+            (
+                "total_commands_processed",
+                server.stats.total_commands_processed(),
+            ),
+            ("expired_keys", keyspace.expired_keys),
+            ("evicted_keys", keyspace.evicted_keys),
+            ("keyspace_hits", keyspace.keyspace_hits),
+            ("keyspace_misses", keyspace.keyspace_misses),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+    }
+
+    fn commandstats(server: &ServerContext) -> Vec<(String, String)> {
+        server
+            .stats
+            .command_stats()
+            .into_iter()
+            .map(|(name, stats)| {
+                let usec_per_call = match stats.calls {
+                    0 => 0.0,
+                    calls => stats.usec as f64 / calls as f64,
+                };
+
+                (
+                    format!("cmdstat_{}", name),
+                    format!(
+                        "calls={},usec={},usec_per_call={:.2},rejected_calls={},failed_calls={}",
+                        stats.calls,
+                        stats.usec,
+                        usec_per_call,
+                        stats.rejected_calls,
+                        stats.failed_calls
+                    ),
+                )
+            })
+            .collect()
+    }
+
+    /// The configured percentiles of the latency of each command which was executed
+    fn latencystats(server: &ServerContext) -> Vec<(String, String)> {
+        let percentiles = server
+            .config
+            .read()
+            .unwrap()
+            .latency_tracking_info_percentiles
+            .clone();
+
+        server
+            .stats
+            .command_stats()
+            .into_iter()
+            .filter(|(_, stats)| !stats.latency.is_empty())
+            .map(|(name, stats)| {
+                let values: Vec<String> = percentiles
+                    .iter()
+                    .map(|percentile| {
+                        format!(
+                            "p{}={:.3}",
+                            percentile,
+                            stats.latency.value_at_percentile(*percentile) as f64
+                        )
+                    })
+                    .collect();
+
+                (
+                    format!("latency_percentiles_usec_{}", name),
+                    values.join(","),
+                )
+            })
+            .collect()
+    }
+
+    /// The keys of the only database, which is left out while it is empty, as in Redis
+    fn keyspace(server: &ServerContext) -> Vec<(String, String)> {
+        let stats = server.shared_store.memory_stats();
+
+        if stats.keys == 0 {
+            return Vec::new();
+        }
+
+        vec![(
+            "db0".to_string(),
+            format!(
+                "keys={},expires={},avg_ttl={}",
+                stats.keys,
+                stats.expires,
+                server.shared_store.average_ttl()
+            ),
+        )]
+    }
+
+    /// Execute the `Info` command
+    ///
+    /// Returns a bulk string of the requested sections, each with a `# Section`
+    /// header followed by `field:value` lines. Unknown sections are ignored.
+    pub async fn execute(
+        self,
+        server: &ServerContext,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut text = String::new();
+
+        for section in SECTIONS {
+            if !self.includes(section) {
+                continue;
+            }
+
+            let fields = match section {
+                "server" => Info::server(server),
+                "clients" => vec![
+                    (
+                        "connected_clients".to_string(),
+                        server.stats.connected_clients().to_string(),
+                    ),
+                    (
+                        "maxclients".to_string(),
+                        server.config.read().unwrap().maxclients.to_string(),
+                    ),
+                ],
+                "memory" => Info::memory(server),
+                "stats" => Info::stats(server),
+                "replication" => server.replication.info(),
+                "commandstats" => Info::commandstats(server),
+                "latencystats" => Info::latencystats(server),
+                "cluster" => vec![(
+                    "cluster_enabled".to_string(),
+                    (server.cluster.is_enabled() as u8).to_string(),
+                )],
+                "keyspace" => Info::keyspace(server),
+                _ => continue,
+            };
+
+            if !text.is_empty() {
+                text.push_str("\r\n");
+            }
+
+            text.push_str(&format!(
+                "# {}{}\r\n",
+                section[..1].to_uppercase(),
+                &section[1..]
+            ));
+
+            for (name, value) in fields {
+                text.push_str(&format!("{}:{}\r\n", name, value));
+            }
+        }
+
+        let response = RESPType::BulkString(Some(BulkStringData {
+            prefix_length: text.len(),
+            text,
+        }));
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
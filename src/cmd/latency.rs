@@ -0,0 +1,111 @@
+use crate::cmd::ParseError;
+use crate::context::ServerContext;
+use crate::protocol_handler::BulkStringData;
+use crate::{ConnectionBase, RESPType};
+
+/// The sub-commands of LATENCY
+#[derive(Debug, Clone, PartialEq)]
+pub enum LatencySubcommand {
+    // The names of the commands to report, or all of them if empty
+    Histogram(Vec<String>),
+}
+
+/// The LATENCY operation in Redis
+#[derive(Debug)]
+pub struct Latency {
+    subcommand: LatencySubcommand,
+}
+
+impl Latency {
+    /// Create a new `Latency` command
+    pub fn new(subcommand: LatencySubcommand) -> Latency {
+        Latency { subcommand }
+    }
+
+    /// Parsing the necessary arguments for the `Latency` command
+    ///
+    /// Syntax:
+    /// LATENCY HISTOGRAM [command [command ...]]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Latency, ParseError> {
+        let subcommand = match cmd_strings.get(1) {
+            Some(arg) => arg.to_lowercase(),
+            None => {
+                return Err(ParseError::SyntaxError(
+                    "ERR wrong number of arguments for 'latency' command".to_string(),
+                ))
+            }
+        };
+
+        match subcommand.as_str() {
+            "histogram" => Ok(Latency::new(LatencySubcommand::Histogram(
+                cmd_strings[2..]
+                    .iter()
+                    .map(|name| name.to_lowercase())
+                    .collect(),
+            ))),
+            _ => Err(ParseError::UnrecognizedCmd(format!(
+                "ERR unknown subcommand '{}'. Try LATENCY HELP.",
+                cmd_strings[1]
+            ))),
+        }
+    }
+
+    fn bulk_string(text: &str) -> RESPType {
+        RESPType::BulkString(Some(BulkStringData {
+            prefix_length: text.len(),
+            text: text.to_string(),
+        }))
+    }
+
+    /// Execute the `Latency` command
+    ///
+    /// HISTOGRAM returns an array reply of alternating command names and their
+    /// reports, for each command which was executed. A report holds the number
+    /// of calls, and the cumulative number of calls which took up to each power
+    /// of two microseconds.
+    pub async fn execute(
+        self,
+        server: &ServerContext,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let response = match self.subcommand {
+            LatencySubcommand::Histogram(names) => RESPType::Array(
+                server
+                    .stats
+                    .command_stats()
+                    .into_iter()
+                    .filter(|(name, stats)| {
+                        stats.calls > 0 && (names.is_empty() || names.contains(name))
+                    })
+                    .flat_map(|(name, stats)| {
+                        let histogram = stats
+                            .latency
+                            .power_of_two_buckets()
+                            .into_iter()
+                            .flat_map(|(bound, count)| {
+                                [
+                                    RESPType::Integer(bound as i64),
+                                    RESPType::Integer(count as i64),
+                                ]
+                            })
+                            .collect();
+
+                        [
+                            Latency::bulk_string(&name),
+                            RESPType::Array(vec![
+                                Latency::bulk_string("calls"),
+                                RESPType::Integer(stats.calls as i64),
+                                Latency::bulk_string("histogram_usec"),
+                                RESPType::Array(histogram),
+                            ]),
+                        ]
+                    })
+                    .collect(),
+            ),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
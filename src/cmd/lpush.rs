@@ -34,6 +34,11 @@ impl Lpush {
         }
     }
 
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
     /// Execute the `Lpush` command
     ///
     /// Returns an integer reply, representing
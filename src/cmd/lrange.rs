@@ -60,6 +60,11 @@ impl Lrange {
         }
     }
 
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
     /// Execute the `Lrange` command
     ///
     /// Returns the elements, as an Array which are part of the
@@ -0,0 +1,197 @@
+use crate::cmd::ParseError;
+use crate::protocol_handler::BulkStringData;
+use crate::{ConnectionBase, MemoryStats, RESPType, SharedStoreBase};
+
+/// The number of elements sampled by MEMORY USAGE, by default
+const DEFAULT_MEMORY_USAGE_SAMPLES: usize = 5;
+
+/// The sub-commands of MEMORY
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemorySubcommand {
+    // The number of bytes used by a key, estimated from a number of samples
+    Usage { key: String, samples: usize },
+    Stats,
+    Doctor,
+}
+
+/// The MEMORY operation in Redis
+#[derive(Debug)]
+pub struct Memory {
+    subcommand: MemorySubcommand,
+}
+
+impl Memory {
+    /// Create a new `MEMORY` command
+    pub fn new(subcommand: MemorySubcommand) -> Memory {
+        Memory { subcommand }
+    }
+
+    /// Parsing the necessary arguments for the `MEMORY` command
+    ///
+    /// Syntax:
+    /// MEMORY USAGE key [SAMPLES count]
+    /// MEMORY STATS
+    /// MEMORY DOCTOR
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Memory, ParseError> {
+        let subcommand = match cmd_strings.get(1) {
+            Some(arg) => arg.to_lowercase(),
+            None => {
+                return Err(ParseError::SyntaxError(
+                    "ERR wrong number of arguments for 'memory' command".to_string(),
+                ))
+            }
+        };
+
+        let wrong_args = || {
+            ParseError::SyntaxError(format!(
+                "ERR wrong number of arguments for 'memory|{}' command",
+                subcommand
+            ))
+        };
+
+        match subcommand.as_str() {
+            "usage" => {
+                let key = cmd_strings.get(2).ok_or_else(wrong_args)?.clone();
+
+                let samples = match cmd_strings.len() {
+                    3 => DEFAULT_MEMORY_USAGE_SAMPLES,
+                    5 if cmd_strings[3].to_uppercase() == "SAMPLES" => {
+                        match cmd_strings[4].parse::<usize>() {
+                            Ok(val) => val,
+                            Err(_) => {
+                                return Err(ParseError::SyntaxError(
+                                    "ERR value is not an integer or out of range".to_string(),
+                                ))
+                            }
+                        }
+                    }
+                    _ => return Err(ParseError::SyntaxError("ERR syntax error".to_string())),
+                };
+
+                Ok(Memory::new(MemorySubcommand::Usage { key, samples }))
+            }
+            "stats" if cmd_strings.len() == 2 => Ok(Memory::new(MemorySubcommand::Stats)),
+            "doctor" if cmd_strings.len() == 2 => Ok(Memory::new(MemorySubcommand::Doctor)),
+            "stats" | "doctor" => Err(wrong_args()),
+            _ => Err(ParseError::UnrecognizedCmd(format!(
+                "ERR unknown subcommand '{}'. Try MEMORY HELP.",
+                cmd_strings[1]
+            ))),
+        }
+    }
+
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        match &self.subcommand {
+            MemorySubcommand::Usage { key, .. } => vec![key],
+            _ => vec![],
+        }
+    }
+
+    /// Execute the `Memory` command
+    ///
+    /// USAGE returns an integer reply of the bytes used by the key, or nil.
+    /// STATS returns an array reply of alternating names and values.
+    /// DOCTOR returns a bulk string reply, with a report on memory issues.
+    pub async fn execute(
+        self,
+        shared_store: &dyn SharedStoreBase,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let response = match self.subcommand {
+            MemorySubcommand::Usage { key, samples } => {
+                match shared_store.memory_usage(key, samples) {
+                    Some(bytes) => RESPType::Integer(bytes as i64),
+                    None => RESPType::BulkString(None),
+                }
+            }
+            MemorySubcommand::Stats => Memory::stats_reply(&shared_store.memory_stats()),
+            MemorySubcommand::Doctor => {
+                let text = Memory::doctor_report(&shared_store.memory_stats());
+
+                RESPType::BulkString(Some(BulkStringData {
+                    prefix_length: text.len(),
+                    text,
+                }))
+            }
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+
+    fn stats_reply(stats: &MemoryStats) -> RESPType {
+        let bulk_string = |text: String| {
+            RESPType::BulkString(Some(BulkStringData {
+                prefix_length: text.len(),
+                text,
+            }))
+        };
+
+        let bytes_per_key = stats.dataset.checked_div(stats.keys).unwrap_or(0);
+
+        let dataset_percentage = if stats.used_memory > 0 {
+            stats.dataset as f64 * 100.0 / stats.used_memory as f64
+        } else {
+            0.0
+        };
+
+        RESPType::Array(vec![
+            bulk_string("peak.allocated".to_string()),
+            RESPType::Integer(stats.peak_memory as i64),
+            bulk_string("total.allocated".to_string()),
+            RESPType::Integer(stats.used_memory as i64),
+            bulk_string("maxmemory".to_string()),
+            RESPType::Integer(stats.maxmemory as i64),
+            bulk_string("maxmemory-policy".to_string()),
+            bulk_string(stats.eviction_policy.to_string()),
+            bulk_string("overhead.total".to_string()),
+            RESPType::Integer(stats.overhead as i64),
+            bulk_string("keys.count".to_string()),
+            RESPType::Integer(stats.keys as i64),
+            bulk_string("keys.bytes-per-key".to_string()),
+            RESPType::Integer(bytes_per_key as i64),
+            bulk_string("expires.count".to_string()),
+            RESPType::Integer(stats.expires as i64),
+            bulk_string("dataset.bytes".to_string()),
+            RESPType::Integer(stats.dataset as i64),
+            bulk_string("dataset.percentage".to_string()),
+            bulk_string(format!("{:.2}", dataset_percentage)),
+        ])
+    }
+
+    /// Build a human readable report, of the possible memory issues
+    fn doctor_report(stats: &MemoryStats) -> String {
+        let mut issues: Vec<String> = Vec::new();
+
+        if stats.maxmemory > 0 && stats.used_memory * 10 >= stats.maxmemory * 9 {
+            issues.push(format!(
+                " * High memory usage: {} of the {} bytes maxmemory limit are used. Keys will be evicted based on the '{}' policy.",
+                stats.used_memory, stats.maxmemory, stats.eviction_policy
+            ));
+        }
+
+        if stats.used_memory > 0 && stats.peak_memory * 2 > stats.used_memory * 3 {
+            issues.push(format!(
+                " * Peak memory: the peak of {} bytes is much higher than the {} bytes currently used. Memory may not have been returned to the operating system.",
+                stats.peak_memory, stats.used_memory
+            ));
+        }
+
+        if stats.keys > 0 && stats.overhead * 2 > stats.used_memory {
+            issues.push(
+                " * High overhead: more than half of the memory is used by the keys themselves, rather than their values. Consider grouping small values into larger collections.".to_string(),
+            );
+        }
+
+        if issues.is_empty() {
+            "No memory issues were detected in this instance.".to_string()
+        } else {
+            format!(
+                "The following memory issues were detected:\n\n{}\n",
+                issues.join("\n\n")
+            )
+        }
+    }
+}
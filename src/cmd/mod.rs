@@ -31,7 +31,97 @@ pub use lrange::Lrange;
 mod rpush;
 pub use rpush::Rpush;
 
-use crate::{Connection, RESPType, SharedStore};
+mod setbit;
+pub use setbit::{Setbit, MAX_BIT_OFFSET};
+
+mod getbit;
+pub use getbit::Getbit;
+
+mod bitcount;
+pub use bitcount::{BitUnit, Bitcount};
+
+mod bitpos;
+pub use bitpos::Bitpos;
+
+mod bitop;
+pub use bitop::{BitOperation, Bitop};
+
+mod bitfield;
+pub use bitfield::{Bitfield, BitfieldEncoding, BitfieldOperation, BitfieldOverflow};
+
+mod geoadd;
+pub use geoadd::Geoadd;
+
+mod geopos;
+pub use geopos::Geopos;
+
+mod geodist;
+pub use geodist::Geodist;
+
+mod geohash;
+pub use geohash::Geohash;
+
+mod geosearch;
+pub use geosearch::{
+    GeoOrder, GeoOrigin, GeoSearchMatch, GeoSearchQuery, GeoShape, Geosearch, Geosearchstore,
+};
+
+mod memory;
+pub use memory::{Memory, MemorySubcommand};
+
+mod object;
+pub use object::{Object, ObjectSubcommand};
+
+mod replicaof;
+pub use replicaof::Replicaof;
+
+mod replconf;
+pub use replconf::Replconf;
+
+mod psync;
+pub use psync::Psync;
+
+mod role;
+pub use role::Role;
+
+mod info;
+pub use info::Info;
+
+mod client;
+pub use client::{Client, ClientSubcommand};
+
+mod cluster;
+pub use cluster::{Cluster, ClusterSubcommand};
+
+mod asking;
+pub use asking::Asking;
+
+mod auth;
+pub use auth::Auth;
+
+mod acl;
+pub use acl::{Acl, AclSubcommand};
+
+mod config;
+pub use config::{Config, ConfigSubcommand};
+
+mod latency;
+pub use latency::{Latency, LatencySubcommand};
+
+mod slowlog;
+pub use slowlog::{Slowlog, SlowlogSubcommand};
+
+mod monitor;
+pub use monitor::Monitor;
+
+mod shutdown;
+pub use shutdown::Shutdown;
+
+use crate::acl::COMMAND_CATEGORIES;
+use crate::config::CommandRenames;
+use crate::context::{ClientContext, ServerContext};
+use crate::protocol_handler::BulkStringData;
+use crate::{ConnectionBase, RESPType};
 use std::fmt;
 
 /// Methods called on `Command` are delegated to the command implementation.
@@ -49,6 +139,35 @@ pub enum Command {
     Lpush(Lpush),
     Lrange(Lrange),
     Rpush(Rpush),
+    Setbit(Setbit),
+    Getbit(Getbit),
+    Bitcount(Bitcount),
+    Bitpos(Bitpos),
+    Bitop(Bitop),
+    Bitfield(Bitfield),
+    Geoadd(Geoadd),
+    Geopos(Geopos),
+    Geodist(Geodist),
+    Geohash(Geohash),
+    Geosearch(Geosearch),
+    Geosearchstore(Geosearchstore),
+    Memory(Memory),
+    Object(Object),
+    Replicaof(Replicaof),
+    Replconf(Replconf),
+    Psync(Psync),
+    Role(Role),
+    Info(Info),
+    Client(Client),
+    Cluster(Cluster),
+    Asking(Asking),
+    Auth(Auth),
+    Acl(Acl),
+    Config(Config),
+    Latency(Latency),
+    Slowlog(Slowlog),
+    Monitor(Monitor),
+    Shutdown(Shutdown),
 }
 
 #[derive(Debug)]
@@ -96,7 +215,10 @@ impl Command {
             cmd_strings.push(text);
         }
 
-        let cmd_name = cmd_strings.get(0).unwrap();
+        let cmd_name = match cmd_strings.first() {
+            Some(cmd_name) => cmd_name,
+            None => return Err(ParseError::UnrecognizedCmd("ERR empty command".to_string())),
+        };
 
         // println!("{:?}", cmd_name.as_str());
         let cmd: Command = match cmd_name.to_lowercase().as_str() {
@@ -111,6 +233,35 @@ impl Command {
             "lpush" => Command::Lpush(Lpush::parse(cmd_strings)?),
             "lrange" => Command::Lrange(Lrange::parse(cmd_strings)?),
             "rpush" => Command::Rpush(Rpush::parse(cmd_strings)?),
+            "setbit" => Command::Setbit(Setbit::parse(cmd_strings)?),
+            "getbit" => Command::Getbit(Getbit::parse(cmd_strings)?),
+            "bitcount" => Command::Bitcount(Bitcount::parse(cmd_strings)?),
+            "bitpos" => Command::Bitpos(Bitpos::parse(cmd_strings)?),
+            "bitop" => Command::Bitop(Bitop::parse(cmd_strings)?),
+            "bitfield" | "bitfield_ro" => Command::Bitfield(Bitfield::parse(cmd_strings)?),
+            "geoadd" => Command::Geoadd(Geoadd::parse(cmd_strings)?),
+            "geopos" => Command::Geopos(Geopos::parse(cmd_strings)?),
+            "geodist" => Command::Geodist(Geodist::parse(cmd_strings)?),
+            "geohash" => Command::Geohash(Geohash::parse(cmd_strings)?),
+            "geosearch" => Command::Geosearch(Geosearch::parse(cmd_strings)?),
+            "geosearchstore" => Command::Geosearchstore(Geosearchstore::parse(cmd_strings)?),
+            "memory" => Command::Memory(Memory::parse(cmd_strings)?),
+            "object" => Command::Object(Object::parse(cmd_strings)?),
+            "replicaof" | "slaveof" => Command::Replicaof(Replicaof::parse(cmd_strings)?),
+            "replconf" => Command::Replconf(Replconf::parse(cmd_strings)?),
+            "psync" => Command::Psync(Psync::parse(cmd_strings)?),
+            "role" => Command::Role(Role::parse(cmd_strings)?),
+            "info" => Command::Info(Info::parse(cmd_strings)?),
+            "client" => Command::Client(Client::parse(cmd_strings)?),
+            "cluster" => Command::Cluster(Cluster::parse(cmd_strings)?),
+            "asking" => Command::Asking(Asking::parse(cmd_strings)?),
+            "auth" => Command::Auth(Auth::parse(cmd_strings)?),
+            "acl" => Command::Acl(Acl::parse(cmd_strings)?),
+            "config" => Command::Config(Config::parse(cmd_strings)?),
+            "latency" => Command::Latency(Latency::parse(cmd_strings)?),
+            "slowlog" => Command::Slowlog(Slowlog::parse(cmd_strings)?),
+            "monitor" => Command::Monitor(Monitor::parse(cmd_strings)?),
+            "shutdown" => Command::Shutdown(Shutdown::parse(cmd_strings)?),
             _ => {
                 return Err(ParseError::UnrecognizedCmd(format!(
                     "unknown command '{}'",
@@ -122,10 +273,181 @@ impl Command {
         Ok(cmd)
     }
 
+    /// Parse a command, once the `renames` of `rename-command` are applied to its name.
+    ///
+    /// The name in the `frame` is replaced by the original one, so that the frame
+    /// can be fed into the replication stream, and applied by replicas.
+    pub fn infer_renamed_command(
+        frame: &mut RESPType,
+        renames: &CommandRenames,
+    ) -> Result<Command, ParseError> {
+        if let RESPType::Array(array) = frame {
+            if let Some(first) = array.first_mut() {
+                let name = Command::extract_string(first.clone())?;
+
+                match renames.resolve(&name) {
+                    Some(original) if original != name.to_lowercase() => {
+                        *first = RESPType::BulkString(Some(BulkStringData {
+                            prefix_length: original.len(),
+                            text: original,
+                        }));
+                    }
+                    Some(_) => {}
+                    None => {
+                        return Err(ParseError::UnrecognizedCmd(format!(
+                            "unknown command '{}'",
+                            name
+                        )))
+                    }
+                }
+            }
+        }
+
+        Command::infer_command(frame.clone())
+    }
+
+    /// The canonical name of the command of the `frame`, if it names a known
+    /// command, e.g. to count the calls which were rejected while parsing.
+    pub fn frame_name(frame: &RESPType) -> Option<&'static str> {
+        let name = match frame {
+            RESPType::Array(array) => Command::extract_string(array.first()?.clone()).ok()?,
+            _ => return None,
+        };
+
+        let name = name.to_lowercase();
+        let original = CommandRenames::original_name(&name);
+
+        COMMAND_CATEGORIES
+            .iter()
+            .map(|(command, _)| *command)
+            .find(|command| *command == original)
+    }
+
+    /// The name and arguments of the command of the `frame`, e.g. for the slow log
+    pub fn frame_args(frame: &RESPType) -> Vec<String> {
+        match frame {
+            RESPType::Array(array) => array
+                .iter()
+                .filter_map(|arg| Command::extract_string(arg.clone()).ok())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether the command modifies the data store
+    pub fn is_write(&self) -> bool {
+        match self {
+            Command::Bitfield(cmd) => !cmd.is_read_only(),
+            Command::Set(_)
+            | Command::Del(_)
+            | Command::Incr(_)
+            | Command::Decr(_)
+            | Command::Lpush(_)
+            | Command::Rpush(_)
+            | Command::Setbit(_)
+            | Command::Bitop(_)
+            | Command::Geoadd(_)
+            | Command::Geosearchstore(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the command must be rejected, when the used memory is over
+    /// the `maxmemory` limit.
+    ///
+    /// This applies to all writes, except those which can only free memory.
+    pub fn is_denyoom(&self) -> bool {
+        self.is_write() && !matches!(self, Command::Del(_))
+    }
+
+    /// The name of the command, as used by the ACL
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Ping(_) => "ping",
+            Command::Echo(_) => "echo",
+            Command::Set(_) => "set",
+            Command::Get(_) => "get",
+            Command::Exists(_) => "exists",
+            Command::Del(_) => "del",
+            Command::Incr(_) => "incr",
+            Command::Decr(_) => "decr",
+            Command::Lpush(_) => "lpush",
+            Command::Lrange(_) => "lrange",
+            Command::Rpush(_) => "rpush",
+            Command::Setbit(_) => "setbit",
+            Command::Getbit(_) => "getbit",
+            Command::Bitcount(_) => "bitcount",
+            Command::Bitpos(_) => "bitpos",
+            Command::Bitop(_) => "bitop",
+            Command::Bitfield(cmd) => cmd.name(),
+            Command::Geoadd(_) => "geoadd",
+            Command::Geopos(_) => "geopos",
+            Command::Geodist(_) => "geodist",
+            Command::Geohash(_) => "geohash",
+            Command::Geosearch(_) => "geosearch",
+            Command::Geosearchstore(_) => "geosearchstore",
+            Command::Memory(_) => "memory",
+            Command::Object(_) => "object",
+            Command::Replicaof(_) => "replicaof",
+            Command::Replconf(_) => "replconf",
+            Command::Psync(_) => "psync",
+            Command::Role(_) => "role",
+            Command::Info(_) => "info",
+            Command::Client(_) => "client",
+            Command::Cluster(_) => "cluster",
+            Command::Asking(_) => "asking",
+            Command::Auth(_) => "auth",
+            Command::Acl(_) => "acl",
+            Command::Config(_) => "config",
+            Command::Latency(_) => "latency",
+            Command::Slowlog(_) => "slowlog",
+            Command::Monitor(_) => "monitor",
+            Command::Shutdown(_) => "shutdown",
+        }
+    }
+
+    /// The keys accessed by the command, which must all belong to the same
+    /// hash slot in cluster mode.
+    pub fn keys(&self) -> Vec<&str> {
+        match self {
+            Command::Set(cmd) => cmd.keys(),
+            Command::Get(cmd) => cmd.keys(),
+            Command::Exists(cmd) => cmd.keys(),
+            Command::Del(cmd) => cmd.keys(),
+            Command::Incr(cmd) => cmd.keys(),
+            Command::Decr(cmd) => cmd.keys(),
+            Command::Lpush(cmd) => cmd.keys(),
+            Command::Lrange(cmd) => cmd.keys(),
+            Command::Rpush(cmd) => cmd.keys(),
+            Command::Setbit(cmd) => cmd.keys(),
+            Command::Getbit(cmd) => cmd.keys(),
+            Command::Bitcount(cmd) => cmd.keys(),
+            Command::Bitpos(cmd) => cmd.keys(),
+            Command::Bitop(cmd) => cmd.keys(),
+            Command::Bitfield(cmd) => cmd.keys(),
+            Command::Geoadd(cmd) => cmd.keys(),
+            Command::Geopos(cmd) => cmd.keys(),
+            Command::Geodist(cmd) => cmd.keys(),
+            Command::Geohash(cmd) => cmd.keys(),
+            Command::Geosearch(cmd) => cmd.keys(),
+            Command::Geosearchstore(cmd) => cmd.keys(),
+            Command::Memory(cmd) => cmd.keys(),
+            Command::Object(cmd) => cmd.keys(),
+            _ => vec![],
+        }
+    }
+
     pub fn extract_string(frame: RESPType) -> Result<String, ParseError> {
         let text: String = match frame {
-            RESPType::BulkString(val) => val.unwrap().text,
+            RESPType::BulkString(Some(val)) => val.text,
             RESPType::SimpleString(val) => val,
+            // The arguments of the commands are text, and the bytes could
+            // be huge, so they aren't echoed
+            RESPType::BulkBytes(_) => {
+                return Err(ParseError::ExpectedStringType(
+                    "ERR Expected String Type got a string which isn't valid UTF-8".to_string(),
+                ))
+            }
             _ => {
                 return Err(ParseError::ExpectedStringType(format!(
                     "ERR Expected String Type got {:?}",
@@ -137,11 +459,17 @@ impl Command {
         Ok(text)
     }
 
+    /// Execute the command against the state of the server, on behalf of the `client`
     pub async fn execute(
         self,
-        shared_store: &SharedStore,
-        cnxn: &mut Connection,
+        server: &ServerContext,
+        client: &mut ClientContext,
+        cnxn: &mut dyn ConnectionBase,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        let shared_store = &server.shared_store;
+        let replication = &server.replication;
+        let cluster = &server.cluster;
+
         match self {
             Command::Ping(cmd) => cmd.execute(cnxn).await,
             Command::Echo(cmd) => cmd.execute(cnxn).await,
@@ -154,6 +482,35 @@ impl Command {
             Command::Lpush(cmd) => cmd.execute(shared_store, cnxn).await,
             Command::Lrange(cmd) => cmd.execute(shared_store, cnxn).await,
             Command::Rpush(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Setbit(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Getbit(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Bitcount(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Bitpos(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Bitop(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Bitfield(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Geoadd(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Geopos(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Geodist(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Geohash(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Geosearch(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Geosearchstore(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Memory(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Object(cmd) => cmd.execute(shared_store, cnxn).await,
+            Command::Replicaof(cmd) => cmd.execute(shared_store, replication, cnxn).await,
+            Command::Replconf(cmd) => cmd.execute(replication, cnxn).await,
+            Command::Psync(cmd) => cmd.execute(shared_store, replication, cnxn).await,
+            Command::Role(cmd) => cmd.execute(replication, cnxn).await,
+            Command::Info(cmd) => cmd.execute(server, cnxn).await,
+            Command::Client(cmd) => cmd.execute(server, client, cnxn).await,
+            Command::Cluster(cmd) => cmd.execute(shared_store, cluster, cnxn).await,
+            Command::Asking(cmd) => cmd.execute(cluster, cnxn).await,
+            Command::Auth(cmd) => cmd.execute(&server.acl, client, cnxn).await,
+            Command::Acl(cmd) => cmd.execute(&server.acl, client, cnxn).await,
+            Command::Config(cmd) => cmd.execute(server, cnxn).await,
+            Command::Latency(cmd) => cmd.execute(server, cnxn).await,
+            Command::Slowlog(cmd) => cmd.execute(&server.slowlog, cnxn).await,
+            Command::Monitor(cmd) => cmd.execute(&server.monitor, cnxn).await,
+            Command::Shutdown(cmd) => cmd.execute(server, cnxn).await,
         }
     }
 }
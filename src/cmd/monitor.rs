@@ -0,0 +1,64 @@
+use crate::cmd::ParseError;
+use crate::{ConnectionBase, Monitor as MonitorFeed, RESPType};
+use tokio::sync::broadcast::error::RecvError;
+
+/// The MONITOR operation in Redis
+///
+/// Turns the connection into a feed of the commands processed by the server.
+/// The feed itself is broadcast by the `ConnectionHandler` of each client.
+#[derive(Debug, Default)]
+pub struct Monitor {}
+
+impl Monitor {
+    /// Create a new `Monitor` command
+    pub fn new() -> Monitor {
+        Monitor {}
+    }
+
+    /// Parsing the necessary arguments for the `Monitor` command
+    ///
+    /// Syntax:
+    /// MONITOR
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Monitor, ParseError> {
+        if cmd_strings.len() != 1 {
+            return Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'monitor' command".to_string(),
+            ));
+        }
+
+        Ok(Monitor::new())
+    }
+
+    /// Execute the `Monitor` command
+    ///
+    /// Returns OK, then a status reply for each command processed by the
+    /// server, until the client disconnects. The lines which the client is
+    /// too slow to read are dropped. Other commands are refused meanwhile.
+    pub async fn execute(
+        self,
+        feed: &MonitorFeed,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Subscribe before the reply, so that no command is missed after it
+        let mut lines = feed.subscribe();
+        cnxn.write_frame(&RESPType::SimpleString("OK".to_string()))
+            .await?;
+
+        loop {
+            tokio::select! {
+                line = lines.recv() => match line {
+                    Ok(line) => cnxn.write_frame(&RESPType::SimpleString(line.to_string())).await?,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                request = async { cnxn.read_frame().await.ok().flatten() } => match request {
+                    Some(_) => {
+                        let err = "ERR MONITOR connections can only receive commands, disconnect to stop";
+                        cnxn.write_frame(&RESPType::Error(err.to_string())).await?;
+                    }
+                    None => return Ok(()),
+                },
+            }
+        }
+    }
+}
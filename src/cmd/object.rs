@@ -0,0 +1,96 @@
+use crate::cmd::ParseError;
+use crate::protocol_handler::BulkStringData;
+use crate::{ConnectionBase, RESPType, SharedStoreBase};
+
+/// The sub-commands of OBJECT
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjectSubcommand {
+    Encoding,
+    Idletime,
+    Freq,
+    Refcount,
+}
+
+/// The OBJECT operation in Redis
+#[derive(Debug)]
+pub struct Object {
+    subcommand: ObjectSubcommand,
+
+    // The key to inspect
+    key: String,
+}
+
+impl Object {
+    /// Create a new `OBJECT` command
+    pub fn new(subcommand: ObjectSubcommand, key: String) -> Object {
+        Object { subcommand, key }
+    }
+
+    /// Parsing the necessary arguments for the `OBJECT` command
+    ///
+    /// Syntax:
+    /// OBJECT <ENCODING | IDLETIME | FREQ | REFCOUNT> key
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Object, ParseError> {
+        let subcommand = match cmd_strings.get(1).map(|arg| arg.to_lowercase()) {
+            Some(arg) if arg == "encoding" => ObjectSubcommand::Encoding,
+            Some(arg) if arg == "idletime" => ObjectSubcommand::Idletime,
+            Some(arg) if arg == "freq" => ObjectSubcommand::Freq,
+            Some(arg) if arg == "refcount" => ObjectSubcommand::Refcount,
+            Some(_) => {
+                return Err(ParseError::UnrecognizedCmd(format!(
+                    "ERR unknown subcommand '{}'. Try OBJECT HELP.",
+                    cmd_strings[1]
+                )))
+            }
+            None => {
+                return Err(ParseError::SyntaxError(
+                    "ERR wrong number of arguments for 'object' command".to_string(),
+                ))
+            }
+        };
+
+        if cmd_strings.len() != 3 {
+            return Err(ParseError::SyntaxError(format!(
+                "ERR wrong number of arguments for 'object|{}' command",
+                cmd_strings[1].to_lowercase()
+            )));
+        }
+
+        Ok(Object::new(subcommand, cmd_strings[2].clone()))
+    }
+
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    /// Execute the `Object` command
+    ///
+    /// ENCODING returns a bulk string reply, and the other sub-commands
+    /// return an integer reply. Returns nil if the key doesn't exist.
+    pub async fn execute(
+        self,
+        shared_store: &dyn SharedStoreBase,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Get the details of the key from the shared store
+        let result = shared_store.object_info(self.key);
+
+        let response = match result {
+            Some(info) => match self.subcommand {
+                ObjectSubcommand::Encoding => RESPType::BulkString(Some(BulkStringData {
+                    prefix_length: info.encoding.len(),
+                    text: info.encoding,
+                })),
+                ObjectSubcommand::Idletime => RESPType::Integer(info.idle_seconds),
+                ObjectSubcommand::Freq => RESPType::Integer(info.frequency as i64),
+                ObjectSubcommand::Refcount => RESPType::Integer(info.refcount),
+            },
+            None => RESPType::BulkString(None),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
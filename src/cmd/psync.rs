@@ -0,0 +1,132 @@
+use crate::cmd::{Command, ParseError};
+use crate::replication::SyncStart;
+use crate::{ConnectionBase, RESPType, Replication, SharedStore};
+
+/// The PSYNC operation in Redis, sent by a replica to start synchronizing
+#[derive(Debug)]
+pub struct Psync {
+    // The replication ID the replica was following, or "?"
+    replid: String,
+
+    // The offset of the next byte the replica needs, or -1
+    offset: i64,
+}
+
+impl Psync {
+    /// Create a new `Psync` command
+    pub fn new(replid: String, offset: i64) -> Psync {
+        Psync { replid, offset }
+    }
+
+    /// Parsing the necessary arguments for the `Psync` command
+    ///
+    /// Syntax:
+    /// PSYNC replicationid offset
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Psync, ParseError> {
+        if cmd_strings.len() != 3 {
+            return Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'psync' command".to_string(),
+            ));
+        }
+
+        match cmd_strings[2].parse::<i64>() {
+            Ok(offset) => Ok(Psync::new(cmd_strings[1].clone(), offset)),
+            Err(_) => Err(ParseError::SyntaxError(
+                "ERR value is not an integer or out of range".to_string(),
+            )),
+        }
+    }
+
+    /// Execute the `Psync` command
+    ///
+    /// Replies with +FULLRESYNC replid offset, followed by the number of entries
+    /// of the snapshot and each entry, or with +CONTINUE replid followed by the
+    /// missed part of the stream. Then the replication stream is sent, until the
+    /// replica disconnects, or falls too far behind.
+    ///
+    /// The connection is only used for replication afterwards.
+    pub async fn execute(
+        self,
+        shared_store: &SharedStore,
+        replication: &Replication,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (start, mut stream, id) = {
+            let _lock = replication.lock_all_writes().await;
+
+            replication.begin_sync(shared_store, &self.replid, self.offset, cnxn.peer_addr())
+        };
+
+        // The replica is removed once the stream ends, even if it is cancelled,
+        // e.g. by CLIENT KILL
+        let _replica = ReplicaGuard { replication, id };
+
+        Psync::stream_to_replica(start, &mut stream, id, replication, cnxn).await
+    }
+
+    async fn stream_to_replica(
+        start: SyncStart,
+        stream: &mut tokio::sync::broadcast::Receiver<std::sync::Arc<RESPType>>,
+        id: u64,
+        replication: &Replication,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match start {
+            SyncStart::Full {
+                replid,
+                offset,
+                entries,
+            } => {
+                let reply = format!("FULLRESYNC {} {}", replid, offset);
+                cnxn.write_frame(&RESPType::SimpleString(reply)).await?;
+                cnxn.write_frame(&RESPType::Integer(entries.len() as i64))
+                    .await?;
+
+                for entry in entries.iter() {
+                    cnxn.write_frame(entry).await?;
+                }
+            }
+            SyncStart::Continue { replid, frames } => {
+                let reply = format!("CONTINUE {}", replid);
+                cnxn.write_frame(&RESPType::SimpleString(reply)).await?;
+
+                for frame in frames.iter() {
+                    cnxn.write_frame(frame).await?;
+                }
+            }
+        }
+
+        loop {
+            tokio::select! {
+                // A lagging replica is disconnected, so that it resyncs,
+                // rather than slowing down the master.
+                frame = stream.recv() => match frame {
+                    Ok(frame) => cnxn.write_frame(&frame).await?,
+                    Err(_) => return Ok(()),
+                },
+                request = async { cnxn.read_frame().await.ok().flatten() } => match request {
+                    Some(request) => {
+                        if let Ok(Command::Replconf(cmd)) = Command::infer_command(request) {
+                            if let Some(offset) = cmd.ack_offset() {
+                                replication.ack(id, offset);
+                            }
+                        }
+                    }
+                    None => return Ok(()),
+                },
+            }
+        }
+    }
+}
+
+/// Removes a replica from the `Replication` state, once dropped
+struct ReplicaGuard<'a> {
+    replication: &'a Replication,
+    id: u64,
+}
+
+impl Drop for ReplicaGuard<'_> {
+    fn drop(&mut self) {
+        self.replication.remove_replica(self.id);
+    }
+}
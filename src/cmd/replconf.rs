@@ -0,0 +1,72 @@
+use crate::cmd::ParseError;
+use crate::{ConnectionBase, RESPType, Replication};
+
+/// The REPLCONF operation in Redis, sent by a replica to configure its link
+#[derive(Debug)]
+pub struct Replconf {
+    // The (option, value) pairs
+    options: Vec<(String, String)>,
+}
+
+impl Replconf {
+    /// Create a new `Replconf` command
+    pub fn new(options: Vec<(String, String)>) -> Replconf {
+        Replconf { options }
+    }
+
+    /// Parsing the necessary arguments for the `Replconf` command
+    ///
+    /// Syntax:
+    /// REPLCONF option value [option value ...]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Replconf, ParseError> {
+        if cmd_strings.len() < 3 || !cmd_strings[1..].chunks_exact(2).remainder().is_empty() {
+            return Err(ParseError::SyntaxError("ERR syntax error".to_string()));
+        }
+
+        let options = cmd_strings[1..]
+            .chunks_exact(2)
+            .map(|pair| (pair[0].to_lowercase(), pair[1].clone()))
+            .collect();
+
+        Ok(Replconf::new(options))
+    }
+
+    /// The offset acknowledged by the replica, with REPLCONF ACK offset
+    pub fn ack_offset(&self) -> Option<i64> {
+        self.options
+            .iter()
+            .find(|(option, _)| option == "ack")
+            .and_then(|(_, value)| value.parse::<i64>().ok())
+    }
+
+    /// Execute the `Replconf` command
+    ///
+    /// The listening port of the replica is recorded, until it sends PSYNC.
+    /// Other options are accepted, and ignored. ACK doesn't have a reply.
+    pub async fn execute(
+        self,
+        replication: &Replication,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for (option, value) in self.options.iter() {
+            match option.as_str() {
+                "ack" => return Ok(()),
+                "listening-port" => match value.parse::<u16>() {
+                    Ok(port) => replication.announce_port(cnxn.peer_addr(), port),
+                    Err(_) => {
+                        let err = RESPType::Error("ERR Invalid listening port".to_string());
+                        cnxn.write_frame(&err).await?;
+
+                        return Ok(());
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        cnxn.write_frame(&RESPType::SimpleString("OK".to_string()))
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use crate::cmd::ParseError;
+use crate::{ConnectionBase, RESPType, Replication, SharedStore};
+
+/// The REPLICAOF operation in Redis, also known as SLAVEOF
+#[derive(Debug)]
+pub struct Replicaof {
+    // The host and port of the master, or `None` to stop replicating
+    master: Option<(String, u16)>,
+}
+
+impl Replicaof {
+    /// Create a new `Replicaof` command
+    pub fn new(master: Option<(String, u16)>) -> Replicaof {
+        Replicaof { master }
+    }
+
+    /// Parsing the necessary arguments for the `Replicaof` command
+    ///
+    /// Syntax:
+    /// REPLICAOF host port
+    /// REPLICAOF NO ONE
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Replicaof, ParseError> {
+        if cmd_strings.len() != 3 {
+            return Err(ParseError::SyntaxError(format!(
+                "ERR wrong number of arguments for '{}' command",
+                cmd_strings[0].to_lowercase()
+            )));
+        }
+
+        if cmd_strings[1].to_uppercase() == "NO" && cmd_strings[2].to_uppercase() == "ONE" {
+            return Ok(Replicaof::new(None));
+        }
+
+        match cmd_strings[2].parse::<u16>() {
+            Ok(port) if port > 0 => Ok(Replicaof::new(Some((cmd_strings[1].clone(), port)))),
+            _ => Err(ParseError::SyntaxError(
+                "ERR Invalid master port".to_string(),
+            )),
+        }
+    }
+
+    /// Execute the `Replicaof` command
+    ///
+    /// Start replicating from the master, which first replaces all the data with
+    /// a snapshot of the master. With NO ONE, stop replicating and become a master,
+    /// keeping the data.
+    pub async fn execute(
+        self,
+        shared_store: &SharedStore,
+        replication: &Replication,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let response = match self.master {
+            Some((host, port)) => {
+                if replication.replicate_from(shared_store, host, port) {
+                    RESPType::SimpleString("OK".to_string())
+                } else {
+                    RESPType::SimpleString("OK Already connected to specified master".to_string())
+                }
+            }
+            None => {
+                replication.promote();
+                RESPType::SimpleString("OK".to_string())
+            }
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use crate::cmd::ParseError;
+use crate::protocol_handler::BulkStringData;
+use crate::replication::Role as ReplicationRole;
+use crate::{ConnectionBase, RESPType, Replication};
+
+/// The ROLE operation in Redis
+#[derive(Debug, Default)]
+pub struct Role {}
+
+impl Role {
+    /// Create a new `Role` command
+    pub fn new() -> Role {
+        Role {}
+    }
+
+    /// Parsing the necessary arguments for the `Role` command
+    ///
+    /// Syntax:
+    /// ROLE
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Role, ParseError> {
+        if cmd_strings.len() != 1 {
+            return Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'role' command".to_string(),
+            ));
+        }
+
+        Ok(Role::new())
+    }
+
+    /// Execute the `Role` command
+    ///
+    /// As a master, returns the offset and the (ip, port, offset) of each replica.
+    /// As a replica, returns the master's host and port, the link state and the offset.
+    pub async fn execute(
+        self,
+        replication: &Replication,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let bulk_string = |text: String| {
+            RESPType::BulkString(Some(BulkStringData {
+                prefix_length: text.len(),
+                text,
+            }))
+        };
+
+        let offset = replication.master_repl_offset();
+
+        let response = match replication.role() {
+            ReplicationRole::Master => {
+                let replicas = replication
+                    .replicas()
+                    .into_iter()
+                    .map(|replica| {
+                        RESPType::Array(vec![
+                            bulk_string(replica.ip),
+                            bulk_string(replica.port.to_string()),
+                            bulk_string(replica.ack_offset.to_string()),
+                        ])
+                    })
+                    .collect();
+
+                RESPType::Array(vec![
+                    bulk_string("master".to_string()),
+                    RESPType::Integer(offset),
+                    RESPType::Array(replicas),
+                ])
+            }
+            ReplicationRole::Replica {
+                host, port, link, ..
+            } => RESPType::Array(vec![
+                bulk_string("slave".to_string()),
+                bulk_string(host),
+                RESPType::Integer(port as i64),
+                bulk_string(link.as_str().to_string()),
+                RESPType::Integer(offset),
+            ]),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -34,6 +34,11 @@ impl Rpush {
         }
     }
 
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
     /// Execute the `Rpush` command
     ///
     /// Returns an integer reply, representing
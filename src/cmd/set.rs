@@ -105,6 +105,11 @@ impl Set {
         }
     }
 
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
     /// Execute the `Set` command
     ///
     /// if 'get' flag is provided, returns the old string value or `None`
@@ -134,6 +139,13 @@ impl Set {
                         RESPType::SimpleString("\"OK\"".to_string())
                     }
                 }
+                DataType::Bytes(bytes) => {
+                    if self.get {
+                        RESPType::BulkBytes([b"\"", bytes.as_slice(), b"\""].concat())
+                    } else {
+                        RESPType::SimpleString("\"OK\"".to_string())
+                    }
+                }
                 _ => RESPType::SimpleString("\"OK\"".to_string()),
             },
             // Success: old_value for this `key` didn't exist
@@ -0,0 +1,88 @@
+use crate::cmd::ParseError;
+use crate::{ConnectionBase, RESPType, SharedStoreBase};
+
+/// The maximum bit offset, which limits bitmaps to 512 MB
+pub const MAX_BIT_OFFSET: u64 = (1 << 32) - 1;
+
+/// The SETBIT operation in Redis
+#[derive(Debug)]
+pub struct Setbit {
+    // The key of the string to modify
+    key: String,
+
+    // The bit offset within the string
+    offset: u64,
+
+    // The bit value to set, either 0 or 1
+    value: u8,
+}
+
+impl Setbit {
+    /// Create a new `SETBIT` command
+    pub fn new(key: String, offset: u64, value: u8) -> Setbit {
+        Setbit { key, offset, value }
+    }
+
+    /// Parsing the necessary arguments for the `SETBIT` command
+    ///
+    /// Syntax:
+    /// SETBIT key offset value
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Setbit, ParseError> {
+        if cmd_strings.len() != 4 {
+            return Err(ParseError::SyntaxError(
+                "ERR wrong number of arguments for 'setbit' command".to_string(),
+            ));
+        }
+
+        let offset = Setbit::parse_offset(&cmd_strings[2])?;
+
+        let value: u8 = match cmd_strings[3].as_str() {
+            "0" => 0,
+            "1" => 1,
+            _ => {
+                return Err(ParseError::SyntaxError(
+                    "ERR bit is not an integer or out of range".to_string(),
+                ))
+            }
+        };
+
+        Ok(Setbit::new(cmd_strings[1].clone(), offset, value))
+    }
+
+    /// Parse a bit offset, which must be a non-negative integer below `MAX_BIT_OFFSET`
+    pub fn parse_offset(arg: &str) -> Result<u64, ParseError> {
+        match arg.parse::<u64>() {
+            Ok(offset) if offset <= MAX_BIT_OFFSET => Ok(offset),
+            _ => Err(ParseError::SyntaxError(
+                "ERR bit offset is not an integer or out of range".to_string(),
+            )),
+        }
+    }
+
+    /// The keys accessed by the command
+    pub fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    /// Execute the `Setbit` command
+    ///
+    /// Returns an integer reply, of the original bit value
+    /// stored at the offset
+    pub async fn execute(
+        self,
+        shared_store: &dyn SharedStoreBase,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Set the bit in the shared store
+        let result = shared_store.setbit(self.key, self.offset, self.value);
+
+        let response = match result {
+            Ok(val) => RESPType::Integer(val as i64),
+            Err(err) => RESPType::Error(err.to_string()),
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use crate::cmd::ParseError;
+use crate::{ConnectionBase, RESPType, ServerContext};
+use tokio::time::Duration;
+
+/// The SHUTDOWN operation in Redis
+#[derive(Debug, Default, PartialEq)]
+pub struct Shutdown {
+    // Whether SAVE or NOSAVE was given
+    save: Option<bool>,
+    now: bool,
+    abort: bool,
+}
+
+impl Shutdown {
+    /// Create a new `Shutdown` command
+    pub fn new(save: Option<bool>, now: bool, abort: bool) -> Shutdown {
+        Shutdown { save, now, abort }
+    }
+
+    /// Parsing the necessary arguments for the `Shutdown` command
+    ///
+    /// Syntax:
+    /// SHUTDOWN [NOSAVE | SAVE] [NOW] [FORCE] [ABORT]
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Shutdown, ParseError> {
+        let syntax_error = || ParseError::SyntaxError("ERR syntax error".to_string());
+        let mut shutdown = Shutdown::default();
+
+        for arg in &cmd_strings[1..] {
+            match arg.to_lowercase().as_str() {
+                "save" if shutdown.save.is_none() => shutdown.save = Some(true),
+                "nosave" if shutdown.save.is_none() => shutdown.save = Some(false),
+                "now" => shutdown.now = true,
+                // The data store isn't persisted, so even FORCE can't make SAVE succeed
+                "force" => {}
+                "abort" => shutdown.abort = true,
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        // ABORT can't be combined with the other flags
+        if shutdown.abort && cmd_strings.len() > 2 {
+            return Err(syntax_error());
+        }
+
+        Ok(shutdown)
+    }
+
+    /// Execute the `Shutdown` command
+    ///
+    /// Unless NOW is given, waits for the replicas to acknowledge the whole
+    /// replication stream, for up to `shutdown-timeout` seconds. Then the
+    /// server stops accepting clients, and closes each connection once its
+    /// current command is done. There is no reply, unless the shutdown was
+    /// aborted meanwhile.
+    ///
+    /// ABORT cancels a shutdown which waits for the replicas, and returns OK.
+    ///
+    /// SAVE fails, as the data store isn't persisted and can't be saved.
+    pub async fn execute(
+        self,
+        server: &ServerContext,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let response = if self.abort {
+            match server.shutdown.abort() {
+                true => RESPType::SimpleString("OK".to_string()),
+                false => RESPType::Error("ERR No shutdown in progress.".to_string()),
+            }
+        } else if self.save == Some(true) {
+            println!("Error trying to save the DB, can't exit: the data store isn't persisted");
+            RESPType::Error("ERR Errors trying to SHUTDOWN. Check logs.".to_string())
+        } else {
+            let timeout = server.config.read().unwrap().shutdown_timeout;
+            let timeout = Duration::from_secs(timeout);
+
+            if server
+                .shutdown
+                .begin(&server.replication, timeout, self.now)
+                .await
+            {
+                return Ok(());
+            }
+
+            println!("Shutdown aborted");
+            RESPType::Error("ERR Errors trying to SHUTDOWN. Check logs.".to_string())
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
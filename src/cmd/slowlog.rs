@@ -0,0 +1,125 @@
+use crate::cmd::ParseError;
+use crate::protocol_handler::BulkStringData;
+use crate::slowlog::SlowLogEntry;
+use crate::{ConnectionBase, RESPType, SlowLog};
+
+/// The number of entries returned by SLOWLOG GET, by default
+const DEFAULT_SLOWLOG_GET_COUNT: usize = 10;
+
+/// The sub-commands of SLOWLOG
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlowlogSubcommand {
+    // The number of newest entries to return, or all of them if `None`
+    Get(Option<usize>),
+    Len,
+    Reset,
+}
+
+/// The SLOWLOG operation in Redis
+#[derive(Debug)]
+pub struct Slowlog {
+    subcommand: SlowlogSubcommand,
+}
+
+impl Slowlog {
+    /// Create a new `Slowlog` command
+    pub fn new(subcommand: SlowlogSubcommand) -> Slowlog {
+        Slowlog { subcommand }
+    }
+
+    /// Parsing the necessary arguments for the `Slowlog` command
+    ///
+    /// Syntax:
+    /// SLOWLOG GET [count]
+    /// SLOWLOG LEN
+    /// SLOWLOG RESET
+    pub fn parse(cmd_strings: Vec<String>) -> Result<Slowlog, ParseError> {
+        let subcommand = match cmd_strings.get(1) {
+            Some(arg) => arg.to_lowercase(),
+            None => {
+                return Err(ParseError::SyntaxError(
+                    "ERR wrong number of arguments for 'slowlog' command".to_string(),
+                ))
+            }
+        };
+
+        let wrong_args = || {
+            ParseError::SyntaxError(format!(
+                "ERR wrong number of arguments for 'slowlog|{}' command",
+                subcommand
+            ))
+        };
+
+        match (subcommand.as_str(), cmd_strings.len()) {
+            ("get", 2) => Ok(Slowlog::new(SlowlogSubcommand::Get(Some(
+                DEFAULT_SLOWLOG_GET_COUNT,
+            )))),
+            // A count of -1 returns every entry
+            ("get", 3) => match cmd_strings[2].parse::<i64>() {
+                Ok(-1) => Ok(Slowlog::new(SlowlogSubcommand::Get(None))),
+                Ok(count) if count >= 0 => {
+                    Ok(Slowlog::new(SlowlogSubcommand::Get(Some(count as usize))))
+                }
+                _ => Err(ParseError::SyntaxError(
+                    "ERR count should be greater than or equal to -1".to_string(),
+                )),
+            },
+            ("len", 2) => Ok(Slowlog::new(SlowlogSubcommand::Len)),
+            ("reset", 2) => Ok(Slowlog::new(SlowlogSubcommand::Reset)),
+            ("get" | "len" | "reset", _) => Err(wrong_args()),
+            _ => Err(ParseError::UnrecognizedCmd(format!(
+                "ERR unknown subcommand '{}'. Try SLOWLOG HELP.",
+                cmd_strings[1]
+            ))),
+        }
+    }
+
+    fn bulk_string(text: String) -> RESPType {
+        RESPType::BulkString(Some(BulkStringData {
+            prefix_length: text.len(),
+            text,
+        }))
+    }
+
+    /// The reply of an entry, as in Redis: its id, timestamp, duration,
+    /// arguments, and the address and name of its client
+    fn entry_reply(entry: SlowLogEntry) -> RESPType {
+        RESPType::Array(vec![
+            RESPType::Integer(entry.id as i64),
+            RESPType::Integer(entry.timestamp),
+            RESPType::Integer(entry.duration as i64),
+            RESPType::Array(entry.args.into_iter().map(Slowlog::bulk_string).collect()),
+            Slowlog::bulk_string(entry.client_addr),
+            Slowlog::bulk_string(entry.client_name),
+        ])
+    }
+
+    /// Execute the `Slowlog` command
+    ///
+    /// GET returns an array reply of the newest entries, from the newest one.
+    /// LEN returns an integer reply of the number of entries. RESET returns OK.
+    pub async fn execute(
+        self,
+        slowlog: &SlowLog,
+        cnxn: &mut dyn ConnectionBase,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let response = match self.subcommand {
+            SlowlogSubcommand::Get(count) => RESPType::Array(
+                slowlog
+                    .get(count)
+                    .into_iter()
+                    .map(Slowlog::entry_reply)
+                    .collect(),
+            ),
+            SlowlogSubcommand::Len => RESPType::Integer(slowlog.len() as i64),
+            SlowlogSubcommand::Reset => {
+                slowlog.reset();
+                RESPType::SimpleString("OK".to_string())
+            }
+        };
+
+        cnxn.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
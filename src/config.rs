@@ -0,0 +1,652 @@
+use crate::acl::COMMAND_CATEGORIES;
+use crate::cli::split_args;
+use crate::tls::TlsAuthClients;
+use crate::ProtocolLimits;
+use crate::{
+    EvictionPolicy, DEFAULT_ACTIVE_EXPIRE_KEYS, DEFAULT_HOST, DEFAULT_HZ, DEFAULT_IO_BUFFER_SIZE,
+    DEFAULT_MAXCLIENTS, DEFAULT_MAXMEMORY, DEFAULT_MAXMEMORY_POLICY, DEFAULT_NUM_SHARDS,
+    DEFAULT_PORT, DEFAULT_PROTECTED_MODE, DEFAULT_PROTO_MAX_BULK_LEN, DEFAULT_REPLICA_READ_ONLY,
+    DEFAULT_SHUTDOWN_TIMEOUT, DEFAULT_SLOWLOG_LOG_SLOWER_THAN, DEFAULT_SLOWLOG_MAX_LEN,
+    DEFAULT_TCP_KEEPALIVE, DEFAULT_TIMEOUT,
+};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// The parameters of CONFIG GET, SET and REWRITE, in the order of CONFIG GET *
+pub const PARAMETERS: &[&str] = &[
+    "bind",
+    "port",
+    "requirepass",
+    "protected-mode",
+    "maxclients",
+    "timeout",
+    "tcp-keepalive",
+    "shutdown-timeout",
+    "proto-max-bulk-len",
+    "tls-port",
+    "tls-cert-file",
+    "tls-key-file",
+    "tls-ca-cert-file",
+    "tls-auth-clients",
+    "unixsocket",
+    "unixsocketperm",
+    "io-buffer-size",
+    "hz",
+    "active-expire-keys",
+    "shards",
+    "maxmemory",
+    "maxmemory-policy",
+    "replica-read-only",
+    "cluster-enabled",
+    "cluster-port",
+    "metrics-port",
+    "latency-tracking-info-percentiles",
+    "slowlog-log-slower-than",
+    "slowlog-max-len",
+];
+
+/// The parameters which CONFIG SET can change, while the server is running
+pub const MUTABLE_PARAMETERS: &[&str] = &[
+    "requirepass",
+    "protected-mode",
+    "maxclients",
+    "timeout",
+    "tcp-keepalive",
+    "shutdown-timeout",
+    "proto-max-bulk-len",
+    "io-buffer-size",
+    "hz",
+    "active-expire-keys",
+    "maxmemory",
+    "maxmemory-policy",
+    "replica-read-only",
+    "latency-tracking-info-percentiles",
+    "slowlog-log-slower-than",
+    "slowlog-max-len",
+];
+
+/// The configuration of the server.
+///
+/// It is read from a `redust.conf` file, and from the command line, in the
+/// syntax of Redis: one directive per line, followed by its arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// The addresses to listen on, for both the plain and the TLS clients
+    pub bind: Vec<String>,
+
+    /// The port of the plain TCP listener, which is disabled with port 0
+    pub port: u16,
+
+    /// The password of the default user. Without one, it needs no password.
+    pub requirepass: Option<String>,
+
+    /// Refuse the clients which don't connect from the loopback interface,
+    /// while the default user needs no password
+    pub protected_mode: bool,
+
+    /// The maximum number of connected clients. The connections over it are
+    /// refused with an error.
+    pub maxclients: usize,
+
+    /// Close the connections of the clients which are idle for this many
+    /// seconds, unless it is 0
+    pub timeout: u64,
+
+    /// Send TCP keepalives to the clients every this many seconds, to detect
+    /// dead peers, unless it is 0
+    pub tcp_keepalive: u64,
+
+    /// The number of seconds which SHUTDOWN waits for the replicas to catch up
+    pub shutdown_timeout: u64,
+
+    /// The maximum length of the bulk strings which the clients send. The
+    /// connections which send longer ones are closed.
+    pub proto_max_bulk_len: usize,
+
+    pub rename_commands: CommandRenames,
+
+    /// The port of the TLS listener, which is disabled without one
+    pub tls_port: Option<u16>,
+
+    /// The certificate chain and private key of the TLS listener, in PEM files
+    pub tls_cert_file: Option<PathBuf>,
+    pub tls_key_file: Option<PathBuf>,
+
+    /// The CA certificates which the certificates of the clients are verified against
+    pub tls_ca_cert_file: Option<PathBuf>,
+    pub tls_auth_clients: TlsAuthClients,
+
+    /// The path of the Unix socket to listen on, if any, and its permissions
+    pub unixsocket: Option<PathBuf>,
+    pub unixsocketperm: Option<u32>,
+
+    /// The initial size of the read buffer of each connection, in bytes
+    pub io_buffer_size: usize,
+
+    /// How many times per second the expired keys are purged, and how many
+    /// keys with an expiry are sampled each time, per shard
+    pub hz: u64,
+    pub active_expire_keys: usize,
+
+    /// The number of shards, which the keyspace is split into
+    pub shards: usize,
+
+    /// The maximum number of bytes to use, before keys are evicted with the
+    /// `maxmemory_policy`. A limit of 0 disables eviction.
+    pub maxmemory: usize,
+    pub maxmemory_policy: EvictionPolicy,
+
+    /// Whether replicas only accept writes from their master
+    pub replica_read_only: bool,
+
+    /// Run as a node of a cluster, whose bus listens on `cluster_port`.
+    /// The bus port defaults to the port of the clients, plus 10000.
+    pub cluster_enabled: bool,
+    pub cluster_port: Option<u16>,
+
+    /// The port of the HTTP listener of the Prometheus metrics, which is
+    /// disabled without one
+    pub metrics_port: Option<u16>,
+
+    /// The percentiles of the latencies of each command, which INFO latencystats reports
+    pub latency_tracking_info_percentiles: Vec<f64>,
+
+    /// The commands which take longer than this many microseconds are added to
+    /// the slow log, which holds the latest `slowlog_max_len` of them. Every
+    /// command is logged with 0, and none with a negative threshold.
+    pub slowlog_log_slower_than: i64,
+    pub slowlog_max_len: usize,
+
+    /// The configuration file which the server was started with, if any,
+    /// which CONFIG REWRITE updates
+    pub config_file: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            bind: vec![DEFAULT_HOST.to_string()],
+            port: DEFAULT_PORT,
+            requirepass: None,
+            protected_mode: DEFAULT_PROTECTED_MODE,
+            maxclients: DEFAULT_MAXCLIENTS,
+            timeout: DEFAULT_TIMEOUT,
+            tcp_keepalive: DEFAULT_TCP_KEEPALIVE,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            proto_max_bulk_len: DEFAULT_PROTO_MAX_BULK_LEN,
+            rename_commands: CommandRenames::new(),
+            tls_port: None,
+            tls_cert_file: None,
+            tls_key_file: None,
+            tls_ca_cert_file: None,
+            tls_auth_clients: TlsAuthClients::Yes,
+            unixsocket: None,
+            unixsocketperm: None,
+            io_buffer_size: DEFAULT_IO_BUFFER_SIZE,
+            hz: DEFAULT_HZ,
+            active_expire_keys: DEFAULT_ACTIVE_EXPIRE_KEYS,
+            shards: DEFAULT_NUM_SHARDS,
+            maxmemory: DEFAULT_MAXMEMORY,
+            maxmemory_policy: DEFAULT_MAXMEMORY_POLICY,
+            replica_read_only: DEFAULT_REPLICA_READ_ONLY,
+            cluster_enabled: false,
+            cluster_port: None,
+            metrics_port: None,
+            latency_tracking_info_percentiles: vec![50.0, 99.0, 99.9],
+            slowlog_log_slower_than: DEFAULT_SLOWLOG_LOG_SLOWER_THAN,
+            slowlog_max_len: DEFAULT_SLOWLOG_MAX_LEN,
+            config_file: None,
+        }
+    }
+}
+
+impl Config {
+    /// Read the configuration file at `path`, over the default configuration
+    pub fn from_file(path: &Path) -> Result<Config, String> {
+        let mut config = Config::default();
+        config.load_file(path)?;
+        config.config_file = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    /// Apply the directives of the configuration file at `path`
+    pub fn load_file(&mut self, path: &Path) -> Result<(), String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("Can't open the config file '{}': {}", path.display(), err))?;
+
+        self.load(&text)
+            .map_err(|err| format!("{}, in '{}'", err, path.display()))
+    }
+
+    /// Apply the directives of the text of a configuration file.
+    ///
+    /// Each line holds a directive and its arguments, which may be quoted as in
+    /// redust-cli. Empty lines, and the lines starting with `#`, are ignored.
+    /// `include <path>` applies the directives of another file.
+    pub fn load(&mut self, text: &str) -> Result<(), String> {
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = |err: &dyn std::fmt::Display| {
+                format!("Bad directive at line {}: '{}': {}", index + 1, line, err)
+            };
+
+            let args = split_args(line).map_err(|err| invalid(&err))?;
+            let (name, args) = args.split_first().expect("the line isn't empty");
+
+            if name.eq_ignore_ascii_case("include") {
+                match args {
+                    [path] => self
+                        .load_file(Path::new(path))
+                        .map_err(|err| invalid(&err))?,
+                    _ => return Err(invalid(&"wrong number of arguments")),
+                }
+                continue;
+            }
+
+            self.set(name, args).map_err(|err| invalid(&err))?;
+        }
+
+        Ok(())
+    }
+
+    /// Apply a single directive, such as `port 6380`, with its arguments
+    pub fn set(&mut self, name: &str, args: &[String]) -> Result<(), String> {
+        let name = name.to_lowercase();
+
+        // `bind`, `rename-command` and `latency-tracking-info-percentiles` are the
+        // only directives, which don't take a single argument
+        match (name.as_str(), args) {
+            ("latency-tracking-info-percentiles", values) => {
+                // The percentiles may also be given as a single, quoted argument
+                let percentiles = values
+                    .iter()
+                    .flat_map(|value| value.split_whitespace())
+                    .map(|value| value.parse::<f64>())
+                    .collect::<Result<Vec<f64>, _>>()
+                    .ok()
+                    .filter(|percentiles| {
+                        percentiles
+                            .iter()
+                            .all(|percentile| (0.0..=100.0).contains(percentile))
+                    })
+                    .ok_or("percentiles must be numbers between 0 and 100")?;
+
+                self.latency_tracking_info_percentiles = percentiles;
+                return Ok(());
+            }
+            ("bind", []) => return Err("wrong number of arguments".to_string()),
+            ("bind", addresses) => {
+                self.bind = addresses.to_vec();
+                return Ok(());
+            }
+            ("rename-command", [command, new_name]) => {
+                return self.rename_commands.rename(command, new_name)
+            }
+            (_, [_]) => {}
+            _ => return Err("wrong number of arguments".to_string()),
+        }
+
+        let value = args[0].as_str();
+        let number = || {
+            value
+                .parse::<u64>()
+                .map_err(|_| "argument must be a number")
+        };
+        let yes_no = || parse_bool(value).ok_or("argument must be 'yes' or 'no'");
+        let path = || Some(PathBuf::from(value)).filter(|_| !value.is_empty());
+
+        match canonical_name(&name) {
+            "port" => self.port = value.parse().map_err(|_| "Invalid port")?,
+            // An empty password removes it
+            "requirepass" => {
+                self.requirepass = Some(value.to_string()).filter(|_| !value.is_empty())
+            }
+            "protected-mode" => self.protected_mode = yes_no()?,
+            "maxclients" => match number()? {
+                0 => return Err("Invalid maxclients, it must be positive".to_string()),
+                maxclients => self.maxclients = maxclients as usize,
+            },
+            "timeout" => self.timeout = number()?,
+            "tcp-keepalive" => self.tcp_keepalive = number()?,
+            "shutdown-timeout" => self.shutdown_timeout = number()?,
+            "proto-max-bulk-len" => match parse_memory(value)? {
+                len if len < 1024 * 1024 => {
+                    return Err("Invalid proto-max-bulk-len, it must be at least 1mb".to_string())
+                }
+                len => self.proto_max_bulk_len = len,
+            },
+            "tls-port" => {
+                // Like the port, the TLS port is disabled with port 0
+                let port: u16 = value.parse().map_err(|_| "Invalid tls-port")?;
+                self.tls_port = Some(port).filter(|port| *port != 0);
+            }
+            "tls-cert-file" => self.tls_cert_file = path(),
+            "tls-key-file" => self.tls_key_file = path(),
+            "tls-ca-cert-file" => self.tls_ca_cert_file = path(),
+            "tls-auth-clients" => {
+                self.tls_auth_clients = TlsAuthClients::parse(value)
+                    .ok_or("argument must be 'yes', 'no' or 'optional'")?
+            }
+            "unixsocket" => self.unixsocket = path(),
+            "unixsocketperm" => {
+                let perm =
+                    u32::from_str_radix(value, 8).map_err(|_| "Invalid socket permissions")?;
+                self.unixsocketperm = Some(perm).filter(|perm| *perm != 0);
+            }
+            "io-buffer-size" => self.io_buffer_size = parse_memory(value)?,
+            "hz" => match number()? {
+                hz @ 1..=500 => self.hz = hz,
+                _ => return Err("Invalid hz, it must be between 1 and 500".to_string()),
+            },
+            "active-expire-keys" => match number()? {
+                0 => return Err("Invalid active-expire-keys, it must be positive".to_string()),
+                keys => self.active_expire_keys = keys as usize,
+            },
+            "shards" => match number()? {
+                0 => return Err("Invalid shards, there must be at least one".to_string()),
+                shards => self.shards = shards as usize,
+            },
+            "maxmemory" => self.maxmemory = parse_memory(value)?,
+            "maxmemory-policy" => {
+                self.maxmemory_policy =
+                    EvictionPolicy::parse(value).ok_or("Invalid maxmemory-policy")?
+            }
+            "replica-read-only" => self.replica_read_only = yes_no()?,
+            "cluster-enabled" => self.cluster_enabled = yes_no()?,
+            "cluster-port" => {
+                let port: u16 = value.parse().map_err(|_| "Invalid cluster-port")?;
+                self.cluster_port = Some(port).filter(|port| *port != 0);
+            }
+            "metrics-port" => {
+                let port: u16 = value.parse().map_err(|_| "Invalid metrics-port")?;
+                self.metrics_port = Some(port).filter(|port| *port != 0);
+            }
+            "slowlog-log-slower-than" => {
+                self.slowlog_log_slower_than =
+                    value.parse().map_err(|_| "argument must be a number")?
+            }
+            "slowlog-max-len" => self.slowlog_max_len = number()? as usize,
+            _ => return Err(format!("Unknown directive '{}'", name)),
+        }
+
+        Ok(())
+    }
+
+    /// The value of the parameter `name`, as CONFIG GET reports it
+    pub fn get(&self, name: &str) -> Option<String> {
+        let path = |path: &Option<PathBuf>| match path {
+            Some(path) => path.display().to_string(),
+            None => String::new(),
+        };
+        let yes_no = |value: bool| if value { "yes" } else { "no" }.to_string();
+
+        let value = match canonical_name(name) {
+            "bind" => self.bind.join(" "),
+            "port" => self.port.to_string(),
+            "requirepass" => self.requirepass.clone().unwrap_or_default(),
+            "protected-mode" => yes_no(self.protected_mode),
+            "maxclients" => self.maxclients.to_string(),
+            "timeout" => self.timeout.to_string(),
+            "tcp-keepalive" => self.tcp_keepalive.to_string(),
+            "shutdown-timeout" => self.shutdown_timeout.to_string(),
+            "proto-max-bulk-len" => self.proto_max_bulk_len.to_string(),
+            "tls-port" => self.tls_port.unwrap_or(0).to_string(),
+            "tls-cert-file" => path(&self.tls_cert_file),
+            "tls-key-file" => path(&self.tls_key_file),
+            "tls-ca-cert-file" => path(&self.tls_ca_cert_file),
+            "tls-auth-clients" => self.tls_auth_clients.to_string(),
+            "unixsocket" => path(&self.unixsocket),
+            "unixsocketperm" => format!("{:o}", self.unixsocketperm.unwrap_or(0)),
+            "io-buffer-size" => self.io_buffer_size.to_string(),
+            "hz" => self.hz.to_string(),
+            "active-expire-keys" => self.active_expire_keys.to_string(),
+            "shards" => self.shards.to_string(),
+            "maxmemory" => self.maxmemory.to_string(),
+            "maxmemory-policy" => self.maxmemory_policy.to_string(),
+            "replica-read-only" => yes_no(self.replica_read_only),
+            "cluster-enabled" => yes_no(self.cluster_enabled),
+            "cluster-port" => self.cluster_port.unwrap_or(0).to_string(),
+            "metrics-port" => self.metrics_port.unwrap_or(0).to_string(),
+            "latency-tracking-info-percentiles" => self
+                .latency_tracking_info_percentiles
+                .iter()
+                .map(|percentile| percentile.to_string())
+                .collect::<Vec<String>>()
+                .join(" "),
+            "slowlog-log-slower-than" => self.slowlog_log_slower_than.to_string(),
+            "slowlog-max-len" => self.slowlog_max_len.to_string(),
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    /// The limits on the frames which the clients send
+    pub fn protocol_limits(&self) -> ProtocolLimits {
+        ProtocolLimits {
+            max_bulk_len: self.proto_max_bulk_len,
+            ..ProtocolLimits::default()
+        }
+    }
+
+    /// The line of a configuration file, which sets the parameter `name` to its value
+    fn directive(&self, name: &str) -> String {
+        let value = self.get(name).unwrap_or_default();
+
+        // Each address of `bind` is an argument of its own
+        let args: Vec<String> = match name {
+            "bind" => self.bind.iter().map(|address| quote_arg(address)).collect(),
+            _ => vec![quote_arg(&value)],
+        };
+
+        format!("{} {}", name, args.join(" "))
+    }
+
+    /// Rewrite the `text` of a configuration file, to hold the current values of
+    /// the parameters.
+    ///
+    /// The first directive of each parameter is replaced in place, and its later
+    /// duplicates are removed. The comments, and the other directives such as
+    /// `rename-command` and `include`, are kept as they are. The parameters which
+    /// aren't in the file yet are appended, unless they hold their default value.
+    pub fn rewrite(&self, text: &str) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        let mut rewritten: HashSet<&str> = HashSet::new();
+
+        for line in text.lines() {
+            let name = match split_args(line.trim()) {
+                Ok(args) if !args.is_empty() => args[0].to_lowercase(),
+                _ => String::new(),
+            };
+
+            match PARAMETERS
+                .iter()
+                .find(|param| **param == canonical_name(&name))
+            {
+                Some(param) if rewritten.insert(param) => lines.push(self.directive(param)),
+                Some(_) => {}
+                None => lines.push(line.to_string()),
+            }
+        }
+
+        let defaults = Config::default();
+        let mut appended = PARAMETERS
+            .iter()
+            .filter(|param| !rewritten.contains(*param) && self.get(param) != defaults.get(param))
+            .peekable();
+
+        if appended.peek().is_some() {
+            lines.push("# Generated by CONFIG REWRITE".to_string());
+            lines.extend(appended.map(|param| self.directive(param)));
+        }
+
+        let mut text = lines.join("\n");
+        text.push('\n');
+        text
+    }
+
+    /// Rewrite the configuration file, which the server was started with.
+    ///
+    /// The new file is written next to it, and then moved over it, so that the
+    /// file is never left half-written.
+    pub fn rewrite_file(&self) -> Result<(), String> {
+        let path = self
+            .config_file
+            .as_ref()
+            .ok_or("The server is running without a config file")?;
+
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(format!("Rewriting config file: {}", err)),
+        };
+
+        let mut temp = path.clone().into_os_string();
+        temp.push(".tmp");
+
+        std::fs::write(&temp, self.rewrite(&text))
+            .and_then(|_| std::fs::rename(&temp, path))
+            .map_err(|err| format!("Rewriting config file: {}", err))
+    }
+}
+
+/// The name of the parameter, which `name` is an alias of
+pub fn canonical_name(name: &str) -> &str {
+    match name {
+        "slave-read-only" => "replica-read-only",
+        name => name,
+    }
+}
+
+/// Quote an argument of a configuration file, if it is empty or holds
+/// whitespace, quotes or special characters, so that it is read back as is
+fn quote_arg(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_graphic() && c != '"' && c != '\'' && c != '\\';
+
+    if !arg.is_empty() && arg.chars().all(plain) {
+        return arg.to_string();
+    }
+
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\x{:02x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+/// Parse a yes/no value of the configuration
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse an amount of memory, such as `100mb`. As in Redis, the units `k`, `m`
+/// and `g` are powers of 1000, and `kb`, `mb` and `gb` are powers of 1024.
+pub fn parse_memory(value: &str) -> Result<usize, String> {
+    let value = value.to_lowercase();
+    let digits = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+
+    let unit: usize = match &value[digits.len()..] {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return Err(format!("Invalid memory unit in '{}'", value)),
+    };
+
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|amount| amount.checked_mul(unit))
+        .ok_or_else(|| format!("Invalid amount of memory '{}'", value))
+}
+
+/// The commands renamed, or disabled, with `rename-command`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandRenames {
+    // From the original name of each command, to its new name.
+    // Disabled commands are renamed to an empty name.
+    renames: HashMap<String, String>,
+}
+
+impl CommandRenames {
+    pub fn new() -> CommandRenames {
+        CommandRenames {
+            renames: HashMap::new(),
+        }
+    }
+
+    /// The name of the command which `name` is an alias of
+    pub(crate) fn original_name(name: &str) -> &str {
+        match name {
+            "slaveof" => "replicaof",
+            name => name,
+        }
+    }
+
+    /// Rename the `command` to `new_name`, or disable it if `new_name` is empty.
+    /// Its aliases are renamed along with it.
+    pub fn rename(&mut self, command: &str, new_name: &str) -> Result<(), String> {
+        let command = command.to_lowercase();
+        let original = CommandRenames::original_name(&command);
+
+        if !COMMAND_CATEGORIES.iter().any(|(name, _)| *name == original) {
+            return Err(format!("No such command '{}' in rename-command", command));
+        }
+
+        let new_name = new_name.to_lowercase();
+        if !new_name.is_empty()
+            && (self.renames.values().any(|name| *name == new_name)
+                || COMMAND_CATEGORIES.iter().any(|(name, _)| *name == new_name))
+        {
+            return Err(format!("Command name '{}' is already in use", new_name));
+        }
+
+        self.renames.insert(original.to_string(), new_name);
+
+        Ok(())
+    }
+
+    /// The original name of the command which a client sent as `name`, or
+    /// `None` if that command was renamed or disabled.
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        let name = name.to_lowercase();
+
+        if let Some((original, _)) = self
+            .renames
+            .iter()
+            .find(|(_, new_name)| !new_name.is_empty() && **new_name == name)
+        {
+            return Some(original.clone());
+        }
+
+        if self
+            .renames
+            .contains_key(CommandRenames::original_name(&name))
+        {
+            return None;
+        }
+
+        Some(name)
+    }
+}
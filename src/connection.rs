@@ -1,7 +1,9 @@
-use crate::{deserialize_buffer, serialize_data, RESPType};
+use crate::protocol_handler::parse_frame;
+use crate::{serialize_data, ProtocolLimits, RESPType, DEFAULT_IO_BUFFER_SIZE};
 use async_trait::async_trait;
 use mockall::automock;
-use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
+use std::net::SocketAddr;
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::TcpStream;
 
 /// I wanted to conditionally compile automock
@@ -27,11 +29,15 @@ pub trait ConnectionBase: Send + Sync {
     async fn read_frame(&mut self) -> Result<Option<RESPType>, Box<dyn std::error::Error>>;
 
     async fn write_frame(&mut self, frame: &RESPType) -> tokio::io::Result<()>;
+
+    /// The address of the remote peer, if it is known
+    fn peer_addr(&self) -> Option<SocketAddr>;
 }
 
 /// The purpose of `Connection` is to read and write frames on the
-/// underlying `TcpStream`, which is established between the client
-/// and the server.
+/// underlying stream, which is established between the client
+/// and the server. This is a `TcpStream` by default, but any
+/// `AsyncRead + AsyncWrite` stream can be used, e.g. a TLS stream.
 ///
 /// When reading frames, the `Connection` uses an internal buffer,
 /// of type Vec<u8> which is filled up until we get a valid frame
@@ -39,34 +45,76 @@ pub trait ConnectionBase: Send + Sync {
 /// bytes, and if a valid frame is found, it is returned to the caller.
 ///
 /// When writing frames, the data is first serialized into the RESP format
-/// and then all of it is written into the TCP Stream
+/// and then all of it is written into the stream
 #[derive(Debug)]
-pub struct Connection {
-    // The TCP Stream for reading and writing to the client
-    stream: TcpStream,
+pub struct Connection<S = TcpStream> {
+    // The stream for reading and writing to the client
+    stream: S,
 
     // The buffer for reading frames.
     buffer: Vec<u8>,
+
+    // The address of the peer, which streams don't all expose
+    peer_addr: Option<SocketAddr>,
+
+    // The limits on the frames which are read
+    limits: ProtocolLimits,
 }
 
 impl Connection {
     pub fn new(socket: TcpStream) -> Connection {
+        let peer_addr = socket.peer_addr().ok();
+        Connection::from_stream(socket, peer_addr)
+    }
+}
+
+impl<S> Connection<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + Sync,
+{
+    /// Create a `Connection` on any stream, with the address of its peer, if any
+    pub fn from_stream(stream: S, peer_addr: Option<SocketAddr>) -> Connection<S> {
+        Connection::with_buffer_size(stream, peer_addr, DEFAULT_IO_BUFFER_SIZE)
+    }
+
+    /// Create a `Connection`, whose read buffer initially holds `buffer_size` bytes.
+    ///
+    /// A greater buffer might improve performance, for clients which send large
+    /// or pipelined requests. It is still allowed to reallocate to increase its
+    /// capacity, for frames which don't fit into it.
+    ///
+    /// Panics: if `buffer_size` exceeds `isize::MAX`
+    pub fn with_buffer_size(
+        stream: S,
+        peer_addr: Option<SocketAddr>,
+        buffer_size: usize,
+    ) -> Connection<S> {
         Connection {
-            stream: socket,
-            // Default to a 4KB read buffer, this should be configured
-            // based on the requirements of the application, as a greater
-            // buffer might improve performance
-            // It is allowed to reallocate to increase its capacity
-            //
-            // Panics: if capacity exceeds `isize::MAX`
-            // TODO: This needs to change
-            buffer: Vec::with_capacity(4 * 1024),
+            stream,
+            buffer: Vec::with_capacity(buffer_size),
+            peer_addr,
+            limits: ProtocolLimits::default(),
         }
     }
+
+    /// Set the limits on the frames which are read. The frames over them
+    /// fail with a `ProtocolError`, without being buffered whole.
+    pub fn set_limits(&mut self, limits: ProtocolLimits) {
+        self.limits = limits;
+    }
+
+    /// The number of bytes received which weren't parsed into a frame yet,
+    /// and the capacity of the read buffer, e.g. for CLIENT LIST
+    pub fn read_buffer(&self) -> (usize, usize) {
+        (self.buffer.len(), self.buffer.capacity())
+    }
 }
 
 #[async_trait]
-impl ConnectionBase for Connection {
+impl<S> ConnectionBase for Connection<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + Sync,
+{
     async fn read_frame(&mut self) -> Result<Option<RESPType>, Box<dyn std::error::Error>> {
         loop {
             // Attempt to deserialize a frame from the data in the buffer.
@@ -76,8 +124,9 @@ impl ConnectionBase for Connection {
             // If a partial frame is in the buffer, this will return `None` and `0`
             // Therefore, we will keep reading more data.
             //
-            // If the frame does not fit into the buffer, we will reallocate space anyway, to keep reading.
-            let (frame, frame_size) = deserialize_buffer(&self.buffer.as_slice());
+            // If the frame does not fit into the buffer, we will reallocate space anyway, to keep reading,
+            // unless the frame is over the limits, which is an error.
+            let (frame, frame_size) = parse_frame(self.buffer.as_slice(), &self.limits)?;
             // If we got a valid frame, return it and
             // drain the buffer upto the frame_size
             if frame.is_some() {
@@ -103,7 +152,7 @@ impl ConnectionBase for Connection {
     }
 
     /// Serializes the frame and attempt to
-    /// write the whole buffer to the TCPStream
+    /// write the whole buffer to the stream
     async fn write_frame(&mut self, frame: &RESPType) -> io::Result<()> {
         let data = serialize_data(&frame).unwrap();
 
@@ -113,4 +162,114 @@ impl ConnectionBase for Connection {
         // Make sure that any buffered contents are written.
         self.stream.flush().await
     }
+
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+}
+
+/// A `ConnectionBase` which collects the written frames in memory,
+/// rather than writing them to a stream.
+///
+/// This is used to execute commands while holding a lock, without waiting
+/// on a slow client, and to discard the replies to the commands which are
+/// received from a master.
+#[derive(Debug, Default)]
+pub struct ReplyBuffer {
+    frames: Vec<RESPType>,
+}
+
+impl ReplyBuffer {
+    pub fn new() -> ReplyBuffer {
+        ReplyBuffer::default()
+    }
+
+    /// Whether any of the collected frames is an error
+    pub fn contains_error(&self) -> bool {
+        self.frames
+            .iter()
+            .any(|frame| matches!(frame, RESPType::Error(_)))
+    }
+
+    /// The messages of the collected errors
+    pub fn errors(&self) -> Vec<&str> {
+        self.frames
+            .iter()
+            .filter_map(|frame| match frame {
+                RESPType::Error(message) => Some(message.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Write all the collected frames to the `cnxn`, in order
+    pub async fn flush_to(self, cnxn: &mut dyn ConnectionBase) -> io::Result<()> {
+        for frame in self.frames.iter() {
+            cnxn.write_frame(frame).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ConnectionBase for ReplyBuffer {
+    async fn read_frame(&mut self) -> Result<Option<RESPType>, Box<dyn std::error::Error>> {
+        Ok(None)
+    }
+
+    async fn write_frame(&mut self, frame: &RESPType) -> io::Result<()> {
+        self.frames.push(frame.clone());
+
+        Ok(())
+    }
+
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        None
+    }
+}
+
+/// Passes the replies of a command through to the connection, while noting
+/// the errors among them, e.g. to count the failed calls.
+pub struct ErrorTracker<'a> {
+    cnxn: &'a mut dyn ConnectionBase,
+    errors: Vec<String>,
+}
+
+impl<'a> ErrorTracker<'a> {
+    pub fn new(cnxn: &'a mut dyn ConnectionBase) -> ErrorTracker<'a> {
+        ErrorTracker {
+            cnxn,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Whether any of the replies was an error
+    pub fn failed(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    /// The messages of the errors among the replies
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+}
+
+#[async_trait]
+impl ConnectionBase for ErrorTracker<'_> {
+    async fn read_frame(&mut self) -> Result<Option<RESPType>, Box<dyn std::error::Error>> {
+        self.cnxn.read_frame().await
+    }
+
+    async fn write_frame(&mut self, frame: &RESPType) -> io::Result<()> {
+        if let RESPType::Error(message) = frame {
+            self.errors.push(message.clone());
+        }
+
+        self.cnxn.write_frame(frame).await
+    }
+
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        self.cnxn.peer_addr()
+    }
 }
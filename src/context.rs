@@ -0,0 +1,98 @@
+use crate::{
+    Acl, Clients, Cluster, Config, Monitor, Replication, SharedStore, Shutdown, SlowLog, Stats,
+    DEFAULT_USER,
+};
+use std::sync::{Arc, RwLock};
+
+/// The server state which commands are executed against, shared by every
+/// connection. Each field is a handle around an `Arc`, so a clone only
+/// increments the reference counts.
+#[derive(Debug, Clone)]
+pub struct ServerContext {
+    pub shared_store: SharedStore,
+    pub replication: Replication,
+
+    /// Disabled, unless the server was started with `run_cluster`
+    pub cluster: Cluster,
+    pub acl: Acl,
+
+    /// Parts of the configuration can be changed by CONFIG SET, while running
+    pub config: Arc<RwLock<Config>>,
+
+    /// The counters of the connections and commands, for INFO
+    pub stats: Stats,
+
+    /// The latest commands which were slower than `slowlog-log-slower-than`
+    pub slowlog: SlowLog,
+
+    /// The feed of the processed commands, to the connections running MONITOR
+    pub monitor: Monitor,
+
+    /// The connected clients, for CLIENT LIST and CLIENT KILL
+    pub clients: Clients,
+
+    /// Triggered by SHUTDOWN, to stop the server
+    pub shutdown: Shutdown,
+}
+
+impl ServerContext {
+    pub fn new(
+        shared_store: SharedStore,
+        replication: Replication,
+        cluster: Cluster,
+        acl: Acl,
+        config: Config,
+    ) -> ServerContext {
+        let clients = Clients::new(config.maxclients);
+
+        ServerContext {
+            shared_store,
+            replication,
+            cluster,
+            acl,
+            config: Arc::new(RwLock::new(config)),
+            stats: Stats::new(),
+            slowlog: SlowLog::new(),
+            monitor: Monitor::new(),
+            clients,
+            shutdown: Shutdown::new(),
+        }
+    }
+}
+
+/// The state of a single connection, which commands may read or change
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientContext {
+    /// The id of the connection in the registry of `Clients`, or 0 if it
+    /// isn't registered, e.g. the link to the master
+    pub id: u64,
+
+    /// The address of the peer
+    pub addr: String,
+
+    /// The user the connection is authenticated as, or will be by default
+    pub user: String,
+    pub authenticated: bool,
+
+    /// The name of the connection, set by CLIENT SETNAME
+    pub name: String,
+}
+
+impl ClientContext {
+    /// Create the state of a new connection from `addr`. It is authenticated
+    /// as the default user, if that one needs no password.
+    pub fn new(addr: &str, acl: &Acl) -> ClientContext {
+        ClientContext {
+            id: 0,
+            addr: addr.to_string(),
+            user: DEFAULT_USER.to_string(),
+            authenticated: acl.default_user_needs_no_auth(),
+            name: String::new(),
+        }
+    }
+
+    /// Describe the connection, for the ACL LOG
+    pub fn info(&self) -> String {
+        format!("addr={} user={}", self.addr, self.user)
+    }
+}
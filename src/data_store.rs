@@ -1,11 +1,28 @@
-use crate::{cmd::ParseError, KEY_EXPIRY_DELAY_MS, KEY_EXPIRY_NUM_KEYS_TO_CHECK};
-use chrono::{DateTime, Duration, Utc};
+use crate::{
+    cli::command_frame,
+    client_pause::ClientPause,
+    cluster,
+    cmd::{
+        BitOperation, BitUnit, BitfieldEncoding, BitfieldOperation, BitfieldOverflow, GeoOrder,
+        GeoOrigin, GeoSearchMatch, GeoSearchQuery, GeoShape, ParseError,
+    },
+    eviction::{self, EvictionPolicy},
+    geo::{self, GeoUnit},
+    protocol_handler::{BulkStringData, RESPType},
+    sorted_set::SortedSet,
+    Config, Replication, DEFAULT_ACTIVE_EXPIRE_KEYS, DEFAULT_HZ, DEFAULT_MAXMEMORY,
+    DEFAULT_MAXMEMORY_POLICY, DEFAULT_NUM_SHARDS, MAXMEMORY_SAMPLES,
+};
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use mockall::automock;
-use rand::seq::index::sample;
+use rand::{seq::index::sample, Rng};
 use std::{
     cell::RefCell,
-    collections::{HashMap, LinkedList},
-    sync::{Arc, Mutex},
+    collections::{BTreeMap, BTreeSet, HashMap, LinkedList},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
 };
 use tokio::time::sleep;
 
@@ -35,6 +52,84 @@ pub trait SharedStoreBase: Send + Sync {
     fn lrange(&self, key: String, start: i64, stop: i64) -> Result<Vec<String>, ParseError>;
 
     fn rpush(&self, key: String, elements: Vec<String>) -> Result<i64, ParseError>;
+
+    fn setbit(&self, key: String, offset: u64, value: u8) -> Result<u8, ParseError>;
+
+    fn getbit(&self, key: String, offset: u64) -> Result<u8, ParseError>;
+
+    fn bitcount(
+        &self,
+        key: String,
+        range: Option<(i64, i64)>,
+        unit: BitUnit,
+    ) -> Result<i64, ParseError>;
+
+    fn bitpos(
+        &self,
+        key: String,
+        bit: u8,
+        start: Option<i64>,
+        end: Option<i64>,
+        unit: BitUnit,
+    ) -> Result<i64, ParseError>;
+
+    fn bitop(
+        &self,
+        operation: BitOperation,
+        destkey: String,
+        keys: Vec<String>,
+    ) -> Result<i64, ParseError>;
+
+    fn bitfield(
+        &self,
+        key: String,
+        operations: Vec<BitfieldOperation>,
+    ) -> Result<Vec<Option<i64>>, ParseError>;
+
+    fn memory_usage(&self, key: String, samples: usize) -> Option<usize>;
+
+    fn memory_stats(&self) -> MemoryStats;
+
+    fn object_info(&self, key: String) -> Option<ObjectInfo>;
+
+    fn geoadd(
+        &self,
+        key: String,
+        locations: Vec<(f64, f64, String)>,
+        nx: bool,
+        xx: bool,
+        ch: bool,
+    ) -> Result<i64, ParseError>;
+
+    fn geopos(
+        &self,
+        key: String,
+        members: Vec<String>,
+    ) -> Result<Vec<Option<(f64, f64)>>, ParseError>;
+
+    fn geodist(
+        &self,
+        key: String,
+        member1: String,
+        member2: String,
+    ) -> Result<Option<f64>, ParseError>;
+
+    fn geohash(&self, key: String, members: Vec<String>)
+        -> Result<Vec<Option<String>>, ParseError>;
+
+    fn geosearch(
+        &self,
+        key: String,
+        query: GeoSearchQuery,
+    ) -> Result<Vec<GeoSearchMatch>, ParseError>;
+
+    fn geosearchstore(
+        &self,
+        destination: String,
+        source: String,
+        query: GeoSearchQuery,
+        storedist: Option<GeoUnit>,
+    ) -> Result<i64, ParseError>;
 }
 
 /// Shared Data Store across all the connections
@@ -54,15 +149,38 @@ pub struct SharedStore {
 
 #[derive(Debug)]
 pub struct GuardedDataStore {
-    /// The `DataStore` which contains the `data` and `date_time`
-    /// is guarded by a `Mutex` to prevent concurrent access as
-    /// this may lead to data inconsistency
+    /// The keyspace is split into independent shards, each a `DataStore`
+    /// which contains the `data` and `date_time` of its keys. Each shard
+    /// is guarded by a `Mutex` to prevent concurrent access as this may
+    /// lead to data inconsistency, while commands on the keys of different
+    /// shards can run in parallel.
     ///
     /// Since there are no asynchronous operations as part of the
     /// critical section, we opt to use `std::sync::Mutex`. Moreover,
     /// the critical section is pretty small.
     ///
-    store: Mutex<DataStore>,
+    shards: Vec<Mutex<DataStore>>,
+
+    /// The maximum number of bytes to use across all the shards, before
+    /// keys are evicted. A limit of 0 disables eviction.
+    maxmemory: AtomicUsize,
+
+    /// The highest total `used_memory` of the shards, since the data store was created
+    peak_memory: AtomicUsize,
+
+    /// How often the expiry task of each shard runs, in milliseconds, and
+    /// how many of the keys with an expiry it samples on each run
+    expiry_delay_ms: AtomicU64,
+    expiry_keys_to_check: AtomicUsize,
+
+    /// The number of runs of the expiry tasks of all the shards, and their
+    /// total duration in microseconds
+    expiry_cycles: AtomicU64,
+    expiry_cycles_usec: AtomicU64,
+
+    /// While clients are paused, keys aren't expired or evicted,
+    /// since that would modify the data store.
+    pause: ClientPause,
 }
 
 #[derive(Debug)]
@@ -75,6 +193,39 @@ pub struct DataStore {
     /// they have a Key Expiry or not.
     /// TimeSpan holds the value when this key will expire.
     date_time: HashMap<String, TimeSpan>,
+
+    /// All keys in `data` are part of this HashMap.
+    /// KeyInfo holds the approximate size, and access statistics of the key,
+    /// which are used to pick keys for eviction.
+    key_info: HashMap<String, KeyInfo>,
+
+    /// The approximate number of bytes used by the keys of the shard
+    used_memory: usize,
+
+    /// Which keys are evicted, once `maxmemory` is reached
+    eviction_policy: EvictionPolicy,
+
+    /// The counters of the keys of the shard, for INFO
+    stats: KeyspaceStats,
+
+    /// The replication stream, which the deletions of expired and evicted keys
+    /// are fed into, as DEL, so that the replicas delete them too
+    replication: Option<Replication>,
+}
+
+/// The counters of the keyspace, as reported in the stats section of INFO
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KeyspaceStats {
+    /// The number of reads of keys which existed, and of keys which didn't
+    pub keyspace_hits: u64,
+    pub keyspace_misses: u64,
+
+    /// The number of keys removed once their expiry passed, either by the
+    /// expiry task or when they were accessed
+    pub expired_keys: u64,
+
+    /// The number of keys removed to stay under the `maxmemory` limit
+    pub evicted_keys: u64,
 }
 
 /// The supported data types which can be stored in the `DataStore`
@@ -82,8 +233,14 @@ pub struct DataStore {
 pub enum DataType {
     String(String),
 
+    // A string which isn't valid UTF-8, e.g. a bitmap with its high bits set.
+    Bytes(Vec<u8>),
+
     // Needs to be behind a RefCell, to mutate in-place.
     LinkedList(RefCell<LinkedList<String>>),
+
+    // Also used to store geospatial indexes, with the geohash as the score.
+    SortedSet(SortedSet),
 }
 
 #[derive(Debug)]
@@ -91,20 +248,798 @@ pub struct TimeSpan {
     expires_at: DateTime<Utc>,
 }
 
+#[derive(Debug)]
+pub struct KeyInfo {
+    /// The approximate number of bytes used by the key and its value
+    size: usize,
+
+    /// When the key was last read or written
+    last_access: DateTime<Utc>,
+
+    /// The logarithmic access frequency counter, which decays over time
+    lfu_counter: u8,
+}
+
+/// A summary of the memory used by the `DataStore`, as reported by MEMORY STATS
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryStats {
+    /// The highest number of bytes used, since the data store was created
+    pub peak_memory: usize,
+
+    /// The approximate number of bytes used by all the keys
+    pub used_memory: usize,
+
+    /// The number of bytes used by the keys themselves, and the HashMap entries
+    pub overhead: usize,
+
+    /// The number of bytes used by the values
+    pub dataset: usize,
+
+    pub maxmemory: usize,
+    pub eviction_policy: EvictionPolicy,
+
+    /// The number of keys
+    pub keys: usize,
+
+    /// The number of keys with an expiry
+    pub expires: usize,
+}
+
+/// The internal details of a key, as reported by OBJECT
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectInfo {
+    /// The name of the internal representation of the value
+    pub encoding: String,
+
+    /// The number of seconds since the key was last read or written
+    pub idle_seconds: i64,
+
+    /// The logarithmic access frequency counter
+    pub frequency: u8,
+
+    /// The number of references to the value
+    pub refcount: i64,
+}
+
+/// The approximate overhead of each key, in bytes, for the entries in
+/// the `data` and `key_info` HashMaps.
+const KEY_OVERHEAD: usize = 96;
+
+impl DataType {
+    /// The approximate number of bytes used by the value.
+    ///
+    /// This accounts for the heap allocations and the per-element overhead
+    /// of the collections, but not for the allocator's internal fragmentation.
+    pub fn approximate_size(&self) -> usize {
+        self.sampled_size(0)
+    }
+
+    /// The approximate number of bytes used by the value, estimated from the
+    /// size of the first `samples` elements of a collection.
+    ///
+    /// If `samples` is 0, all the elements are used.
+    pub fn sampled_size(&self, samples: usize) -> usize {
+        match self {
+            DataType::String(val) => std::mem::size_of::<String>() + val.len(),
+            DataType::Bytes(val) => std::mem::size_of::<Vec<u8>>() + val.len(),
+            DataType::LinkedList(list) => {
+                let list = list.borrow();
+
+                let sizes = list
+                    .iter()
+                    // Each node holds the previous and next pointers
+                    .map(|elem| std::mem::size_of::<String>() + 16 + elem.len());
+
+                DataType::estimate_elements_size(sizes, list.len(), samples)
+                    + std::mem::size_of::<LinkedList<String>>()
+            }
+            DataType::SortedSet(set) => {
+                let sizes = set
+                    .iter()
+                    // Each member is stored in both the HashMap and the BTreeSet
+                    .map(|(member, _)| 2 * (std::mem::size_of::<String>() + 8 + member.len()) + 16);
+
+                DataType::estimate_elements_size(sizes, set.len(), samples)
+                    + std::mem::size_of::<SortedSet>()
+            }
+        }
+    }
+
+    /// The name of the internal representation of the value, as reported by OBJECT ENCODING
+    pub fn encoding(&self) -> &'static str {
+        match self {
+            DataType::String(val) => {
+                if val.parse::<i64>().is_ok() {
+                    "int"
+                } else if val.len() <= 44 {
+                    "embstr"
+                } else {
+                    "raw"
+                }
+            }
+            DataType::Bytes(val) => {
+                if val.len() <= 44 {
+                    "embstr"
+                } else {
+                    "raw"
+                }
+            }
+            DataType::LinkedList(_) => "linkedlist",
+            DataType::SortedSet(_) => "skiplist",
+        }
+    }
+
+    /// Extrapolate the total size of `len` elements, from the `sizes` of the first `samples`
+    fn estimate_elements_size(
+        sizes: impl Iterator<Item = usize>,
+        len: usize,
+        samples: usize,
+    ) -> usize {
+        if samples == 0 || samples >= len {
+            return sizes.sum();
+        }
+
+        let sampled: usize = sizes.take(samples).sum();
+        sampled * len / samples
+    }
+}
+
+impl DataStore {
+    /// Insert the `value` at `key`, and update the memory accounting.
+    ///
+    /// All inserts into `data` must go through this method, so that the
+    /// `key_info` and `used_memory` are kept in sync.
+    ///
+    /// Will return the old value, if the key already existed.
+    fn insert(&mut self, key: String, value: DataType) -> Option<DataType> {
+        let size = key.len() + KEY_OVERHEAD + value.approximate_size();
+
+        self.used_memory += size;
+
+        match self.key_info.get_mut(&key) {
+            Some(info) => {
+                self.used_memory -= info.size;
+                info.size = size;
+            }
+            None => {
+                self.key_info.insert(
+                    key.clone(),
+                    KeyInfo {
+                        size,
+                        last_access: Utc::now(),
+                        lfu_counter: eviction::LFU_INIT_VAL,
+                    },
+                );
+            }
+        }
+
+        self.touch(&key);
+
+        self.data.insert(key, value)
+    }
+
+    /// Remove the `key` from all the HashMaps, and update the memory accounting.
+    ///
+    /// Will return the removed value, if the key existed.
+    fn remove(&mut self, key: &str) -> Option<DataType> {
+        self.date_time.remove(key);
+
+        if let Some(info) = self.key_info.remove(key) {
+            self.used_memory -= info.size;
+        }
+
+        self.data.remove(key)
+    }
+
+    /// Remove the `key`, whose expiry has passed
+    fn expire(&mut self, key: &str) {
+        if self.remove(key).is_some() {
+            self.stats.expired_keys += 1;
+            self.feed_deletion(key);
+        }
+    }
+
+    /// Feed the deletion of the `key`, which wasn't deleted by a command, into
+    /// the replication stream. This happens under the lock of the shard, so
+    /// that it is fed before any later write of the key.
+    fn feed_deletion(&self, key: &str) {
+        if let Some(replication) = &self.replication {
+            replication.feed(&command_frame(&["DEL".to_string(), key.to_string()]));
+        }
+    }
+
+    /// Whether the shard belongs to a replica, which leaves the expiry and
+    /// eviction of its keys to its master
+    fn is_replica(&self) -> bool {
+        self.replication
+            .as_ref()
+            .is_some_and(|replication| replication.is_replica())
+    }
+
+    /// Record a read of the `key` by a command, as a hit if it exists or a miss.
+    ///
+    /// Expired keys must have been removed first, so that they are misses.
+    fn record_lookup(&mut self, key: &str) {
+        if self.data.contains_key(key) {
+            self.stats.keyspace_hits += 1;
+        } else {
+            self.stats.keyspace_misses += 1;
+        }
+    }
+
+    /// Recalculate the size of the value at `key`, after it was mutated in-place.
+    fn resize(&mut self, key: &str) {
+        let size = match self.data.get(key) {
+            Some(value) => key.len() + KEY_OVERHEAD + value.approximate_size(),
+            None => return,
+        };
+
+        if let Some(info) = self.key_info.get_mut(key) {
+            self.used_memory = self.used_memory - info.size + size;
+            info.size = size;
+        }
+    }
+
+    /// Record an access of the `key`, for the LRU and LFU eviction policies.
+    fn touch(&mut self, key: &str) {
+        if let Some(info) = self.key_info.get_mut(key) {
+            let counter = eviction::lfu_decay(info.lfu_counter, info.last_access);
+
+            info.lfu_counter = eviction::lfu_increment(counter);
+            info.last_access = Utc::now();
+        }
+    }
+
+    /// Pick a random sample of up to `count` keys from the `map`.
+    fn sample_keys<V>(map: &HashMap<String, V>, count: usize) -> Vec<String> {
+        let mut rng = rand::thread_rng();
+        let num_to_get = std::cmp::min(map.len(), count);
+
+        let mut indices: Vec<usize> = sample(&mut rng, map.len(), num_to_get).into_vec();
+        indices.sort_unstable();
+
+        // Walk the keys once, collecting the keys at the sampled indices
+        let mut indices = indices.into_iter().peekable();
+        let mut keys: Vec<String> = Vec::new();
+
+        for (i, key) in map.keys().enumerate() {
+            if indices.peek() == Some(&i) {
+                keys.push(key.clone());
+                indices.next();
+            }
+        }
+
+        keys
+    }
+
+    /// Pick the best key to evict, from a sample of keys, based on the `eviction_policy`.
+    ///
+    /// Rather than keeping all the keys ordered by access time or frequency, a sample of
+    /// `MAXMEMORY_SAMPLES` keys is checked, which approximates the exact algorithms.
+    ///
+    /// The key is returned with its rank, where the lowest rank across the shards
+    /// should be evicted first.
+    ///
+    /// Will return `None` if there are no keys, which can be evicted.
+    fn pick_eviction_candidate(&self) -> Option<(i64, String)> {
+        let candidates = if self.eviction_policy.is_volatile() {
+            DataStore::sample_keys(&self.date_time, MAXMEMORY_SAMPLES)
+        } else {
+            DataStore::sample_keys(&self.key_info, MAXMEMORY_SAMPLES)
+        };
+
+        candidates
+            .into_iter()
+            .filter(|key| self.key_info.contains_key(key))
+            .filter_map(|key| {
+                let info = &self.key_info[&key];
+
+                let rank = match self.eviction_policy {
+                    EvictionPolicy::NoEviction => return None,
+                    EvictionPolicy::AllKeysRandom | EvictionPolicy::VolatileRandom => {
+                        rand::thread_rng().gen()
+                    }
+                    EvictionPolicy::AllKeysLru | EvictionPolicy::VolatileLru => {
+                        info.last_access.timestamp_millis()
+                    }
+                    EvictionPolicy::AllKeysLfu | EvictionPolicy::VolatileLfu => {
+                        eviction::lfu_decay(info.lfu_counter, info.last_access) as i64
+                    }
+                    EvictionPolicy::VolatileTtl => {
+                        self.date_time[&key].expires_at.timestamp_millis()
+                    }
+                };
+
+                Some((rank, key))
+            })
+            .min()
+    }
+}
+
+/// Get the index of the shard which holds the `key`.
+///
+/// Keys are assigned by their cluster hash slot, so that the keys of a
+/// `{hashtag}` are held by the same shard, as they are by the same node.
+fn shard_index(key: &str, num_shards: usize) -> usize {
+    cluster::key_hash_slot(key) as usize % num_shards
+}
+
+/// The locked shards of the keys of a multi-key command.
+///
+/// The shards are always locked in ascending order, so that commands
+/// which lock several shards can't deadlock each other.
+struct ShardGuards<'a> {
+    num_shards: usize,
+    guards: BTreeMap<usize, std::sync::MutexGuard<'a, DataStore>>,
+}
+
+impl<'a> ShardGuards<'a> {
+    /// Get the locked shard of the `key`, which must be one of the locked keys
+    fn get(&mut self, key: &str) -> &mut std::sync::MutexGuard<'a, DataStore> {
+        self.guards
+            .get_mut(&shard_index(key, self.num_shards))
+            .expect("the shard of the key is locked")
+    }
+}
+
 impl SharedStore {
+    /// Create a data store with `DEFAULT_NUM_SHARDS` shards
     pub fn new() -> SharedStore {
+        SharedStore::with_shards(DEFAULT_NUM_SHARDS)
+    }
+
+    /// Create a data store, whose keyspace is split into `num_shards` shards.
+    /// Each shard has its own expiry task.
+    pub fn with_shards(num_shards: usize) -> SharedStore {
+        let shards = (0..num_shards.max(1))
+            .map(|_| {
+                Mutex::new(DataStore {
+                    data: HashMap::new(),
+                    date_time: HashMap::new(),
+                    key_info: HashMap::new(),
+                    used_memory: 0,
+                    eviction_policy: DEFAULT_MAXMEMORY_POLICY,
+                    stats: KeyspaceStats::default(),
+                    replication: None,
+                })
+            })
+            .collect();
+
         let shared = Arc::new(GuardedDataStore {
-            store: Mutex::new(DataStore {
-                data: HashMap::new(),
-                date_time: HashMap::new(),
-            }),
+            shards,
+            maxmemory: AtomicUsize::new(DEFAULT_MAXMEMORY),
+            peak_memory: AtomicUsize::new(0),
+            expiry_delay_ms: AtomicU64::new(1000 / DEFAULT_HZ),
+            expiry_keys_to_check: AtomicUsize::new(DEFAULT_ACTIVE_EXPIRE_KEYS),
+            expiry_cycles: AtomicU64::new(0),
+            expiry_cycles_usec: AtomicU64::new(0),
+            pause: ClientPause::new(),
         });
 
-        tokio::spawn(run_key_expiry(shared.clone()));
+        for index in 0..shared.shards.len() {
+            tokio::spawn(run_key_expiry(shared.clone(), index));
+        }
 
         SharedStore { shared }
     }
 
+    /// Create a data store with the shards, memory limit and expiry of the `config`
+    pub fn with_config(config: &Config) -> SharedStore {
+        let store = SharedStore::with_shards(config.shards);
+        store.set_maxmemory(config.maxmemory);
+        store.set_eviction_policy(config.maxmemory_policy);
+        store.set_active_expiry(config.hz, config.active_expire_keys);
+        store
+    }
+
+    /// The number of shards, which the keyspace is split into
+    pub fn num_shards(&self) -> usize {
+        self.shared.shards.len()
+    }
+
+    /// Lock the shard which holds the `key`
+    fn shard(&self, key: &str) -> std::sync::MutexGuard<'_, DataStore> {
+        self.shared.shards[shard_index(key, self.num_shards())]
+            .lock()
+            .unwrap()
+    }
+
+    /// Lock the shards which hold the `keys`, for a multi-key command
+    fn lock_shards(&self, keys: &[&str]) -> ShardGuards<'_> {
+        let num_shards = self.num_shards();
+
+        let indices: BTreeSet<usize> = keys
+            .iter()
+            .map(|key| shard_index(key, num_shards))
+            .collect();
+
+        ShardGuards {
+            num_shards,
+            guards: indices
+                .into_iter()
+                .map(|index| (index, self.shared.shards[index].lock().unwrap()))
+                .collect(),
+        }
+    }
+
+    /// Lock all the shards, in ascending order
+    fn lock_all_shards(&self) -> Vec<std::sync::MutexGuard<'_, DataStore>> {
+        self.shared
+            .shards
+            .iter()
+            .map(|shard| shard.lock().unwrap())
+            .collect()
+    }
+
+    /// The state of CLIENT PAUSE, which also holds the expiry and eviction of keys
+    pub fn client_pause(&self) -> &ClientPause {
+        &self.shared.pause
+    }
+
+    /// Set the maximum number of bytes to use, before keys are evicted.
+    ///
+    /// A limit of 0 disables eviction.
+    pub fn set_maxmemory(&self, maxmemory: usize) {
+        self.shared.maxmemory.store(maxmemory, Ordering::Relaxed);
+    }
+
+    /// Run the expiry task of each shard `hz` times per second, sampling up to
+    /// `keys_to_check` of the keys with an expiry on each run.
+    pub fn set_active_expiry(&self, hz: u64, keys_to_check: usize) {
+        let delay_ms = 1000 / hz.clamp(1, 1000);
+        self.shared
+            .expiry_delay_ms
+            .store(delay_ms, Ordering::Relaxed);
+        self.shared
+            .expiry_keys_to_check
+            .store(keys_to_check.max(1), Ordering::Relaxed);
+    }
+
+    /// Set which keys are evicted, once the `maxmemory` limit is reached.
+    pub fn set_eviction_policy(&self, policy: EvictionPolicy) {
+        for shard in self.shared.shards.iter() {
+            shard.lock().unwrap().eviction_policy = policy;
+        }
+    }
+
+    /// Feed the deletions of the expired and evicted keys into the stream of
+    /// the `replication`. Its keys are then only expired and evicted while it
+    /// is a master.
+    pub fn set_replication(&self, replication: &Replication) {
+        for shard in self.shared.shards.iter() {
+            shard.lock().unwrap().replication = Some(replication.clone());
+        }
+    }
+
+    /// Get the approximate number of bytes used by all the keys.
+    ///
+    /// The peak memory is also updated, since it's tracked across the shards.
+    pub fn used_memory(&self) -> usize {
+        let used_memory: usize = self
+            .shared
+            .shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().used_memory)
+            .sum();
+
+        self.shared
+            .peak_memory
+            .fetch_max(used_memory, Ordering::Relaxed);
+
+        used_memory
+    }
+
+    /// The counters of the keyspace, summed across the shards
+    pub fn keyspace_stats(&self) -> KeyspaceStats {
+        self.lock_all_shards()
+            .iter()
+            .fold(KeyspaceStats::default(), |total, mutex| KeyspaceStats {
+                keyspace_hits: total.keyspace_hits + mutex.stats.keyspace_hits,
+                keyspace_misses: total.keyspace_misses + mutex.stats.keyspace_misses,
+                expired_keys: total.expired_keys + mutex.stats.expired_keys,
+                evicted_keys: total.evicted_keys + mutex.stats.evicted_keys,
+            })
+    }
+
+    /// The number of runs of the expiry tasks, and their total duration in microseconds
+    pub fn expiry_cycles(&self) -> (u64, u64) {
+        (
+            self.shared.expiry_cycles.load(Ordering::Relaxed),
+            self.shared.expiry_cycles_usec.load(Ordering::Relaxed),
+        )
+    }
+
+    /// The average time to live of the keys with an expiry, in milliseconds,
+    /// estimated from a sample of each shard. Will return 0 if there are none.
+    pub fn average_ttl(&self) -> i64 {
+        let now = Utc::now();
+
+        let ttls: Vec<i64> = self
+            .lock_all_shards()
+            .iter()
+            .flat_map(|mutex| {
+                DataStore::sample_keys(&mutex.date_time, MAXMEMORY_SAMPLES)
+                    .into_iter()
+                    .map(|key| (mutex.date_time[&key].expires_at - now).num_milliseconds())
+                    .collect::<Vec<i64>>()
+            })
+            .map(|ttl| ttl.max(0))
+            .collect();
+
+        match ttls.len() {
+            0 => 0,
+            len => ttls.iter().sum::<i64>() / len as i64,
+        }
+    }
+
+    /// Reset the counters of the keyspace, and the peak memory to the memory
+    /// used now, for CONFIG RESETSTAT
+    pub fn reset_stats(&self) {
+        for mutex in self.lock_all_shards().iter_mut() {
+            mutex.stats = KeyspaceStats::default();
+        }
+
+        let used_memory = self.used_memory();
+        self.shared
+            .peak_memory
+            .store(used_memory, Ordering::Relaxed);
+    }
+
+    /// Serialize every key, with its value and expiry, for the full
+    /// resynchronization of a replica.
+    ///
+    /// Each entry is an array of the type, the key, the expiry as a unix timestamp
+    /// in milliseconds (or -1), followed by the elements of the value. Sorted sets
+    /// are flattened into alternating members and scores.
+    pub fn snapshot(&self) -> Vec<RESPType> {
+        self.with_all_shards_locked(|snapshot| snapshot())
+    }
+
+    /// Run `f` while the Mutex of every shard is held, so that no key is expired
+    /// or evicted meanwhile. `f` may take a consistent snapshot, with the function
+    /// which it is given.
+    ///
+    /// The shards must be locked before the state of the replication, where the
+    /// deletions of those keys are fed.
+    pub fn with_all_shards_locked<T>(&self, f: impl FnOnce(&dyn Fn() -> Vec<RESPType>) -> T) -> T {
+        let shards = self.lock_all_shards();
+        f(&|| SharedStore::snapshot_of(&shards))
+    }
+
+    fn snapshot_of(shards: &[std::sync::MutexGuard<'_, DataStore>]) -> Vec<RESPType> {
+        let now = Utc::now();
+        let bulk_string = |text: String| {
+            RESPType::BulkString(Some(BulkStringData {
+                prefix_length: text.len(),
+                text,
+            }))
+        };
+
+        let mut entries: Vec<RESPType> = Vec::new();
+
+        for (mutex, key, value) in shards.iter().flat_map(|mutex| {
+            mutex
+                .data
+                .iter()
+                .map(move |(key, value)| (mutex, key, value))
+        }) {
+            let expires_at = match mutex.date_time.get(key) {
+                // Expired keys, which haven't been purged yet, are skipped
+                Some(span) if span.expires_at <= now => continue,
+                Some(span) => span.expires_at.timestamp_millis(),
+                None => -1,
+            };
+
+            let (kind, elements): (&str, Vec<RESPType>) = match value {
+                DataType::String(text) => ("string", vec![bulk_string(text.clone())]),
+                DataType::Bytes(bytes) => ("string", vec![RESPType::BulkBytes(bytes.clone())]),
+                DataType::LinkedList(list) => (
+                    "list",
+                    list.borrow().iter().cloned().map(bulk_string).collect(),
+                ),
+                DataType::SortedSet(set) => (
+                    "zset",
+                    set.iter()
+                        .flat_map(|(member, score)| [member.clone(), score.to_string()])
+                        .map(bulk_string)
+                        .collect(),
+                ),
+            };
+
+            let mut entry = vec![
+                bulk_string(kind.to_string()),
+                bulk_string(key.clone()),
+                RESPType::Integer(expires_at),
+            ];
+            entry.extend(elements);
+
+            entries.push(RESPType::Array(entry));
+        }
+
+        entries
+    }
+
+    /// Replace all the keys in the data store, with the `entries` of a snapshot.
+    ///
+    /// Will return an error if an entry is malformed, in which case the
+    /// entries before it are kept.
+    pub fn load_snapshot(&self, entries: Vec<RESPType>) -> Result<(), ParseError> {
+        // Acquire the Mutex of every shard
+        let mut shards = self.lock_all_shards();
+        let num_shards = shards.len();
+
+        for mutex in shards.iter_mut() {
+            let keys: Vec<String> = mutex.data.keys().cloned().collect();
+            for key in keys {
+                mutex.remove(&key);
+            }
+        }
+
+        let malformed =
+            || ParseError::SyntaxError("ERR malformed replication snapshot".to_string());
+
+        for entry in entries {
+            let mut parts = match entry {
+                RESPType::Array(parts) if parts.len() >= 3 => parts.into_iter(),
+                _ => return Err(malformed()),
+            };
+
+            let kind = crate::cmd::Command::extract_string(parts.next().unwrap())?;
+            let key = crate::cmd::Command::extract_string(parts.next().unwrap())?;
+            let expires_at = match parts.next().unwrap() {
+                RESPType::Integer(millis) => millis,
+                _ => return Err(malformed()),
+            };
+
+            let parts: Vec<RESPType> = parts.collect();
+
+            let value = match (kind.as_str(), parts.as_slice()) {
+                // The strings which aren't valid UTF-8 are sent as bytes
+                ("string", [RESPType::BulkBytes(bytes)]) => DataType::Bytes(bytes.clone()),
+                _ => SharedStore::snapshot_value(&kind, parts)?,
+            };
+
+            let mutex = &mut shards[shard_index(&key, num_shards)];
+
+            if expires_at >= 0 {
+                let expires_at = match Utc.timestamp_millis_opt(expires_at).single() {
+                    Some(expires_at) => expires_at,
+                    None => return Err(malformed()),
+                };
+
+                mutex.date_time.insert(key.clone(), TimeSpan { expires_at });
+            }
+
+            mutex.insert(key, value);
+        }
+
+        Ok(())
+    }
+
+    /// Build the value of a snapshot entry of `kind`, from its text `parts`
+    fn snapshot_value(kind: &str, parts: Vec<RESPType>) -> Result<DataType, ParseError> {
+        let malformed =
+            || ParseError::SyntaxError("ERR malformed replication snapshot".to_string());
+
+        let elements: Vec<String> = parts
+            .into_iter()
+            .map(crate::cmd::Command::extract_string)
+            .collect::<Result<_, _>>()?;
+
+        let value = match kind {
+            "string" if elements.len() == 1 => {
+                DataType::String(elements.into_iter().next().unwrap())
+            }
+            "list" => DataType::LinkedList(RefCell::new(elements.into_iter().collect())),
+            "zset" if elements.chunks_exact(2).remainder().is_empty() => {
+                let mut set = SortedSet::new();
+
+                for pair in elements.chunks_exact(2) {
+                    let score = pair[1].parse::<f64>().map_err(|_| malformed())?;
+                    set.insert(pair[0].clone(), score);
+                }
+
+                DataType::SortedSet(set)
+            }
+            _ => return Err(malformed()),
+        };
+
+        Ok(value)
+    }
+
+    /// Get up to `count` keys which belong to the cluster hash `slot`, in no
+    /// particular order. Expired keys, which haven't been purged yet, are skipped.
+    pub fn keys_in_slot(&self, slot: u16, count: usize) -> Vec<String> {
+        // Acquire the Mutex of the slot's shard, which holds all of its keys
+        let mutex: std::sync::MutexGuard<'_, DataStore> = self.shared.shards
+            [slot as usize % self.num_shards()]
+        .lock()
+        .unwrap();
+
+        let now = Utc::now();
+
+        mutex
+            .data
+            .keys()
+            .filter(|key| match mutex.date_time.get(*key) {
+                Some(span) => span.expires_at > now,
+                None => true,
+            })
+            .filter(|key| cluster::key_hash_slot(key) == slot)
+            .take(count)
+            .cloned()
+            .collect()
+    }
+
+    /// Count the keys which belong to the cluster hash `slot`
+    pub fn count_keys_in_slot(&self, slot: u16) -> usize {
+        self.keys_in_slot(slot, usize::MAX).len()
+    }
+
+    /// Whether the used memory is over the `maxmemory` limit, so that keys
+    /// must be evicted before a command
+    pub fn needs_eviction(&self) -> bool {
+        // Writes are held anyway, while clients are paused
+        if self.shared.pause.is_paused(true) {
+            return false;
+        }
+
+        let maxmemory = self.shared.maxmemory.load(Ordering::Relaxed);
+
+        maxmemory != 0 && self.used_memory() > maxmemory
+    }
+
+    /// Evict keys until the used memory is below the `maxmemory` limit.
+    ///
+    /// This is called before executing a command, so that writes don't
+    /// grow the data store beyond the limit.
+    ///
+    /// Will return an OOM error if not enough keys could be evicted, e.g.
+    /// with the `noeviction` policy, in which case writes must be rejected.
+    pub fn evict_keys_if_needed(&self) -> Result<(), ParseError> {
+        if !self.needs_eviction() {
+            return Ok(());
+        }
+
+        let maxmemory = self.shared.maxmemory.load(Ordering::Relaxed);
+
+        // Acquire the Mutex of every shard, since the best key to evict is
+        // picked from a sample of each shard.
+        let mut shards = self.lock_all_shards();
+
+        // Replicas delete the keys which their master evicts instead
+        if shards[0].is_replica() {
+            return Ok(());
+        }
+
+        while shards.iter().map(|mutex| mutex.used_memory).sum::<usize>() > maxmemory {
+            let candidate = shards
+                .iter()
+                .enumerate()
+                .filter_map(|(index, mutex)| {
+                    mutex
+                        .pick_eviction_candidate()
+                        .map(|(rank, key)| (rank, index, key))
+                })
+                .min();
+
+            match candidate {
+                Some((_, index, key)) => {
+                    shards[index].remove(&key);
+                    shards[index].stats.evicted_keys += 1;
+                    shards[index].feed_deletion(&key);
+                }
+                None => {
+                    return Err(ParseError::ConditionNotMet(
+                        "OOM command not allowed when used memory > 'maxmemory'.".to_string(),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Adjust (increment or decrement) the value at `key`
     /// by the provided `amount`
     ///
@@ -123,7 +1058,7 @@ impl SharedStore {
                         Ok(ref mut num) => {
                             *num += amount;
 
-                            mutex.data.insert(key, DataType::String(num.to_string()));
+                            mutex.insert(key, DataType::String(num.to_string()));
 
                             return Ok(*num);
                         }
@@ -134,71 +1069,338 @@ impl SharedStore {
                         }
                     }
                 }
+                DataType::Bytes(_) => {
+                    return Err(ParseError::ConditionNotMet(
+                        "ERR value type is not integer or out of range".to_string(),
+                    ))
+                }
                 _ => {
                     return Err(ParseError::ConditionNotMet(
                         "ERR value type is not string".to_string(),
                     ));
                 }
-            },
-            // Key:Val didn't exist
-            None => {
-                let value: i64 = amount;
-                mutex
-                    .data
-                    .insert(key.clone(), DataType::String(value.to_string()));
-                return Ok(value);
+            },
+            // Key:Val didn't exist
+            None => {
+                let value: i64 = amount;
+                mutex.insert(key.clone(), DataType::String(value.to_string()));
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Push `elements` to a `key`, based on the
+    /// defined `action`
+    ///
+    fn push_front_or_back(
+        &self,
+        mutex: &mut std::sync::MutexGuard<'_, DataStore>,
+        key: String,
+        elements: Vec<String>,
+        action: String,
+    ) -> Result<i64, ParseError> {
+        match mutex.data.get(&key) {
+            Some(value) => match value {
+                DataType::LinkedList(ref_list) => {
+                    let length: i64 = {
+                        let mut list = ref_list.borrow_mut();
+
+                        for elem in elements {
+                            if action == "front" {
+                                list.push_front(elem);
+                            } else if action == "back" {
+                                list.push_back(elem);
+                            }
+                        }
+
+                        list.len() as i64
+                    };
+
+                    // The list was mutated in-place, so its size has changed
+                    mutex.resize(&key);
+                    mutex.touch(&key);
+
+                    Ok(length)
+                }
+                _ => {
+                    return Err(ParseError::ConditionNotMet(
+                        "ERR value type is not list".to_string(),
+                    ));
+                }
+            },
+            None => {
+                // Convert Vec to LinkedList by exhausting the iterator
+                let list: LinkedList<String> = elements.into_iter().collect();
+                let length: i64 = list.len() as i64;
+
+                mutex.insert(key, DataType::LinkedList(list.into()));
+
+                return Ok(length);
+            }
+        }
+    }
+
+    fn normalize_index(index: i64, len: i64) -> i64 {
+        if index < 0 {
+            len + index
+        } else {
+            index
+        }
+    }
+
+    /// Remove the `key` from both HashMaps, if its expiry time has passed.
+    ///
+    /// Commands which read a value before the expiry task has had the chance
+    /// to purge it, should call this first, so expired keys are never observed.
+    fn remove_if_expired(mutex: &mut std::sync::MutexGuard<'_, DataStore>, key: &str) {
+        let expired = match mutex.date_time.get(key) {
+            Some(val) => Utc::now() >= val.expires_at,
+            None => false,
+        };
+
+        if expired {
+            mutex.expire(key);
+        }
+    }
+
+    /// Record the read of the `key` by a command, once it was expired if needed,
+    /// for the keyspace hits and misses
+    fn lookup_read(mutex: &mut std::sync::MutexGuard<'_, DataStore>, key: &str) {
+        SharedStore::remove_if_expired(mutex, key);
+        mutex.record_lookup(key);
+    }
+
+    /// Get the bytes of the string stored at `key`, to be used as a bitmap.
+    ///
+    /// Will return `None` if the key does not exist, and an error
+    /// if the key holds a value which is not a string.
+    fn get_bitmap(
+        mutex: &mut std::sync::MutexGuard<'_, DataStore>,
+        key: &str,
+    ) -> Result<Option<Vec<u8>>, ParseError> {
+        SharedStore::remove_if_expired(mutex, key);
+        mutex.touch(key);
+
+        match mutex.data.get(key) {
+            Some(DataType::String(val)) => Ok(Some(val.as_bytes().to_vec())),
+            Some(DataType::Bytes(val)) => Ok(Some(val.clone())),
+            Some(_) => Err(ParseError::ConditionNotMet(
+                "WRONGTYPE Operation against a key holding the wrong kind of value".to_string(),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Store the `bytes` of a bitmap at `key`.
+    ///
+    /// Any expiry which was set on the key is retained.
+    fn set_bitmap(mutex: &mut std::sync::MutexGuard<'_, DataStore>, key: String, bytes: Vec<u8>) {
+        mutex.insert(key, SharedStore::bytes_to_value(bytes));
+    }
+
+    /// Convert the bytes of a bitmap into a string value, which can be stored.
+    ///
+    /// The bytes which are valid UTF-8 are stored as a `String`, as any other
+    /// string is, and the rest are stored as they are.
+    fn bytes_to_value(bytes: Vec<u8>) -> DataType {
+        match String::from_utf8(bytes) {
+            Ok(text) => DataType::String(text),
+            Err(err) => DataType::Bytes(err.into_bytes()),
+        }
+    }
+
+    /// Normalize the inclusive `start` and `end` indices against `len`,
+    /// as done for ranges in BITCOUNT and BITPOS.
+    ///
+    /// Will return `None` if the range is empty.
+    fn normalize_range(start: i64, end: i64, len: i64) -> Option<(usize, usize)> {
+        let start = SharedStore::normalize_index(start, len).max(0);
+        let end = SharedStore::normalize_index(end, len).max(0).min(len - 1);
+
+        if len == 0 || start > end {
+            None
+        } else {
+            Some((start as usize, end as usize))
+        }
+    }
+
+    /// Get the sorted set stored at `key`.
+    ///
+    /// Will return `None` if the key does not exist, and an error
+    /// if the key holds a value which is not a sorted set.
+    fn get_sorted_set<'a>(
+        mutex: &'a mut std::sync::MutexGuard<'_, DataStore>,
+        key: &str,
+    ) -> Result<Option<&'a SortedSet>, ParseError> {
+        SharedStore::remove_if_expired(mutex, key);
+        mutex.touch(key);
+
+        match mutex.data.get(key) {
+            Some(DataType::SortedSet(set)) => Ok(Some(set)),
+            Some(_) => Err(ParseError::ConditionNotMet(
+                "WRONGTYPE Operation against a key holding the wrong kind of value".to_string(),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Find the members of the geospatial index `set`, which lie within the `query` area.
+    ///
+    /// Every member is checked against the area, and the matches are then sorted and limited.
+    fn search_locations(
+        set: &SortedSet,
+        query: &GeoSearchQuery,
+    ) -> Result<Vec<GeoSearchMatch>, ParseError> {
+        let center: (f64, f64) = match &query.origin {
+            GeoOrigin::Member(member) => match set.score(member) {
+                Some(score) => geo::decode(score as u64),
+                None => {
+                    return Err(ParseError::ConditionNotMet(
+                        "ERR could not decode requested zset member".to_string(),
+                    ))
+                }
+            },
+            GeoOrigin::LonLat(longitude, latitude) => (*longitude, *latitude),
+        };
+
+        let mut matches: Vec<GeoSearchMatch> = Vec::new();
+
+        for (member, score) in set.iter() {
+            let hash = score as u64;
+            let (longitude, latitude) = geo::decode(hash);
+            let distance = geo::distance(center.0, center.1, longitude, latitude);
+
+            let within = match query.shape {
+                GeoShape::Radius(radius) => distance <= radius,
+                GeoShape::Box(width, height) => {
+                    geo::is_within_box(center, (longitude, latitude), width, height)
+                }
+            };
+
+            if within {
+                matches.push(GeoSearchMatch {
+                    member: member.clone(),
+                    distance,
+                    hash,
+                    longitude,
+                    latitude,
+                });
+
+                // With ANY, return as soon as enough matches are found
+                if query.any && Some(matches.len()) == query.count {
+                    break;
+                }
             }
         }
+
+        // Results are sorted in ascending order by default, when limited by COUNT
+        let order = match (&query.order, query.count) {
+            (Some(order), _) => Some(order.clone()),
+            (None, Some(_)) if !query.any => Some(GeoOrder::Asc),
+            _ => None,
+        };
+
+        match order {
+            Some(GeoOrder::Asc) => matches.sort_by(|a, b| a.distance.total_cmp(&b.distance)),
+            Some(GeoOrder::Desc) => matches.sort_by(|a, b| b.distance.total_cmp(&a.distance)),
+            None => {}
+        }
+
+        if let Some(count) = query.count {
+            matches.truncate(count);
+        }
+
+        Ok(matches)
     }
 
-    /// Push `elements` to a `key`, based on the
-    /// defined `action`
+    /// Read the bit at `offset`, where bit 0 is the most significant bit of the first byte.
     ///
-    fn push_front_or_back(
-        &self,
-        mutex: &mut std::sync::MutexGuard<'_, DataStore>,
-        key: String,
-        elements: Vec<String>,
-        action: String,
-    ) -> Result<i64, ParseError> {
-        match mutex.data.get(&key) {
-            Some(value) => match value {
-                DataType::LinkedList(ref_list) => {
-                    let mut list = ref_list.borrow_mut();
+    /// Bits beyond the end of the bitmap are read as 0.
+    fn read_bit(bytes: &[u8], offset: u64) -> u8 {
+        match bytes.get((offset / 8) as usize) {
+            Some(byte) => (byte >> (7 - (offset % 8))) & 1,
+            None => 0,
+        }
+    }
 
-                    for elem in elements {
-                        if action == "front" {
-                            list.push_front(elem);
-                        } else if action == "back" {
-                            list.push_back(elem);
-                        }
-                    }
+    /// Write the bit at `offset`, growing the bitmap with zero bytes if necessary.
+    fn write_bit(bytes: &mut Vec<u8>, offset: u64, value: u8) {
+        let byte_index = (offset / 8) as usize;
 
-                    return Ok(list.len() as i64);
-                }
-                _ => {
-                    return Err(ParseError::ConditionNotMet(
-                        "ERR value type is not list".to_string(),
-                    ));
-                }
-            },
-            None => {
-                // Convert Vec to LinkedList by exhausting the iterator
-                let list: LinkedList<String> = elements.into_iter().collect();
-                let length: i64 = list.len() as i64;
+        if byte_index >= bytes.len() {
+            bytes.resize(byte_index + 1, 0);
+        }
 
-                mutex.data.insert(key, DataType::LinkedList(list.into()));
+        let mask = 1 << (7 - (offset % 8));
 
-                return Ok(length);
-            }
+        if value == 1 {
+            bytes[byte_index] |= mask;
+        } else {
+            bytes[byte_index] &= !mask;
         }
     }
 
-    fn normalize_index(index: i64, len: i64) -> i64 {
-        if index < 0 {
-            len + index
+    /// Read an integer of `encoding.bits` width, starting at the bit `offset`.
+    fn read_bitfield(bytes: &[u8], offset: u64, encoding: &BitfieldEncoding) -> i64 {
+        let mut value: u64 = 0;
+
+        for i in 0..encoding.bits as u64 {
+            value = (value << 1) | SharedStore::read_bit(bytes, offset + i) as u64;
+        }
+
+        // Sign-extend the value, if the most significant bit is set
+        if encoding.signed && encoding.bits < 64 && (value >> (encoding.bits - 1)) & 1 == 1 {
+            value |= u64::MAX << encoding.bits;
+        }
+
+        value as i64
+    }
+
+    /// Write the lower `encoding.bits` of `value`, starting at the bit `offset`.
+    fn write_bitfield(bytes: &mut Vec<u8>, offset: u64, encoding: &BitfieldEncoding, value: i64) {
+        let value = value as u64;
+
+        for i in 0..encoding.bits as u64 {
+            let bit = (value >> (encoding.bits as u64 - 1 - i)) & 1;
+            SharedStore::write_bit(bytes, offset + i, bit as u8);
+        }
+    }
+
+    /// Fit `value` into the range of the `encoding`, according to the `overflow` behavior.
+    ///
+    /// Will return `None` if the value overflows, and the behavior is `FAIL`.
+    fn apply_overflow(
+        value: i128,
+        encoding: &BitfieldEncoding,
+        overflow: &BitfieldOverflow,
+    ) -> Option<i64> {
+        let (min, max): (i128, i128) = if encoding.signed {
+            (
+                -(1i128 << (encoding.bits - 1)),
+                (1i128 << (encoding.bits - 1)) - 1,
+            )
         } else {
-            index
+            (0, (1i128 << encoding.bits) - 1)
+        };
+
+        if value >= min && value <= max {
+            return Some(value as i64);
+        }
+
+        match overflow {
+            BitfieldOverflow::Wrap => {
+                let range = 1i128 << encoding.bits;
+                let wrapped = (value - min).rem_euclid(range) + min;
+                Some(wrapped as i64)
+            }
+            BitfieldOverflow::Sat => {
+                if value < min {
+                    Some(min as i64)
+                } else {
+                    Some(max as i64)
+                }
+            }
+            BitfieldOverflow::Fail => None,
         }
     }
 }
@@ -217,8 +1419,8 @@ impl SharedStoreBase for SharedStore {
         nx: bool,
         xx: bool,
     ) -> Result<Option<DataType>, ParseError> {
-        // Acquire the Mutex
-        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shared.store.lock().unwrap();
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
 
         // To check for xx and nx flags
         //
@@ -245,7 +1447,7 @@ impl SharedStoreBase for SharedStore {
         // If an old `value` existed for this `key`, it is returned.
         // We clone the key, as to not "move" its ownership, since we need its reference
         // for the expiry tasks later.
-        let old_value: Option<DataType> = mutex.data.insert(key.clone(), value.clone());
+        let old_value: Option<DataType> = mutex.insert(key.clone(), value.clone());
 
         // Replace or delete the date_time entry.
         // If the `old_value` existed, then check if the corresponding key had an `expiration` time
@@ -272,45 +1474,33 @@ impl SharedStoreBase for SharedStore {
     ///
     /// Will return `None` if no value is found for the corresponding key.
     fn get(&self, key: String) -> Option<DataType> {
-        // Acquire the Mutex
-        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shared.store.lock().unwrap();
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
+
+        // Remove the key from both HashMaps, if `current_time` > `expires_at`
+        SharedStore::lookup_read(&mut mutex, &key);
 
         // If the value exists, and is not expired we return `DataType`
-        match mutex.data.get(&key) {
-            // If we have a `value` at `key`
-            Some(value) => {
-                // Check if the key exists in the `date_time` HashMap,
-                // and remove from both HashMaps, if `current_time` > `expires_at`
-                match mutex.date_time.get(&key) {
-                    Some(val) => {
-                        if Utc::now() >= val.expires_at {
-                            mutex.date_time.remove(&key);
-                            mutex.data.remove(&key);
-                            return None;
-                        } else {
-                            return Some(value.clone());
-                        }
-                    }
-                    None => return Some(value.clone()),
-                }
-            }
-            None => {
-                return None;
-            }
+        let value = mutex.data.get(&key).cloned();
+        if value.is_some() {
+            mutex.touch(&key);
         }
+
+        value
     }
 
     /// Check if the provided `keys` are part of the HashMap
     ///
     /// Will return a `u64` integer count of the number of keys, that exist.
     fn exists(&self, keys: Vec<String>) -> u64 {
-        // Acquire the Mutex
-        let mutex: std::sync::MutexGuard<'_, DataStore> = self.shared.store.lock().unwrap();
+        // Acquire the Mutex of each of the keys' shards, together
+        let key_refs: Vec<&str> = keys.iter().map(|key| key.as_str()).collect();
+        let mut shards = self.lock_shards(&key_refs);
 
         let mut count: u64 = 0;
 
-        for k in keys {
-            if mutex.data.contains_key(&k) == true {
+        for k in keys.iter() {
+            if shards.get(k).data.contains_key(k) {
                 count += 1;
             }
         }
@@ -322,19 +1512,16 @@ impl SharedStoreBase for SharedStore {
     ///
     /// Will return a `u64` integer count of the number of keys, that were successfully deleted
     fn del(&self, keys: Vec<String>) -> u64 {
-        // Acquire the Mutex
-        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shared.store.lock().unwrap();
+        // Acquire the Mutex of each of the keys' shards, together
+        let key_refs: Vec<&str> = keys.iter().map(|key| key.as_str()).collect();
+        let mut shards = self.lock_shards(&key_refs);
 
         let mut count: u64 = 0;
 
-        for k in keys {
-            match mutex.data.remove(&k) {
-                Some(_) => {
-                    // Also try to remove from `date_time` map
-                    mutex.date_time.remove(&k);
-                    count += 1;
-                }
-                None => {}
+        for k in keys.iter() {
+            // Also removes from the `date_time` map
+            if shards.get(k).remove(k).is_some() {
+                count += 1;
             }
         }
 
@@ -346,8 +1533,8 @@ impl SharedStoreBase for SharedStore {
     ///
     /// Will return the new incremented i64 integer value.
     fn incr(&self, key: String) -> Result<i64, ParseError> {
-        // Acquire the Mutex
-        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shared.store.lock().unwrap();
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
 
         return self._adjust_by(&mut mutex, key, 1);
     }
@@ -357,8 +1544,8 @@ impl SharedStoreBase for SharedStore {
     ///
     /// Will return the new decremented i64 integer value.
     fn decr(&self, key: String) -> Result<i64, ParseError> {
-        // Acquire the Mutex
-        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shared.store.lock().unwrap();
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
 
         return self._adjust_by(&mut mutex, key, -1);
     }
@@ -369,8 +1556,8 @@ impl SharedStoreBase for SharedStore {
     ///
     /// Will return the number of elements, which are part of the list.
     fn lpush(&self, key: String, elements: Vec<String>) -> Result<i64, ParseError> {
-        // Acquire the Mutex
-        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shared.store.lock().unwrap();
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
 
         return self.push_front_or_back(&mut mutex, key, elements, "front".to_string());
     }
@@ -381,8 +1568,11 @@ impl SharedStoreBase for SharedStore {
     ///
     /// Will return the elements, which are part of the list, in the defined range.
     fn lrange(&self, key: String, start: i64, stop: i64) -> Result<Vec<String>, ParseError> {
-        // Acquire the Mutex
-        let mutex: std::sync::MutexGuard<'_, DataStore> = self.shared.store.lock().unwrap();
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
+
+        SharedStore::lookup_read(&mut mutex, &key);
+        mutex.touch(&key);
 
         // Does key exist, and if so get it's value and ensure it's a LinkedList
         let list = match mutex.data.get(&key) {
@@ -430,11 +1620,541 @@ impl SharedStoreBase for SharedStore {
     ///
     /// Will return the number of elements, which are part of the list.
     fn rpush(&self, key: String, elements: Vec<String>) -> Result<i64, ParseError> {
-        // Acquire the Mutex
-        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shared.store.lock().unwrap();
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
 
         return self.push_front_or_back(&mut mutex, key, elements, "back".to_string());
     }
+
+    /// Set or clear the bit at `offset` of the string stored at `key`.
+    ///
+    /// The string is grown with zero bytes, if the `offset` is beyond its length.
+    /// Creates a new string if the key doesn't exist previously.
+    ///
+    /// Will return the original bit value at `offset`.
+    fn setbit(&self, key: String, offset: u64, value: u8) -> Result<u8, ParseError> {
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
+
+        let mut bytes = SharedStore::get_bitmap(&mut mutex, &key)?.unwrap_or_default();
+
+        let original = SharedStore::read_bit(&bytes, offset);
+        SharedStore::write_bit(&mut bytes, offset, value);
+
+        SharedStore::set_bitmap(&mut mutex, key, bytes);
+
+        Ok(original)
+    }
+
+    /// Get the bit at `offset` of the string stored at `key`.
+    ///
+    /// Will return 0 if the key doesn't exist, or the `offset` is beyond the string length.
+    fn getbit(&self, key: String, offset: u64) -> Result<u8, ParseError> {
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
+
+        SharedStore::lookup_read(&mut mutex, &key);
+        let bytes = SharedStore::get_bitmap(&mut mutex, &key)?.unwrap_or_default();
+
+        Ok(SharedStore::read_bit(&bytes, offset))
+    }
+
+    /// Count the number of set bits in the string stored at `key`.
+    ///
+    /// The `range` is inclusive, and interpreted as byte or bit indices based on the `unit`.
+    ///
+    /// Will return 0 if the key doesn't exist.
+    fn bitcount(
+        &self,
+        key: String,
+        range: Option<(i64, i64)>,
+        unit: BitUnit,
+    ) -> Result<i64, ParseError> {
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
+
+        SharedStore::lookup_read(&mut mutex, &key);
+        let bytes = match SharedStore::get_bitmap(&mut mutex, &key)? {
+            Some(bytes) => bytes,
+            None => return Ok(0),
+        };
+
+        let (start, end) = range.unwrap_or((0, -1));
+
+        let count = match unit {
+            BitUnit::Byte => match SharedStore::normalize_range(start, end, bytes.len() as i64) {
+                Some((start, end)) => bytes[start..=end]
+                    .iter()
+                    .map(|b| b.count_ones() as i64)
+                    .sum(),
+                None => 0,
+            },
+            BitUnit::Bit => {
+                match SharedStore::normalize_range(start, end, bytes.len() as i64 * 8) {
+                    Some((start, end)) => (start as u64..=end as u64)
+                        .map(|offset| SharedStore::read_bit(&bytes, offset) as i64)
+                        .sum(),
+                    None => 0,
+                }
+            }
+        };
+
+        Ok(count)
+    }
+
+    /// Find the position of the first bit set to `bit` in the string stored at `key`.
+    ///
+    /// The `start` and `end` are inclusive, and interpreted as byte or bit indices based on the `unit`.
+    ///
+    /// Will return -1 if the bit isn't found. When looking for a clear bit, without
+    /// an `end`, the string is considered to be padded with zeros on the right.
+    fn bitpos(
+        &self,
+        key: String,
+        bit: u8,
+        start: Option<i64>,
+        end: Option<i64>,
+        unit: BitUnit,
+    ) -> Result<i64, ParseError> {
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
+
+        SharedStore::lookup_read(&mut mutex, &key);
+        let bytes = match SharedStore::get_bitmap(&mut mutex, &key)? {
+            Some(bytes) => bytes,
+            None => return Ok(if bit == 1 { -1 } else { 0 }),
+        };
+
+        // Get the range of bits to search, based on the unit
+        let (first, last) = match unit {
+            BitUnit::Byte => match SharedStore::normalize_range(
+                start.unwrap_or(0),
+                end.unwrap_or(-1),
+                bytes.len() as i64,
+            ) {
+                Some((start, end)) => (start as u64 * 8, end as u64 * 8 + 7),
+                None => return Ok(-1),
+            },
+            BitUnit::Bit => match SharedStore::normalize_range(
+                start.unwrap_or(0),
+                end.unwrap_or(-1),
+                bytes.len() as i64 * 8,
+            ) {
+                Some((start, end)) => (start as u64, end as u64),
+                None => return Ok(-1),
+            },
+        };
+
+        for offset in first..=last {
+            if SharedStore::read_bit(&bytes, offset) == bit {
+                return Ok(offset as i64);
+            }
+        }
+
+        if bit == 0 && end.is_none() {
+            Ok(last as i64 + 1)
+        } else {
+            Ok(-1)
+        }
+    }
+
+    /// Perform a bitwise `operation` between the strings stored at `keys`, and
+    /// store the result at `destkey`.
+    ///
+    /// Missing keys and shorter strings are considered to be padded with zero bytes.
+    /// If the result is empty, `destkey` is deleted.
+    ///
+    /// Will return the length of the string stored at `destkey`.
+    fn bitop(
+        &self,
+        operation: BitOperation,
+        destkey: String,
+        keys: Vec<String>,
+    ) -> Result<i64, ParseError> {
+        // Acquire the Mutex of each of the keys' shards, together
+        let mut key_refs: Vec<&str> = keys.iter().map(|key| key.as_str()).collect();
+        key_refs.push(&destkey);
+        let mut shards = self.lock_shards(&key_refs);
+
+        let mut sources: Vec<Vec<u8>> = Vec::new();
+
+        for key in keys.iter() {
+            SharedStore::lookup_read(shards.get(key), key);
+            sources.push(SharedStore::get_bitmap(shards.get(key), key)?.unwrap_or_default());
+        }
+
+        let length = sources.iter().map(|s| s.len()).max().unwrap_or(0);
+
+        let result: Vec<u8> = (0..length)
+            .map(|i| {
+                let mut bytes = sources.iter().map(|s| *s.get(i).unwrap_or(&0));
+
+                match operation {
+                    BitOperation::And => bytes.fold(0xFF, |acc, b| acc & b),
+                    BitOperation::Or => bytes.fold(0x00, |acc, b| acc | b),
+                    BitOperation::Xor => bytes.fold(0x00, |acc, b| acc ^ b),
+                    BitOperation::Not => !bytes.next().unwrap_or(0),
+                }
+            })
+            .collect();
+
+        // The destination key is overwritten, which also clears its expiry
+        let mutex = shards.get(&destkey);
+        mutex.remove(&destkey);
+
+        if !result.is_empty() {
+            mutex.insert(destkey, SharedStore::bytes_to_value(result));
+        }
+
+        Ok(length as i64)
+    }
+
+    /// Perform the bitfield `operations` on the string stored at `key`, in order.
+    ///
+    /// The string is only created or grown, if any of the operations write to it.
+    ///
+    /// Will return the result of each GET, SET and INCRBY operation, where `None`
+    /// represents an operation which failed due to the `FAIL` overflow behavior.
+    fn bitfield(
+        &self,
+        key: String,
+        operations: Vec<BitfieldOperation>,
+    ) -> Result<Vec<Option<i64>>, ParseError> {
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
+
+        let mut bytes = SharedStore::get_bitmap(&mut mutex, &key)?.unwrap_or_default();
+
+        let mut overflow = BitfieldOverflow::Wrap;
+        let mut modified = false;
+        let mut results: Vec<Option<i64>> = Vec::new();
+
+        for operation in operations {
+            match operation {
+                BitfieldOperation::Get { encoding, offset } => {
+                    results.push(Some(SharedStore::read_bitfield(&bytes, offset, &encoding)));
+                }
+                BitfieldOperation::Set {
+                    encoding,
+                    offset,
+                    value,
+                } => {
+                    let old = SharedStore::read_bitfield(&bytes, offset, &encoding);
+
+                    match SharedStore::apply_overflow(value as i128, &encoding, &overflow) {
+                        Some(new) => {
+                            SharedStore::write_bitfield(&mut bytes, offset, &encoding, new);
+                            modified = true;
+                            results.push(Some(old));
+                        }
+                        None => results.push(None),
+                    }
+                }
+                BitfieldOperation::Incrby {
+                    encoding,
+                    offset,
+                    increment,
+                } => {
+                    let old = SharedStore::read_bitfield(&bytes, offset, &encoding);
+                    let value = old as i128 + increment as i128;
+
+                    match SharedStore::apply_overflow(value, &encoding, &overflow) {
+                        Some(new) => {
+                            SharedStore::write_bitfield(&mut bytes, offset, &encoding, new);
+                            modified = true;
+                            results.push(Some(new));
+                        }
+                        None => results.push(None),
+                    }
+                }
+                BitfieldOperation::Overflow(behavior) => overflow = behavior,
+            }
+        }
+
+        if modified {
+            SharedStore::set_bitmap(&mut mutex, key, bytes);
+        }
+
+        Ok(results)
+    }
+
+    /// Get the approximate number of bytes used by the `key` and its value.
+    ///
+    /// The size of collections is estimated from `samples` of their elements,
+    /// or all of the elements if `samples` is 0. The access time isn't updated.
+    ///
+    /// Will return `None` if the key doesn't exist.
+    fn memory_usage(&self, key: String, samples: usize) -> Option<usize> {
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
+
+        SharedStore::remove_if_expired(&mut mutex, &key);
+
+        mutex
+            .data
+            .get(&key)
+            .map(|value| key.len() + KEY_OVERHEAD + value.sampled_size(samples))
+    }
+
+    /// Get a summary of the memory used by the data store.
+    fn memory_stats(&self) -> MemoryStats {
+        // Acquire the Mutex of every shard, for consistent totals
+        let shards = self.lock_all_shards();
+
+        let used_memory: usize = shards.iter().map(|mutex| mutex.used_memory).sum();
+        let overhead: usize = shards
+            .iter()
+            .flat_map(|mutex| mutex.data.keys())
+            .map(|key| key.len() + KEY_OVERHEAD)
+            .sum();
+
+        let peak_memory = self
+            .shared
+            .peak_memory
+            .fetch_max(used_memory, Ordering::Relaxed)
+            .max(used_memory);
+
+        MemoryStats {
+            peak_memory,
+            used_memory,
+            overhead,
+            dataset: used_memory.saturating_sub(overhead),
+            maxmemory: self.shared.maxmemory.load(Ordering::Relaxed),
+            eviction_policy: shards[0].eviction_policy,
+            keys: shards.iter().map(|mutex| mutex.data.len()).sum(),
+            expires: shards.iter().map(|mutex| mutex.date_time.len()).sum(),
+        }
+    }
+
+    /// Get the internal details of the `key`.
+    ///
+    /// Unlike Redis, both the idle time and the access frequency are tracked
+    /// regardless of the eviction policy. The access time isn't updated.
+    ///
+    /// Will return `None` if the key doesn't exist.
+    fn object_info(&self, key: String) -> Option<ObjectInfo> {
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
+
+        SharedStore::remove_if_expired(&mut mutex, &key);
+
+        let value = mutex.data.get(&key)?;
+        let info = mutex.key_info.get(&key)?;
+
+        Some(ObjectInfo {
+            encoding: value.encoding().to_string(),
+            idle_seconds: (Utc::now() - info.last_access).num_seconds(),
+            frequency: eviction::lfu_decay(info.lfu_counter, info.last_access),
+            // Values are never shared between keys
+            refcount: 1,
+        })
+    }
+
+    /// Add the (longitude, latitude, member) `locations` to the geospatial index stored at `key`.
+    ///
+    /// Creates a new sorted set if it doesn't exist previously. With `nx`, only new members
+    /// are added, and with `xx`, only existing members are updated.
+    ///
+    /// Will return the number of members added, or changed if `ch` is provided.
+    fn geoadd(
+        &self,
+        key: String,
+        locations: Vec<(f64, f64, String)>,
+        nx: bool,
+        xx: bool,
+        ch: bool,
+    ) -> Result<i64, ParseError> {
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
+
+        if SharedStore::get_sorted_set(&mut mutex, &key)?.is_none() {
+            mutex.insert(key.clone(), DataType::SortedSet(SortedSet::default()));
+        }
+
+        // Update the set in-place, as it may be large
+        let set = match mutex.data.get_mut(&key) {
+            Some(DataType::SortedSet(set)) => set,
+            _ => unreachable!("The sorted set was just checked or inserted"),
+        };
+
+        let mut added: i64 = 0;
+        let mut updated: i64 = 0;
+
+        for (longitude, latitude, member) in locations {
+            let score = geo::encode(longitude, latitude) as f64;
+
+            match set.score(&member) {
+                Some(old_score) => {
+                    if !nx && old_score != score {
+                        set.insert(member, score);
+                        updated += 1;
+                    }
+                }
+                None => {
+                    if !xx {
+                        set.insert(member, score);
+                        added += 1;
+                    }
+                }
+            }
+        }
+
+        // An empty set is only left when nothing was added to a new one
+        if set.is_empty() {
+            mutex.remove(&key);
+        } else {
+            mutex.resize(&key);
+        }
+
+        if ch {
+            Ok(added + updated)
+        } else {
+            Ok(added)
+        }
+    }
+
+    /// Get the (longitude, latitude) of each of the `members` of the geospatial index stored at `key`.
+    ///
+    /// Will return `None` for each member which doesn't exist.
+    fn geopos(
+        &self,
+        key: String,
+        members: Vec<String>,
+    ) -> Result<Vec<Option<(f64, f64)>>, ParseError> {
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
+
+        SharedStore::lookup_read(&mut mutex, &key);
+        let set = SharedStore::get_sorted_set(&mut mutex, &key)?;
+
+        Ok(members
+            .iter()
+            .map(|member| {
+                set.and_then(|set| set.score(member))
+                    .map(|score| geo::decode(score as u64))
+            })
+            .collect())
+    }
+
+    /// Get the distance in meters between two members of the geospatial index stored at `key`.
+    ///
+    /// Will return `None` if either of the members doesn't exist.
+    fn geodist(
+        &self,
+        key: String,
+        member1: String,
+        member2: String,
+    ) -> Result<Option<f64>, ParseError> {
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
+
+        SharedStore::lookup_read(&mut mutex, &key);
+        let set = match SharedStore::get_sorted_set(&mut mutex, &key)? {
+            Some(set) => set,
+            None => return Ok(None),
+        };
+
+        match (set.score(&member1), set.score(&member2)) {
+            (Some(score1), Some(score2)) => {
+                let (longitude1, latitude1) = geo::decode(score1 as u64);
+                let (longitude2, latitude2) = geo::decode(score2 as u64);
+
+                Ok(Some(geo::distance(
+                    longitude1, latitude1, longitude2, latitude2,
+                )))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Get the geohash string of each of the `members` of the geospatial index stored at `key`.
+    ///
+    /// Will return `None` for each member which doesn't exist.
+    fn geohash(
+        &self,
+        key: String,
+        members: Vec<String>,
+    ) -> Result<Vec<Option<String>>, ParseError> {
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
+
+        SharedStore::lookup_read(&mut mutex, &key);
+        let set = SharedStore::get_sorted_set(&mut mutex, &key)?;
+
+        Ok(members
+            .iter()
+            .map(|member| {
+                set.and_then(|set| set.score(member)).map(|score| {
+                    let (longitude, latitude) = geo::decode(score as u64);
+                    geo::to_geohash_string(longitude, latitude)
+                })
+            })
+            .collect())
+    }
+
+    /// Search the members of the geospatial index stored at `key`, within the `query` area.
+    ///
+    /// Will return an empty Vec if the key doesn't exist.
+    fn geosearch(
+        &self,
+        key: String,
+        query: GeoSearchQuery,
+    ) -> Result<Vec<GeoSearchMatch>, ParseError> {
+        // Acquire the Mutex of the key's shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shard(&key);
+
+        SharedStore::lookup_read(&mut mutex, &key);
+        match SharedStore::get_sorted_set(&mut mutex, &key)? {
+            Some(set) => SharedStore::search_locations(set, &query),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Search the members of the geospatial index stored at `source`, within the `query` area,
+    /// and store them in a new sorted set at `destination`.
+    ///
+    /// The members are stored with their geohash, or with their distance in the provided
+    /// unit if `storedist` is provided. If there are no matches, `destination` is deleted.
+    ///
+    /// Will return the number of members stored.
+    fn geosearchstore(
+        &self,
+        destination: String,
+        source: String,
+        query: GeoSearchQuery,
+        storedist: Option<GeoUnit>,
+    ) -> Result<i64, ParseError> {
+        // Acquire the Mutex of both keys' shards, together
+        let mut shards = self.lock_shards(&[&destination, &source]);
+
+        SharedStore::lookup_read(shards.get(&source), &source);
+        let matches = match SharedStore::get_sorted_set(shards.get(&source), &source)? {
+            Some(set) => SharedStore::search_locations(set, &query)?,
+            None => Vec::new(),
+        };
+
+        let mut set = SortedSet::new();
+
+        for found in matches {
+            let score = match &storedist {
+                Some(unit) => found.distance / unit.to_meters(),
+                None => found.hash as f64,
+            };
+            set.insert(found.member, score);
+        }
+
+        let length = set.len() as i64;
+
+        // The destination key is overwritten, which also clears its expiry
+        let mutex = shards.get(&destination);
+        mutex.remove(&destination);
+
+        if !set.is_empty() {
+            mutex.insert(destination, DataType::SortedSet(set));
+        }
+
+        Ok(length)
+    }
 }
 
 impl GuardedDataStore {
@@ -443,10 +2163,24 @@ impl GuardedDataStore {
     /// 2. Delete all the expired keys from both HashMaps.
     /// 3. If more than 25% of the set of 20 keys was expired (5 keys were expired), restart the process from step 1.
     ///
-    fn purge_expired_keys(&self) {
+    /// Keys aren't purged while clients are paused, they are expired once the pause ends.
+    /// Neither are they on replicas, which delete them when their master does.
+    ///
+    /// Each shard is purged independently, by its own task.
+    ///
+    fn purge_expired_keys(&self, index: usize) {
+        if self.pause.is_paused(true) {
+            return;
+        }
+
         let mut restart: bool = true;
-        // Acquire the Mutex
-        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.store.lock().unwrap();
+        // Acquire the Mutex of the shard
+        let mut mutex: std::sync::MutexGuard<'_, DataStore> = self.shards[index].lock().unwrap();
+
+        if mutex.is_replica() {
+            return;
+        }
+        let replication = mutex.replication.clone();
 
         while restart == true {
             let random_keys: Vec<String>;
@@ -463,7 +2197,8 @@ impl GuardedDataStore {
                     return;
                 }
 
-                let num_to_get = std::cmp::min(num_keys, KEY_EXPIRY_NUM_KEYS_TO_CHECK);
+                let keys_to_check = self.expiry_keys_to_check.load(Ordering::Relaxed);
+                let num_to_get = std::cmp::min(num_keys, keys_to_check);
 
                 let indices = sample(&mut rng, num_keys, num_to_get);
                 // Cloning the individual key is necessary, otherwise we'll still have borrowed the
@@ -482,8 +2217,17 @@ impl GuardedDataStore {
             for key in random_keys {
                 let value = mutex.date_time.get(&key).unwrap();
                 if Utc::now() >= value.expires_at {
-                    mutex.data.remove(&key);
-                    mutex.date_time.remove(&key);
+                    // The keys which are being written are left for the next run, so
+                    // that their deletion isn't fed in between a write and its feed
+                    let _lock = match &replication {
+                        Some(replication) => match replication.try_lock_writes(&[&key]) {
+                            Some(lock) => Some(lock),
+                            None => continue,
+                        },
+                        None => None,
+                    };
+
+                    mutex.expire(&key);
                     keys_removed += 1;
                 }
             }
@@ -497,15 +2241,23 @@ impl GuardedDataStore {
     }
 }
 
-/// Async function, which calls the `purge_expired_keys` function every X duration
+/// Async function, which calls the `purge_expired_keys` function every X duration,
+/// for the shard at `index`
 ///
 /// The reason to split this is that async context and synchronized mutexes cannot be shared.
-async fn run_key_expiry(shared: Arc<GuardedDataStore>) {
+async fn run_key_expiry(shared: Arc<GuardedDataStore>, index: usize) {
     loop {
-        // Purge the expired keys
-        shared.purge_expired_keys();
-
-        // Sleep for 100 ms
-        let _ = sleep(std::time::Duration::from_millis(KEY_EXPIRY_DELAY_MS)).await;
+        // Purge the expired keys, timing the run
+        let started = std::time::Instant::now();
+        shared.purge_expired_keys(index);
+
+        shared.expiry_cycles.fetch_add(1, Ordering::Relaxed);
+        shared
+            .expiry_cycles_usec
+            .fetch_add(started.elapsed().as_micros() as u64, Ordering::Relaxed);
+
+        // Sleep until the next run, 100 ms by default
+        let delay_ms = shared.expiry_delay_ms.load(Ordering::Relaxed);
+        let _ = sleep(std::time::Duration::from_millis(delay_ms)).await;
     }
 }
@@ -0,0 +1,105 @@
+// Handles the policies and the approximated LFU counter, which are used to
+// evict keys from the `DataStore` once the `maxmemory` limit is reached.
+use chrono::{DateTime, Utc};
+use std::fmt;
+
+/// The initial LFU counter of new keys, so they aren't evicted right away
+pub const LFU_INIT_VAL: u8 = 5;
+
+/// The higher the factor, the more accesses are needed to increment the LFU counter
+const LFU_LOG_FACTOR: f64 = 10.0;
+
+/// The number of minutes, after which the LFU counter of an idle key is decremented
+const LFU_DECAY_TIME_MINUTES: i64 = 1;
+
+/// Which keys are evicted, once the `maxmemory` limit is reached.
+///
+/// The `volatile` policies only evict keys which have an expiry set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EvictionPolicy {
+    NoEviction,
+    AllKeysLru,
+    AllKeysLfu,
+    AllKeysRandom,
+    VolatileLru,
+    VolatileLfu,
+    VolatileRandom,
+    VolatileTtl,
+}
+
+impl EvictionPolicy {
+    /// Parse the name of a policy, such as `allkeys-lru`
+    pub fn parse(name: &str) -> Option<EvictionPolicy> {
+        match name.to_lowercase().as_str() {
+            "noeviction" => Some(EvictionPolicy::NoEviction),
+            "allkeys-lru" => Some(EvictionPolicy::AllKeysLru),
+            "allkeys-lfu" => Some(EvictionPolicy::AllKeysLfu),
+            "allkeys-random" => Some(EvictionPolicy::AllKeysRandom),
+            "volatile-lru" => Some(EvictionPolicy::VolatileLru),
+            "volatile-lfu" => Some(EvictionPolicy::VolatileLfu),
+            "volatile-random" => Some(EvictionPolicy::VolatileRandom),
+            "volatile-ttl" => Some(EvictionPolicy::VolatileTtl),
+            _ => None,
+        }
+    }
+
+    /// Whether only keys with an expiry are candidates for eviction
+    pub fn is_volatile(&self) -> bool {
+        matches!(
+            self,
+            EvictionPolicy::VolatileLru
+                | EvictionPolicy::VolatileLfu
+                | EvictionPolicy::VolatileRandom
+                | EvictionPolicy::VolatileTtl
+        )
+    }
+}
+
+impl fmt::Display for EvictionPolicy {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            EvictionPolicy::NoEviction => "noeviction",
+            EvictionPolicy::AllKeysLru => "allkeys-lru",
+            EvictionPolicy::AllKeysLfu => "allkeys-lfu",
+            EvictionPolicy::AllKeysRandom => "allkeys-random",
+            EvictionPolicy::VolatileLru => "volatile-lru",
+            EvictionPolicy::VolatileLfu => "volatile-lfu",
+            EvictionPolicy::VolatileRandom => "volatile-random",
+            EvictionPolicy::VolatileTtl => "volatile-ttl",
+        };
+
+        name.fmt(fmt)
+    }
+}
+
+/// Logarithmically increment the LFU `counter`.
+///
+/// The counter is only 8 bits, so the probability of an increment
+/// decreases as the counter grows. With the default factor, a counter
+/// of 255 is reached after about a million accesses.
+pub fn lfu_increment(counter: u8) -> u8 {
+    if counter == u8::MAX {
+        return counter;
+    }
+
+    let base = counter.saturating_sub(LFU_INIT_VAL) as f64;
+    let probability = 1.0 / (base * LFU_LOG_FACTOR + 1.0);
+
+    if rand::random::<f64>() < probability {
+        counter + 1
+    } else {
+        counter
+    }
+}
+
+/// Decrement the LFU `counter` by the number of decay periods, which
+/// have elapsed since the key was `last_access`ed.
+pub fn lfu_decay(counter: u8, last_access: DateTime<Utc>) -> u8 {
+    let periods = (Utc::now() - last_access).num_minutes() / LFU_DECAY_TIME_MINUTES;
+
+    if periods >= counter as i64 {
+        0
+    } else {
+        counter - periods.max(0) as u8
+    }
+}
@@ -0,0 +1,190 @@
+// Handles the geohash encoding and distance calculations for the GEO commands.
+//
+// Locations are encoded the same way as in Redis, into a 52 bit interleaved
+// geohash, which is stored as the score of a member in a `SortedSet`.
+
+/// The limits of the longitude and latitude, which can be indexed.
+///
+/// The latitude is limited to the range of the Web Mercator projection (EPSG:3857).
+pub const LONGITUDE_MIN: f64 = -180.0;
+pub const LONGITUDE_MAX: f64 = 180.0;
+pub const LATITUDE_MIN: f64 = -85.05112878;
+pub const LATITUDE_MAX: f64 = 85.05112878;
+
+/// The number of bits used for each of the longitude and latitude
+const GEO_STEP: u32 = 26;
+
+/// The Earth's quadratic mean radius in meters, as used by Redis
+const EARTH_RADIUS_IN_METERS: f64 = 6372797.560856;
+
+/// The alphabet of the standard geohash string representation
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// The units in which distances can be provided and returned
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoUnit {
+    Meters,
+    Kilometers,
+    Feet,
+    Miles,
+}
+
+impl GeoUnit {
+    /// Parse the M | KM | FT | MI argument
+    pub fn parse(arg: &str) -> Option<GeoUnit> {
+        match arg.to_lowercase().as_str() {
+            "m" => Some(GeoUnit::Meters),
+            "km" => Some(GeoUnit::Kilometers),
+            "ft" => Some(GeoUnit::Feet),
+            "mi" => Some(GeoUnit::Miles),
+            _ => None,
+        }
+    }
+
+    /// The number of meters in a single unit
+    pub fn to_meters(&self) -> f64 {
+        match self {
+            GeoUnit::Meters => 1.0,
+            GeoUnit::Kilometers => 1000.0,
+            GeoUnit::Feet => 0.3048,
+            GeoUnit::Miles => 1609.34,
+        }
+    }
+}
+
+/// Check if the `longitude` and `latitude` pair can be indexed
+pub fn is_valid_coordinate(longitude: f64, latitude: f64) -> bool {
+    (LONGITUDE_MIN..=LONGITUDE_MAX).contains(&longitude)
+        && (LATITUDE_MIN..=LATITUDE_MAX).contains(&latitude)
+}
+
+/// Encode the `longitude` and `latitude` into a 52 bit geohash
+pub fn encode(longitude: f64, latitude: f64) -> u64 {
+    encode_in_range(
+        longitude,
+        latitude,
+        (LONGITUDE_MIN, LONGITUDE_MAX),
+        (LATITUDE_MIN, LATITUDE_MAX),
+    )
+}
+
+/// Decode a 52 bit geohash into the (longitude, latitude) at the center of its area
+pub fn decode(hash: u64) -> (f64, f64) {
+    let latitude_offset = deinterleave(hash);
+    let longitude_offset = deinterleave(hash >> 1);
+
+    let cells = (1u64 << GEO_STEP) as f64;
+    let longitude_unit = (LONGITUDE_MAX - LONGITUDE_MIN) / cells;
+    let latitude_unit = (LATITUDE_MAX - LATITUDE_MIN) / cells;
+
+    let longitude = LONGITUDE_MIN + (longitude_offset as f64 + 0.5) * longitude_unit;
+    let latitude = LATITUDE_MIN + (latitude_offset as f64 + 0.5) * latitude_unit;
+
+    (
+        longitude.clamp(LONGITUDE_MIN, LONGITUDE_MAX),
+        latitude.clamp(LATITUDE_MIN, LATITUDE_MAX),
+    )
+}
+
+/// Get the standard 11 character geohash string, for the `longitude` and `latitude`
+///
+/// The standard representation uses the full [-90, 90] latitude range, so the
+/// location is re-encoded, rather than using the stored 52 bit geohash.
+pub fn to_geohash_string(longitude: f64, latitude: f64) -> String {
+    let hash = encode_in_range(
+        longitude,
+        latitude,
+        (LONGITUDE_MIN, LONGITUDE_MAX),
+        (-90.0, 90.0),
+    );
+
+    (0..11)
+        .map(|i| {
+            // The 52 bits don't fill the last character, which is padded with zeros
+            let index = if i == 10 {
+                0
+            } else {
+                (hash >> (52 - ((i + 1) * 5))) & 0x1f
+            };
+
+            GEOHASH_ALPHABET[index as usize] as char
+        })
+        .collect()
+}
+
+/// Calculate the distance in meters between two points, using the haversine formula
+pub fn distance(longitude1: f64, latitude1: f64, longitude2: f64, latitude2: f64) -> f64 {
+    let latitude1 = latitude1.to_radians();
+    let latitude2 = latitude2.to_radians();
+    let u = ((latitude2 - latitude1) / 2.0).sin();
+    let v = ((longitude2 - longitude1).to_radians() / 2.0).sin();
+
+    let a = u * u + latitude1.cos() * latitude2.cos() * v * v;
+
+    2.0 * EARTH_RADIUS_IN_METERS * a.sqrt().asin()
+}
+
+/// Check if the point lies within the box of `width` by `height` meters,
+/// centered at the `center`.
+pub fn is_within_box(center: (f64, f64), point: (f64, f64), width: f64, height: f64) -> bool {
+    let (center_longitude, center_latitude) = center;
+    let (longitude, latitude) = point;
+
+    // The distance along the meridian, between the two latitudes
+    let latitude_distance = distance(
+        center_longitude,
+        center_latitude,
+        center_longitude,
+        latitude,
+    );
+    if latitude_distance > height / 2.0 {
+        return false;
+    }
+
+    // The distance along the parallel of the point, between the two longitudes
+    let longitude_distance = distance(center_longitude, latitude, longitude, latitude);
+    longitude_distance <= width / 2.0
+}
+
+fn encode_in_range(
+    longitude: f64,
+    latitude: f64,
+    longitude_range: (f64, f64),
+    latitude_range: (f64, f64),
+) -> u64 {
+    let cells = (1u64 << GEO_STEP) as f64;
+
+    let longitude_offset =
+        (longitude - longitude_range.0) / (longitude_range.1 - longitude_range.0) * cells;
+    let latitude_offset =
+        (latitude - latitude_range.0) / (latitude_range.1 - latitude_range.0) * cells;
+
+    // The maximum values would otherwise overflow into the next cell
+    let max_offset = (1u64 << GEO_STEP) - 1;
+    let longitude_offset = (longitude_offset as u64).min(max_offset);
+    let latitude_offset = (latitude_offset as u64).min(max_offset);
+
+    interleave(latitude_offset) | (interleave(longitude_offset) << 1)
+}
+
+/// Spread the lower 32 bits of `value` into the even bits of the result
+fn interleave(value: u64) -> u64 {
+    let mut result: u64 = 0;
+
+    for i in 0..32 {
+        result |= ((value >> i) & 1) << (2 * i);
+    }
+
+    result
+}
+
+/// Collect the even bits of `value`, into the lower 32 bits of the result
+fn deinterleave(value: u64) -> u64 {
+    let mut result: u64 = 0;
+
+    for i in 0..32 {
+        result |= ((value >> (2 * i)) & 1) << i;
+    }
+
+    result
+}
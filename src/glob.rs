@@ -0,0 +1,110 @@
+/// Match the `text` against a glob-style `pattern`, as in Redis.
+///
+/// Supported patterns:
+/// - `?` matches any single character
+/// - `*` matches any sequence of characters, including an empty one
+/// - `[abc]` matches one of the characters, `[^abc]` any other one, and `[a-z]` a range
+/// - `\x` matches the character `x` literally
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    match_bytes(pattern.as_bytes(), text.as_bytes())
+}
+
+fn match_bytes(mut pattern: &[u8], mut text: &[u8]) -> bool {
+    while let Some(&p) = pattern.first() {
+        match p {
+            b'*' => {
+                // Consecutive stars match the same as a single one
+                while pattern.first() == Some(&b'*') {
+                    pattern = &pattern[1..];
+                }
+
+                if pattern.is_empty() {
+                    return true;
+                }
+
+                return (0..=text.len()).any(|start| match_bytes(pattern, &text[start..]));
+            }
+            b'?' => {
+                if text.is_empty() {
+                    return false;
+                }
+
+                text = &text[1..];
+                pattern = &pattern[1..];
+            }
+            b'[' => {
+                let c = match text.first() {
+                    Some(c) => *c,
+                    None => return false,
+                };
+
+                let (matched, rest) = match_class(&pattern[1..], c);
+                if !matched {
+                    return false;
+                }
+
+                text = &text[1..];
+                pattern = rest;
+            }
+            _ => {
+                // An escaped character is matched literally
+                let (literal, rest) = match (p, pattern.get(1)) {
+                    (b'\\', Some(escaped)) => (*escaped, &pattern[2..]),
+                    _ => (p, &pattern[1..]),
+                };
+
+                if text.first() != Some(&literal) {
+                    return false;
+                }
+
+                text = &text[1..];
+                pattern = rest;
+            }
+        }
+    }
+
+    text.is_empty()
+}
+
+/// Match the character `c` against a `[...]` class, whose opening bracket
+/// was already consumed. Returns whether it matched, and the rest of the
+/// pattern after the closing bracket.
+fn match_class(mut pattern: &[u8], c: u8) -> (bool, &[u8]) {
+    let negated = pattern.first() == Some(&b'^');
+    if negated {
+        pattern = &pattern[1..];
+    }
+
+    let mut matched = false;
+
+    loop {
+        match pattern {
+            // An unclosed class ends with the pattern
+            [] => break,
+            [b']', rest @ ..] => {
+                pattern = rest;
+                break;
+            }
+            [b'\\', escaped, rest @ ..] => {
+                matched |= *escaped == c;
+                pattern = rest;
+            }
+            [start, b'-', end, rest @ ..] if *end != b']' => {
+                let (low, high) = if start <= end {
+                    (*start, *end)
+                } else {
+                    (*end, *start)
+                };
+
+                matched |= low <= c && c <= high;
+                pattern = rest;
+            }
+            [literal, rest @ ..] => {
+                matched |= *literal == c;
+                pattern = rest;
+            }
+        }
+    }
+
+    (matched != negated, pattern)
+}
@@ -0,0 +1,129 @@
+// A histogram of latencies in the style of HDR histograms: the values are
+// counted in buckets whose width grows with their magnitude, so that any
+// value is recorded with a bounded relative error, in a fixed amount of memory.
+
+/// The number of linear sub-buckets, which each power of two is split into.
+/// Values are recorded with a relative error of at most 1/16.
+const SUB_BUCKET_BITS: u32 = 4;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+
+/// The values below `SUB_BUCKETS` each have a bucket of their own, and each
+/// power of two above has `SUB_BUCKETS` buckets.
+const NUM_BUCKETS: usize = SUB_BUCKETS + (64 - SUB_BUCKET_BITS as usize) * SUB_BUCKETS;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyHistogram {
+    counts: Vec<u64>,
+    total: u64,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> LatencyHistogram {
+        LatencyHistogram {
+            counts: vec![0; NUM_BUCKETS],
+            total: 0,
+        }
+    }
+
+    /// The index of the bucket, which the `value` is counted in
+    fn bucket_index(value: u64) -> usize {
+        if value < SUB_BUCKETS as u64 {
+            return value as usize;
+        }
+
+        let exponent = 63 - value.leading_zeros();
+        let sub_bucket = (value >> (exponent - SUB_BUCKET_BITS)) as usize - SUB_BUCKETS;
+
+        SUB_BUCKETS + (exponent - SUB_BUCKET_BITS) as usize * SUB_BUCKETS + sub_bucket
+    }
+
+    /// The highest value, which is counted in the bucket at `index`
+    fn bucket_upper_bound(index: usize) -> u64 {
+        if index < SUB_BUCKETS {
+            return index as u64;
+        }
+
+        let shift = ((index - SUB_BUCKETS) / SUB_BUCKETS) as u32;
+        let mantissa = SUB_BUCKETS + (index - SUB_BUCKETS) % SUB_BUCKETS;
+
+        // The bounds of the highest buckets don't fit into a u64
+        let bound = ((mantissa as u128 + 1) << shift) - 1;
+        bound.min(u64::MAX as u128) as u64
+    }
+
+    /// Count a latency of `value` microseconds
+    pub fn record(&mut self, value: u64) {
+        self.counts[LatencyHistogram::bucket_index(value)] += 1;
+        self.total += 1;
+    }
+
+    /// The number of recorded values
+    pub fn len(&self) -> u64 {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// The value below which `percentile` percent of the recorded values lie,
+    /// e.g. `value_at_percentile(99.0)`. Will return 0 if there are none.
+    pub fn value_at_percentile(&self, percentile: f64) -> u64 {
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0) * self.total as f64).ceil() as u64;
+        let rank = rank.max(1);
+
+        let mut count = 0;
+        for (index, bucket) in self.counts.iter().enumerate() {
+            count += bucket;
+            if count >= rank {
+                return LatencyHistogram::bucket_upper_bound(index);
+            }
+        }
+
+        0
+    }
+
+    /// The cumulative counts of the values up to each power of two, from the
+    /// lowest to the highest one which holds a recorded value, as in the
+    /// reply of LATENCY HISTOGRAM.
+    pub fn power_of_two_buckets(&self) -> Vec<(u64, u64)> {
+        let mut buckets: Vec<(u64, u64)> = Vec::new();
+        let mut count = 0;
+
+        for (index, bucket) in self.counts.iter().enumerate() {
+            if *bucket == 0 {
+                continue;
+            }
+
+            count += bucket;
+            let bound = LatencyHistogram::bucket_upper_bound(index)
+                .max(1)
+                .checked_next_power_of_two()
+                .unwrap_or(u64::MAX);
+
+            // Fill in the powers of two between the recorded values
+            let mut next = match buckets.last() {
+                Some((last, _)) => last.saturating_mul(2),
+                None => bound,
+            };
+            while next < bound {
+                let previous = buckets.last().map_or(0, |(_, count)| *count);
+                buckets.push((next, previous));
+                next = next.saturating_mul(2);
+            }
+
+            match buckets.last_mut() {
+                Some(last) if last.0 == bound => last.1 = count,
+                _ => buckets.push((bound, count)),
+            }
+        }
+
+        buckets
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        LatencyHistogram::new()
+    }
+}
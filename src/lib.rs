@@ -1,20 +1,91 @@
+pub mod acl;
+pub use acl::{Acl, DEFAULT_USER};
+pub mod cli;
+pub mod client_pause;
+pub mod clients;
+pub use client_pause::{ClientPause, PauseMode};
+pub use clients::Clients;
+pub mod cluster;
+pub use cluster::Cluster;
 pub mod cmd;
+pub mod config;
+pub use config::Config;
 pub mod connection;
+pub mod context;
+pub use context::{ClientContext, ServerContext};
 pub mod data_store;
 pub use connection::Connection;
 pub use connection::ConnectionBase;
+pub use connection::ErrorTracker;
 pub use connection::MockConnectionBase;
+pub use connection::ReplyBuffer;
 pub use data_store::DataType;
+pub use data_store::KeyspaceStats;
+pub use data_store::MemoryStats;
 pub use data_store::MockSharedStoreBase;
+pub use data_store::ObjectInfo;
 pub use data_store::SharedStore;
 pub use data_store::SharedStoreBase;
+pub mod eviction;
+pub use eviction::EvictionPolicy;
+pub mod geo;
+pub mod glob;
+pub mod histogram;
+pub mod metrics;
+pub mod monitor;
+pub use monitor::Monitor;
 pub mod protocol_handler;
 pub use protocol_handler::deserialize_buffer;
 pub use protocol_handler::serialize_data;
+pub use protocol_handler::ProtocolLimits;
 pub use protocol_handler::RESPType;
+pub mod replication;
+pub use replication::Replication;
 pub mod server;
+pub mod shutdown;
+pub use shutdown::Shutdown;
+pub mod slowlog;
+pub use slowlog::SlowLog;
+pub mod sorted_set;
+pub mod stats;
+pub use stats::Stats;
+pub mod tls;
 
 pub const DEFAULT_HOST: &str = "127.0.0.1";
 pub const DEFAULT_PORT: u16 = 6666;
-pub const KEY_EXPIRY_DELAY_MS: u64 = 100;
-pub const KEY_EXPIRY_NUM_KEYS_TO_CHECK: usize = 20;
+pub const DEFAULT_HZ: u64 = 10;
+pub const DEFAULT_ACTIVE_EXPIRE_KEYS: usize = 20;
+pub const DEFAULT_IO_BUFFER_SIZE: usize = 4 * 1024;
+pub const DEFAULT_NUM_SHARDS: usize = 16;
+pub const DEFAULT_MAXMEMORY: usize = 0;
+pub const DEFAULT_MAXMEMORY_POLICY: EvictionPolicy = EvictionPolicy::NoEviction;
+pub const MAXMEMORY_SAMPLES: usize = 5;
+pub const REPL_BACKLOG_SIZE: usize = 1024 * 1024;
+pub const REPL_STREAM_CAPACITY: usize = 16 * 1024;
+pub const REPL_RECONNECT_DELAY_MS: u64 = 1000;
+pub const REPL_ACK_PERIOD_MS: u64 = 1000;
+pub const DEFAULT_REPLICA_READ_ONLY: bool = true;
+
+pub const ACLLOG_MAX_LEN: usize = 128;
+pub const DEFAULT_PROTECTED_MODE: bool = true;
+
+pub const DEFAULT_MAXCLIENTS: usize = 10000;
+pub const ACCEPT_BACKOFF_MIN_MS: u64 = 5;
+pub const ACCEPT_BACKOFF_MAX_MS: u64 = 1000;
+pub const DEFAULT_TIMEOUT: u64 = 0;
+pub const DEFAULT_SHUTDOWN_TIMEOUT: u64 = 10;
+pub const DEFAULT_TCP_KEEPALIVE: u64 = 300;
+
+pub const DEFAULT_PROTO_MAX_BULK_LEN: usize = 512 * 1024 * 1024;
+pub const PROTO_MAX_MULTIBULK_LEN: usize = 1024 * 1024;
+pub const PROTO_INLINE_MAX_SIZE: usize = 64 * 1024;
+pub const PROTO_MAX_NESTING: usize = 128;
+
+pub const DEFAULT_SLOWLOG_LOG_SLOWER_THAN: i64 = 10000;
+pub const DEFAULT_SLOWLOG_MAX_LEN: usize = 128;
+pub const MONITOR_FEED_CAPACITY: usize = 1024;
+
+pub const CLUSTER_SLOTS: u16 = 16384;
+pub const CLUSTER_NODE_TIMEOUT_MS: u64 = 15000;
+pub const CLUSTER_PING_PERIOD_MS: u64 = 100;
+pub const CLUSTER_LINK_RETRY_MS: u64 = 1000;
@@ -0,0 +1,299 @@
+// The metrics of the server, served over HTTP in the text format of Prometheus,
+// e.g. `curl http://127.0.0.1:9121/metrics`. They hold the same counters as
+// INFO, for the scrapers which don't speak RESP.
+use crate::context::ServerContext;
+use crate::server::accept_with_backoff;
+use crate::stats::CommandStats;
+use crate::SharedStoreBase;
+use std::fmt::Display;
+use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::{timeout, Duration};
+
+/// The maximum size of the head of a request, past which it is refused
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// How long a client has to send its request and read the response, before
+/// its connection is closed
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The content type of the text format of Prometheus
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// The metric families of the server, in the text format of Prometheus
+struct MetricsWriter {
+    text: String,
+}
+
+impl MetricsWriter {
+    /// Add a metric family of the `kind`, e.g. `counter`, with a sample for
+    /// each of the `samples` labels
+    fn family<V: Display>(&mut self, name: &str, kind: &str, help: &str, samples: &[(String, V)]) {
+        self.text
+            .push_str(&format!("# HELP redust_{} {}\n", name, help));
+        self.text
+            .push_str(&format!("# TYPE redust_{} {}\n", name, kind));
+
+        for (labels, value) in samples {
+            self.text
+                .push_str(&format!("redust_{}{} {}\n", name, labels, value));
+        }
+    }
+
+    /// Add a metric family with a single sample, without labels
+    fn metric<V: Display>(&mut self, name: &str, kind: &str, help: &str, value: V) {
+        self.family(name, kind, help, &[(String::new(), value)]);
+    }
+}
+
+/// Render the metrics of the `server`
+pub fn render(server: &ServerContext) -> String {
+    let mut metrics = MetricsWriter {
+        text: String::new(),
+    };
+
+    let stats = &server.stats;
+    metrics.metric(
+        "uptime_in_seconds",
+        "gauge",
+        "Number of seconds since the server was started",
+        stats.uptime_in_seconds(),
+    );
+    metrics.metric(
+        "connected_clients",
+        "gauge",
+        "Number of clients connected now",
+        stats.connected_clients(),
+    );
+    metrics.metric(
+        "connections_received_total",
+        "counter",
+        "Number of connections accepted",
+        stats.total_connections_received(),
+    );
+    metrics.metric(
+        "rejected_connections_total",
+        "counter",
+        "Number of connections refused over maxclients",
+        stats.rejected_connections(),
+    );
+    metrics.metric(
+        "commands_processed_total",
+        "counter",
+        "Number of commands executed",
+        stats.total_commands_processed(),
+    );
+
+    // The counters of each command, labelled with its name
+    let commands = stats.command_stats();
+    let by_command = |value: &dyn Fn(&CommandStats) -> String| {
+        commands
+            .iter()
+            .map(|(name, stats)| (format!("{{cmd=\"{}\"}}", name), value(stats)))
+            .collect::<Vec<(String, String)>>()
+    };
+
+    metrics.family(
+        "commands_total",
+        "counter",
+        "Number of executions of each command",
+        &by_command(&|stats| stats.calls.to_string()),
+    );
+    metrics.family(
+        "commands_duration_seconds_total",
+        "counter",
+        "Total duration of the executions of each command",
+        &by_command(&|stats| (stats.usec as f64 / 1e6).to_string()),
+    );
+    metrics.family(
+        "commands_rejected_calls_total",
+        "counter",
+        "Number of calls of each command refused before their execution",
+        &by_command(&|stats| stats.rejected_calls.to_string()),
+    );
+    metrics.family(
+        "commands_failed_calls_total",
+        "counter",
+        "Number of executions of each command which replied with an error",
+        &by_command(&|stats| stats.failed_calls.to_string()),
+    );
+
+    let errors: Vec<(String, u64)> = stats
+        .error_stats()
+        .into_iter()
+        .map(|(code, count)| (format!("{{err=\"{}\"}}", code), count))
+        .collect();
+    metrics.family(
+        "errors_total",
+        "counter",
+        "Number of error replies of each type",
+        &errors,
+    );
+
+    // The only database is left out while it is empty, as in INFO keyspace
+    let memory = server.shared_store.memory_stats();
+    let db = |value: usize| match memory.keys {
+        0 => Vec::new(),
+        _ => vec![("{db=\"db0\"}".to_string(), value)],
+    };
+    metrics.family(
+        "db_keys",
+        "gauge",
+        "Number of keys of each database",
+        &db(memory.keys),
+    );
+    metrics.family(
+        "db_keys_expiring",
+        "gauge",
+        "Number of keys with an expiry of each database",
+        &db(memory.expires),
+    );
+
+    let keyspace = server.shared_store.keyspace_stats();
+    metrics.metric(
+        "keyspace_hits_total",
+        "counter",
+        "Number of reads of keys which existed",
+        keyspace.keyspace_hits,
+    );
+    metrics.metric(
+        "keyspace_misses_total",
+        "counter",
+        "Number of reads of keys which didn't exist",
+        keyspace.keyspace_misses,
+    );
+    metrics.metric(
+        "expired_keys_total",
+        "counter",
+        "Number of keys removed once their expiry passed",
+        keyspace.expired_keys,
+    );
+    metrics.metric(
+        "evicted_keys_total",
+        "counter",
+        "Number of keys removed to stay under the maxmemory limit",
+        keyspace.evicted_keys,
+    );
+
+    metrics.metric(
+        "memory_used_bytes",
+        "gauge",
+        "Approximate number of bytes used by the keys",
+        memory.used_memory,
+    );
+    metrics.metric(
+        "memory_used_peak_bytes",
+        "gauge",
+        "Highest number of bytes used by the keys",
+        memory.peak_memory,
+    );
+    metrics.metric(
+        "memory_used_dataset_bytes",
+        "gauge",
+        "Approximate number of bytes used by the values of the keys",
+        memory.dataset,
+    );
+    metrics.metric(
+        "memory_max_bytes",
+        "gauge",
+        "The maxmemory limit, or 0 without one",
+        memory.maxmemory,
+    );
+
+    let (cycles, usec) = server.shared_store.expiry_cycles();
+    metrics.metric(
+        "expire_cycles_total",
+        "counter",
+        "Number of runs of the expiry tasks of the shards",
+        cycles,
+    );
+    metrics.metric(
+        "expire_cycles_duration_seconds_total",
+        "counter",
+        "Total duration of the runs of the expiry tasks",
+        usec as f64 / 1e6,
+    );
+
+    metrics.text
+}
+
+/// Serve the metrics of the `server` on the `listener`, to each HTTP client.
+///
+/// As for the other clients, accepting is retried after errors, and the
+/// connections count towards `maxclients`.
+pub async fn run(listener: TcpListener, server: ServerContext) -> io::Result<()> {
+    loop {
+        let (mut socket, _) = accept_with_backoff(|| listener.accept()).await;
+        let server = server.clone();
+
+        let slot = match server.clients.try_reserve() {
+            Some(slot) => slot,
+            None => {
+                server.stats.connection_rejected();
+                tokio::spawn(async move {
+                    let refused = respond(&mut socket, "503 Service Unavailable", "");
+                    let _ = timeout(REQUEST_TIMEOUT, refused).await;
+                });
+                continue;
+            }
+        };
+
+        tokio::spawn(async move {
+            match timeout(REQUEST_TIMEOUT, handle(socket, &server)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => println!("Metrics Error | {:?}", err),
+                Err(_) => println!("Metrics Error | The request timed out"),
+            }
+
+            drop(slot);
+        });
+    }
+}
+
+/// Answer a single request, and close the connection
+async fn handle(mut socket: TcpStream, server: &ServerContext) -> io::Result<()> {
+    let mut head: Vec<u8> = Vec::new();
+    let mut buffer = [0u8; 1024];
+
+    // Read the request line and headers. The body, if any, is ignored.
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = socket.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+
+        head.extend_from_slice(&buffer[..n]);
+        if head.len() > MAX_REQUEST_HEAD {
+            return respond(&mut socket, "431 Request Header Fields Too Large", "").await;
+        }
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line
+        .next()
+        .unwrap_or_default()
+        .split('?')
+        .next()
+        .unwrap_or_default();
+
+    match (method, path) {
+        ("GET", "/metrics") => respond(&mut socket, "200 OK", &render(server)).await,
+        (_, "/metrics") => respond(&mut socket, "405 Method Not Allowed", "").await,
+        _ => respond(&mut socket, "404 Not Found", "").await,
+    }
+}
+
+async fn respond(socket: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        CONTENT_TYPE,
+        body.len(),
+        body
+    );
+
+    socket.write_all(response.as_bytes()).await?;
+    socket.shutdown().await
+}
@@ -0,0 +1,87 @@
+// The feed of MONITOR. Each command processed by the server is broadcast to
+// the connections which run MONITOR, as a line of its arguments.
+use crate::MONITOR_FEED_CAPACITY;
+use chrono::Utc;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// The sender of the lines of the commands to the monitors.
+///
+/// The lines are broadcast over a bounded channel, so that a monitor which
+/// reads slower than the commands are processed misses lines, rather than
+/// slowing down the server. A clone only increments the reference count.
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    feed: broadcast::Sender<Arc<String>>,
+}
+
+impl Monitor {
+    pub fn new() -> Monitor {
+        let (feed, _) = broadcast::channel(MONITOR_FEED_CAPACITY);
+        Monitor { feed }
+    }
+
+    /// Receive the lines of the commands processed from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<String>> {
+        self.feed.subscribe()
+    }
+
+    /// Whether any connection is monitoring, so that the lines are only
+    /// formatted when they are received
+    pub fn is_active(&self) -> bool {
+        self.feed.receiver_count() > 0
+    }
+
+    /// Send the line of a command with `args`, from the client at `client_addr`,
+    /// as in Redis: `1339518083.107412 [0 127.0.0.1:60866] "SET" "key" "value"`
+    pub fn feed(&self, args: &[String], client_addr: &str) {
+        if !self.is_active() {
+            return;
+        }
+
+        let now = Utc::now();
+        let mut line = format!(
+            "{}.{:06} [0 {}]",
+            now.timestamp(),
+            now.timestamp_subsec_micros(),
+            client_addr
+        );
+
+        for arg in args {
+            line.push(' ');
+            line.push_str(&quote(arg));
+        }
+
+        // There may be no monitor left since it was checked
+        let _ = self.feed.send(Arc::new(line));
+    }
+}
+
+impl Default for Monitor {
+    fn default() -> Self {
+        Monitor::new()
+    }
+}
+
+/// Quote the `arg`, escaping the quotes, backslashes and non-printable bytes
+pub fn quote(arg: &str) -> String {
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+
+    for byte in arg.bytes() {
+        match byte {
+            b'\\' => quoted.push_str("\\\\"),
+            b'"' => quoted.push_str("\\\""),
+            b'\n' => quoted.push_str("\\n"),
+            b'\r' => quoted.push_str("\\r"),
+            b'\t' => quoted.push_str("\\t"),
+            0x07 => quoted.push_str("\\a"),
+            0x08 => quoted.push_str("\\b"),
+            byte if byte.is_ascii_graphic() || byte == b' ' => quoted.push(byte as char),
+            byte => quoted.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
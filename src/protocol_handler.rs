@@ -1,24 +1,91 @@
 // The handles the REdis Serialization Protocol parsing for all necessary types.
+use crate::{
+    DEFAULT_PROTO_MAX_BULK_LEN, PROTO_INLINE_MAX_SIZE, PROTO_MAX_MULTIBULK_LEN, PROTO_MAX_NESTING,
+};
 use std::fmt;
 
 const MSG_SEPERATOR: &[u8; 2] = b"\r\n";
 const MSG_SEPERATOR_SIZE: usize = MSG_SEPERATOR.len();
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum RESPType {
     SimpleString(String),
     Error(String),
     Integer(i64),
     BulkString(Option<BulkStringData>),
+    /// A bulk string which isn't valid UTF-8, e.g. a bitmap with its high bits set
+    BulkBytes(Vec<u8>),
     Array(Vec<RESPType>),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BulkStringData {
     pub text: String,
     pub prefix_length: usize,
 }
 
+/// The limits on the frames which are parsed, so that a client can't make the
+/// server buffer endless or huge frames
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProtocolLimits {
+    /// The maximum length of a bulk string
+    pub max_bulk_len: usize,
+
+    /// The maximum number of elements of an array
+    pub max_multibulk_len: usize,
+
+    /// The maximum length of a line, e.g. a simple string, or the length
+    /// prefix of a bulk string
+    pub max_inline_size: usize,
+}
+
+impl Default for ProtocolLimits {
+    fn default() -> Self {
+        ProtocolLimits {
+            max_bulk_len: DEFAULT_PROTO_MAX_BULK_LEN,
+            max_multibulk_len: PROTO_MAX_MULTIBULK_LEN,
+            max_inline_size: PROTO_INLINE_MAX_SIZE,
+        }
+    }
+}
+
+/// The frames which can't be parsed. The connections which send them are
+/// closed, as the rest of their stream can't be parsed either.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProtocolError {
+    InvalidBulkLength,
+    InvalidMultibulkLength,
+    InvalidInteger,
+    TooBigInline,
+    TooDeepNesting,
+    UnexpectedByte(u8),
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProtocolError::InvalidBulkLength => write!(fmt, "Protocol error: invalid bulk length"),
+            ProtocolError::InvalidMultibulkLength => {
+                write!(fmt, "Protocol error: invalid multibulk length")
+            }
+            ProtocolError::InvalidInteger => write!(fmt, "Protocol error: invalid integer"),
+            ProtocolError::TooBigInline => write!(fmt, "Protocol error: too big inline request"),
+            ProtocolError::TooDeepNesting => write!(fmt, "Protocol error: too deep nesting"),
+            ProtocolError::UnexpectedByte(byte) => write!(
+                fmt,
+                "Protocol error: unexpected byte '{}'",
+                byte.escape_ascii()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+/// The result of parsing a buffer: a frame and its size, or `None` and 0 if
+/// the buffer doesn't hold a whole frame yet
+type Parsed = Result<(Option<RESPType>, usize), ProtocolError>;
+
 impl fmt::Display for RESPType {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -26,6 +93,7 @@ impl fmt::Display for RESPType {
             RESPType::Error(msg) => msg.fmt(fmt),
             RESPType::Integer(num) => num.fmt(fmt),
             RESPType::BulkString(msg) => write!(fmt, "{:?}", msg),
+            RESPType::BulkBytes(bytes) => write!(fmt, "\"{}\"", bytes.escape_ascii()),
             RESPType::Array(parts) => {
                 for (i, part) in parts.iter().enumerate() {
                     if i > 0 {
@@ -51,27 +119,50 @@ trait BaseSerializer {
         Some(serialized.into_bytes())
     }
 
-    fn deserialize(&self, buffer: &[u8]) -> (Option<RESPType>, usize);
+    fn deserialize(&self, buffer: &[u8], limits: &ProtocolLimits) -> Parsed;
 
     fn get_symbol(&self) -> &str;
 
-    /// To extract the length prefix for Bulk Strings and Arrays
-    fn extract_length(&self, buffer: &[u8]) -> Option<(i32, usize)> {
-        // Find the position of the first occurence of '\r\n' delimiter
-        let separator: Option<usize> = buffer
+    /// Find the position of the first occurence of '\r\n' delimiter. Lines
+    /// which are longer than the limit are refused, even before they end.
+    fn find_separator(
+        &self,
+        buffer: &[u8],
+        limits: &ProtocolLimits,
+    ) -> Result<Option<usize>, ProtocolError> {
+        // The separator can't be further than the longest line
+        let line_end = limits.max_inline_size.saturating_add(MSG_SEPERATOR_SIZE);
+        let separator: Option<usize> = buffer[..buffer.len().min(line_end)]
             .windows(2)
             .position(|window: &[u8]| window == b"\r\n");
 
-        if let Some(crlf_pos) = separator {
-            // Convert the byte slice to a string slice, skipping the initial symbol
-            if let Ok(length_str) = std::str::from_utf8(&buffer[1..crlf_pos]) {
-                // Parse the length_str
-                if let Ok(length) = length_str.parse::<i32>() {
-                    return Some((length, crlf_pos));
-                }
-            }
+        match separator {
+            Some(position) => Ok(Some(position)),
+            // The line may still end within the limit
+            None if buffer.len() < line_end => Ok(None),
+            None => Err(ProtocolError::TooBigInline),
         }
-        None
+    }
+
+    /// To extract the length prefix for Bulk Strings and Arrays, which is
+    /// `invalid` if it isn't a number
+    fn extract_length(
+        &self,
+        buffer: &[u8],
+        limits: &ProtocolLimits,
+        invalid: ProtocolError,
+    ) -> Result<Option<(i64, usize)>, ProtocolError> {
+        let crlf_pos = match self.find_separator(buffer, limits)? {
+            Some(crlf_pos) => crlf_pos,
+            None => return Ok(None),
+        };
+
+        // Convert the byte slice to a string slice, skipping the initial symbol
+        std::str::from_utf8(&buffer[1..crlf_pos])
+            .ok()
+            .and_then(|length_str| length_str.parse::<i64>().ok())
+            .map(|length| Some((length, crlf_pos)))
+            .ok_or(invalid)
     }
 }
 
@@ -84,18 +175,16 @@ impl BaseSerializer for SimpleStringSerializer {
         &Self::SYMBOL
     }
 
-    fn deserialize(&self, buffer: &[u8]) -> (Option<RESPType>, usize) {
-        let separator: Option<usize> = buffer
-            .windows(2)
-            .position(|window: &[u8]| window == b"\r\n");
+    fn deserialize(&self, buffer: &[u8], limits: &ProtocolLimits) -> Parsed {
+        let separator: Option<usize> = self.find_separator(buffer, limits)?;
 
         if let Some(position) = separator {
             // The separator was found
             let payload: String = String::from_utf8_lossy(&buffer[1..position]).to_string();
-            return (Some(RESPType::SimpleString(payload)), position + 2);
+            Ok((Some(RESPType::SimpleString(payload)), position + 2))
         } else {
             // The separator was not found
-            return (None, 0);
+            Ok((None, 0))
         }
     }
 }
@@ -109,18 +198,16 @@ impl BaseSerializer for ErrorSerializer {
         &Self::SYMBOL
     }
 
-    fn deserialize(&self, buffer: &[u8]) -> (Option<RESPType>, usize) {
-        let separator: Option<usize> = buffer
-            .windows(2)
-            .position(|window: &[u8]| window == b"\r\n");
+    fn deserialize(&self, buffer: &[u8], limits: &ProtocolLimits) -> Parsed {
+        let separator: Option<usize> = self.find_separator(buffer, limits)?;
 
         if let Some(position) = separator {
             // The separator was found
             let payload: String = String::from_utf8_lossy(&buffer[1..position]).to_string();
-            return (Some(RESPType::Error(payload)), position + 2);
+            Ok((Some(RESPType::Error(payload)), position + 2))
         } else {
             // The separator was not found
-            return (None, 0);
+            Ok((None, 0))
         }
     }
 }
@@ -134,27 +221,20 @@ impl BaseSerializer for IntegerSerializer {
         &Self::SYMBOL
     }
 
-    fn deserialize(&self, buffer: &[u8]) -> (Option<RESPType>, usize) {
-        let separator: Option<usize> = buffer
-            .windows(2)
-            .position(|window: &[u8]| window == b"\r\n");
+    fn deserialize(&self, buffer: &[u8], limits: &ProtocolLimits) -> Parsed {
+        let separator: Option<usize> = self.find_separator(buffer, limits)?;
 
         if let Some(position) = separator {
             // The separator was found
             let payload: Result<i64, _> = String::from_utf8_lossy(&buffer[1..position]).parse();
 
             match payload {
-                Ok(integer) => {
-                    return (Some(RESPType::Integer(integer)), position + 2);
-                }
-                Err(_) => {
-                    println!("Failed to parse integer.");
-                    return (None, 0);
-                }
+                Ok(integer) => Ok((Some(RESPType::Integer(integer)), position + 2)),
+                Err(_) => Err(ProtocolError::InvalidInteger),
             }
         } else {
             // The separator was not found
-            return (None, 0);
+            Ok((None, 0))
         }
     }
 }
@@ -184,17 +264,32 @@ impl BaseSerializer for BulkStringSerializer {
                 }
                 None => return Some(format!("{}{}\r\n", self.get_symbol(), -1).into_bytes()),
             },
+            RESPType::BulkBytes(bytes) => {
+                let mut buf = format!("{}{}\r\n", self.get_symbol(), bytes.len()).into_bytes();
+                buf.extend_from_slice(bytes);
+                buf.extend_from_slice(MSG_SEPERATOR);
+                return Some(buf);
+            }
             _ => return None,
         };
     }
 
-    fn deserialize(&self, buffer: &[u8]) -> (Option<RESPType>, usize) {
+    fn deserialize(&self, buffer: &[u8], limits: &ProtocolLimits) -> Parsed {
         // Found a prefix length, with the start_pos of the 1st CRLF
-        let length_and_pos = self.extract_length(buffer);
+        let length_and_pos =
+            self.extract_length(buffer, limits, ProtocolError::InvalidBulkLength)?;
 
         if let Some((prefix_length, crlf_pos)) = length_and_pos {
+            // The length is checked before the string is received, so that it
+            // isn't buffered
+            if prefix_length < -1
+                || (prefix_length > 0 && prefix_length as u64 > limits.max_bulk_len as u64)
+            {
+                return Err(ProtocolError::InvalidBulkLength);
+            }
+
             if prefix_length == -1 {
-                return (Some(RESPType::BulkString(None)), 5);
+                Ok((Some(RESPType::BulkString(None)), 5))
             } else {
                 let prefix_length_u = prefix_length as usize;
 
@@ -203,6 +298,12 @@ impl BaseSerializer for BulkStringSerializer {
                 let start_index: usize = crlf_pos + 2;
                 let end_index: usize = start_index + prefix_length_u;
 
+                // The rest of the bulk string hasn't been read yet, e.g. when it
+                // doesn't fit into the read buffer of the connection
+                if end_index > buffer.len() {
+                    return Ok((None, 0));
+                }
+
                 // Create a new buffer containing the specified bytes
                 let new_buffer: Vec<u8>;
                 if prefix_length == 0 {
@@ -212,37 +313,40 @@ impl BaseSerializer for BulkStringSerializer {
                 }
 
                 if new_buffer.len() != prefix_length_u {
-                    return (None, 0);
+                    Ok((None, 0))
                 } else {
                     // Ensure end_index is within bounds
                     if (end_index + 2) > buffer.len() {
-                        return (None, 0);
+                        return Ok((None, 0));
                     }
 
                     // Verify the two bytes after the end index are CRLF
                     if buffer[end_index..end_index + 2] != *MSG_SEPERATOR {
-                        return (None, 0);
+                        Ok((None, 0))
                     } else {
-                        // Unwrap to string, and construct the enum
-                        let text = String::from_utf8(new_buffer).unwrap();
-                        let bulk_str_data = BulkStringData {
-                            text,
-                            prefix_length: prefix_length_u,
+                        // Unwrap to string, and construct the enum. The
+                        // strings which aren't valid UTF-8 are kept as bytes.
+                        let frame = match String::from_utf8(new_buffer) {
+                            Ok(text) => RESPType::BulkString(Some(BulkStringData {
+                                text,
+                                prefix_length: prefix_length_u,
+                            })),
+                            Err(err) => RESPType::BulkBytes(err.into_bytes()),
                         };
 
-                        return (
-                            Some(RESPType::BulkString(Some(bulk_str_data))),
+                        Ok((
+                            Some(frame),
                             1 + prefix_length_u.to_string().len()
                                 + MSG_SEPERATOR_SIZE
                                 + prefix_length_u
                                 + MSG_SEPERATOR_SIZE,
-                        );
+                        ))
                     }
                 }
             }
         } else {
             // Did not find prefix_length and CRLF
-            return (None, 0);
+            Ok((None, 0))
         }
     }
 }
@@ -285,13 +389,31 @@ impl BaseSerializer for ArraySerializer {
         };
     }
 
-    fn deserialize(&self, buffer: &[u8]) -> (Option<RESPType>, usize) {
+    fn deserialize(&self, buffer: &[u8], limits: &ProtocolLimits) -> Parsed {
+        self.deserialize_nested(buffer, limits, 0)
+    }
+}
+
+impl ArraySerializer {
+    /// Deserialize an array within `depth` other arrays. The nesting is limited,
+    /// as each level is parsed recursively on the stack.
+    fn deserialize_nested(&self, buffer: &[u8], limits: &ProtocolLimits, depth: usize) -> Parsed {
+        if depth >= PROTO_MAX_NESTING {
+            return Err(ProtocolError::TooDeepNesting);
+        }
+
         // Found a prefix length, with the start_pos of the 1st CRLF
-        let length_and_pos = self.extract_length(buffer);
+        let length_and_pos =
+            self.extract_length(buffer, limits, ProtocolError::InvalidMultibulkLength)?;
 
         if let Some((array_length, crlf_pos)) = length_and_pos {
-            if array_length == 0 {
-                return (Some(RESPType::Array(Vec::new())), 2 + MSG_SEPERATOR_SIZE);
+            if array_length < -1 || array_length > limits.max_multibulk_len as i64 {
+                return Err(ProtocolError::InvalidMultibulkLength);
+            }
+
+            // The null array is read as an empty one
+            if array_length <= 0 {
+                Ok((Some(RESPType::Array(Vec::new())), crlf_pos + 2))
             } else {
                 // Iterate from `crlf_pos + 2` for `array_length` iterations
                 let mut next_index: usize = crlf_pos + 2;
@@ -299,44 +421,62 @@ impl BaseSerializer for ArraySerializer {
                 let mut vec: Vec<RESPType> = Vec::new();
 
                 for _ in 0..array_length {
-                    let (result, length) = deserialize_buffer(&buffer[next_index..]);
+                    let (result, length) =
+                        parse_nested_frame(&buffer[next_index..], limits, depth + 1)?;
                     match result {
                         Some(child_payload) => vec.push(child_payload),
-                        None => return (None, 0),
+                        None => return Ok((None, 0)),
                     }
                     next_index = next_index + length;
                 }
-                (Some(RESPType::Array(vec)), next_index)
+                Ok((Some(RESPType::Array(vec)), next_index))
             }
         } else {
             // Did not find prefix_length and CRLF
-            return (None, 0);
+            Ok((None, 0))
         }
     }
 }
 
-pub fn deserialize_buffer(input_buf: &[u8]) -> (Option<RESPType>, usize) {
+/// Parse a frame from the `input_buf`, within the `limits`
+///
+/// Returns the frame and its size, or `None` and 0 if the buffer doesn't hold
+/// a whole frame yet, or an error if the frame is invalid.
+pub fn parse_frame(input_buf: &[u8], limits: &ProtocolLimits) -> Parsed {
+    parse_nested_frame(input_buf, limits, 0)
+}
+
+/// Parse a frame from the `input_buf`, which is nested within `depth` arrays
+fn parse_nested_frame(input_buf: &[u8], limits: &ProtocolLimits, depth: usize) -> Parsed {
     if let Some(&first_byte) = input_buf.first() {
         match first_byte {
-            b'+' => SimpleStringSerializer.deserialize(&input_buf),
-            b'-' => ErrorSerializer.deserialize(&input_buf),
-            b':' => IntegerSerializer.deserialize(&input_buf),
-            b'$' => BulkStringSerializer.deserialize(&input_buf),
-            b'*' => ArraySerializer.deserialize(&input_buf),
-            _ => (None, 0),
+            b'+' => SimpleStringSerializer.deserialize(input_buf, limits),
+            b'-' => ErrorSerializer.deserialize(input_buf, limits),
+            b':' => IntegerSerializer.deserialize(input_buf, limits),
+            b'$' => BulkStringSerializer.deserialize(input_buf, limits),
+            b'*' => ArraySerializer.deserialize_nested(input_buf, limits, depth),
+            byte => Err(ProtocolError::UnexpectedByte(byte)),
         }
     } else {
         // Handle the case when the buffer is empty
-        (None, 0)
+        Ok((None, 0))
     }
 }
 
+/// Parse a frame from the `input_buf`, within the default limits. Invalid
+/// frames are reported as `None` and 0, as the incomplete ones.
+pub fn deserialize_buffer(input_buf: &[u8]) -> (Option<RESPType>, usize) {
+    parse_frame(input_buf, &ProtocolLimits::default()).unwrap_or((None, 0))
+}
+
 pub fn serialize_data(input_data: &RESPType) -> Option<Vec<u8>> {
     match input_data {
         RESPType::SimpleString(_) => SimpleStringSerializer.serialize(&input_data),
         RESPType::Error(_) => ErrorSerializer.serialize(&input_data),
         RESPType::Integer(_) => IntegerSerializer.serialize(&input_data),
-        RESPType::BulkString(_) => BulkStringSerializer.serialize(&input_data),
+        RESPType::BulkString(_) | RESPType::BulkBytes(_) => {
+            BulkStringSerializer.serialize(&input_data)
+        }
         RESPType::Array(_) => ArraySerializer.serialize(&input_data),
     }
 }
@@ -0,0 +1,817 @@
+use crate::{
+    cluster, cmd::Command, deserialize_buffer, protocol_handler::BulkStringData, serialize_data,
+    Acl, ClientContext, Cluster, Config, Connection, ConnectionBase, RESPType, ReplyBuffer,
+    ServerContext, SharedStore, DEFAULT_NUM_SHARDS, DEFAULT_REPLICA_READ_ONLY, REPL_ACK_PERIOD_MS,
+    REPL_BACKLOG_SIZE, REPL_RECONNECT_DELAY_MS, REPL_STREAM_CAPACITY,
+};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    net::TcpStream,
+    sync::broadcast,
+    task::JoinHandle,
+    time::{interval, sleep, Duration},
+};
+
+/// The errors of the replica link task, which must be `Send` to be held across
+/// the awaits of a spawned task.
+type LinkError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The replication state of the server, shared across each `ConnectionHandler`.
+///
+/// Like `SharedStore`, cloning `Replication` only increments an atomic
+/// reference count.
+///
+/// As a master, every write is serialized into the replication stream, which
+/// is kept in a bounded backlog and broadcast to the connected replicas. The
+/// stream is identified by a replication ID, and the offset is the number of
+/// bytes in the stream so far, so that a replica which was briefly disconnected
+/// can continue from its offset with PSYNC.
+///
+/// As a replica, a task keeps the link with the master, and applies the stream
+/// to the `SharedStore`. The stream is also fed into the replica's own backlog,
+/// so that replicas can be chained.
+#[derive(Debug, Clone)]
+pub struct Replication {
+    shared: Arc<GuardedReplication>,
+}
+
+#[derive(Debug)]
+struct GuardedReplication {
+    state: Mutex<ReplicationState>,
+
+    /// Held while a write is applied to the data store and fed into the
+    /// replication stream, so that the stream has the same order as the
+    /// writes, and a snapshot is never taken in between.
+    ///
+    /// A key is guarded by the lock of its hash slot, like the shards of the
+    /// data store, and there are as many locks as shards, so only the writes on keys of the same lock are ordered.
+    /// The writes on other keys commute, so their order in the stream doesn't
+    /// matter, and they run in parallel.
+    ///
+    /// These are `tokio::sync::Mutex`es, as they are held across the execution
+    /// of a command.
+    write_locks: Vec<tokio::sync::Mutex<()>>,
+}
+
+/// The write locks of the keys of a command, or all of them
+#[derive(Debug)]
+pub struct WriteGuard<'a> {
+    _guards: Vec<tokio::sync::MutexGuard<'a, ()>>,
+}
+
+#[derive(Debug)]
+struct ReplicationState {
+    role: Role,
+
+    /// The ID of the current replication stream
+    replid: String,
+
+    /// The ID of the previous replication stream, which is still accepted by
+    /// PSYNC up to `second_replid_offset`, after a replica is promoted.
+    replid2: String,
+    second_replid_offset: i64,
+
+    /// The number of bytes in the replication stream so far
+    master_repl_offset: i64,
+
+    /// The most recent bytes of the replication stream, up to `REPL_BACKLOG_SIZE`
+    backlog: VecDeque<u8>,
+
+    /// Broadcasts each frame of the stream, to the connected replicas.
+    /// Replicas which fall behind by more than `REPL_STREAM_CAPACITY` frames
+    /// are disconnected, and have to resync.
+    stream: broadcast::Sender<Arc<RESPType>>,
+
+    /// The connected replicas, by a unique ID
+    replicas: HashMap<u64, ReplicaInfo>,
+    next_replica_id: u64,
+
+    /// The ports announced with REPLCONF listening-port, by the address of
+    /// the connection, until the replica sends PSYNC.
+    announced_ports: HashMap<SocketAddr, u16>,
+
+    /// The port this server is listening on, which is announced to a master
+    listening_port: u16,
+
+    /// Whether writes from clients are rejected, as a replica
+    read_only: bool,
+
+    /// The task which keeps the link with the master, as a replica
+    link_task: Option<JoinHandle<()>>,
+}
+
+/// The role of the server in replication
+#[derive(Debug, Clone, PartialEq)]
+pub enum Role {
+    Master,
+    Replica {
+        host: String,
+        port: u16,
+        link: LinkStatus,
+
+        /// When data was last received from the master
+        last_io: Option<DateTime<Utc>>,
+    },
+}
+
+/// The state of the link from a replica to its master
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkStatus {
+    /// Waiting to connect to the master
+    Connect,
+
+    /// Connected, and performing the handshake
+    Connecting,
+
+    /// Receiving the snapshot of the master
+    Sync,
+
+    /// Receiving the replication stream
+    Connected,
+}
+
+impl LinkStatus {
+    /// The name of the link status, as reported by ROLE
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkStatus::Connect => "connect",
+            LinkStatus::Connecting => "connecting",
+            LinkStatus::Sync => "sync",
+            LinkStatus::Connected => "connected",
+        }
+    }
+}
+
+/// A replica which is connected to this server
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicaInfo {
+    pub ip: String,
+    pub port: u16,
+
+    /// The offset of the stream, which the replica has acknowledged
+    pub ack_offset: i64,
+
+    /// When the replica last acknowledged its offset
+    pub last_ack: DateTime<Utc>,
+}
+
+/// How a replica starts to synchronize, as decided by PSYNC
+#[derive(Debug)]
+pub enum SyncStart {
+    /// Load a snapshot of the data store, at `offset` of the stream `replid`
+    Full {
+        replid: String,
+        offset: i64,
+        entries: Vec<RESPType>,
+    },
+
+    /// Continue with the `frames` of the stream `replid`, from the backlog
+    Continue {
+        replid: String,
+        frames: Vec<RESPType>,
+    },
+}
+
+impl Replication {
+    pub fn new() -> Replication {
+        Replication::with_shards(DEFAULT_NUM_SHARDS)
+    }
+
+    /// Create the replication state of a data store, whose keyspace is split
+    /// into `num_shards` shards. Its keys are guarded by a write lock per shard.
+    pub fn with_shards(num_shards: usize) -> Replication {
+        let (stream, _) = broadcast::channel(REPL_STREAM_CAPACITY);
+
+        Replication {
+            shared: Arc::new(GuardedReplication {
+                state: Mutex::new(ReplicationState {
+                    role: Role::Master,
+                    replid: Replication::generate_replid(),
+                    replid2: "0".repeat(40),
+                    second_replid_offset: -1,
+                    master_repl_offset: 0,
+                    backlog: VecDeque::new(),
+                    stream,
+                    replicas: HashMap::new(),
+                    next_replica_id: 0,
+                    announced_ports: HashMap::new(),
+                    listening_port: 0,
+                    read_only: DEFAULT_REPLICA_READ_ONLY,
+                    link_task: None,
+                }),
+                write_locks: (0..num_shards.max(1))
+                    .map(|_| tokio::sync::Mutex::new(()))
+                    .collect(),
+            }),
+        }
+    }
+
+    /// Generate a random replication ID, of 40 hex characters
+    fn generate_replid() -> String {
+        let mut rng = rand::thread_rng();
+
+        (0..40)
+            .map(|_| std::char::from_digit(rng.gen_range(0..16), 16).unwrap())
+            .collect()
+    }
+
+    /// Set the port this server is listening on, which is announced to a master
+    pub fn set_listening_port(&self, port: u16) {
+        self.shared.state.lock().unwrap().listening_port = port;
+    }
+
+    /// Set whether writes from clients are rejected, as a replica
+    pub fn set_read_only(&self, read_only: bool) {
+        self.shared.state.lock().unwrap().read_only = read_only;
+    }
+
+    /// Whether writes from clients must be rejected
+    pub fn is_read_only_replica(&self) -> bool {
+        let state = self.shared.state.lock().unwrap();
+
+        state.read_only && state.role != Role::Master
+    }
+
+    pub fn role(&self) -> Role {
+        self.shared.state.lock().unwrap().role.clone()
+    }
+
+    pub fn is_replica(&self) -> bool {
+        self.shared.state.lock().unwrap().role != Role::Master
+    }
+
+    pub fn master_repl_offset(&self) -> i64 {
+        self.shared.state.lock().unwrap().master_repl_offset
+    }
+
+    /// The replicas connected to this server
+    pub fn replicas(&self) -> Vec<ReplicaInfo> {
+        let state = self.shared.state.lock().unwrap();
+
+        let mut ids: Vec<&u64> = state.replicas.keys().collect();
+        ids.sort_unstable();
+
+        ids.into_iter()
+            .map(|id| state.replicas[id].clone())
+            .collect()
+    }
+
+    /// Acquire the locks of the `keys`, which must be held while a write on
+    /// them is applied to the data store and fed into the replication stream.
+    ///
+    /// The locks are taken in ascending order, so that writes on several keys
+    /// can't deadlock each other. Writes without keys take all of them.
+    pub async fn lock_writes(&self, keys: &[&str]) -> WriteGuard<'_> {
+        if keys.is_empty() {
+            return self.lock_all_writes().await;
+        }
+
+        let mut guards = Vec::with_capacity(keys.len());
+        for index in self.write_lock_indices(keys) {
+            guards.push(self.shared.write_locks[index].lock().await);
+        }
+
+        WriteGuard { _guards: guards }
+    }
+
+    /// Acquire the locks of the `keys` without waiting, unless one of them is held
+    pub fn try_lock_writes(&self, keys: &[&str]) -> Option<WriteGuard<'_>> {
+        let guards = self
+            .write_lock_indices(keys)
+            .into_iter()
+            .map(|index| self.shared.write_locks[index].try_lock().ok())
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(WriteGuard { _guards: guards })
+    }
+
+    /// The indices of the write locks of the `keys`, in ascending order
+    fn write_lock_indices(&self, keys: &[&str]) -> BTreeSet<usize> {
+        let num_locks = self.shared.write_locks.len();
+
+        keys.iter()
+            .map(|key| cluster::key_hash_slot(key) as usize % num_locks)
+            .collect()
+    }
+
+    /// Acquire all the write locks, e.g. to take a snapshot in between writes
+    pub async fn lock_all_writes(&self) -> WriteGuard<'_> {
+        let mut guards = Vec::with_capacity(self.shared.write_locks.len());
+        for lock in &self.shared.write_locks {
+            guards.push(lock.lock().await);
+        }
+
+        WriteGuard { _guards: guards }
+    }
+
+    /// Feed a write `frame` into the replication stream.
+    ///
+    /// Writes from clients to a (writable) replica aren't fed, since they
+    /// aren't part of the master's stream.
+    pub fn feed(&self, frame: &RESPType) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if state.role == Role::Master {
+            Replication::append_to_stream(&mut state, frame);
+        }
+    }
+
+    /// Append the `frame` to the backlog, and broadcast it to the replicas
+    fn append_to_stream(state: &mut ReplicationState, frame: &RESPType) {
+        let bytes = serialize_data(frame).unwrap();
+
+        state.master_repl_offset += bytes.len() as i64;
+        state.backlog.extend(bytes);
+
+        if state.backlog.len() > REPL_BACKLOG_SIZE {
+            let excess = state.backlog.len() - REPL_BACKLOG_SIZE;
+            state.backlog.drain(0..excess);
+        }
+
+        // An error means there are no replicas, which is fine
+        let _ = state.stream.send(Arc::new(frame.clone()));
+    }
+
+    /// Disconnect the replicas of this server, by replacing the broadcast channel
+    fn disconnect_replicas(state: &mut ReplicationState) {
+        let (stream, _) = broadcast::channel(REPL_STREAM_CAPACITY);
+
+        state.stream = stream;
+        state.replicas.clear();
+    }
+
+    /// Record the port announced with REPLCONF listening-port, by a replica at `addr`
+    pub fn announce_port(&self, addr: Option<SocketAddr>, port: u16) {
+        if let Some(addr) = addr {
+            let mut state = self.shared.state.lock().unwrap();
+
+            state.announced_ports.insert(addr, port);
+        }
+    }
+
+    /// Start the synchronization of a replica at `addr`, which requested to
+    /// continue the stream `replid` from `psync_offset` (the offset of the
+    /// next byte it needs).
+    ///
+    /// A partial resynchronization is possible, if the stream matches the
+    /// current (or previous) replication ID, and the requested bytes are
+    /// still in the backlog. Otherwise a snapshot of the `shared_store` is taken.
+    ///
+    /// All the write locks must be held, and the shards are locked, so that the
+    /// start of the sync and the subscription to the stream are consistent.
+    ///
+    /// Will return how to synchronize, the stream to follow, and the ID of the replica.
+    pub fn begin_sync(
+        &self,
+        shared_store: &SharedStore,
+        replid: &str,
+        psync_offset: i64,
+        addr: Option<SocketAddr>,
+    ) -> (SyncStart, broadcast::Receiver<Arc<RESPType>>, u64) {
+        shared_store.with_all_shards_locked(|snapshot| {
+            let mut state = self.shared.state.lock().unwrap();
+
+            let backlog_start = state.master_repl_offset - state.backlog.len() as i64 + 1;
+            let known_stream = replid == state.replid
+                || (replid == state.replid2 && psync_offset <= state.second_replid_offset);
+
+            let start = if known_stream
+                && psync_offset >= backlog_start
+                && psync_offset <= state.master_repl_offset + 1
+            {
+                let skip = (psync_offset - backlog_start) as usize;
+                let bytes: Vec<u8> = state.backlog.range(skip..).copied().collect();
+
+                SyncStart::Continue {
+                    replid: state.replid.clone(),
+                    frames: Replication::split_frames(&bytes),
+                }
+            } else {
+                SyncStart::Full {
+                    replid: state.replid.clone(),
+                    offset: state.master_repl_offset,
+                    entries: snapshot(),
+                }
+            };
+
+            let id = state.next_replica_id;
+            state.next_replica_id += 1;
+
+            let port = match addr {
+                Some(addr) => state
+                    .announced_ports
+                    .remove(&addr)
+                    .unwrap_or_else(|| addr.port()),
+                None => 0,
+            };
+
+            state.replicas.insert(
+                id,
+                ReplicaInfo {
+                    ip: addr.map(|addr| addr.ip().to_string()).unwrap_or_default(),
+                    port,
+                    ack_offset: 0,
+                    last_ack: Utc::now(),
+                },
+            );
+
+            (start, state.stream.subscribe(), id)
+        })
+    }
+
+    /// Split the serialized bytes of the stream, back into frames
+    fn split_frames(bytes: &[u8]) -> Vec<RESPType> {
+        let mut frames: Vec<RESPType> = Vec::new();
+        let mut position: usize = 0;
+
+        while position < bytes.len() {
+            match deserialize_buffer(&bytes[position..]) {
+                (Some(frame), size) => {
+                    frames.push(frame);
+                    position += size;
+                }
+                (None, _) => break,
+            }
+        }
+
+        frames
+    }
+
+    /// Record the offset acknowledged by the replica with the `id`
+    pub fn ack(&self, id: u64, offset: i64) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if let Some(replica) = state.replicas.get_mut(&id) {
+            replica.ack_offset = offset;
+            replica.last_ack = Utc::now();
+        }
+    }
+
+    /// Forget the replica with the `id`, once it is disconnected
+    pub fn remove_replica(&self, id: u64) {
+        self.shared.state.lock().unwrap().replicas.remove(&id);
+    }
+
+    /// Make this server a replica of the master at `host`:`port`, and start
+    /// the task which keeps the link with it.
+    ///
+    /// Will return `false` if this server is already a replica of that master.
+    pub fn replicate_from(&self, shared_store: &SharedStore, host: String, port: u16) -> bool {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if let Role::Replica {
+            host: current_host,
+            port: current_port,
+            ..
+        } = &state.role
+        {
+            if *current_host == host && *current_port == port {
+                return false;
+            }
+        }
+
+        if let Some(task) = state.link_task.take() {
+            task.abort();
+        }
+
+        // The replicas of this server will have to resync with the new stream
+        Replication::disconnect_replicas(&mut state);
+
+        state.role = Role::Replica {
+            host: host.clone(),
+            port,
+            link: LinkStatus::Connect,
+            last_io: None,
+        };
+
+        state.link_task = Some(tokio::spawn(run_replica_link(
+            self.clone(),
+            shared_store.clone(),
+            host,
+            port,
+        )));
+
+        true
+    }
+
+    /// Stop replicating, and promote this server to a master.
+    ///
+    /// The stream of the old master is kept as the previous replication ID,
+    /// so that the other replicas can continue from it with PSYNC.
+    pub fn promote(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if state.role == Role::Master {
+            return;
+        }
+
+        if let Some(task) = state.link_task.take() {
+            task.abort();
+        }
+
+        state.replid2 = std::mem::replace(&mut state.replid, Replication::generate_replid());
+        state.second_replid_offset = state.master_repl_offset + 1;
+        state.role = Role::Master;
+    }
+
+    fn set_link_status(&self, link: LinkStatus) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        if let Role::Replica { link: status, .. } = &mut state.role {
+            *status = link;
+        }
+    }
+
+    /// Apply a `frame` of the master's stream to the data store of the `context`,
+    /// and feed it into this server's own stream.
+    async fn apply_from_master(
+        &self,
+        context: &ServerContext,
+        master: &mut ClientContext,
+        frame: RESPType,
+    ) {
+        let _lock = self.lock_all_writes().await;
+
+        // Replies to the master's stream are discarded
+        if let Ok(cmd) = Command::infer_command(frame.clone()) {
+            let mut replies = ReplyBuffer::new();
+            let _ = cmd.execute(context, master, &mut replies).await;
+        }
+
+        let mut state = self.shared.state.lock().unwrap();
+
+        if let Role::Replica { last_io, .. } = &mut state.role {
+            *last_io = Some(Utc::now());
+        }
+
+        Replication::append_to_stream(&mut state, &frame);
+    }
+
+    /// Replace the data store with the snapshot of the master, and continue
+    /// from `offset` of its stream `replid`.
+    async fn load_full_sync(
+        &self,
+        shared_store: &SharedStore,
+        entries: Vec<RESPType>,
+        replid: String,
+        offset: i64,
+    ) -> Result<(), LinkError> {
+        let _lock = self.lock_all_writes().await;
+
+        shared_store.load_snapshot(entries)?;
+
+        let mut state = self.shared.state.lock().unwrap();
+
+        state.replid = replid;
+        state.replid2 = "0".repeat(40);
+        state.second_replid_offset = -1;
+        state.master_repl_offset = offset;
+        state.backlog.clear();
+
+        // The replicas of this server have data from before the snapshot
+        Replication::disconnect_replicas(&mut state);
+
+        Ok(())
+    }
+
+    /// Connect to the master, synchronize, and apply its stream until the link
+    /// is broken.
+    async fn sync_with_master(
+        &self,
+        shared_store: &SharedStore,
+        host: &str,
+        port: u16,
+    ) -> Result<(), LinkError> {
+        let socket = TcpStream::connect((host, port)).await?;
+        let mut cnxn = Connection::new(socket);
+
+        self.set_link_status(LinkStatus::Connecting);
+
+        let listening_port = self.shared.state.lock().unwrap().listening_port;
+
+        Replication::request(&mut cnxn, &["PING"]).await?;
+        Replication::request(
+            &mut cnxn,
+            &["REPLCONF", "listening-port", &listening_port.to_string()],
+        )
+        .await?;
+
+        // Attempt to continue from the current stream, the master decides
+        // whether a full resynchronization is needed instead.
+        let (replid, offset) = {
+            let state = self.shared.state.lock().unwrap();
+            (state.replid.clone(), state.master_repl_offset)
+        };
+
+        self.set_link_status(LinkStatus::Sync);
+
+        let reply =
+            Replication::request(&mut cnxn, &["PSYNC", &replid, &(offset + 1).to_string()]).await?;
+        let reply: Vec<&str> = reply.split(' ').collect();
+
+        match reply.as_slice() {
+            ["FULLRESYNC", new_replid, new_offset] => {
+                let new_offset: i64 = new_offset.parse()?;
+
+                // The snapshot is a count, followed by each of the entries
+                let count = match Replication::read(&mut cnxn).await? {
+                    RESPType::Integer(count) => count,
+                    frame => return Err(format!("Unexpected snapshot {:?}", frame).into()),
+                };
+
+                let mut entries: Vec<RESPType> = Vec::new();
+                for _ in 0..count {
+                    entries.push(Replication::read(&mut cnxn).await?);
+                }
+
+                self.load_full_sync(shared_store, entries, new_replid.to_string(), new_offset)
+                    .await?;
+            }
+            ["CONTINUE"] => {}
+            ["CONTINUE", new_replid] => {
+                let mut state = self.shared.state.lock().unwrap();
+
+                // The master was promoted, and continues the stream with a new ID
+                if state.replid != *new_replid {
+                    state.replid2 = std::mem::replace(&mut state.replid, new_replid.to_string());
+                    state.second_replid_offset = state.master_repl_offset + 1;
+                }
+            }
+            _ => return Err(format!("Unexpected PSYNC reply {:?}", reply).into()),
+        }
+
+        self.set_link_status(LinkStatus::Connected);
+
+        let mut ack = interval(Duration::from_millis(REPL_ACK_PERIOD_MS));
+
+        // The stream only holds writes, which are applied with every permission,
+        // and don't depend on the cluster state
+        let context = ServerContext::new(
+            shared_store.clone(),
+            self.clone(),
+            Cluster::disabled(),
+            Acl::new(),
+            Config::default(),
+        );
+        let mut master = ClientContext::new(&format!("{}:{}", host, port), &context.acl);
+
+        loop {
+            tokio::select! {
+                frame = Replication::read(&mut cnxn) => {
+                    self.apply_from_master(&context, &mut master, frame?).await;
+                }
+                _ = ack.tick() => {
+                    let offset = self.master_repl_offset().to_string();
+                    cnxn.write_frame(&Replication::command(&["REPLCONF", "ACK", &offset])).await?;
+                }
+            }
+        }
+    }
+
+    /// Read a frame from the master
+    async fn read(cnxn: &mut Connection) -> Result<RESPType, LinkError> {
+        match cnxn.read_frame().await {
+            Ok(Some(frame)) => Ok(frame),
+            Ok(None) => Err("Connection closed by master".into()),
+            Err(err) => Err(err.to_string().into()),
+        }
+    }
+
+    /// Send a command to the master, and read its simple string reply
+    async fn request(cnxn: &mut Connection, parts: &[&str]) -> Result<String, LinkError> {
+        cnxn.write_frame(&Replication::command(parts)).await?;
+
+        match Replication::read(cnxn).await? {
+            RESPType::SimpleString(reply) => Ok(reply),
+            frame => Err(format!("Unexpected reply to {} {:?}", parts[0], frame).into()),
+        }
+    }
+
+    /// Build the frame of a command, from its `parts`
+    fn command(parts: &[&str]) -> RESPType {
+        RESPType::Array(
+            parts
+                .iter()
+                .map(|part| {
+                    RESPType::BulkString(Some(BulkStringData {
+                        text: part.to_string(),
+                        prefix_length: part.len(),
+                    }))
+                })
+                .collect(),
+        )
+    }
+
+    /// The fields of the replication section of INFO
+    pub fn info(&self) -> Vec<(String, String)> {
+        let state = self.shared.state.lock().unwrap();
+        let now = Utc::now();
+
+        let mut fields: Vec<(String, String)> = Vec::new();
+        let mut field = |name: &str, value: String| fields.push((name.to_string(), value));
+
+        match &state.role {
+            Role::Master => field("role", "master".to_string()),
+            Role::Replica {
+                host,
+                port,
+                link,
+                last_io,
+            } => {
+                field("role", "slave".to_string());
+                field("master_host", host.clone());
+                field("master_port", port.to_string());
+                field(
+                    "master_link_status",
+                    if *link == LinkStatus::Connected {
+                        "up".to_string()
+                    } else {
+                        "down".to_string()
+                    },
+                );
+                field(
+                    "master_last_io_seconds_ago",
+                    last_io
+                        .map(|last_io| (now - last_io).num_seconds())
+                        .unwrap_or(-1)
+                        .to_string(),
+                );
+                field(
+                    "master_sync_in_progress",
+                    ((*link == LinkStatus::Sync) as u8).to_string(),
+                );
+                field("slave_repl_offset", state.master_repl_offset.to_string());
+                field("slave_read_only", (state.read_only as u8).to_string());
+            }
+        }
+
+        let mut ids: Vec<&u64> = state.replicas.keys().collect();
+        ids.sort_unstable();
+
+        field("connected_slaves", ids.len().to_string());
+
+        for (i, id) in ids.into_iter().enumerate() {
+            let replica = &state.replicas[id];
+
+            field(
+                &format!("slave{}", i),
+                format!(
+                    "ip={},port={},state=online,offset={},lag={}",
+                    replica.ip,
+                    replica.port,
+                    replica.ack_offset,
+                    (now - replica.last_ack).num_seconds()
+                ),
+            );
+        }
+
+        field("master_replid", state.replid.clone());
+        field("master_replid2", state.replid2.clone());
+        field("master_repl_offset", state.master_repl_offset.to_string());
+        field("second_repl_offset", state.second_replid_offset.to_string());
+        field("repl_backlog_active", "1".to_string());
+        field("repl_backlog_size", REPL_BACKLOG_SIZE.to_string());
+        field(
+            "repl_backlog_first_byte_offset",
+            (state.master_repl_offset - state.backlog.len() as i64 + 1).to_string(),
+        );
+        field("repl_backlog_histlen", state.backlog.len().to_string());
+
+        fields
+    }
+}
+
+impl Default for Replication {
+    fn default() -> Self {
+        Replication::new()
+    }
+}
+
+/// Keep the link with the master, reconnecting after a delay whenever it is broken.
+///
+/// The task is aborted, when the server stops replicating from this master.
+async fn run_replica_link(
+    replication: Replication,
+    shared_store: SharedStore,
+    host: String,
+    port: u16,
+) {
+    loop {
+        if let Err(err) = replication
+            .sync_with_master(&shared_store, &host, port)
+            .await
+        {
+            println!("Replication Error | {:?}", err);
+        }
+
+        replication.set_link_status(LinkStatus::Connect);
+
+        sleep(Duration::from_millis(REPL_RECONNECT_DELAY_MS)).await;
+    }
+}
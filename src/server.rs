@@ -1,23 +1,153 @@
-use crate::{cmd::Command, Connection, ConnectionBase, RESPType, SharedStore};
-use tokio::net::TcpListener;
+use crate::protocol_handler::ProtocolError;
+use crate::tls::{self, TlsAcceptor};
+use crate::{
+    acl::{self, AclError},
+    clients::{ClientSlot, ConnectedClient},
+    cmd::{Command, ParseError},
+    metrics, Acl, ClientContext, Clients, Cluster, Config, Connection, ConnectionBase,
+    ErrorTracker, RESPType, Replication, ReplyBuffer, ServerContext, SharedStore, SharedStoreBase,
+    Stats, ACCEPT_BACKOFF_MAX_MS, ACCEPT_BACKOFF_MIN_MS,
+};
+use socket2::{SockRef, TcpKeepalive};
+use std::future::Future;
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{self, AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream, UnixListener};
+use tokio::signal::unix::{signal, Signal, SignalKind};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+/// The reply to the clients refused by protected mode, before the connection is closed
+const PROTECTED_MODE_ERROR: &str = "DENIED Redust is running in protected mode because protected mode is enabled and no password is set for the default user. In this mode connections are only accepted from the loopback interface. To accept other clients, set a password with requirepass, or disable protected mode.";
+
+const MAXCLIENTS_ERROR: &str = "ERR max number of clients reached";
+
+/// The sockets which the server accepts clients on. There is a TCP listener
+/// for each of the `bind` addresses of the `Config`.
+#[derive(Debug, Default)]
+pub struct Listeners {
+    /// Plain TCP clients
+    pub tcp: Vec<TcpListener>,
+
+    /// TLS clients, with the certificates of the `Config`
+    pub tls: Vec<TcpListener>,
+
+    /// Local clients, on a Unix domain socket
+    pub unix: Option<UnixListener>,
+
+    /// Prometheus scrapers, which get the metrics of the server over HTTP
+    pub metrics: Vec<TcpListener>,
+
+    /// Shut down gracefully on SIGINT and SIGTERM, rather than being killed.
+    /// The handlers are installed for the whole process, so only the binary sets it.
+    pub signals: bool,
+}
+
+/// Listen on the Unix socket at `path`, replacing a stale socket file left by
+/// a previous server. The permissions of the socket are set to `perm`, if any.
+pub fn bind_unix(path: &Path, perm: Option<u32>) -> io::Result<UnixListener> {
+    if std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+
+    if let Some(perm) = perm {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(perm))?;
+    }
+
+    Ok(listener)
+}
 
 /// Server listener state. Created in the `run` call. It includes a `run` method
 /// which performs the TCP listening and initialization of per-connection state.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Listener {
-    listener: TcpListener,
-
-    /// Holds the data store around an `Arc`
+    /// Holds the data store, replication, cluster and ACL states.
     /// This is shared across each `Handler`
-    shared_store: SharedStore,
+    context: ServerContext,
+
+    /// Cloned into each `ConnectionHandler`. Once they are all dropped, every
+    /// connection is closed, and the shutdown is complete.
+    shutdown_complete: mpsc::Sender<()>,
 }
 
 /// Per-connection handler. Reads requests from `Connection`
-/// and passes the `SharedStore` to the individual commands.
+/// and passes the `ServerContext` to the individual commands.
 #[derive(Debug)]
-struct ConnectionHandler {
-    connection: Connection,
-    shared_store: SharedStore,
+struct ConnectionHandler<S = TcpStream> {
+    connection: Connection<S>,
+    context: ServerContext,
+
+    /// The state of this connection, e.g. the user it is authenticated as
+    client: ClientContext,
+
+    /// The entry of this connection in the registry of clients
+    registration: Arc<ConnectedClient>,
+
+    /// Set by ASKING, to allow the next command to access an importing slot
+    asking: bool,
+
+    /// Dropped once the connection is closed
+    _shutdown_complete: mpsc::Sender<()>,
+}
+
+/// Owned by the task of a connection, to release what the connection holds
+/// once the task ends. This happens on drop, so that it also happens when
+/// the task panics.
+struct ConnectionCleanup {
+    clients: Clients,
+    stats: Stats,
+
+    /// The id of the connection in the registry of clients
+    id: u64,
+
+    /// The slot of the connection, which is free for another one afterwards
+    _slot: ClientSlot,
+}
+
+impl Drop for ConnectionCleanup {
+    fn drop(&mut self) {
+        self.clients.unregister(self.id);
+        self.stats.client_disconnected();
+    }
+}
+
+/// Accept the next connection with `accept`, retrying after errors. The
+/// errors of a single connection, e.g. reset before it was accepted, are
+/// retried right away. Others, e.g. when the process runs out of file
+/// descriptors, are retried after a delay which doubles with each of them,
+/// so that connections can close meanwhile.
+pub(crate) async fn accept_with_backoff<T, F, Fut>(mut accept: F) -> T
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<T>>,
+{
+    let mut backoff = ACCEPT_BACKOFF_MIN_MS;
+
+    loop {
+        let err = match accept().await {
+            Ok(accepted) => return accepted,
+            Err(err) => err,
+        };
+
+        match err.kind() {
+            ErrorKind::ConnectionAborted
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionRefused
+            | ErrorKind::Interrupted => continue,
+            _ => {
+                println!("Accept Error | {:?}, retrying in {}ms", err, backoff);
+                tokio::time::sleep(Duration::from_millis(backoff)).await;
+                backoff = (backoff * 2).min(ACCEPT_BACKOFF_MAX_MS);
+            }
+        }
+    }
 }
 
 impl Listener {
@@ -26,78 +156,693 @@ impl Listener {
     /// Listen for inbound connections. For each inbound connection, spawn a
     /// tokio task to process that connection.
     ///
-    /// Returns `Err` if accepting returns an error. This can happen for a
-    /// number reasons. e.g. if the operating system has reached an
-    /// internal limit for max number of sockets, `tokio::net::tcp::listener::TcpListener.accept()` will fail.
-    async fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    /// Accepting is retried after errors, e.g. if the operating system has
+    /// reached its limit of open sockets, rather than stopping the listener.
+    async fn run(&self, listener: TcpListener) -> io::Result<()> {
         loop {
-            // Accept a new socket. The `accept` method internally attempts to
-            // recover errors, so if an error occurs here, we should propagate it
-            let (socket, _) = self.listener.accept().await?;
-
-            // Create the necessary per-connection handler
-            let mut handler = ConnectionHandler {
-                // Initialize the connection state. This allocates read/write
-                // buffers, and to perform RESP (de)-serialization
-                connection: Connection::new(socket),
-
-                // Get the shared data store. Internally, this is an
-                // `Arc`, so a clone only increments the reference count.
-                shared_store: self.shared_store.clone(),
+            // Accept a new socket. Sockets which are already closed are skipped.
+            let (socket, addr) = accept_with_backoff(|| listener.accept()).await;
+            let laddr = match socket.local_addr() {
+                Ok(laddr) => laddr,
+                Err(_) => continue,
             };
+            self.set_keepalive(&socket);
+
+            // Initialize the connection state. This allocates read/write
+            // buffers, and to perform RESP (de)-serialization
+            let connection = self.connection(socket, Some(addr));
+            self.handle(
+                connection,
+                addr.to_string(),
+                laddr.to_string(),
+                addr.ip().is_loopback(),
+            );
+        }
+    }
+
+    /// Run the TLS listener. The TLS handshake of each inbound connection is
+    /// performed by its own task, so that slow clients don't hold the others.
+    async fn run_tls(&self, listener: TcpListener, acceptor: TlsAcceptor) -> io::Result<()> {
+        loop {
+            let (socket, addr) = accept_with_backoff(|| listener.accept()).await;
+            let laddr = match socket.local_addr() {
+                Ok(laddr) => laddr,
+                Err(_) => continue,
+            };
+            self.set_keepalive(&socket);
+            let acceptor = acceptor.clone();
+            let listener = self.clone();
 
-            // Spawn a new task to process the connection.
             tokio::spawn(async move {
-                // Process the connection. If an error is encountered, print it.
-                if let Err(err) = handler.run().await {
-                    println!("Connection Error | {:?}", err);
+                // Handshakes in progress are dropped on shutdown, to not hold it
+                let shutdown = listener.context.shutdown.clone();
+                let handshake = tokio::select! {
+                    handshake = acceptor.accept(socket) => handshake,
+                    _ = shutdown.stopped() => return,
+                };
+
+                match handshake {
+                    Ok(stream) => {
+                        let connection = listener.connection(stream, Some(addr));
+                        listener.handle(
+                            connection,
+                            addr.to_string(),
+                            laddr.to_string(),
+                            addr.ip().is_loopback(),
+                        )
+                    }
+                    Err(err) => println!("TLS Handshake Error | {:?}", err),
                 }
             });
         }
     }
+
+    /// Run the Unix socket listener. Its clients are always local.
+    async fn run_unix(&self, listener: UnixListener) -> io::Result<()> {
+        // Clients are named after the path of the socket, as in Redis
+        let path = match listener.local_addr()?.as_pathname() {
+            Some(path) => path.display().to_string(),
+            None => String::new(),
+        };
+
+        loop {
+            let (socket, _) = accept_with_backoff(|| listener.accept()).await;
+
+            let connection = self.connection(socket, None);
+            let addr = format!("{}:0", path);
+            self.handle(connection, addr.clone(), addr, true);
+        }
+    }
+
+    /// Enable the TCP keepalives of the `socket`, with the interval of `tcp-keepalive`.
+    /// As in Redis, the probes of an unresponsive peer are sent every third of it.
+    fn set_keepalive(&self, socket: &TcpStream) {
+        let interval = self.context.config.read().unwrap().tcp_keepalive;
+        if interval == 0 {
+            return;
+        }
+
+        let keepalive = TcpKeepalive::new()
+            .with_time(Duration::from_secs(interval))
+            .with_interval(Duration::from_secs((interval / 3).max(1)));
+
+        if let Err(err) = SockRef::from(socket).set_tcp_keepalive(&keepalive) {
+            println!("TCP Keepalive Error | {:?}", err);
+        }
+    }
+
+    /// Create the `Connection` of a new client, with the read buffer size of the `Config`
+    fn connection<S>(&self, stream: S, peer_addr: Option<SocketAddr>) -> Connection<S>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + Sync,
+    {
+        let buffer_size = self.context.config.read().unwrap().io_buffer_size;
+        Connection::with_buffer_size(stream, peer_addr, buffer_size)
+    }
+
+    /// Spawn the task which processes the connection of a new client, from
+    /// `addr` to `laddr`. Only `local` clients are accepted in protected mode.
+    fn handle<S>(&self, mut connection: Connection<S>, addr: String, laddr: String, local: bool)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static,
+    {
+        // The connections over `maxclients` are refused, as in Redis
+        let slot = match self.context.clients.try_reserve() {
+            Some(slot) => slot,
+            None => {
+                self.context.stats.connection_rejected();
+                tokio::spawn(async move {
+                    let err = RESPType::Error(MAXCLIENTS_ERROR.to_string());
+                    let _ = connection.write_frame(&err).await;
+                });
+                return;
+            }
+        };
+
+        // In protected mode, only local clients are accepted until a password is set
+        if self.context.config.read().unwrap().protected_mode
+            && !local
+            && self.context.acl.default_user_needs_no_auth()
+        {
+            tokio::spawn(async move {
+                let err = RESPType::Error(PROTECTED_MODE_ERROR.to_string());
+                let _ = connection.write_frame(&err).await;
+            });
+            return;
+        }
+
+        self.context.stats.client_connected();
+
+        let mut client = ClientContext::new(&addr, &self.context.acl);
+        let registration = self.context.clients.register(&addr, &laddr, &client.user);
+        client.id = registration.id;
+
+        let cleanup = ConnectionCleanup {
+            clients: self.context.clients.clone(),
+            stats: self.context.stats.clone(),
+            id: registration.id,
+            _slot: slot,
+        };
+
+        // Create the necessary per-connection handler
+        let mut handler = ConnectionHandler {
+            connection,
+
+            // Get the shared state. Internally, this is made of
+            // `Arc`s, so a clone only increments the reference counts.
+            context: self.context.clone(),
+
+            client,
+            registration,
+            asking: false,
+            _shutdown_complete: self.shutdown_complete.clone(),
+        };
+
+        // Spawn a new task to process the connection.
+        tokio::spawn(async move {
+            let _cleanup = cleanup;
+
+            // Process the connection. If an error is encountered, print it.
+            if let Err(err) = handler.run().await {
+                println!("Connection Error | {:?}", err);
+            }
+        });
+    }
 }
 
-impl ConnectionHandler {
+impl<S> ConnectionHandler<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + Sync,
+{
     /// Process a single connection.
     ///
     /// Request frames are read from the socket and processed. Responses are
     /// written back to the socket.
     async fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let registration = self.registration.clone();
+        let shutdown = self.context.shutdown.clone();
+
         loop {
-            let frame: RESPType;
+            let mut frame: RESPType;
+
+            // Clients which stay idle for longer than `timeout` are disconnected.
+            // Replicas and monitors aren't, as they don't come back to this loop.
+            let timeout = {
+                let config = self.context.config.read().unwrap();
+                self.connection.set_limits(config.protocol_limits());
+                config.timeout
+            };
+            let idle = async {
+                match timeout {
+                    0 => std::future::pending().await,
+                    timeout => tokio::time::sleep(Duration::from_secs(timeout)).await,
+                }
+            };
+
+            // Killed clients are disconnected before their next command
+            let read = tokio::select! {
+                biased;
+                _ = registration.killed() => return Ok(()),
+                _ = shutdown.stopped() => return Ok(()),
+                read = self.connection.read_frame() => match read {
+                    Ok(Some(val)) => Ok(val),
+                    Ok(None) => return Ok(()),
+                    Err(err) => match err.downcast_ref::<ProtocolError>() {
+                        Some(err) => Err(err.to_string()),
+                        None => return Err(err),
+                    },
+                },
+                _ = idle => return Ok(()),
+            };
 
-            match self.connection.read_frame().await {
-                Ok(Some(val)) => frame = val,
-                Ok(None) => return Ok(()),
-                Err(err) => return Err(err),
+            // The rest of the stream can't be parsed after a protocol error, so
+            // the connection is closed once the error is replied
+            match read {
+                Ok(val) => frame = val,
+                Err(err) => {
+                    self.reject(None, format!("ERR {}", err)).await;
+                    return Ok(());
+                }
+            }
+
+            // Empty and null arrays hold no command, and are skipped without a
+            // reply, as in Redis
+            if frame == RESPType::Array(Vec::new()) {
+                continue;
             }
 
-            // Convert the RespType into a command struct.
-            // This will return an error if the frame is not a valid command.
-            match Command::infer_command(frame) {
+            // Convert the RespType into a command struct, under its original name if
+            // it was renamed. This will return an error if the frame is not a valid
+            // command. The frame is kept, to be fed into the replication stream.
+            let cmd = {
+                let config = self.context.config.read().unwrap();
+                Command::infer_renamed_command(&mut frame, &config.rename_commands)
+            };
+
+            match cmd {
                 Ok(cmd) => {
+                    let (query_buffer, read_buffer_size) = self.connection.read_buffer();
+                    registration.command_received(cmd.name(), query_buffer, read_buffer_size);
+
+                    // Connections must authenticate first, and may then only run the
+                    // commands, and access the keys, which their user is allowed to
+                    if let Err(err) = self.check_acl(&cmd) {
+                        // The connections of deleted users are closed
+                        if err == AclError::NoUser {
+                            return Ok(());
+                        }
+
+                        self.reject(Some(cmd.name()), err.to_string()).await;
+                        continue;
+                    }
+
+                    // ASKING only applies to the command which follows it
+                    let asking = std::mem::replace(&mut self.asking, false);
+
+                    // In cluster mode, commands for the keys of other nodes are redirected
+                    if self.context.cluster.is_enabled() {
+                        let routed = self.context.cluster.route(&cmd.keys(), asking, |key| {
+                            self.context.shared_store.exists(vec![key.to_string()]) == 1
+                        });
+
+                        if let Err(err) = routed {
+                            self.reject(Some(cmd.name()), err).await;
+                            continue;
+                        }
+
+                        if matches!(cmd, Command::Asking(_)) {
+                            self.asking = true;
+                        }
+                    }
+
+                    // Hold the command while clients are paused. CLIENT commands are
+                    // exempt, so that the pause can be lifted with CLIENT UNPAUSE.
+                    if !matches!(cmd, Command::Client(_)) {
+                        self.context
+                            .shared_store
+                            .client_pause()
+                            .wait(cmd.is_write())
+                            .await;
+                    }
+
+                    // Replicas only accept writes from their master, by default
+                    if cmd.is_write() && self.context.replication.is_read_only_replica() {
+                        let err = "READONLY You can't write against a read only replica.";
+                        self.reject(Some(cmd.name()), err.to_string()).await;
+                        continue;
+                    }
+
+                    // Evict keys if the used memory is over the `maxmemory` limit.
+                    // If not enough memory could be freed, commands which may grow
+                    // the data store are rejected. The evicted keys may be written by
+                    // other connections, so all the write locks are held, for their
+                    // deletions to be fed in order with the writes.
+                    let evicted = match self.context.shared_store.needs_eviction() {
+                        true => {
+                            let _lock = self.context.replication.lock_all_writes().await;
+                            self.context.shared_store.evict_keys_if_needed()
+                        }
+                        false => Ok(()),
+                    };
+                    if let Err(err) = evicted {
+                        if cmd.is_denyoom() {
+                            self.reject(Some(cmd.name()), err.to_string()).await;
+                            continue;
+                        }
+                    }
+
+                    self.context.stats.command_processed();
+
+                    // The commands are fed to the monitors, except AUTH, to not show
+                    // passwords, and the administrative ones, as in Redis
+                    let name = cmd.name();
+                    if self.context.monitor.is_active()
+                        && !matches!(cmd, Command::Auth(_))
+                        && !acl::commands_in_category("admin")
+                            .is_some_and(|commands| commands.contains(&name))
+                    {
+                        self.context
+                            .monitor
+                            .feed(&Command::frame_args(&frame), &self.client.addr);
+                    }
+
+                    // After PSYNC or MONITOR, the connection was used as a stream
+                    let is_stream = matches!(cmd, Command::Psync(_) | Command::Monitor(_));
+
+                    // Each execution is timed, for INFO commandstats and latencystats,
+                    // and the slow log. AUTH is left out of the latter, as in Redis,
+                    // to not log passwords, and so are streams.
+                    let slowlog = !matches!(cmd, Command::Auth(_)) && !is_stream;
+                    let started = Instant::now();
+
+                    if cmd.is_write() {
+                        // Writes are applied and fed into the replication stream under
+                        // the write locks of their keys, so that replicas apply them in
+                        // the same order.
+                        // The replies are buffered, to not hold the lock while writing
+                        // to a slow client.
+                        let mut replies = ReplyBuffer::new();
+                        {
+                            let _lock = self.context.replication.lock_writes(&cmd.keys()).await;
+
+                            let _ = cmd
+                                .execute(&self.context, &mut self.client, &mut replies)
+                                .await;
+
+                            let duration = started.elapsed();
+                            let failed = replies.contains_error();
+                            self.context.stats.record_call(name, duration, failed);
+
+                            for err in replies.errors() {
+                                self.context.stats.record_error(err);
+                            }
+
+                            if slowlog {
+                                self.log_if_slow(&frame, duration);
+                            }
+
+                            // Failed writes didn't change the data store
+                            if !replies.contains_error() {
+                                self.context.replication.feed(&frame);
+                            }
+                        }
+
+                        let _ = replies.flush_to(&mut self.connection).await;
+                        continue;
+                    }
+
+                    match cmd {
+                        Command::Psync(_) => registration.set_replica(),
+                        Command::Monitor(_) => registration.set_monitor(),
+                        _ => {}
+                    }
+
+                    // Streams run until the client disconnects, is killed, or the
+                    // server shuts down
+                    let killed = async {
+                        match is_stream {
+                            true => tokio::select! {
+                                _ = registration.killed() => {}
+                                _ = shutdown.stopped() => {}
+                            },
+                            false => std::future::pending().await,
+                        }
+                    };
+
                     // Execute the command
                     // The connection is passed into the execute function which allows the
                     // concrete command to write the response directly to the connection stream
-                    let _ = cmd.execute(&self.shared_store, &mut self.connection).await;
+                    let mut replies = ErrorTracker::new(&mut self.connection);
+                    let execution = async {
+                        let _ = cmd
+                            .execute(&self.context, &mut self.client, &mut replies)
+                            .await;
+                    };
+                    tokio::select! {
+                        _ = execution => {}
+                        _ = killed => {}
+                    }
+                    registration.set_identity(&self.client.name, &self.client.user);
+
+                    let duration = started.elapsed();
+                    let failed = replies.failed();
+                    self.context.stats.record_call(name, duration, failed);
+
+                    for err in replies.errors() {
+                        self.context.stats.record_error(err);
+                    }
+
+                    if slowlog {
+                        self.log_if_slow(&frame, duration);
+                    }
+
+                    if is_stream {
+                        return Ok(());
+                    }
                 }
                 Err(err) => {
-                    let err = RESPType::Error(err.to_string());
-                    let _ = self.connection.write_frame(&err).await;
+                    // Known commands with invalid arguments are counted as rejected,
+                    // unlike unknown, renamed or disabled ones
+                    let name = match err {
+                        ParseError::UnrecognizedCmd(_) => None,
+                        _ => Command::frame_name(&frame),
+                    };
+
+                    self.reject(name, err.to_string()).await;
                 }
             };
         }
     }
+
+    /// Reply with the error `err` to a call which was refused before its execution.
+    /// It is counted as a rejected call of the command `name`, if it is known.
+    async fn reject(&mut self, name: Option<&str>, err: String) {
+        if let Some(name) = name {
+            self.context.stats.record_rejected_call(name);
+        }
+
+        self.context.stats.record_error(&err);
+        let _ = self.connection.write_frame(&RESPType::Error(err)).await;
+    }
+
+    /// Add the command of the `frame` to the slow log, if its execution took
+    /// at least `slowlog-log-slower-than` microseconds
+    fn log_if_slow(&self, frame: &RESPType, duration: Duration) {
+        let (threshold, max_len) = {
+            let config = self.context.config.read().unwrap();
+            (config.slowlog_log_slower_than, config.slowlog_max_len)
+        };
+
+        if threshold < 0 || (duration.as_micros() as i64) < threshold {
+            return;
+        }
+
+        self.context.slowlog.push(
+            &Command::frame_args(frame),
+            duration,
+            &self.client.addr,
+            &self.client.name,
+            max_len,
+        );
+    }
+
+    /// Check the command against the ACL, on behalf of the user of the connection.
+    /// AUTH is always allowed, to authenticate as another user.
+    fn check_acl(&self, cmd: &Command) -> Result<(), AclError> {
+        if matches!(cmd, Command::Auth(_)) {
+            return Ok(());
+        }
+
+        if !self.client.authenticated {
+            return Err(AclError::NoAuth);
+        }
+
+        self.context.acl.check(
+            &self.client.user,
+            &self.client.info(),
+            cmd.name(),
+            &cmd.keys(),
+        )
+    }
 }
 
 pub async fn run(listener: TcpListener) -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize the listener state
-    let mut server = Listener {
-        listener,
-        shared_store: SharedStore::new(),
+    run_with_config(listener, Config::default()).await
+}
+
+/// Run the server with the `config`, rather than the default one
+pub async fn run_with_config(
+    listener: TcpListener,
+    config: Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let listeners = Listeners {
+        tcp: vec![listener],
+        ..Listeners::default()
     };
 
-    server.run().await?;
+    serve(listeners, Cluster::disabled(), config).await
+}
+
+/// Run the server on each of the `listeners`, e.g. on both a plain TCP and a TLS
+/// listener. Returns an error if there is none, or if the TLS certificates of the
+/// `config` can't be loaded.
+pub async fn run_with_listeners(
+    listeners: Listeners,
+    config: Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    serve(listeners, Cluster::disabled(), config).await
+}
+
+/// Run the server as a node of a cluster. The other nodes connect to the
+/// cluster bus on the `bus_listener`, once they are introduced with CLUSTER MEET.
+pub async fn run_cluster(
+    listener: TcpListener,
+    bus_listener: TcpListener,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let listeners = Listeners {
+        tcp: vec![listener],
+        ..Listeners::default()
+    };
 
-    Ok(())
+    run_cluster_with_listeners(listeners, bus_listener, Config::default()).await
+}
+
+/// Run the server as a node of a cluster, on each of the `listeners`. The node
+/// is announced with the address of the first TCP listener.
+pub async fn run_cluster_with_listeners(
+    listeners: Listeners,
+    bus_listener: TcpListener,
+    config: Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let address = match listeners.tcp.first() {
+        Some(listener) => listener.local_addr()?,
+        None => return Err("A cluster node needs a TCP listener".into()),
+    };
+
+    // When listening on an unspecified address, the IP of the node is learned
+    // from the links of the other nodes.
+    let cluster = Cluster::new(
+        address.ip(),
+        address.port(),
+        bus_listener.local_addr()?.port(),
+    );
+    cluster.start_bus(bus_listener);
+
+    serve(listeners, cluster, config).await
+}
+
+async fn serve(
+    listeners: Listeners,
+    cluster: Cluster,
+    mut config: Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // The configuration reports the ports which are actually listened on,
+    // e.g. when the listeners were bound to port 0
+    config.port = match listeners.tcp.first() {
+        Some(listener) => listener.local_addr()?.port(),
+        None => 0,
+    };
+    if let Some(listener) = listeners.tls.first() {
+        config.tls_port = Some(listener.local_addr()?.port());
+    }
+    if let Some(listener) = listeners.metrics.first() {
+        config.metrics_port = Some(listener.local_addr()?.port());
+    }
+
+    // The certificates are loaded before accepting any client
+    let acceptor = if listeners.tls.is_empty() {
+        None
+    } else {
+        Some(tls::acceptor(&config)?)
+    };
+
+    // The listening port is announced to a master, when replicating
+    // Without a TCP listener, there is no port to announce
+    let port = match listeners.tcp.first().or(listeners.tls.first()) {
+        Some(listener) => listener.local_addr()?.port(),
+        None if listeners.unix.is_some() => 0,
+        None => return Err("No listener to accept clients on".into()),
+    };
+
+    // The default user needs the password of `requirepass`, if there is one
+    let acl = Acl::new();
+    acl.set_requirepass(config.requirepass.as_deref());
+
+    // Initialize the listener state. The writes are locked per shard of the
+    // data store, for replication.
+    let shared_store = SharedStore::with_config(&config);
+    let replication = Replication::with_shards(shared_store.num_shards());
+    replication.set_listening_port(port);
+    replication.set_read_only(config.replica_read_only);
+    shared_store.set_replication(&replication);
+    let (shutdown_complete, mut connections_closed) = mpsc::channel(1);
+    let server = Listener {
+        context: ServerContext::new(shared_store, replication, cluster, acl, config),
+        shutdown_complete,
+    };
+    let context = server.context.clone();
+
+    // SIGINT and SIGTERM shut the server down, as SHUTDOWN does
+    if listeners.signals {
+        let interrupt = signal(SignalKind::interrupt())?;
+        let terminate = signal(SignalKind::terminate())?;
+        tokio::spawn(shutdown_on_signals(context.clone(), interrupt, terminate));
+    }
+
+    // Each listener accepts its clients in its own task, until one of them
+    // fails, or the server shuts down
+    let mut tasks = JoinSet::new();
+
+    for listener in listeners.tcp {
+        let server = server.clone();
+        tasks.spawn(async move { server.run(listener).await });
+    }
+
+    if let Some(acceptor) = acceptor {
+        for listener in listeners.tls {
+            let server = server.clone();
+            let acceptor = acceptor.clone();
+            tasks.spawn(async move { server.run_tls(listener, acceptor).await });
+        }
+    }
+
+    for listener in listeners.metrics {
+        let context = server.context.clone();
+        tasks.spawn(async move { metrics::run(listener, context).await });
+    }
+
+    if let Some(listener) = listeners.unix {
+        let server = server.clone();
+        tasks.spawn(async move { server.run_unix(listener).await });
+    }
+
+    // From now on, only the connections hold the sender of `shutdown_complete`
+    drop(server);
+
+    let result: Result<(), Box<dyn std::error::Error + Send + Sync>> = tokio::select! {
+        result = async {
+            while let Some(result) = tasks.join_next().await {
+                result??;
+            }
+            Ok(())
+        } => result,
+        _ = context.shutdown.stopped() => Ok(()),
+    };
+
+    // Stop accepting, then wait for each connection to finish its current command
+    context.shutdown.trigger();
+    tasks.shutdown().await;
+    let _ = connections_closed.recv().await;
+
+    result.map_err(|err| err as Box<dyn std::error::Error>)
+}
+
+/// Shut the server down on the first SIGINT or SIGTERM, once the replicas
+/// caught up, and right away on the next one
+async fn shutdown_on_signals(context: ServerContext, mut interrupt: Signal, mut terminate: Signal) {
+    loop {
+        tokio::select! {
+            _ = interrupt.recv() => {}
+            _ = terminate.recv() => {}
+        }
+
+        if context.shutdown.is_stopping() {
+            return;
+        }
+
+        println!("Received a signal, shutting down");
+        let timeout = context.config.read().unwrap().shutdown_timeout;
+
+        tokio::select! {
+            shut_down = context.shutdown.begin(&context.replication, Duration::from_secs(timeout), false) => {
+                match shut_down {
+                    true => return,
+                    // SHUTDOWN ABORT cancels it, until the next signal
+                    false => println!("Shutdown aborted"),
+                }
+            }
+            _ = interrupt.recv() => context.shutdown.trigger(),
+            _ = terminate.recv() => context.shutdown.trigger(),
+        }
+    }
 }
@@ -0,0 +1,118 @@
+// The graceful shutdown of the server, on SHUTDOWN or a signal. Once it is
+// triggered, the listeners stop accepting, and each connection is closed once
+// its current command is done.
+use crate::Replication;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{watch, Notify};
+use tokio::time::{sleep, Duration, Instant};
+
+/// How often the offsets of the replicas are checked, while waiting for them
+const REPLICA_POLL_INTERVAL_MS: u64 = 10;
+
+/// The shutdown state, shared by the listeners and the connections. The
+/// shutdown is broadcast over a watch channel, so that the tasks which start
+/// waiting for it after it was triggered see it too. A clone only increments
+/// the reference count.
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    shared: Arc<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    stopping: watch::Sender<bool>,
+
+    /// Notified by SHUTDOWN ABORT, while a shutdown waits for the replicas
+    pending: Mutex<Option<Arc<Notify>>>,
+}
+
+impl Shutdown {
+    pub fn new() -> Shutdown {
+        let (stopping, _) = watch::channel(false);
+
+        Shutdown {
+            shared: Arc::new(State {
+                stopping,
+                pending: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Shut the server down, once the replicas acknowledged all of the
+    /// replication stream, for up to `timeout`, or right away if `now`.
+    ///
+    /// Returns false if the shutdown was aborted meanwhile.
+    pub async fn begin(&self, replication: &Replication, timeout: Duration, now: bool) -> bool {
+        if !now {
+            let aborted = Arc::new(Notify::new());
+            *self.shared.pending.lock().unwrap() = Some(aborted.clone());
+
+            let waited = tokio::select! {
+                _ = aborted.notified() => false,
+                _ = Shutdown::wait_for_replicas(replication, timeout) => true,
+            };
+
+            self.shared.pending.lock().unwrap().take();
+            if !waited {
+                return false;
+            }
+        }
+
+        self.trigger();
+        true
+    }
+
+    /// Wait until the replicas acknowledged the offset of the master, or the `timeout`
+    async fn wait_for_replicas(replication: &Replication, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+
+        while Instant::now() < deadline {
+            let offset = replication.master_repl_offset();
+            let lagging = replication
+                .replicas()
+                .iter()
+                .filter(|replica| replica.ack_offset < offset)
+                .count();
+
+            if lagging == 0 {
+                return;
+            }
+
+            sleep(Duration::from_millis(REPLICA_POLL_INTERVAL_MS)).await;
+        }
+
+        println!("Shutting down with lagging replicas");
+    }
+
+    /// Cancel the shutdown which waits for the replicas, if there is one
+    pub fn abort(&self) -> bool {
+        match self.shared.pending.lock().unwrap().take() {
+            Some(aborted) => {
+                aborted.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop the server right away
+    pub fn trigger(&self) {
+        self.shared.stopping.send_replace(true);
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        *self.shared.stopping.borrow()
+    }
+
+    /// Wait until the server is stopping
+    pub async fn stopped(&self) {
+        let mut stopping = self.shared.stopping.subscribe();
+        let _ = stopping.wait_for(|stopping| *stopping).await;
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Shutdown::new()
+    }
+}
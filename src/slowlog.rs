@@ -0,0 +1,170 @@
+// The slow log of the server, as reported by SLOWLOG. It holds the latest
+// commands whose execution took longer than `slowlog-log-slower-than`.
+use chrono::Utc;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The number of arguments kept of a command, including its name. The last one
+/// kept tells how many were left out.
+const SLOWLOG_ENTRY_MAX_ARGC: usize = 32;
+
+/// The number of bytes kept of each argument
+const SLOWLOG_ENTRY_MAX_STRING: usize = 128;
+
+/// The configuration parameters whose values are secrets
+const SECRET_CONFIG_PARAMS: [&str; 2] = ["requirepass", "masterauth"];
+
+/// What replaces the secrets in the arguments
+const REDACTED: &str = "(redacted)";
+
+/// A command of the slow log
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlowLogEntry {
+    /// Unique and increasing across the lifetime of the server, even after a reset
+    pub id: u64,
+
+    /// The Unix time at which the command was logged, in seconds
+    pub timestamp: i64,
+
+    /// The duration of the execution, in microseconds
+    pub duration: u64,
+
+    /// The name and arguments of the command, truncated
+    pub args: Vec<String>,
+
+    /// The address and name of the client which ran the command
+    pub client_addr: String,
+    pub client_name: String,
+}
+
+/// The latest slow commands, shared by every connection. A clone only
+/// increments the reference count.
+#[derive(Debug, Clone, Default)]
+pub struct SlowLog {
+    shared: Arc<Mutex<Entries>>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    /// The entries, from the newest to the oldest
+    entries: VecDeque<SlowLogEntry>,
+    next_id: u64,
+}
+
+impl SlowLog {
+    pub fn new() -> SlowLog {
+        SlowLog::default()
+    }
+
+    /// Redact the passwords of ACL SETUSER and CONFIG SET, as in Redis
+    fn redact_args(args: &[String]) -> Vec<String> {
+        let mut redacted = args.to_vec();
+        let is = |i: usize, name: &str| {
+            args.get(i)
+                .is_some_and(|arg| arg.eq_ignore_ascii_case(name))
+        };
+
+        if is(0, "acl") && is(1, "setuser") {
+            for rule in redacted.iter_mut().skip(3) {
+                if rule.starts_with(['>', '<', '#', '!']) {
+                    *rule = REDACTED.to_string();
+                }
+            }
+        } else if is(0, "config") && is(1, "set") {
+            for pair in redacted[2..].chunks_mut(2) {
+                if let [name, value] = pair {
+                    if SECRET_CONFIG_PARAMS
+                        .iter()
+                        .any(|param| name.eq_ignore_ascii_case(param))
+                    {
+                        *value = REDACTED.to_string();
+                    }
+                }
+            }
+        }
+
+        redacted
+    }
+
+    /// Truncate the arguments of a command, as in Redis
+    fn truncate_args(args: &[String]) -> Vec<String> {
+        let truncate = |arg: &String| {
+            if arg.len() <= SLOWLOG_ENTRY_MAX_STRING {
+                return arg.clone();
+            }
+
+            let mut end = SLOWLOG_ENTRY_MAX_STRING;
+            while !arg.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            format!("{}... ({} more bytes)", &arg[..end], arg.len() - end)
+        };
+
+        if args.len() <= SLOWLOG_ENTRY_MAX_ARGC {
+            return args.iter().map(truncate).collect();
+        }
+
+        let mut truncated: Vec<String> = args[..SLOWLOG_ENTRY_MAX_ARGC - 1]
+            .iter()
+            .map(truncate)
+            .collect();
+        truncated.push(format!(
+            "... ({} more arguments)",
+            args.len() - SLOWLOG_ENTRY_MAX_ARGC + 1
+        ));
+
+        truncated
+    }
+
+    /// Log a command with `args`, which took `duration`. The oldest entries are
+    /// dropped to keep at most `max_len` of them.
+    pub fn push(
+        &self,
+        args: &[String],
+        duration: Duration,
+        client_addr: &str,
+        client_name: &str,
+        max_len: usize,
+    ) {
+        let mut log = self.shared.lock().unwrap();
+
+        let entry = SlowLogEntry {
+            id: log.next_id,
+            timestamp: Utc::now().timestamp(),
+            duration: duration.as_micros() as u64,
+            args: SlowLog::truncate_args(&SlowLog::redact_args(args)),
+            client_addr: client_addr.to_string(),
+            client_name: client_name.to_string(),
+        };
+
+        log.next_id += 1;
+        log.entries.push_front(entry);
+        log.entries.truncate(max_len);
+    }
+
+    /// The `count` newest entries, or all of them if `None`
+    pub fn get(&self, count: Option<usize>) -> Vec<SlowLogEntry> {
+        let log = self.shared.lock().unwrap();
+
+        log.entries
+            .iter()
+            .take(count.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every entry. The ids keep increasing.
+    pub fn reset(&self) {
+        self.shared.lock().unwrap().entries.clear();
+    }
+}
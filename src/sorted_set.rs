@@ -0,0 +1,97 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, HashMap},
+};
+
+/// A set of unique members, ordered by their score.
+///
+/// Members are kept both in a `HashMap`, for `O(1)` lookups of a member's score,
+/// and in a `BTreeSet`, which keeps the members ordered by (score, member) so
+/// that ranges can be iterated in order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SortedSet {
+    /// The score of each member
+    scores: HashMap<String, f64>,
+
+    /// The members, ordered by score and then lexicographically
+    ordered: BTreeSet<ScoredMember>,
+}
+
+/// An entry of the ordered index of a `SortedSet`
+#[derive(Debug, Clone, PartialEq)]
+struct ScoredMember {
+    score: f64,
+    member: String,
+}
+
+impl Eq for ScoredMember {}
+
+impl PartialOrd for ScoredMember {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScoredMember {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| self.member.cmp(&other.member))
+    }
+}
+
+impl SortedSet {
+    pub fn new() -> SortedSet {
+        SortedSet::default()
+    }
+
+    /// Insert the `member` with `score`, or update its score if it already exists.
+    ///
+    /// Will return the previous score of the member, if it existed.
+    pub fn insert(&mut self, member: String, score: f64) -> Option<f64> {
+        let previous = self.scores.insert(member.clone(), score);
+
+        if let Some(old_score) = previous {
+            self.ordered.remove(&ScoredMember {
+                score: old_score,
+                member: member.clone(),
+            });
+        }
+
+        self.ordered.insert(ScoredMember { score, member });
+
+        previous
+    }
+
+    /// Remove the `member`, returning its score if it existed.
+    pub fn remove(&mut self, member: &str) -> Option<f64> {
+        let score = self.scores.remove(member)?;
+
+        self.ordered.remove(&ScoredMember {
+            score,
+            member: member.to_string(),
+        });
+
+        Some(score)
+    }
+
+    /// Get the score of the `member`
+    pub fn score(&self, member: &str) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    /// Iterate over the (member, score) pairs, in ascending order of score
+    pub fn iter(&self) -> impl Iterator<Item = (&String, f64)> {
+        self.ordered
+            .iter()
+            .map(|entry| (&entry.member, entry.score))
+    }
+}
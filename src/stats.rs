@@ -0,0 +1,204 @@
+// The counters of the server, as reported by INFO. The counters of the keys
+// themselves are kept by the shards of the `SharedStore`.
+use crate::histogram::LatencyHistogram;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+use std::time::Duration;
+
+/// The counters of a single command, as reported by INFO commandstats
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandStats {
+    /// The number of executions, and their total duration in microseconds
+    pub calls: u64,
+    pub usec: u64,
+
+    /// The number of calls refused before their execution, e.g. by the ACL
+    pub rejected_calls: u64,
+
+    /// The number of executions which replied with an error
+    pub failed_calls: u64,
+
+    /// The durations of the executions, in microseconds
+    pub latency: LatencyHistogram,
+}
+
+/// The counters of the connections and commands of the server.
+///
+/// The counters are shared by every connection, so a clone only increments
+/// the reference count.
+#[derive(Debug, Clone)]
+pub struct Stats {
+    shared: Arc<Counters>,
+}
+
+#[derive(Debug)]
+struct Counters {
+    /// When the server was started
+    started_at: DateTime<Utc>,
+
+    /// The number of clients connected now
+    connected_clients: AtomicUsize,
+
+    /// The number of connections accepted since the start, or the last CONFIG RESETSTAT
+    total_connections_received: AtomicU64,
+
+    /// The number of connections refused over `maxclients`, since the start,
+    /// or the last CONFIG RESETSTAT
+    rejected_connections: AtomicU64,
+
+    /// The number of commands executed since the start, or the last CONFIG RESETSTAT
+    total_commands_processed: AtomicU64,
+
+    /// The counters of each command which was called, by name
+    commands: Mutex<BTreeMap<String, CommandStats>>,
+
+    /// The number of error replies of each type, e.g. `ERR` or `WRONGTYPE`
+    errors: Mutex<BTreeMap<String, u64>>,
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        Stats {
+            shared: Arc::new(Counters {
+                started_at: Utc::now(),
+                connected_clients: AtomicUsize::new(0),
+                total_connections_received: AtomicU64::new(0),
+                rejected_connections: AtomicU64::new(0),
+                total_commands_processed: AtomicU64::new(0),
+                commands: Mutex::new(BTreeMap::new()),
+                errors: Mutex::new(BTreeMap::new()),
+            }),
+        }
+    }
+
+    /// The number of seconds since the server was started
+    pub fn uptime_in_seconds(&self) -> i64 {
+        (Utc::now() - self.shared.started_at).num_seconds()
+    }
+
+    /// Record a new client, which is connected until `client_disconnected`
+    pub fn client_connected(&self) {
+        self.shared
+            .connected_clients
+            .fetch_add(1, Ordering::Relaxed);
+        self.shared
+            .total_connections_received
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn client_disconnected(&self) {
+        self.shared
+            .connected_clients
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn connection_rejected(&self) {
+        self.shared
+            .rejected_connections
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn command_processed(&self) {
+        self.shared
+            .total_commands_processed
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record an execution of the `command`, which took `duration`, and
+    /// whether it `failed` with an error reply
+    pub fn record_call(&self, command: &str, duration: Duration, failed: bool) {
+        let usec = duration.as_micros() as u64;
+
+        let mut commands = self.shared.commands.lock().unwrap();
+        let stats = commands.entry(command.to_string()).or_default();
+
+        stats.calls += 1;
+        stats.usec += usec;
+        stats.failed_calls += failed as u64;
+        stats.latency.record(usec);
+    }
+
+    /// Record a call of the `command`, which was refused before its execution
+    pub fn record_rejected_call(&self, command: &str) {
+        let mut commands = self.shared.commands.lock().unwrap();
+        commands
+            .entry(command.to_string())
+            .or_default()
+            .rejected_calls += 1;
+    }
+
+    /// Record an error reply with the `message`. Its type is the code which it
+    /// starts with, or `ERR` if it has none, as in Redis.
+    pub fn record_error(&self, message: &str) {
+        let code = message
+            .split(' ')
+            .next()
+            .filter(|code| !code.is_empty() && code.chars().all(|c| c.is_ascii_uppercase()))
+            .unwrap_or("ERR");
+
+        let mut errors = self.shared.errors.lock().unwrap();
+        *errors.entry(code.to_string()).or_default() += 1;
+    }
+
+    /// The number of error replies of each type, in the order of the types
+    pub fn error_stats(&self) -> Vec<(String, u64)> {
+        let errors = self.shared.errors.lock().unwrap();
+
+        errors
+            .iter()
+            .map(|(code, count)| (code.clone(), *count))
+            .collect()
+    }
+
+    /// The counters of each command which was called, in the order of their names
+    pub fn command_stats(&self) -> Vec<(String, CommandStats)> {
+        let commands = self.shared.commands.lock().unwrap();
+
+        commands
+            .iter()
+            .map(|(name, stats)| (name.clone(), stats.clone()))
+            .collect()
+    }
+
+    pub fn connected_clients(&self) -> usize {
+        self.shared.connected_clients.load(Ordering::Relaxed)
+    }
+
+    pub fn total_connections_received(&self) -> u64 {
+        self.shared
+            .total_connections_received
+            .load(Ordering::Relaxed)
+    }
+
+    pub fn rejected_connections(&self) -> u64 {
+        self.shared.rejected_connections.load(Ordering::Relaxed)
+    }
+
+    pub fn total_commands_processed(&self) -> u64 {
+        self.shared.total_commands_processed.load(Ordering::Relaxed)
+    }
+
+    /// Reset the totals and the counters of the commands and errors, for CONFIG RESETSTAT.
+    /// The connected clients are kept.
+    pub fn reset(&self) {
+        self.shared.commands.lock().unwrap().clear();
+        self.shared.errors.lock().unwrap().clear();
+        self.shared
+            .total_connections_received
+            .store(0, Ordering::Relaxed);
+        self.shared.rejected_connections.store(0, Ordering::Relaxed);
+        self.shared
+            .total_commands_processed
+            .store(0, Ordering::Relaxed);
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Stats::new()
+    }
+}
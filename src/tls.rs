@@ -0,0 +1,107 @@
+use crate::Config;
+use std::{error::Error, fmt, path::Path, sync::Arc};
+use tokio_rustls::rustls::{
+    crypto::ring,
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+
+pub use tokio_rustls::TlsAcceptor;
+
+/// Whether TLS clients must authenticate with a certificate, signed by the
+/// CA of `tls-ca-cert-file`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TlsAuthClients {
+    Yes,
+    No,
+
+    /// Clients may connect without a certificate, but one they present must be valid
+    Optional,
+}
+
+impl TlsAuthClients {
+    pub fn parse(value: &str) -> Option<TlsAuthClients> {
+        match value.to_lowercase().as_str() {
+            "yes" => Some(TlsAuthClients::Yes),
+            "no" => Some(TlsAuthClients::No),
+            "optional" => Some(TlsAuthClients::Optional),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TlsAuthClients {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            TlsAuthClients::Yes => "yes",
+            TlsAuthClients::No => "no",
+            TlsAuthClients::Optional => "optional",
+        };
+
+        name.fmt(fmt)
+    }
+}
+
+fn read_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, Box<dyn Error>> {
+    let invalid = |err: &dyn std::fmt::Display| format!("{}: {}", path.display(), err);
+
+    let certs = CertificateDer::pem_file_iter(path)
+        .map_err(|err| invalid(&err))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| invalid(&err))?;
+
+    if certs.is_empty() {
+        return Err(invalid(&"No certificate found").into());
+    }
+
+    Ok(certs)
+}
+
+/// Build the acceptor of the TLS listener, from the certificate, key and
+/// CA files of the `config`.
+///
+/// Returns an error if a file is missing or invalid, or if clients must be
+/// authenticated without a CA to verify their certificates.
+pub fn acceptor(config: &Config) -> Result<TlsAcceptor, Box<dyn Error>> {
+    let cert_file = config
+        .tls_cert_file
+        .as_ref()
+        .ok_or("tls-cert-file is needed for TLS")?;
+    let key_file = config
+        .tls_key_file
+        .as_ref()
+        .ok_or("tls-key-file is needed for TLS")?;
+
+    let certs = read_certs(cert_file)?;
+    let key = PrivateKeyDer::from_pem_file(key_file)
+        .map_err(|err| format!("{}: {}", key_file.display(), err))?;
+
+    let provider = Arc::new(ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+
+    let builder = match (config.tls_auth_clients, &config.tls_ca_cert_file) {
+        (TlsAuthClients::No, _) => builder.with_no_client_auth(),
+        (_, None) => return Err("tls-ca-cert-file is needed to authenticate the clients".into()),
+        (auth_clients, Some(ca_cert_file)) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs(ca_cert_file)? {
+                roots.add(cert)?;
+            }
+
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+            let verifier = if auth_clients == TlsAuthClients::Optional {
+                verifier.allow_unauthenticated().build()?
+            } else {
+                verifier.build()?
+            };
+
+            builder.with_client_cert_verifier(verifier)
+        }
+    };
+
+    let server_config = builder.with_single_cert(certs, key)?;
+
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
@@ -0,0 +1,65 @@
+// Helpers shared by the tests which need their own server, rather than
+// the one listening on `DEFAULT_PORT`. Not all of them are used by each test.
+#![allow(dead_code)]
+
+use redis::{Client, Connection};
+use redust::{server, Config};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::time::sleep;
+
+/// Start a server on a random local port, and return the port
+pub async fn start_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        let _ = server::run(listener).await;
+    });
+
+    port
+}
+
+pub fn connect(port: u16) -> Connection {
+    let client = Client::open(format!("redis://127.0.0.1:{}/", port)).unwrap();
+    client.get_connection().unwrap()
+}
+
+/// Poll the `condition` until it holds, or panic after 5 seconds
+pub async fn wait_until(mut condition: impl FnMut() -> bool) {
+    for _ in 0..100 {
+        if condition() {
+            return;
+        }
+
+        sleep(Duration::from_millis(50)).await;
+    }
+
+    panic!("Condition was not met in time");
+}
+
+/// Start a cluster node on random local ports, and return the client and bus ports
+pub async fn start_cluster_node() -> (u16, u16) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bus_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let bus_port = bus_listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        let _ = server::run_cluster(listener, bus_listener).await;
+    });
+
+    (port, bus_port)
+}
+
+/// Start a server with the `config` on a random local port, and return the port
+pub async fn start_server_with_config(config: Config) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        let _ = server::run_with_config(listener, config).await;
+    });
+
+    port
+}
@@ -0,0 +1,399 @@
+mod common;
+
+use common::{connect, start_server, start_server_with_config};
+use redis::{RedisResult, Value};
+use redust::acl::{hash_password, User};
+use redust::glob::glob_match;
+use redust::{Acl, Config};
+use rstest::rstest;
+
+#[rstest]
+#[case("*", "anything", true)]
+#[case("user:*", "user:1", true)]
+#[case("user:*", "session:1", false)]
+#[case("h?llo", "hello", true)]
+#[case("h?llo", "hllo", false)]
+#[case("h[ae]llo", "hallo", true)]
+#[case("h[ae]llo", "hillo", false)]
+#[case("h[^e]llo", "hallo", true)]
+#[case("h[^e]llo", "hello", false)]
+#[case("h[a-c]llo", "hbllo", true)]
+#[case("h\\*llo", "h*llo", true)]
+#[case("h\\*llo", "hello", false)]
+#[case("*:*:end", "a:b:c:end", true)]
+fn test_glob_match(#[case] pattern: &str, #[case] text: &str, #[case] expected: bool) {
+    assert_eq!(expected, glob_match(pattern, text));
+}
+
+#[test]
+fn test_hash_password_is_sha256() {
+    assert_eq!(
+        "2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae",
+        hash_password("foo")
+    );
+}
+
+#[test]
+fn test_user_rules() {
+    let mut user = User::new("alice");
+    assert!(!user.check_password("secret"));
+    assert!(!user.can_run("get"));
+
+    for rule in ["on", ">secret", "~cache:*", "+@read", "-lrange"] {
+        user.apply_rule(rule).unwrap();
+    }
+
+    assert!(user.check_password("secret"));
+    assert!(!user.check_password("other"));
+    assert!(user.can_run("get"));
+    assert!(user.can_run("geopos"));
+    assert!(!user.can_run("lrange"));
+    assert!(!user.can_run("set"));
+    assert!(user.can_access_key("cache:1"));
+    assert!(!user.can_access_key("other"));
+    assert!(!user.can_access_channel("news"));
+    assert_eq!("-@all +@read -lrange", user.commands_description());
+
+    assert_eq!(
+        Err("Unknown command or category name in ACL".to_string()),
+        user.apply_rule("+@nothing")
+    );
+    assert_eq!(Err("Syntax error".to_string()), user.apply_rule("bogus"));
+
+    user.apply_rule("reset").unwrap();
+    assert_eq!(User::new("alice"), user);
+}
+
+#[test]
+fn test_requirepass_only_changes_default_password() {
+    let acl = Acl::new();
+    assert!(acl.default_user_needs_no_auth());
+
+    acl.set_requirepass(Some("secret"));
+    assert!(!acl.default_user_needs_no_auth());
+    assert!(acl.authenticate("default", "secret"));
+    assert!(!acl.authenticate("default", "other"));
+    assert!(acl.get_user("default").unwrap().can_run("set"));
+
+    acl.set_requirepass(None);
+    assert!(acl.default_user_needs_no_auth());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_requirepass_needs_auth() -> RedisResult<()> {
+    let config = Config {
+        requirepass: Some("secret".to_string()),
+        ..Config::default()
+    };
+    let port = start_server_with_config(config).await;
+    let mut cnxn = connect(port);
+
+    let err = redis::cmd("GET")
+        .arg("Key")
+        .query::<Value>(&mut cnxn)
+        .unwrap_err();
+    assert_eq!(Some("NOAUTH"), err.code());
+
+    let err = redis::cmd("AUTH")
+        .arg("wrong")
+        .query::<Value>(&mut cnxn)
+        .unwrap_err();
+    assert_eq!(Some("WRONGPASS"), err.code());
+
+    let reply: String = redis::cmd("AUTH").arg("secret").query(&mut cnxn)?;
+    assert_eq!("OK", reply);
+
+    let value: Option<String> = redis::cmd("GET").arg("Key").query(&mut cnxn)?;
+    assert_eq!(None, value);
+
+    let user: String = redis::cmd("ACL").arg("WHOAMI").query(&mut cnxn)?;
+    assert_eq!("default", user);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_auth_without_requirepass() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    let err = redis::cmd("AUTH")
+        .arg("secret")
+        .query::<Value>(&mut cnxn)
+        .unwrap_err();
+    assert!(err.to_string().contains("without any password configured"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_user_permissions() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut admin = connect(port);
+    let mut cnxn = connect(port);
+
+    let reply: String = redis::cmd("ACL")
+        .arg(&[
+            "SETUSER", "alice", "on", ">pw", "~cache:*", "+@read", "+set",
+        ])
+        .query(&mut admin)?;
+    assert_eq!("OK", reply);
+
+    let reply: String = redis::cmd("AUTH").arg(&["alice", "pw"]).query(&mut cnxn)?;
+    assert_eq!("OK", reply);
+
+    let _: () = redis::cmd("SET")
+        .arg(&["cache:1", "value"])
+        .query(&mut cnxn)?;
+    let value: String = redis::cmd("GET").arg("cache:1").query(&mut cnxn)?;
+    assert_eq!("value", value);
+
+    let err = redis::cmd("GET")
+        .arg("other")
+        .query::<Value>(&mut cnxn)
+        .unwrap_err();
+    assert_eq!(Some("NOPERM"), err.code());
+    assert!(err.to_string().contains("No permissions to access a key"));
+
+    let err = redis::cmd("DEL")
+        .arg("cache:1")
+        .query::<Value>(&mut cnxn)
+        .unwrap_err();
+    assert_eq!(Some("NOPERM"), err.code());
+    assert!(err.to_string().contains("'del' command"));
+
+    // Each key of a multi-key command is checked
+    let err = redis::cmd("EXISTS")
+        .arg(&["cache:1", "other"])
+        .query::<Value>(&mut cnxn)
+        .unwrap_err();
+    assert_eq!(Some("NOPERM"), err.code());
+
+    // Disabled users can't authenticate
+    let _: () = redis::cmd("ACL")
+        .arg(&["SETUSER", "alice", "off"])
+        .query(&mut admin)?;
+    let err = redis::cmd("AUTH")
+        .arg(&["alice", "pw"])
+        .query::<Value>(&mut cnxn)
+        .unwrap_err();
+    assert_eq!(Some("WRONGPASS"), err.code());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_bitfield_ro_is_a_read_command() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut admin = connect(port);
+    let mut cnxn = connect(port);
+
+    let _: () = redis::cmd("ACL")
+        .arg(&["SETUSER", "alice", "on", ">pw", "~*", "+@read"])
+        .query(&mut admin)?;
+    let _: () = redis::cmd("AUTH").arg(&["alice", "pw"]).query(&mut cnxn)?;
+
+    let values: Vec<i64> = redis::cmd("BITFIELD_RO")
+        .arg(&["bits", "GET", "u8", "0"])
+        .query(&mut cnxn)?;
+    assert_eq!(vec![0], values);
+
+    // BITFIELD may write, even with only GET sub-commands
+    let err = redis::cmd("BITFIELD")
+        .arg(&["bits", "GET", "u8", "0"])
+        .query::<Value>(&mut cnxn)
+        .unwrap_err();
+    assert_eq!(Some("NOPERM"), err.code());
+    assert!(err.to_string().contains("'bitfield' command"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_getuser_and_list() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    let _: () = redis::cmd("ACL")
+        .arg(&[
+            "SETUSER",
+            "bob",
+            "on",
+            "#2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae",
+            "~*",
+            "&news:*",
+            "+get",
+        ])
+        .query(&mut cnxn)?;
+
+    let user: Vec<Value> = redis::cmd("ACL")
+        .arg(&["GETUSER", "bob"])
+        .query(&mut cnxn)?;
+    let fields: Vec<String> = user
+        .iter()
+        .step_by(2)
+        .map(|field| redis::from_redis_value(field).unwrap())
+        .collect();
+    assert_eq!(
+        vec![
+            "flags",
+            "passwords",
+            "commands",
+            "keys",
+            "channels",
+            "selectors"
+        ],
+        fields
+    );
+
+    let commands: String = redis::from_redis_value(&user[5])?;
+    assert_eq!("-@all +get", commands);
+
+    let users: Vec<String> = redis::cmd("ACL").arg("LIST").query(&mut cnxn)?;
+    assert_eq!(
+        vec![
+            "user bob on #2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae ~* &news:* -@all +get",
+            "user default on nopass ~* &* +@all",
+        ],
+        users
+    );
+
+    // The password hash authenticates with its password
+    let mut other = connect(port);
+    let reply: String = redis::cmd("AUTH").arg(&["bob", "foo"]).query(&mut other)?;
+    assert_eq!("OK", reply);
+
+    let missing: Option<Vec<Value>> = redis::cmd("ACL")
+        .arg(&["GETUSER", "nobody"])
+        .query(&mut cnxn)?;
+    assert_eq!(None, missing);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_setuser_invalid_rule_leaves_user_unchanged() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    let err = redis::cmd("ACL")
+        .arg(&["SETUSER", "carol", "on", "+nosuchcommand"])
+        .query::<Value>(&mut cnxn)
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Error in ACL SETUSER modifier '+nosuchcommand'"));
+
+    let users: Vec<String> = redis::cmd("ACL").arg("USERS").query(&mut cnxn)?;
+    assert_eq!(vec!["default"], users);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_deluser() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut admin = connect(port);
+    let mut cnxn = connect(port);
+
+    let _: () = redis::cmd("ACL")
+        .arg(&["SETUSER", "dave", "on", "nopass", "+@all", "~*"])
+        .query(&mut admin)?;
+    let _: () = redis::cmd("AUTH").arg(&["dave", "any"]).query(&mut cnxn)?;
+
+    let err = redis::cmd("ACL")
+        .arg(&["DELUSER", "default"])
+        .query::<Value>(&mut admin)
+        .unwrap_err();
+    assert!(err.to_string().contains("cannot be removed"));
+
+    let deleted: i64 = redis::cmd("ACL")
+        .arg(&["DELUSER", "dave", "nobody"])
+        .query(&mut admin)?;
+    assert_eq!(1, deleted);
+
+    // The connections of the deleted user are closed
+    let result = redis::cmd("PING").query::<Value>(&mut cnxn);
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_cat() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    let categories: Vec<String> = redis::cmd("ACL").arg("CAT").query(&mut cnxn)?;
+    assert!(categories.contains(&"read".to_string()));
+    assert!(categories.contains(&"dangerous".to_string()));
+
+    let commands: Vec<String> = redis::cmd("ACL").arg(&["CAT", "list"]).query(&mut cnxn)?;
+    assert_eq!(vec!["lpush", "rpush", "lrange"], commands);
+
+    let err = redis::cmd("ACL")
+        .arg(&["CAT", "nothing"])
+        .query::<Value>(&mut cnxn)
+        .unwrap_err();
+    assert!(err.to_string().contains("Unknown category 'nothing'"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_log() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut admin = connect(port);
+    let mut cnxn = connect(port);
+
+    let _: () = redis::cmd("ACL")
+        .arg(&["SETUSER", "erin", "on", ">pw", "+get", "~allowed"])
+        .query(&mut admin)?;
+    let _: () = redis::cmd("AUTH").arg(&["erin", "pw"]).query(&mut cnxn)?;
+
+    for _ in 0..2 {
+        let _ = redis::cmd("GET").arg("denied").query::<Value>(&mut cnxn);
+    }
+    let _ = redis::cmd("SET")
+        .arg(&["allowed", "1"])
+        .query::<Value>(&mut cnxn);
+    let _ = redis::cmd("AUTH")
+        .arg(&["erin", "wrong"])
+        .query::<Value>(&mut cnxn);
+
+    let entries: Vec<Vec<Value>> = redis::cmd("ACL").arg("LOG").query(&mut admin)?;
+    assert_eq!(3, entries.len());
+
+    // The most recent entries come first, and identical denials are grouped
+    let field = |entry: &Vec<Value>, name: &str| -> Value {
+        let index = entry
+            .iter()
+            .position(|value| {
+                redis::from_redis_value::<String>(value).ok().as_deref() == Some(name)
+            })
+            .unwrap();
+        entry[index + 1].clone()
+    };
+
+    let reasons: Vec<String> = entries
+        .iter()
+        .map(|entry| redis::from_redis_value(&field(entry, "reason")).unwrap())
+        .collect();
+    assert_eq!(vec!["auth", "command", "key"], reasons);
+
+    let count: i64 = redis::from_redis_value(&field(&entries[2], "count"))?;
+    assert_eq!(2, count);
+    let object: String = redis::from_redis_value(&field(&entries[2], "object"))?;
+    assert_eq!("denied", object);
+    let username: String = redis::from_redis_value(&field(&entries[1], "username"))?;
+    assert_eq!("erin", username);
+
+    let entries: Vec<Value> = redis::cmd("ACL").arg(&["LOG", "1"]).query(&mut admin)?;
+    assert_eq!(1, entries.len());
+
+    let _: () = redis::cmd("ACL").arg(&["LOG", "RESET"]).query(&mut admin)?;
+    let entries: Vec<Value> = redis::cmd("ACL").arg("LOG").query(&mut admin)?;
+    assert!(entries.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use common::start_server;
+use redust::cli::{command_frame, format_reply, split_args, ArgsError, CliConnection, PipeSummary};
+use redust::protocol_handler::BulkStringData;
+use redust::{serialize_data, RESPType};
+use rstest::rstest;
+
+fn bulk_string(text: &str) -> RESPType {
+    RESPType::BulkString(Some(BulkStringData {
+        text: text.to_string(),
+        prefix_length: text.len(),
+    }))
+}
+
+#[rstest]
+#[case("SET key value", vec!["SET", "key", "value"])]
+#[case("  GET   key  ", vec!["GET", "key"])]
+#[case("SET key \"hello world\"", vec!["SET", "key", "hello world"])]
+#[case("SET key 'it\\'s'", vec!["SET", "key", "it's"])]
+#[case("SET key 'no \\n escape'", vec!["SET", "key", "no \\n escape"])]
+#[case("SET key \"a\\nb\\t\\\"c\\\"\"", vec!["SET", "key", "a\nb\t\"c\""])]
+#[case("SET key \"\\x41\\x42\"", vec!["SET", "key", "AB"])]
+#[case("SET key \"\\xc3\\xa9\"", vec!["SET", "key", "é"])]
+#[case("SET key \"\"", vec!["SET", "key", ""])]
+#[case("", vec![])]
+fn test_split_args(#[case] line: &str, #[case] expected: Vec<&str>) {
+    let expected: Vec<String> = expected.into_iter().map(String::from).collect();
+    assert_eq!(Ok(expected), split_args(line));
+}
+
+#[rstest]
+#[case("SET key \"unclosed", ArgsError::Incomplete)]
+#[case("SET key 'unclosed", ArgsError::Incomplete)]
+#[case("SET key \"closed\"trailing", ArgsError::Invalid)]
+#[case("SET key \"\\xZZ\"", ArgsError::Invalid)]
+#[case("SET key \"\\xff\"", ArgsError::Invalid)]
+fn test_split_args_errors(#[case] line: &str, #[case] expected: ArgsError) {
+    assert_eq!(Err(expected), split_args(line));
+}
+
+#[test]
+fn test_split_args_multi_line() {
+    // The REPL joins the lines of an unclosed quote, with a newline
+    assert_eq!(
+        Ok(vec![
+            "SET".to_string(),
+            "key".to_string(),
+            "line 1\nline 2".to_string()
+        ]),
+        split_args("SET key \"line 1\nline 2\"")
+    );
+}
+
+#[rstest]
+#[case(RESPType::SimpleString("OK".to_string()), "OK")]
+#[case(RESPType::Error("ERR unknown".to_string()), "(error) ERR unknown")]
+#[case(RESPType::Integer(42), "(integer) 42")]
+#[case(RESPType::BulkString(None), "(nil)")]
+#[case(bulk_string("say \"hi\"\r"), "\"say \\\"hi\\\"\\r\"")]
+#[case(bulk_string("# Server\r\nport:6666\r\n"), "# Server\r\nport:6666\r\n")]
+#[case(RESPType::BulkBytes(vec![0x80, b'a', 0xff]), "\"\\x80a\\xff\"")]
+#[case(RESPType::Array(vec![]), "(empty array)")]
+#[case(
+    RESPType::Array(vec![bulk_string("a"), RESPType::Integer(1)]),
+    "1) \"a\"\n2) (integer) 1"
+)]
+#[case(
+    RESPType::Array(vec![
+        RESPType::Array(vec![bulk_string("a"), bulk_string("b")]),
+        RESPType::Array(vec![]),
+        bulk_string("c"),
+    ]),
+    "1) 1) \"a\"\n   2) \"b\"\n2) (empty array)\n3) \"c\""
+)]
+#[case(
+    RESPType::Array(vec![bulk_string("one\ntwo"), bulk_string("three")]),
+    "1) one\n   two\n2) \"three\""
+)]
+fn test_format_reply(#[case] reply: RESPType, #[case] expected: &str) {
+    assert_eq!(expected, format_reply(&reply));
+}
+
+#[test]
+fn test_format_reply_aligns_numbers() {
+    let reply = RESPType::Array((1..=10).map(RESPType::Integer).collect());
+    let text = format_reply(&reply);
+    let lines: Vec<&str> = text.lines().collect();
+
+    assert_eq!(" 1) (integer) 1", lines[0]);
+    assert_eq!("10) (integer) 10", lines[9]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_request_and_pipe() {
+    let port = start_server().await;
+    let mut cnxn = CliConnection::connect("127.0.0.1", port).unwrap();
+
+    let args = split_args("SET greeting \"hello world\"").unwrap();
+    let reply = cnxn.request(&args).unwrap();
+    assert!(matches!(reply, RESPType::SimpleString(_)));
+
+    assert_eq!(
+        bulk_string("hello world"),
+        cnxn.request(&split_args("GET greeting").unwrap()).unwrap()
+    );
+
+    // One command per line, with an error
+    let mut input = String::new();
+    for i in 0..1000 {
+        input.push_str(&format!("RPUSH list {}\n", i));
+    }
+    input.push_str("INCR greeting\n");
+
+    assert_eq!(
+        PipeSummary {
+            replies: 1001,
+            errors: 1
+        },
+        cnxn.pipe(input.as_bytes()).unwrap()
+    );
+
+    // Raw protocol
+    let mut input: Vec<u8> = Vec::new();
+    for i in 0..1000 {
+        let args = vec!["SET".to_string(), format!("key:{}", i), i.to_string()];
+        input.extend(serialize_data(&command_frame(&args)).unwrap());
+    }
+
+    assert_eq!(
+        PipeSummary {
+            replies: 1000,
+            errors: 0
+        },
+        cnxn.pipe(&input).unwrap()
+    );
+
+    // Empty arrays get no reply, so they aren't waited for
+    assert_eq!(
+        PipeSummary {
+            replies: 1,
+            errors: 0
+        },
+        cnxn.pipe(b"*0\r\n*1\r\n$4\r\nPING\r\n*0\r\n").unwrap()
+    );
+
+    assert_eq!(
+        bulk_string("999"),
+        cnxn.request(&split_args("GET key:999").unwrap()).unwrap()
+    );
+    let last = cnxn
+        .request(&split_args("LRANGE list -1 -1").unwrap())
+        .unwrap();
+    assert_eq!(RESPType::Array(vec![bulk_string("999")]), last);
+}
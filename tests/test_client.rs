@@ -0,0 +1,345 @@
+mod common;
+
+use common::{connect, start_server, start_server_with_config, wait_until};
+use redis::{Connection, RedisResult, Value};
+use redust::Config;
+use std::collections::HashMap;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// The fields of the line of CLIENT LIST with the `id`
+fn client_fields(cnxn: &mut Connection, id: i64) -> Option<HashMap<String, String>> {
+    let list: String = redis::cmd("CLIENT").arg("LIST").query(cnxn).unwrap();
+
+    list.lines()
+        .map(|line| {
+            line.split(' ')
+                .filter_map(|field| field.split_once('='))
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>()
+        })
+        .find(|fields| fields["id"] == id.to_string())
+}
+
+fn client_id(cnxn: &mut Connection) -> i64 {
+    redis::cmd("CLIENT").arg("ID").query(cnxn).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_client_id_and_name() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+    let mut other = connect(port);
+
+    // Each connection has its own id
+    let id = client_id(&mut cnxn);
+    assert!(id > 0);
+    assert_ne!(id, client_id(&mut other));
+
+    let name: Option<String> = redis::cmd("CLIENT").arg("GETNAME").query(&mut cnxn)?;
+    assert_eq!(None, name);
+
+    let _: () = redis::cmd("CLIENT")
+        .arg(&["SETNAME", "worker-1"])
+        .query(&mut cnxn)?;
+    let name: Option<String> = redis::cmd("CLIENT").arg("GETNAME").query(&mut cnxn)?;
+    assert_eq!(Some("worker-1".to_string()), name);
+
+    let result: RedisResult<()> = redis::cmd("CLIENT")
+        .arg(&["SETNAME", "a name"])
+        .query(&mut cnxn);
+    assert!(result.is_err());
+
+    // The name is reported to the other clients
+    let fields = client_fields(&mut other, id).unwrap();
+    assert_eq!("worker-1", fields["name"]);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_client_list_and_info() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+    let mut other = connect(port);
+
+    let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut other)?;
+
+    let id = client_id(&mut other);
+    let fields = client_fields(&mut cnxn, id).unwrap();
+    assert!(fields["addr"].starts_with("127.0.0.1:"));
+    assert_eq!(format!("127.0.0.1:{}", port), fields["laddr"]);
+    assert_eq!("N", fields["flags"]);
+    assert_eq!("0", fields["db"]);
+    assert_eq!("client", fields["cmd"]);
+    assert_eq!("default", fields["user"]);
+    for field in ["age", "idle", "qbuf", "rbs"] {
+        assert!(fields[field].parse::<u64>().is_ok());
+    }
+
+    // LIST can be filtered by id
+    let list: String = redis::cmd("CLIENT")
+        .arg(&["LIST", "ID", &id.to_string()])
+        .query(&mut cnxn)?;
+    assert_eq!(1, list.lines().count());
+    assert!(list.starts_with(&format!("id={} ", id)));
+
+    // INFO describes the current connection
+    let info: String = redis::cmd("CLIENT").arg("INFO").query(&mut other)?;
+    assert!(info.starts_with(&format!("id={} ", id)));
+    assert!(info.ends_with("cmd=client user=default\n"));
+
+    let _: () = redis::cmd("CLIENT")
+        .arg(&["NO-EVICT", "on"])
+        .query(&mut other)?;
+    assert_eq!("e", client_fields(&mut cnxn, id).unwrap()["flags"]);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_client_kill() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    // By id, which closes the connection
+    let mut victim = connect(port);
+    let id = client_id(&mut victim);
+    let killed: i64 = redis::cmd("CLIENT")
+        .arg(&["KILL", "ID", &id.to_string()])
+        .query(&mut cnxn)?;
+    assert_eq!(1, killed);
+    wait_until(|| client_fields(&mut cnxn, id).is_none()).await;
+    assert!(redis::cmd("PING").query::<String>(&mut victim).is_err());
+
+    // By address, with the legacy form
+    let mut victim = connect(port);
+    let id = client_id(&mut victim);
+    let addr = client_fields(&mut cnxn, id).unwrap()["addr"].clone();
+    let _: () = redis::cmd("CLIENT")
+        .arg(&["KILL", &addr])
+        .query(&mut cnxn)?;
+    wait_until(|| client_fields(&mut cnxn, id).is_none()).await;
+
+    let result: RedisResult<()> = redis::cmd("CLIENT").arg(&["KILL", &addr]).query(&mut cnxn);
+    assert!(result.is_err());
+
+    // By user, which skips the current connection
+    let _: () = redis::cmd("ACL")
+        .arg(&["SETUSER", "alice", "on", ">pw", "+@all"])
+        .query(&mut cnxn)?;
+    let mut victim = connect(port);
+    let _: () = redis::cmd("AUTH")
+        .arg(&["alice", "pw"])
+        .query(&mut victim)?;
+    let id = client_id(&mut victim);
+    let killed: i64 = redis::cmd("CLIENT")
+        .arg(&["KILL", "USER", "alice"])
+        .query(&mut cnxn)?;
+    assert_eq!(1, killed);
+    wait_until(|| client_fields(&mut cnxn, id).is_none()).await;
+
+    let result: RedisResult<Value> = redis::cmd("CLIENT")
+        .arg(&["KILL", "USER", "nobody"])
+        .query(&mut cnxn);
+    assert!(result.is_err());
+
+    // By type, where SKIPME no also kills the current connection
+    let victim = connect(port);
+    let killed: i64 = redis::cmd("CLIENT")
+        .arg(&["KILL", "TYPE", "normal", "SKIPME", "no"])
+        .query(&mut cnxn)?;
+    assert_eq!(2, killed);
+    drop(victim);
+
+    let result: RedisResult<String> = redis::cmd("PING").query(&mut cnxn);
+    assert!(result.is_err());
+
+    let mut cnxn = connect(port);
+    let result: RedisResult<Value> = redis::cmd("CLIENT")
+        .arg(&["KILL", "TYPE", "nosuchtype"])
+        .query(&mut cnxn);
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_idle_timeout() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    let keepalive: Vec<String> = redis::cmd("CONFIG")
+        .arg(&["GET", "tcp-keepalive"])
+        .query(&mut cnxn)?;
+    assert_eq!(vec!["tcp-keepalive", "300"], keepalive);
+
+    let _: () = redis::cmd("CONFIG")
+        .arg(&["SET", "timeout", "1"])
+        .query(&mut cnxn)?;
+
+    // The idle connection is closed, while the one polling the list stays open
+    let mut idle = connect(port);
+    let id = client_id(&mut idle);
+    wait_until(|| client_fields(&mut cnxn, id).is_none()).await;
+    assert!(redis::cmd("PING").query::<String>(&mut idle).is_err());
+
+    Ok(())
+}
+
+/// Connect to the server, and return the error which it refused the
+/// connection with, if any
+async fn refused_with(port: u16) -> Option<String> {
+    let mut socket = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let mut reply = String::new();
+
+    // Accepted connections stay open, without a reply
+    let read = socket.read_to_string(&mut reply);
+    match tokio::time::timeout(std::time::Duration::from_millis(200), read).await {
+        Ok(_) => Some(reply),
+        Err(_) => None,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_maxclients() -> RedisResult<()> {
+    let config = Config {
+        maxclients: 2,
+        ..Config::default()
+    };
+    let port = start_server_with_config(config).await;
+    let mut cnxn = connect(port);
+    let other = connect(port);
+
+    assert_eq!(
+        Some("-ERR max number of clients reached\r\n".to_string()),
+        refused_with(port).await
+    );
+
+    let info: String = redis::cmd("INFO").query(&mut cnxn)?;
+    assert!(info.contains("maxclients:2\r\n"));
+    assert!(info.contains("rejected_connections:1\r\n"));
+
+    // Raising the limit accepts more clients
+    let _: () = redis::cmd("CONFIG")
+        .arg(&["SET", "maxclients", "3"])
+        .query(&mut cnxn)?;
+    let third = connect(port);
+
+    // Lowering it keeps the connected clients, but refuses new ones until
+    // enough of them disconnect
+    let _: () = redis::cmd("CONFIG")
+        .arg(&["SET", "maxclients", "2"])
+        .query(&mut cnxn)?;
+    assert!(refused_with(port).await.is_some());
+
+    drop(third);
+    wait_until(|| {
+        let info: String = redis::cmd("INFO").arg("clients").query(&mut cnxn).unwrap();
+        info.contains("connected_clients:2\r\n")
+    })
+    .await;
+    assert!(refused_with(port).await.is_some());
+
+    drop(other);
+    wait_until(|| {
+        let info: String = redis::cmd("INFO").arg("clients").query(&mut cnxn).unwrap();
+        info.contains("connected_clients:1\r\n")
+    })
+    .await;
+    assert_eq!(None, refused_with(port).await);
+
+    Ok(())
+}
+
+/// Send raw bytes over a new connection, and return everything which the
+/// server replies until it closes the connection
+async fn reply_until_closed(port: u16, request: &[u8]) -> String {
+    let mut socket = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    socket.write_all(request).await.unwrap();
+
+    let mut reply = String::new();
+    let read = socket.read_to_string(&mut reply);
+    tokio::time::timeout(std::time::Duration::from_secs(5), read)
+        .await
+        .expect("The connection wasn't closed")
+        .unwrap();
+    reply
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_protocol_limits() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    // The frames over the limits are refused, without waiting for their data
+    assert_eq!(
+        "-ERR Protocol error: invalid multibulk length\r\n",
+        reply_until_closed(port, b"*2147483647\r\n").await
+    );
+    assert_eq!(
+        "-ERR Protocol error: invalid bulk length\r\n",
+        reply_until_closed(port, b"*1\r\n$4294967296\r\n").await
+    );
+    // Just as much as the server reads before it refuses the line, as the
+    // reply would be lost if the connection was closed with unread data
+    let line = [b"+".to_vec(), vec![b'a'; 64 * 1024 + 1]].concat();
+    assert_eq!(
+        "-ERR Protocol error: too big inline request\r\n",
+        reply_until_closed(port, &line).await
+    );
+
+    assert_eq!(
+        "-ERR Protocol error: invalid multibulk length\r\n",
+        reply_until_closed(port, b"*-2\r\n").await
+    );
+
+    // Deeply nested arrays are refused, rather than overflowing the stack
+    assert_eq!(
+        "-ERR Protocol error: too deep nesting\r\n",
+        reply_until_closed(port, &b"*1\r\n".repeat(129)).await
+    );
+
+    // Empty and null arrays are skipped, without a reply
+    assert_eq!(
+        "+\"PONG\"\r\n-ERR Protocol error: invalid multibulk length\r\n",
+        reply_until_closed(port, b"*0\r\n*-1\r\n*1\r\n$4\r\nPING\r\n*-2\r\n").await
+    );
+
+    // A null bulk string isn't an argument, and the connection goes on
+    assert_eq!(
+        "-ERR Expected String Type got BulkString(None)\r\n+\"PONG\"\r\n-ERR Protocol error: invalid multibulk length\r\n",
+        reply_until_closed(port, b"*1\r\n$-1\r\n*1\r\n$4\r\nPING\r\n*-2\r\n").await
+    );
+
+    // The commands before the error are still processed
+    assert_eq!(
+        "+\"PONG\"\r\n-ERR Protocol error: invalid bulk length\r\n",
+        reply_until_closed(port, b"*1\r\n$4\r\nPING\r\n$-5\r\n").await
+    );
+
+    let len: Vec<String> = redis::cmd("CONFIG")
+        .arg(&["GET", "proto-max-bulk-len"])
+        .query(&mut cnxn)?;
+    assert_eq!(vec!["proto-max-bulk-len", "536870912"], len);
+
+    let result: RedisResult<()> = redis::cmd("CONFIG")
+        .arg(&["SET", "proto-max-bulk-len", "1kb"])
+        .query(&mut cnxn);
+    assert!(result.is_err());
+
+    // A lower limit applies to the connected clients too
+    let _: () = redis::cmd("CONFIG")
+        .arg(&["SET", "proto-max-bulk-len", "1mb"])
+        .query(&mut cnxn)?;
+    let value = "a".repeat(1024 * 1024 + 1);
+    let result: RedisResult<()> = redis::cmd("SET").arg(&["Key", &value]).query(&mut cnxn);
+    assert!(result.is_err());
+
+    let mut cnxn = connect(port);
+    let _: () = redis::cmd("SET")
+        .arg(&["Key", &value[1..]])
+        .query(&mut cnxn)?;
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use common::{connect, start_server};
+use redis::RedisResult;
+use redust::{DataType, PauseMode, SharedStore, SharedStoreBase};
+use rstest::rstest;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_pause_write_holds_writes_and_serves_reads() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut admin = connect(port);
+    let mut cnxn = connect(port);
+
+    let _: () = redis::cmd("SET").arg("Key").arg("Value").query(&mut cnxn)?;
+
+    let reply: String = redis::cmd("CLIENT")
+        .arg(&["PAUSE", "500", "WRITE"])
+        .query(&mut admin)?;
+    assert_eq!("OK", reply);
+
+    let start = Instant::now();
+
+    let value: String = redis::cmd("GET").arg("Key").query(&mut cnxn)?;
+    assert_eq!("Value", value);
+    assert!(start.elapsed() < Duration::from_millis(400));
+
+    // The write is only executed once the pause ends
+    let _: () = redis::cmd("SET").arg("Key").arg("Other").query(&mut cnxn)?;
+    assert!(start.elapsed() >= Duration::from_millis(400));
+
+    let value: String = redis::cmd("GET").arg("Key").query(&mut cnxn)?;
+    assert_eq!("Other", value);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_unpause_resumes_held_commands() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut admin = connect(port);
+
+    let _: () = redis::cmd("CLIENT")
+        .arg(&["PAUSE", "10000", "ALL"])
+        .query(&mut admin)?;
+
+    // Reads are also held, with ALL
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut cnxn = connect(port);
+        let value: RedisResult<Option<String>> = redis::cmd("GET").arg("Key").query(&mut cnxn);
+        sender.send(value).unwrap();
+    });
+
+    sleep(Duration::from_millis(300)).await;
+    assert!(receiver.try_recv().is_err());
+
+    // CLIENT commands aren't held, so the pause can be lifted
+    let _: () = redis::cmd("CLIENT").arg("UNPAUSE").query(&mut admin)?;
+
+    let value = receiver.recv_timeout(Duration::from_secs(2)).unwrap()?;
+    assert_eq!(None, value);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(vec!["PAUSE", "abc"], "timeout is not an integer or out of range")]
+#[case(vec!["PAUSE", "10", "READ"], "syntax error")]
+#[case(vec!["UNPAUSE", "10"], "wrong number of arguments for 'client|unpause' command")]
+#[case(vec!["FREEZE"], "unknown subcommand 'FREEZE'")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_client_pause_invalid_arguments(#[case] args: Vec<&str>, #[case] expected: &str) {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    let result: RedisResult<String> = redis::cmd("CLIENT").arg(&args).query(&mut cnxn);
+
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains(expected), "{}", err);
+}
+
+#[tokio::test]
+async fn test_pause_holds_key_expiry() {
+    let store = SharedStore::new();
+
+    store
+        .set(
+            "Key".to_string(),
+            DataType::String("Value".to_string()),
+            Some(chrono::Duration::milliseconds(50)),
+            false,
+            false,
+        )
+        .unwrap();
+
+    store
+        .client_pause()
+        .pause(PauseMode::Write, Duration::from_millis(5000));
+
+    // The key has expired, but isn't purged while paused
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(1, store.memory_stats().keys);
+
+    store.client_pause().unpause();
+
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(0, store.memory_stats().keys);
+}
@@ -0,0 +1,367 @@
+mod common;
+
+use common::{connect, start_cluster_node, start_server, wait_until};
+use redis::{Connection, RedisResult};
+use redust::cluster::{crc16, key_hash_slot};
+use redust::server;
+use rstest::rstest;
+use tokio::net::TcpListener;
+
+#[rstest]
+#[case("123456789", 0x31C3)]
+#[case("", 0)]
+fn test_crc16(#[case] input: &str, #[case] expected: u16) {
+    assert_eq!(expected, crc16(input.as_bytes()));
+}
+
+#[rstest]
+#[case("somekey", 11058)]
+#[case("foo", 12182)]
+#[case("123456789", 12739)]
+#[case("{user1000}.following", 3443)]
+#[case("{user1000}.followers", 3443)]
+#[case("foo{{bar}}zap", key_hash_slot("{bar"))]
+#[case("foo{bar}{zap}", key_hash_slot("bar"))]
+fn test_key_hash_slot(#[case] key: &str, #[case] expected: u16) {
+    assert_eq!(expected, key_hash_slot(key));
+}
+
+#[test]
+fn test_key_hash_slot_empty_hashtag_hashes_the_whole_key() {
+    assert_eq!(crc16(b"foo{}{bar}") % 16384, key_hash_slot("foo{}{bar}"));
+}
+
+fn error_detail(result: RedisResult<redis::Value>) -> (String, String) {
+    let err = result.unwrap_err();
+
+    (
+        err.code().unwrap_or_default().to_string(),
+        err.detail().unwrap_or_default().to_string(),
+    )
+}
+
+fn node_id(cnxn: &mut Connection) -> String {
+    redis::cmd("CLUSTER").arg("MYID").query(cnxn).unwrap()
+}
+
+/// The (start, end, port, node ID) of each slot range in CLUSTER SLOTS
+fn cluster_slots(cnxn: &mut Connection) -> Vec<(i64, i64, i64, String)> {
+    let ranges: Vec<Vec<redis::Value>> = redis::cmd("CLUSTER").arg("SLOTS").query(cnxn).unwrap();
+
+    ranges
+        .into_iter()
+        .map(|range| {
+            let (ip, port, id): (String, i64, String) = redis::from_redis_value(&range[2]).unwrap();
+            assert_eq!("127.0.0.1", ip);
+
+            (
+                redis::from_redis_value(&range[0]).unwrap(),
+                redis::from_redis_value(&range[1]).unwrap(),
+                port,
+                id,
+            )
+        })
+        .collect()
+}
+
+/// Start two nodes, which split the slots in half
+async fn start_two_node_cluster() -> ((u16, Connection), (u16, Connection)) {
+    let (port_a, _) = start_cluster_node().await;
+    let (port_b, bus_port_b) = start_cluster_node().await;
+
+    let mut a = connect(port_a);
+    let mut b = connect(port_b);
+
+    let _: () = redis::cmd("CLUSTER")
+        .arg("ADDSLOTSRANGE")
+        .arg(0)
+        .arg(8191)
+        .query(&mut a)
+        .unwrap();
+    let _: () = redis::cmd("CLUSTER")
+        .arg("ADDSLOTSRANGE")
+        .arg(8192)
+        .arg(16383)
+        .query(&mut b)
+        .unwrap();
+
+    let _: () = redis::cmd("CLUSTER")
+        .arg("MEET")
+        .arg("127.0.0.1")
+        .arg(port_b)
+        .arg(bus_port_b)
+        .query(&mut a)
+        .unwrap();
+
+    // Both nodes know all the slots, once they exchanged messages
+    wait_until(|| {
+        [&mut a, &mut b].into_iter().all(|cnxn| {
+            let info: String = redis::cmd("CLUSTER").arg("INFO").query(cnxn).unwrap();
+            info.contains("cluster_state:ok") && info.contains("cluster_known_nodes:2")
+        })
+    })
+    .await;
+
+    ((port_a, a), (port_b, b))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_cluster_commands_when_disabled() {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    // KEYSLOT only hashes the key
+    let slot: i64 = redis::cmd("CLUSTER")
+        .arg("KEYSLOT")
+        .arg("somekey")
+        .query(&mut cnxn)
+        .unwrap();
+    assert_eq!(11058, slot);
+
+    let result: RedisResult<String> = redis::cmd("CLUSTER").arg("INFO").query(&mut cnxn);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("cluster support disabled"));
+
+    let info: String = redis::cmd("INFO").arg("cluster").query(&mut cnxn).unwrap();
+    assert!(info.contains("cluster_enabled:0"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_single_node_keys_in_slot() -> RedisResult<()> {
+    let (port, _) = start_cluster_node().await;
+    let mut cnxn = connect(port);
+
+    // No slot is served, until it is assigned
+    let (code, _) = error_detail(redis::cmd("GET").arg("foo").query(&mut cnxn));
+    assert_eq!("CLUSTERDOWN", code);
+
+    let _: () = redis::cmd("CLUSTER")
+        .arg("ADDSLOTSRANGE")
+        .arg(0)
+        .arg(16383)
+        .query(&mut cnxn)?;
+
+    let info: String = redis::cmd("INFO").arg("cluster").query(&mut cnxn)?;
+    assert!(info.contains("cluster_enabled:1"));
+
+    for key in ["{user}.a", "{user}.b", "{user}.c", "other"] {
+        let _: () = redis::cmd("SET").arg(key).arg("1").query(&mut cnxn)?;
+    }
+
+    let slot = key_hash_slot("user");
+
+    let count: i64 = redis::cmd("CLUSTER")
+        .arg("COUNTKEYSINSLOT")
+        .arg(slot)
+        .query(&mut cnxn)?;
+    assert_eq!(3, count);
+
+    let mut keys: Vec<String> = redis::cmd("CLUSTER")
+        .arg("GETKEYSINSLOT")
+        .arg(slot)
+        .arg(10)
+        .query(&mut cnxn)?;
+    keys.sort();
+    assert_eq!(vec!["{user}.a", "{user}.b", "{user}.c"], keys);
+
+    let keys: Vec<String> = redis::cmd("CLUSTER")
+        .arg("GETKEYSINSLOT")
+        .arg(slot)
+        .arg(2)
+        .query(&mut cnxn)?;
+    assert_eq!(2, keys.len());
+
+    // Keys with the same hashtag can be used together
+    let deleted: i64 = redis::cmd("DEL")
+        .arg(&["{user}.a", "{user}.b"])
+        .query(&mut cnxn)?;
+    assert_eq!(2, deleted);
+
+    let (code, _) = error_detail(
+        redis::cmd("EXISTS")
+            .arg(&["{user}.c", "other"])
+            .query(&mut cnxn),
+    );
+    assert_eq!("CROSSSLOT", code);
+
+    let (code, _) = error_detail(redis::cmd("DEL").arg(&["foo", "bar"]).query(&mut cnxn));
+    assert_eq!("CROSSSLOT", code);
+
+    let result: RedisResult<()> = redis::cmd("CLUSTER")
+        .arg("ADDSLOTS")
+        .arg(0)
+        .query(&mut cnxn);
+    assert!(result.unwrap_err().to_string().contains("already busy"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_moved_redirect_between_nodes() -> RedisResult<()> {
+    let ((port_a, mut a), (port_b, mut b)) = start_two_node_cluster().await;
+
+    // "foo" hashes to 12182, which is served by the second node
+    let (code, detail) = error_detail(redis::cmd("SET").arg("foo").arg("bar").query(&mut a));
+    assert_eq!("MOVED", code);
+    assert_eq!(format!("12182 127.0.0.1:{}", port_b), detail);
+
+    let _: () = redis::cmd("SET").arg("foo").arg("bar").query(&mut b)?;
+
+    // "bar" hashes to 5061, which is served by the first node
+    let (code, detail) = error_detail(redis::cmd("GET").arg("bar").query(&mut b));
+    assert_eq!("MOVED", code);
+    assert_eq!(format!("5061 127.0.0.1:{}", port_a), detail);
+
+    // Both nodes report the same slot ranges
+    let slots_a = cluster_slots(&mut a);
+    assert_eq!(slots_a, cluster_slots(&mut b));
+
+    assert_eq!(
+        vec![
+            (0, 8191, port_a as i64, node_id(&mut a)),
+            (8192, 16383, port_b as i64, node_id(&mut b)),
+        ],
+        slots_a
+    );
+
+    let nodes: String = redis::cmd("CLUSTER").arg("NODES").query(&mut a)?;
+    let myself = nodes.lines().find(|line| line.contains("myself")).unwrap();
+    assert!(myself.starts_with(&node_id(&mut a)));
+    assert!(myself.ends_with(" 0-8191"));
+    assert_eq!(2, nodes.lines().count());
+
+    let shards: Vec<redis::Value> = redis::cmd("CLUSTER").arg("SHARDS").query(&mut b)?;
+    assert_eq!(2, shards.len());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_ask_redirect_while_migrating_a_slot() -> RedisResult<()> {
+    let ((_, mut a), (_, mut b)) = start_two_node_cluster().await;
+
+    let id_a = node_id(&mut a);
+    let id_b = node_id(&mut b);
+
+    // "a" hashes to 15495 on the second node, as does "{a}x"
+    let slot = key_hash_slot("a");
+    let _: () = redis::cmd("SET").arg("a").arg("old").query(&mut b)?;
+
+    let _: () = redis::cmd("CLUSTER")
+        .arg("SETSLOT")
+        .arg(slot)
+        .arg("IMPORTING")
+        .arg(&id_b)
+        .query(&mut a)?;
+    let _: () = redis::cmd("CLUSTER")
+        .arg("SETSLOT")
+        .arg(slot)
+        .arg("MIGRATING")
+        .arg(&id_a)
+        .query(&mut b)?;
+
+    let nodes: String = redis::cmd("CLUSTER").arg("NODES").query(&mut b)?;
+    assert!(nodes.contains(&format!("[{}->-{}]", slot, id_a)));
+
+    // Existing keys are still served by the migrating node
+    let value: Option<String> = redis::cmd("GET").arg("a").query(&mut b)?;
+    assert_eq!(Some("old".to_string()), value);
+
+    // Missing keys are redirected to the importing node
+    let (code, detail) = error_detail(redis::cmd("GET").arg("{a}x").query(&mut b));
+    assert_eq!("ASK", code);
+    assert!(detail.starts_with(&format!("{} 127.0.0.1:", slot)));
+
+    // Only some of the keys were moved
+    let (code, _) = error_detail(redis::cmd("EXISTS").arg(&["a", "{a}x"]).query(&mut b));
+    assert_eq!("TRYAGAIN", code);
+
+    // The importing node only serves the slot after ASKING, for one command
+    let (code, _) = error_detail(redis::cmd("SET").arg("{a}x").arg("new").query(&mut a));
+    assert_eq!("MOVED", code);
+
+    let _: () = redis::cmd("ASKING").query(&mut a)?;
+    let _: () = redis::cmd("SET").arg("{a}x").arg("new").query(&mut a)?;
+
+    let (code, _) = error_detail(redis::cmd("GET").arg("{a}x").query(&mut a));
+    assert_eq!("MOVED", code);
+
+    // Once the slot is assigned to the importing node, the other node learns it
+    let _: () = redis::cmd("CLUSTER")
+        .arg("SETSLOT")
+        .arg(slot)
+        .arg("NODE")
+        .arg(&id_a)
+        .query(&mut a)?;
+
+    let value: Option<String> = redis::cmd("GET").arg("{a}x").query(&mut a)?;
+    assert_eq!(Some("new".to_string()), value);
+
+    wait_until(|| {
+        let result: RedisResult<redis::Value> = redis::cmd("GET").arg("{a}x").query(&mut b);
+        error_detail(result).0 == "MOVED"
+    })
+    .await;
+
+    // The rest of the second node's slots are unchanged
+    let _: () = redis::cmd("SET").arg("foo").arg("bar").query(&mut b)?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_slots_of_a_handshake_node_are_unassigned() -> RedisResult<()> {
+    let (port_a, _) = start_cluster_node().await;
+    let mut a = connect(port_a);
+
+    // The second node only starts once its handshake node owns a slot
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bus_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port_b = listener.local_addr().unwrap().port();
+    let bus_port_b = bus_listener.local_addr().unwrap().port();
+
+    let _: () = redis::cmd("CLUSTER")
+        .arg("MEET")
+        .arg("127.0.0.1")
+        .arg(port_b)
+        .arg(bus_port_b)
+        .query(&mut a)?;
+
+    let nodes: String = redis::cmd("CLUSTER").arg("NODES").query(&mut a)?;
+    let handshake_id = nodes
+        .lines()
+        .find(|line| line.contains("handshake"))
+        .and_then(|line| line.split(' ').next())
+        .unwrap()
+        .to_string();
+
+    let _: () = redis::cmd("CLUSTER")
+        .arg("SETSLOT")
+        .arg(0)
+        .arg("NODE")
+        .arg(&handshake_id)
+        .query(&mut a)?;
+    assert_eq!(1, cluster_slots(&mut a).len());
+
+    tokio::spawn(async move {
+        let _ = server::run_cluster(listener, bus_listener).await;
+    });
+
+    wait_until(|| {
+        let info: String = redis::cmd("CLUSTER").arg("INFO").query(&mut a).unwrap();
+        info.contains("cluster_known_nodes:2")
+            && !redis::cmd("CLUSTER")
+                .arg("NODES")
+                .query::<String>(&mut a)
+                .unwrap()
+                .contains("handshake")
+    })
+    .await;
+
+    // The slot isn't owned by the forgotten node anymore
+    assert!(cluster_slots(&mut a).is_empty());
+
+    Ok(())
+}
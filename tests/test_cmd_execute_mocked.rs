@@ -1,8 +1,9 @@
 use mockall::predicate::{eq, ne};
 use predicates::ord::EqPredicate;
+use redust::cmd::{BitfieldEncoding, BitfieldOperation, Object, ObjectSubcommand};
 use redust::protocol_handler::BulkStringData;
-use redust::DataType;
-use redust::{cmd::Echo, cmd::Exists, cmd::Get, cmd::Ping, cmd::Set, RESPType};
+use redust::{cmd::Bitfield, cmd::Echo, cmd::Exists, cmd::Get, cmd::Ping, cmd::Set, RESPType};
+use redust::{DataType, ObjectInfo};
 use redust::{MockConnectionBase, MockSharedStoreBase};
 use rstest::rstest;
 
@@ -525,3 +526,116 @@ async fn test_exists_execute_all_keys_exist_cnxn_err(
     let result = exists_cmd.execute(&mock_shared_store, &mut mock_cnxn).await;
     assert!(result.is_err());
 }
+
+/// BITFIELD Execute Command
+///
+/// Assumption:
+/// 1. The second INCRBY operation fails due to an overflow
+/// 2. Good Connection
+#[rstest]
+// Equal to
+#[case(vec![Some(10), None], eq(RESPType::Array(vec![RESPType::Integer(10), RESPType::BulkString(None)])))]
+// Not Equal to
+#[case(vec![Some(10), None], ne(RESPType::Array(vec![RESPType::Integer(10), RESPType::Integer(0)])))]
+#[tokio::test]
+async fn test_bitfield_execute_overflow_fail_cnxn_ok(
+    #[case] store_result: Vec<Option<i64>>,
+    #[case] expected_input_cnxn_write_frame: EqPredicate<RESPType>,
+) {
+    let encoding = BitfieldEncoding {
+        signed: false,
+        bits: 4,
+    };
+    let operations = vec![
+        BitfieldOperation::Incrby {
+            encoding: encoding.clone(),
+            offset: 0,
+            increment: 10,
+        },
+        BitfieldOperation::Incrby {
+            encoding,
+            offset: 0,
+            increment: 10,
+        },
+    ];
+
+    // Create the Command instance
+    let bitfield_cmd = Bitfield::new("Key".to_string(), operations.clone());
+
+    // Create the Shared Store Mock
+    let mut mock_shared_store = MockSharedStoreBase::new();
+
+    mock_shared_store
+        .expect_bitfield()
+        .with(eq("Key".to_string()), eq(operations))
+        .times(1)
+        .returning(move |_, _| Ok(store_result.clone()));
+
+    // Create the Connection Mock
+    let mut mock_cnxn = MockConnectionBase::new();
+
+    // Add the expected conditions, to assert for the Mocked Connection
+    mock_cnxn
+        .expect_write_frame()
+        .with(expected_input_cnxn_write_frame)
+        .times(1)
+        .returning(|_| Ok(()));
+
+    // Call the function to test
+    let result = bitfield_cmd
+        .execute(&mock_shared_store, &mut mock_cnxn)
+        .await;
+    assert!(result.is_ok());
+}
+
+/// Object Execute Command
+///
+/// Assumption:
+/// 1. Good Connection
+#[rstest]
+// Key exists
+#[case(ObjectSubcommand::Encoding, Some("embstr".to_string()), eq(RESPType::BulkString(Some(BulkStringData{text: "embstr".to_string(), prefix_length: 6}))))]
+#[case(ObjectSubcommand::Idletime, Some("embstr".to_string()), eq(RESPType::Integer(42)))]
+#[case(ObjectSubcommand::Freq, Some("embstr".to_string()), eq(RESPType::Integer(7)))]
+#[case(ObjectSubcommand::Refcount, Some("embstr".to_string()), eq(RESPType::Integer(1)))]
+// Key doesn't exist
+#[case(ObjectSubcommand::Encoding, None, eq(RESPType::BulkString(None)))]
+#[tokio::test]
+async fn test_object_execute_cnxn_ok(
+    #[case] subcommand: ObjectSubcommand,
+    #[case] encoding: Option<String>,
+    #[case] expected_input_cnxn_write_frame: EqPredicate<RESPType>,
+) {
+    // Create the Command instance
+    let object_cmd = Object::new(subcommand, "Key".to_string());
+
+    // Create the Shared Store Mock
+    let mut mock_shared_store = MockSharedStoreBase::new();
+
+    mock_shared_store
+        .expect_object_info()
+        .with(eq("Key".to_string()))
+        .times(1)
+        .returning(move |_| {
+            encoding.clone().map(|encoding| ObjectInfo {
+                encoding,
+                idle_seconds: 42,
+                frequency: 7,
+                refcount: 1,
+            })
+        });
+
+    // Create the Connection Mock
+    let mut mock_cnxn = MockConnectionBase::new();
+
+    // Add the expected conditions, to assert for the Mocked Connection
+    mock_cnxn
+        .expect_write_frame()
+        .with(expected_input_cnxn_write_frame)
+        .times(1)
+        .returning(|_| Ok(()));
+
+    // Call the function to test
+    let result = object_cmd.execute(&mock_shared_store, &mut mock_cnxn).await;
+    assert!(result.is_ok());
+}
@@ -272,3 +272,532 @@ fn test_set_and_get_key_expiry(
 
     Ok(())
 }
+
+#[rstest]
+// Set a bit on a new key, growing the string
+#[case("BitKey1", vec![(7, 1, 0), (7, 0, 1), (100, 1, 0)], vec![(7, 0), (100, 1), (5000, 0)])]
+// Overwriting a set bit returns the original value
+#[case("BitKey2", vec![(0, 1, 0), (0, 1, 1)], vec![(0, 1), (1, 0)])]
+fn test_setbit_and_getbit(
+    #[case] key: &str,
+    #[case] setbit_calls: Vec<(u64, u8, i64)>,
+    #[case] getbit_calls: Vec<(u64, i64)>,
+    mut cnxn: Connection,
+) -> RedisResult<()> {
+    for (offset, value, expected_original) in setbit_calls {
+        let original: i64 = redis::cmd("SETBIT")
+            .arg(key)
+            .arg(offset)
+            .arg(value)
+            .query(&mut cnxn)?;
+
+        assert_eq!(expected_original, original);
+    }
+
+    for (offset, expected_bit) in getbit_calls {
+        let bit: i64 = redis::cmd("GETBIT").arg(key).arg(offset).query(&mut cnxn)?;
+
+        assert_eq!(expected_bit, bit);
+    }
+
+    Ok(())
+}
+
+#[rstest]
+// The whole string
+#[case(vec![], 26)]
+// Byte ranges
+#[case(vec!["0", "0"], 4)]
+#[case(vec!["1", "1"], 6)]
+#[case(vec!["1", "-2"], 18)]
+// Bit ranges
+#[case(vec!["5", "30", "BIT"], 17)]
+// Empty range
+#[case(vec!["3", "1"], 0)]
+fn test_bitcount(
+    #[case] range_args: Vec<&str>,
+    #[case] expected_count: i64,
+    mut cnxn: Connection,
+) -> RedisResult<()> {
+    let _: String = redis::cmd("SET")
+        .arg("BitcountKey")
+        .arg("foobar")
+        .query(&mut cnxn)?;
+
+    let count: i64 = redis::cmd("BITCOUNT")
+        .arg("BitcountKey")
+        .arg(range_args)
+        .query(&mut cnxn)?;
+
+    assert_eq!(expected_count, count);
+
+    Ok(())
+}
+
+#[rstest]
+// The bytes 0xDF 0xBF 0x00, i.e. 11011111 10111111 00000000
+// First clear bit
+#[case("BitposKey1", "\u{7ff}\0", vec!["0"], 2)]
+// First set bit
+#[case("BitposKey2", "\u{7ff}\0", vec!["1"], 0)]
+// First set bit from the 3rd byte
+#[case("BitposKey3", "\u{7ff}\0", vec!["1", "2"], -1)]
+// First clear bit within an explicit end
+#[case("BitposKey4", "\u{7ff}\0", vec!["0", "1", "1"], 9)]
+// First set bit of a missing key
+#[case("BitposKey5", "", vec!["1"], -1)]
+// Bit ranges
+#[case("BitposKey6", "\u{7ff}\0", vec!["1", "10", "15", "BIT"], 10)]
+fn test_bitpos(
+    #[case] key: &str,
+    #[case] value: &str,
+    #[case] args: Vec<&str>,
+    #[case] expected_position: i64,
+    mut cnxn: Connection,
+) -> RedisResult<()> {
+    if !value.is_empty() {
+        let _: () = redis::cmd("SET").arg(key).arg(value).query(&mut cnxn)?;
+    }
+
+    let position: i64 = redis::cmd("BITPOS").arg(key).arg(args).query(&mut cnxn)?;
+
+    assert_eq!(expected_position, position);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("AND", vec!["BitopSrc1", "BitopSrc2"], "`bc`ab")]
+#[case("OR", vec!["BitopSrc1", "BitopSrc2"], "goofev")]
+#[case("XOR", vec!["BitopSrc1", "BitopSrc2"], "\u{7}\r\u{c}\u{6}\u{4}\u{14}")]
+fn test_bitop(
+    #[case] operation: &str,
+    #[case] keys: Vec<&str>,
+    #[case] expected_value: &str,
+    mut cnxn: Connection,
+) -> RedisResult<()> {
+    let _: String = redis::cmd("SET")
+        .arg("BitopSrc1")
+        .arg("foobar")
+        .query(&mut cnxn)?;
+    let _: String = redis::cmd("SET")
+        .arg("BitopSrc2")
+        .arg("abcdef")
+        .query(&mut cnxn)?;
+
+    let destkey = format!("BitopDest{}", operation);
+    let length: i64 = redis::cmd("BITOP")
+        .arg(operation)
+        .arg(&destkey)
+        .arg(keys)
+        .query(&mut cnxn)?;
+
+    assert_eq!(6, length);
+
+    let value: String = redis::cmd("GET").arg(&destkey).query(&mut cnxn)?;
+    assert_eq!(expected_value, value);
+
+    Ok(())
+}
+
+#[rstest]
+// Signed and unsigned reads of a written byte
+#[case(
+    "BitfieldKey1",
+    vec!["SET", "u8", "0", "200", "GET", "u8", "0", "GET", "i8", "0"],
+    vec![Some(0), Some(200), Some(-56)]
+)]
+// Wrapping increments
+#[case(
+    "BitfieldKey2",
+    vec!["INCRBY", "u2", "100", "1", "INCRBY", "u2", "100", "3"],
+    vec![Some(1), Some(0)]
+)]
+// Saturating and failing increments
+#[case(
+    "BitfieldKey3",
+    vec!["OVERFLOW", "SAT", "INCRBY", "i8", "#1", "200", "OVERFLOW", "FAIL", "INCRBY", "i8", "#1", "1"],
+    vec![Some(127), None]
+)]
+fn test_bitfield(
+    #[case] key: &str,
+    #[case] args: Vec<&str>,
+    #[case] expected_values: Vec<Option<i64>>,
+    mut cnxn: Connection,
+) -> RedisResult<()> {
+    let values: Vec<Option<i64>> = redis::cmd("BITFIELD").arg(key).arg(args).query(&mut cnxn)?;
+
+    assert_eq!(expected_values, values);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_bitmaps_round_trip_high_bits(mut cnxn: Connection) -> RedisResult<()> {
+    // The first bit is the high bit of the first byte
+    let _: i64 = redis::cmd("SETBIT")
+        .arg(&["HighBitKey1", "0", "1"])
+        .query(&mut cnxn)?;
+    let value: Vec<u8> = redis::cmd("GET").arg("HighBitKey1").query(&mut cnxn)?;
+    assert_eq!(vec![0x80], value);
+
+    // The string is grown with zero bytes
+    let _: i64 = redis::cmd("SETBIT")
+        .arg(&["HighBitKey2", "96", "1"])
+        .query(&mut cnxn)?;
+    let value: Vec<u8> = redis::cmd("GET").arg("HighBitKey2").query(&mut cnxn)?;
+    assert_eq!([vec![0; 12], vec![0x80]].concat(), value);
+    let position: i64 = redis::cmd("BITPOS")
+        .arg(&["HighBitKey2", "1"])
+        .query(&mut cnxn)?;
+    assert_eq!(96, position);
+
+    let _: () = redis::cmd("SET")
+        .arg(&["HighBitKey3", "abc"])
+        .query(&mut cnxn)?;
+    let length: i64 = redis::cmd("BITOP")
+        .arg(&["NOT", "HighBitKey4", "HighBitKey3"])
+        .query(&mut cnxn)?;
+    assert_eq!(3, length);
+    let value: Vec<u8> = redis::cmd("GET").arg("HighBitKey4").query(&mut cnxn)?;
+    assert_eq!(vec![!b'a', !b'b', !b'c'], value);
+
+    // Inverting the bytes again gives back the string
+    let _: i64 = redis::cmd("BITOP")
+        .arg(&["NOT", "HighBitKey5", "HighBitKey4"])
+        .query(&mut cnxn)?;
+    let value: String = redis::cmd("GET").arg("HighBitKey5").query(&mut cnxn)?;
+    assert_eq!("abc", value);
+
+    let values: Vec<i64> = redis::cmd("BITFIELD")
+        .arg(&["HighBitKey6", "INCRBY", "i64", "0", "9223372036854775807"])
+        .arg(&["GET", "i64", "0"])
+        .query(&mut cnxn)?;
+    assert_eq!(vec![i64::MAX, i64::MAX], values);
+    let value: Vec<u8> = redis::cmd("GET").arg("HighBitKey6").query(&mut cnxn)?;
+    assert_eq!(i64::MAX.to_be_bytes().to_vec(), value);
+
+    // The bits of a text string are those of its UTF-8 bytes, 0xC3 0xA9
+    let _: () = redis::cmd("SET")
+        .arg("HighBitKey7")
+        .arg("é")
+        .query(&mut cnxn)?;
+    let count: i64 = redis::cmd("BITCOUNT").arg("HighBitKey7").query(&mut cnxn)?;
+    assert_eq!(8, count);
+    let _: i64 = redis::cmd("SETBIT")
+        .arg(&["HighBitKey7", "0", "0"])
+        .query(&mut cnxn)?;
+    let value: Vec<u8> = redis::cmd("GET").arg("HighBitKey7").query(&mut cnxn)?;
+    assert_eq!(vec![0x43, 0xA9], value);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_bitfield_ro_rejects_writes(mut cnxn: Connection) -> RedisResult<()> {
+    let response: Result<Vec<i64>, redis::RedisError> = redis::cmd("BITFIELD_RO")
+        .arg("BitfieldRoKey")
+        .arg(&["SET", "u8", "0", "1"])
+        .query(&mut cnxn);
+
+    assert!(response
+        .err()
+        .unwrap()
+        .to_string()
+        .ends_with("BITFIELD_RO only supports the GET subcommand"));
+
+    Ok(())
+}
+
+#[rstest]
+#[case(u64::MAX.to_string())]
+#[case(i64::MAX.to_string())]
+#[case(format!("#{}", u64::MAX / 8))]
+fn test_bitfield_rejects_huge_offsets(
+    #[case] offset: String,
+    mut cnxn: Connection,
+) -> RedisResult<()> {
+    let response: RedisResult<Vec<i64>> = redis::cmd("BITFIELD")
+        .arg("BitfieldHugeKey")
+        .arg(&["SET", "u8", &offset, "1"])
+        .query(&mut cnxn);
+
+    assert!(response
+        .err()
+        .unwrap()
+        .to_string()
+        .ends_with("bit offset is not an integer or out of range"));
+
+    // The shard of the key is still usable
+    let values: Vec<i64> = redis::cmd("BITFIELD")
+        .arg("BitfieldHugeKey")
+        .arg(&["GET", "u8", "0"])
+        .query(&mut cnxn)?;
+    assert_eq!(vec![0], values);
+
+    Ok(())
+}
+
+#[fixture]
+fn sicily(#[default("Sicily")] key: &str) -> String {
+    let mut cnxn = cnxn();
+
+    let _: i64 = redis::cmd("GEOADD")
+        .arg(key)
+        .arg(&["13.361389", "38.115556", "Palermo"])
+        .arg(&["15.087269", "37.502669", "Catania"])
+        .arg(&["12.758489", "38.788135", "edge1"])
+        .arg(&["17.241510", "38.788135", "edge2"])
+        .query(&mut cnxn)
+        .unwrap();
+
+    key.to_string()
+}
+
+#[rstest]
+// Adding existing members
+#[case("GeoaddKey1", vec!["13.361389", "38.115556", "Palermo"], Ok(0))]
+// Adding new members
+#[case("GeoaddKey2", vec!["15.554", "38.193", "Messina"], Ok(1))]
+// With CH, updating an existing member
+#[case("GeoaddKey3", vec!["CH", "13.361389", "38.5", "Palermo"], Ok(1))]
+// With XX, new members are not added
+#[case("GeoaddKey4", vec!["XX", "15.554", "38.193", "Messina"], Ok(0))]
+// With NX and XX
+#[case("GeoaddKey5", vec!["NX", "XX", "15.087269", "37.502669", "Catania"], Err(String::from("XX and NX options at the same time are not compatible")))]
+// Invalid latitude
+#[case("GeoaddKey6", vec!["13.361389", "86.0", "Palermo"], Err(String::from("invalid longitude,latitude pair 13.361389,86.000000")))]
+fn test_geoadd(
+    #[case] key: &str,
+    #[case] args: Vec<&str>,
+    #[case] expected_response: Result<i64, String>,
+    mut cnxn: Connection,
+) -> RedisResult<()> {
+    let sicily = sicily::get(key);
+    let actual_response: Result<i64, redis::RedisError> =
+        redis::cmd("GEOADD").arg(&sicily).arg(args).query(&mut cnxn);
+
+    match (expected_response, actual_response) {
+        (Ok(expected), Ok(actual)) => assert_eq!(expected, actual),
+        (Err(expected_err), Err(actual_err)) => {
+            assert!(actual_err.to_string().ends_with(expected_err.as_str()))
+        }
+        _ => panic!("Expected and actual responses do not match."),
+    }
+
+    Ok(())
+}
+
+#[rstest]
+fn test_geopos_geodist_and_geohash(sicily: String, mut cnxn: Connection) -> RedisResult<()> {
+    let positions: Vec<Option<(f64, f64)>> = redis::cmd("GEOPOS")
+        .arg(&sicily)
+        .arg(&["Palermo", "Missing"])
+        .query(&mut cnxn)?;
+
+    let (longitude, latitude) = positions[0].unwrap();
+    assert!((longitude - 13.361389).abs() < 0.00001);
+    assert!((latitude - 38.115556).abs() < 0.00001);
+    assert_eq!(None, positions[1]);
+
+    let distance: String = redis::cmd("GEODIST")
+        .arg(&sicily)
+        .arg(&["Palermo", "Catania", "km"])
+        .query(&mut cnxn)?;
+    assert_eq!("166.2742", distance);
+
+    let hashes: Vec<Option<String>> = redis::cmd("GEOHASH")
+        .arg(&sicily)
+        .arg(&["Palermo", "Catania", "Missing"])
+        .query(&mut cnxn)?;
+    assert_eq!(
+        vec![
+            Some("sqc8b49rny0".to_string()),
+            Some("sqdtr74hyu0".to_string()),
+            None
+        ],
+        hashes
+    );
+
+    Ok(())
+}
+
+#[rstest]
+// Radius around a position, sorted by distance
+#[case(vec!["FROMLONLAT", "15", "37", "BYRADIUS", "200", "km", "ASC"], vec!["Catania", "Palermo"])]
+// Radius around a member, in descending order
+#[case(vec!["FROMMEMBER", "Catania", "BYRADIUS", "200", "km", "DESC"], vec!["Palermo", "Catania"])]
+// Box around a position, limited by COUNT
+#[case(vec!["FROMLONLAT", "15", "37", "BYBOX", "400", "400", "km", "COUNT", "3"], vec!["Catania", "Palermo", "edge2"])]
+fn test_geosearch(
+    #[case] args: Vec<&str>,
+    #[case] expected_members: Vec<&str>,
+    sicily: String,
+    mut cnxn: Connection,
+) -> RedisResult<()> {
+    let members: Vec<String> = redis::cmd("GEOSEARCH")
+        .arg(&sicily)
+        .arg(args)
+        .query(&mut cnxn)?;
+
+    assert_eq!(expected_members, members);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_geosearch_withdist(sicily: String, mut cnxn: Connection) -> RedisResult<()> {
+    let results: Vec<Vec<String>> = redis::cmd("GEOSEARCH")
+        .arg(&sicily)
+        .arg(&[
+            "FROMLONLAT",
+            "15",
+            "37",
+            "BYRADIUS",
+            "200",
+            "km",
+            "ASC",
+            "WITHDIST",
+        ])
+        .query(&mut cnxn)?;
+
+    assert_eq!(
+        vec![
+            vec!["Catania".to_string(), "56.4413".to_string()],
+            vec!["Palermo".to_string(), "190.4424".to_string()]
+        ],
+        results
+    );
+
+    Ok(())
+}
+
+#[rstest]
+fn test_geosearchstore(sicily: String, mut cnxn: Connection) -> RedisResult<()> {
+    let stored: i64 = redis::cmd("GEOSEARCHSTORE")
+        .arg("SicilyNearby")
+        .arg(&sicily)
+        .arg(&["FROMLONLAT", "15", "37", "BYRADIUS", "100", "km"])
+        .query(&mut cnxn)?;
+    assert_eq!(1, stored);
+
+    let members: Vec<String> = redis::cmd("GEOSEARCH")
+        .arg("SicilyNearby")
+        .arg(&["FROMLONLAT", "15", "37", "BYRADIUS", "500", "km"])
+        .query(&mut cnxn)?;
+    assert_eq!(vec!["Catania".to_string()], members);
+
+    Ok(())
+}
+
+#[rstest]
+// Integer strings are encoded as int
+#[case("ObjectKey1", "12345", "int")]
+// Short strings are embedded
+#[case("ObjectKey2", "Hello, World!", "embstr")]
+// Strings longer than 44 bytes are raw
+#[case(
+    "ObjectKey3",
+    "A string which is definitely longer than forty four bytes",
+    "raw"
+)]
+fn test_object_encoding(
+    #[case] key: &str,
+    #[case] value: &str,
+    #[case] expected: &str,
+    mut cnxn: Connection,
+) -> RedisResult<()> {
+    let _: () = redis::cmd("SET").arg(key).arg(value).query(&mut cnxn)?;
+
+    let encoding: String = redis::cmd("OBJECT")
+        .arg("ENCODING")
+        .arg(key)
+        .query(&mut cnxn)?;
+    assert_eq!(expected, encoding);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_object_details_of_list(mut cnxn: Connection) -> RedisResult<()> {
+    let _: () = redis::cmd("DEL").arg("ObjectList").query(&mut cnxn)?;
+    let _: () = redis::cmd("LPUSH")
+        .arg("ObjectList")
+        .arg(&["a", "b", "c"])
+        .query(&mut cnxn)?;
+
+    let encoding: String = redis::cmd("OBJECT")
+        .arg(&["ENCODING", "ObjectList"])
+        .query(&mut cnxn)?;
+    assert_eq!("linkedlist", encoding);
+
+    let refcount: i64 = redis::cmd("OBJECT")
+        .arg(&["REFCOUNT", "ObjectList"])
+        .query(&mut cnxn)?;
+    assert_eq!(1, refcount);
+
+    let idletime: i64 = redis::cmd("OBJECT")
+        .arg(&["IDLETIME", "ObjectList"])
+        .query(&mut cnxn)?;
+    assert!(idletime <= 1);
+
+    // A new key starts with the initial LFU counter, and is bumped on access
+    let freq: i64 = redis::cmd("OBJECT")
+        .arg(&["FREQ", "ObjectList"])
+        .query(&mut cnxn)?;
+    assert!(freq >= 5);
+
+    let missing: Option<String> = redis::cmd("OBJECT")
+        .arg(&["ENCODING", "ObjectMissingKey"])
+        .query(&mut cnxn)?;
+    assert_eq!(None, missing);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_memory_usage(mut cnxn: Connection) -> RedisResult<()> {
+    let _: () = redis::cmd("SET")
+        .arg("MemoryKey")
+        .arg("Hello, World!")
+        .query(&mut cnxn)?;
+
+    let usage: i64 = redis::cmd("MEMORY")
+        .arg(&["USAGE", "MemoryKey"])
+        .query(&mut cnxn)?;
+    assert!(usage > "MemoryKey".len() as i64 + "Hello, World!".len() as i64);
+
+    let sampled: i64 = redis::cmd("MEMORY")
+        .arg(&["USAGE", "MemoryKey", "SAMPLES", "0"])
+        .query(&mut cnxn)?;
+    assert_eq!(usage, sampled);
+
+    let missing: Option<i64> = redis::cmd("MEMORY")
+        .arg(&["USAGE", "MemoryMissingKey"])
+        .query(&mut cnxn)?;
+    assert_eq!(None, missing);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_memory_stats_and_doctor(mut cnxn: Connection) -> RedisResult<()> {
+    let _: () = redis::cmd("SET")
+        .arg("MemoryStatsKey")
+        .arg("value")
+        .query(&mut cnxn)?;
+
+    let stats: Vec<redis::Value> = redis::cmd("MEMORY").arg("STATS").query(&mut cnxn)?;
+    let names: Vec<String> = stats
+        .iter()
+        .step_by(2)
+        .map(|name| redis::from_redis_value(name).unwrap())
+        .collect();
+    assert!(names.contains(&"total.allocated".to_string()));
+    assert!(names.contains(&"keys.count".to_string()));
+
+    let report: String = redis::cmd("MEMORY").arg("DOCTOR").query(&mut cnxn)?;
+    assert!(!report.is_empty());
+
+    Ok(())
+}
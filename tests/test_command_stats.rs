@@ -0,0 +1,202 @@
+mod common;
+
+use common::{connect, start_server};
+use redis::{RedisResult, Value};
+use redust::histogram::LatencyHistogram;
+use std::collections::HashMap;
+
+/// The fields of the INFO `section`, by name
+fn info(cnxn: &mut redis::Connection, section: &str) -> HashMap<String, String> {
+    let text: String = redis::cmd("INFO").arg(section).query(cnxn).unwrap();
+
+    text.lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+/// The counters of a `cmdstat_<command>` line, by name
+fn command_stats(cnxn: &mut redis::Connection, command: &str) -> HashMap<String, String> {
+    let line = info(cnxn, "commandstats")
+        .remove(&format!("cmdstat_{}", command))
+        .unwrap_or_else(|| panic!("No stats for {}", command));
+
+    line.split(',')
+        .filter_map(|counter| counter.split_once('='))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_commandstats() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut cnxn)?;
+    let _: () = redis::cmd("LPUSH").arg(&["List", "a"]).query(&mut cnxn)?;
+    let _: String = redis::cmd("GET").arg("Key").query(&mut cnxn)?;
+
+    // Errors of the execution fail the call, those of the arguments reject it
+    assert!(redis::cmd("GET")
+        .arg("List")
+        .query::<Value>(&mut cnxn)
+        .is_err());
+    assert!(redis::cmd("INCR")
+        .arg("Key")
+        .query::<Value>(&mut cnxn)
+        .is_err());
+    assert!(redis::cmd("GET").query::<Value>(&mut cnxn).is_err());
+
+    let stats = command_stats(&mut cnxn, "get");
+    assert_eq!("2", stats["calls"]);
+    assert_eq!("1", stats["failed_calls"]);
+    assert_eq!("1", stats["rejected_calls"]);
+    assert!(stats.contains_key("usec"));
+    assert!(stats.contains_key("usec_per_call"));
+
+    let stats = command_stats(&mut cnxn, "incr");
+    assert_eq!("1", stats["calls"]);
+    assert_eq!("1", stats["failed_calls"]);
+    assert_eq!("0", stats["rejected_calls"]);
+
+    let stats = command_stats(&mut cnxn, "set");
+    assert_eq!("1", stats["calls"]);
+    assert_eq!("0", stats["failed_calls"]);
+
+    // Calls denied by the ACL are rejected
+    let mut admin = connect(port);
+    let _: () = redis::cmd("ACL")
+        .arg(&["SETUSER", "alice", "on", ">pw", "~*", "+get"])
+        .query(&mut admin)?;
+    let _: () = redis::cmd("AUTH").arg(&["alice", "pw"]).query(&mut cnxn)?;
+    let err = redis::cmd("DEL")
+        .arg("Key")
+        .query::<Value>(&mut cnxn)
+        .unwrap_err();
+    assert_eq!(Some("NOPERM"), err.code());
+
+    let stats = command_stats(&mut admin, "del");
+    assert_eq!("0", stats["calls"]);
+    assert_eq!("1", stats["rejected_calls"]);
+
+    // The commands stats aren't part of the default sections
+    let text: String = redis::cmd("INFO").query(&mut admin)?;
+    assert!(!text.contains("# Commandstats"));
+    let text: String = redis::cmd("INFO").arg("everything").query(&mut admin)?;
+    assert!(text.contains("# Commandstats"));
+    assert!(text.contains("# Latencystats"));
+
+    let _: () = redis::cmd("CONFIG").arg("RESETSTAT").query(&mut admin)?;
+    let stats = info(&mut admin, "commandstats");
+    assert!(!stats.contains_key("cmdstat_get"));
+    assert!(!stats.contains_key("cmdstat_del"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_latencystats() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    for _ in 0..10 {
+        let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut cnxn)?;
+    }
+
+    let line = info(&mut cnxn, "latencystats")
+        .remove("latency_percentiles_usec_set")
+        .unwrap();
+    let labels: Vec<&str> = line
+        .split(',')
+        .filter_map(|percentile| percentile.split_once('='))
+        .map(|(label, _)| label)
+        .collect();
+    assert_eq!(vec!["p50", "p99", "p99.9"], labels);
+
+    let _: () = redis::cmd("CONFIG")
+        .arg(&["SET", "latency-tracking-info-percentiles", "0 100"])
+        .query(&mut cnxn)?;
+    let reply: Vec<String> = redis::cmd("CONFIG")
+        .arg(&["GET", "latency-tracking-info-percentiles"])
+        .query(&mut cnxn)?;
+    assert_eq!("0 100", reply[1]);
+
+    let line = info(&mut cnxn, "latencystats")
+        .remove("latency_percentiles_usec_set")
+        .unwrap();
+    let values: Vec<f64> = line
+        .split(',')
+        .filter_map(|percentile| percentile.split_once('='))
+        .map(|(_, value)| value.parse().unwrap())
+        .collect();
+    assert_eq!(2, values.len());
+    assert!(values[0] <= values[1]);
+
+    assert!(redis::cmd("CONFIG")
+        .arg(&["SET", "latency-tracking-info-percentiles", "101"])
+        .query::<Value>(&mut cnxn)
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_latency_histogram() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    for _ in 0..3 {
+        let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut cnxn)?;
+    }
+    let _: Option<String> = redis::cmd("GET").arg("Key").query(&mut cnxn)?;
+
+    let reply: Vec<Value> = redis::cmd("LATENCY")
+        .arg(&["HISTOGRAM", "SET"])
+        .query(&mut cnxn)?;
+    assert_eq!(2, reply.len());
+    assert_eq!(Value::Data(b"set".to_vec()), reply[0]);
+
+    let report = match &reply[1] {
+        Value::Bulk(report) => report,
+        other => panic!("Unexpected report {:?}", other),
+    };
+    assert_eq!(Value::Data(b"calls".to_vec()), report[0]);
+    assert_eq!(Value::Int(3), report[1]);
+    assert_eq!(Value::Data(b"histogram_usec".to_vec()), report[2]);
+
+    // The counts are cumulative up to each power of two, ending with every call
+    let histogram = match &report[3] {
+        Value::Bulk(histogram) => histogram,
+        other => panic!("Unexpected histogram {:?}", other),
+    };
+    assert_eq!(Some(&Value::Int(3)), histogram.last());
+
+    let reply: Vec<Value> = redis::cmd("LATENCY").arg("HISTOGRAM").query(&mut cnxn)?;
+    assert!(reply.contains(&Value::Data(b"get".to_vec())));
+    assert!(reply.contains(&Value::Data(b"set".to_vec())));
+
+    Ok(())
+}
+
+#[test]
+fn test_latency_histogram_percentiles() {
+    let mut histogram = LatencyHistogram::new();
+    assert!(histogram.is_empty());
+    assert_eq!(0, histogram.value_at_percentile(50.0));
+
+    for value in 1..=1000 {
+        histogram.record(value);
+    }
+    assert_eq!(1000, histogram.len());
+
+    // The values are within 1/16 of the exact percentiles
+    for (percentile, exact) in [(50.0, 500), (99.0, 990), (100.0, 1000)] {
+        let value = histogram.value_at_percentile(percentile);
+        assert!(value >= exact && value <= exact + exact / 16, "{}", value);
+    }
+
+    let buckets = histogram.power_of_two_buckets();
+    assert_eq!(Some(&(1, 1)), buckets.first());
+    assert_eq!(Some(&(1024, 1000)), buckets.last());
+    assert!(buckets.windows(2).all(|pair| pair[1].0 == pair[0].0 * 2));
+}
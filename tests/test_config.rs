@@ -0,0 +1,430 @@
+mod common;
+
+use common::{connect, start_server_with_config};
+use redis::{Client, RedisResult, Value};
+use redust::config::CommandRenames;
+use redust::{config, server, Config};
+use std::io::Read;
+use std::net::{IpAddr, TcpStream, UdpSocket};
+use tokio::net::TcpListener;
+
+#[test]
+fn test_command_renames() {
+    let mut renames = CommandRenames::new();
+    renames.rename("CLIENT", "secret-client").unwrap();
+    renames.rename("replicaof", "").unwrap();
+    renames.rename("bitfield", "").unwrap();
+
+    assert_eq!(Some("client".to_string()), renames.resolve("SECRET-CLIENT"));
+    assert_eq!(None, renames.resolve("client"));
+    assert_eq!(Some("get".to_string()), renames.resolve("GET"));
+
+    // Aliases are disabled along with their command
+    assert_eq!(None, renames.resolve("replicaof"));
+    assert_eq!(None, renames.resolve("slaveof"));
+
+    // BITFIELD_RO is a command of its own, rather than an alias
+    assert_eq!(None, renames.resolve("bitfield"));
+    assert_eq!(
+        Some("bitfield_ro".to_string()),
+        renames.resolve("BITFIELD_RO")
+    );
+
+    assert!(renames.rename("nosuchcommand", "other").is_err());
+    assert!(renames.rename("info", "get").is_err());
+    assert!(renames.rename("info", "secret-client").is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_renamed_and_disabled_commands() -> RedisResult<()> {
+    let mut config = Config::default();
+    config.rename_commands.rename("set", "secret-set").unwrap();
+    config.rename_commands.rename("memory", "").unwrap();
+    config
+        .rename_commands
+        .rename("bitfield", "secret-bitfield")
+        .unwrap();
+
+    let port = start_server_with_config(config).await;
+    let mut cnxn = connect(port);
+
+    let err = redis::cmd("SET")
+        .arg(&["Key", "Value"])
+        .query::<Value>(&mut cnxn)
+        .unwrap_err();
+    assert_eq!(Some("unknown"), err.code());
+    assert_eq!(Some("command 'SET'"), err.detail());
+
+    let _: Value = redis::cmd("SECRET-SET")
+        .arg(&["Key", "Value"])
+        .query(&mut cnxn)?;
+    let value: String = redis::cmd("GET").arg("Key").query(&mut cnxn)?;
+    assert_eq!("Value", value);
+
+    let err = redis::cmd("MEMORY")
+        .arg("USAGE")
+        .arg("Key")
+        .query::<Value>(&mut cnxn)
+        .unwrap_err();
+    assert_eq!(Some("unknown"), err.code());
+
+    // Renaming BITFIELD leaves BITFIELD_RO as it is
+    let _: Value = redis::cmd("SECRET-BITFIELD")
+        .arg(&["Bits", "SET", "u8", "0", "7"])
+        .query(&mut cnxn)?;
+    let values: Vec<i64> = redis::cmd("BITFIELD_RO")
+        .arg(&["Bits", "GET", "u8", "0"])
+        .query(&mut cnxn)?;
+    assert_eq!(vec![7], values);
+
+    Ok(())
+}
+
+/// The address of a non-loopback interface of this host, if it has one
+fn external_ip() -> Option<IpAddr> {
+    // No packet is sent, connecting only selects the interface of the route
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("10.255.255.255:1").ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+
+    if ip.is_loopback() || ip.is_unspecified() {
+        None
+    } else {
+        Some(ip)
+    }
+}
+
+/// Start a server on all the interfaces, and return its port
+async fn start_public_server(config: Config) -> u16 {
+    let listener = TcpListener::bind("0.0.0.0:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        let _ = server::run_with_config(listener, config).await;
+    });
+
+    port
+}
+
+fn ping(ip: IpAddr, port: u16) -> RedisResult<Value> {
+    let client = Client::open(format!("redis://{}:{}/", ip, port))?;
+    let mut cnxn = client.get_connection()?;
+    redis::cmd("PING").query(&mut cnxn)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_protected_mode() {
+    let ip = match external_ip() {
+        Some(ip) => ip,
+        None => {
+            println!("Skipped, as this host has no external interface");
+            return;
+        }
+    };
+
+    // Without a password, only local clients are accepted
+    // The refused clients get an error, before the connection is closed
+    let port = start_public_server(Config::default()).await;
+    let mut reply = String::new();
+    TcpStream::connect((ip, port))
+        .unwrap()
+        .read_to_string(&mut reply)
+        .unwrap();
+    assert!(reply.starts_with("-DENIED Redust is running in protected mode"));
+    assert!(ping("127.0.0.1".parse().unwrap(), port).is_ok());
+
+    // With a password, other clients may connect and authenticate
+    let config = Config {
+        requirepass: Some("secret".to_string()),
+        ..Config::default()
+    };
+    let port = start_public_server(config).await;
+    let err = ping(ip, port).unwrap_err();
+    assert_eq!(Some("NOAUTH"), err.code());
+
+    let config = Config {
+        protected_mode: false,
+        ..Config::default()
+    };
+    let port = start_public_server(config).await;
+    assert!(ping(ip, port).is_ok());
+}
+
+#[test]
+fn test_config_file() {
+    let mut config = Config::default();
+    config
+        .load(
+            "# A comment, then an empty line\n\
+             \n\
+             port 7000\n\
+             bind 127.0.0.1 ::1\n\
+             requirepass \"with a space\"\n\
+             protected-mode no\n\
+             rename-command CLIENT \"\"\n\
+             maxmemory 2mb\n\
+             maxmemory-policy allkeys-lru\n\
+             hz 50\n\
+             active-expire-keys 40\n\
+             shards 4\n\
+             io-buffer-size 16kb\n\
+             cluster-enabled yes\n",
+        )
+        .unwrap();
+
+    assert_eq!(7000, config.port);
+    assert_eq!(vec!["127.0.0.1", "::1"], config.bind);
+    assert_eq!(Some("with a space".to_string()), config.requirepass);
+    assert!(!config.protected_mode);
+    assert_eq!(None, config.rename_commands.resolve("client"));
+    assert_eq!(2 * 1024 * 1024, config.maxmemory);
+    assert_eq!(redust::EvictionPolicy::AllKeysLru, config.maxmemory_policy);
+    assert_eq!((50, 40), (config.hz, config.active_expire_keys));
+    assert_eq!(4, config.shards);
+    assert_eq!(16 * 1024, config.io_buffer_size);
+    assert!(config.cluster_enabled);
+
+    // The errors name the line of the bad directive
+    let err = Config::default().load("port 7000\nhz 0\n").unwrap_err();
+    assert!(err.contains("line 2"));
+    assert!(Config::default().load("nosuchdirective yes").is_err());
+    assert!(Config::default().load("port 1 2").is_err());
+    assert!(Config::default().load("requirepass \"unbalanced").is_err());
+}
+
+#[test]
+fn test_config_file_include() {
+    let dir = std::env::temp_dir().join(format!("redust-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let included = dir.join("included.conf");
+    std::fs::write(&included, "port 7001\nrequirepass secret\n").unwrap();
+
+    let main = dir.join("redust.conf");
+    let text = format!("port 7000\ninclude {}\nshards 2\n", included.display());
+    std::fs::write(&main, text).unwrap();
+
+    // Later directives override the earlier ones, including those of the included file
+    let config = Config::from_file(&main).unwrap();
+    assert_eq!(7001, config.port);
+    assert_eq!(Some("secret".to_string()), config.requirepass);
+    assert_eq!(2, config.shards);
+
+    let err = Config::from_file(&dir.join("missing.conf")).unwrap_err();
+    assert!(err.contains("missing.conf"));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_parse_memory() {
+    assert_eq!(Ok(100), config::parse_memory("100"));
+    assert_eq!(Ok(1000), config::parse_memory("1k"));
+    assert_eq!(Ok(1024), config::parse_memory("1KB"));
+    assert_eq!(Ok(3 * 1024 * 1024 * 1024), config::parse_memory("3gb"));
+    assert!(config::parse_memory("1tb").is_err());
+    assert!(config::parse_memory("mb").is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_configured_server() -> RedisResult<()> {
+    let mut config = Config::default();
+    config
+        .load("hz 100\nactive-expire-keys 100\nshards 2\nio-buffer-size 64\n")
+        .unwrap();
+
+    let port = start_server_with_config(config).await;
+    let mut cnxn = connect(port);
+
+    // Requests larger than the read buffer are still read whole
+    let value = "x".repeat(1000);
+    let _: () = redis::cmd("SET").arg("Key").arg(&value).query(&mut cnxn)?;
+    let read: String = redis::cmd("GET").arg("Key").query(&mut cnxn)?;
+    assert_eq!(value, read);
+
+    // The expiry task runs every 10 ms
+    let _: () = redis::cmd("SET")
+        .arg(&["Temp", "Value", "PX", "20"])
+        .query(&mut cnxn)?;
+    common::wait_until(|| {
+        redis::cmd("OBJECT")
+            .arg(&["ENCODING", "Temp"])
+            .query::<Value>(&mut cnxn)
+            .is_ok_and(|value| value == Value::Nil)
+    })
+    .await;
+
+    Ok(())
+}
+
+/// CONFIG GET, as a list of names and values
+fn config_get(cnxn: &mut redis::Connection, patterns: &[&str]) -> RedisResult<Vec<String>> {
+    redis::cmd("CONFIG").arg("GET").arg(patterns).query(cnxn)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_config_get() -> RedisResult<()> {
+    let port = start_server_with_config(Config::default()).await;
+    let mut cnxn = connect(port);
+
+    assert_eq!(vec!["hz", "10"], config_get(&mut cnxn, &["hz"])?);
+    assert_eq!(
+        vec!["maxmemory", "0", "maxmemory-policy", "noeviction"],
+        config_get(&mut cnxn, &["MAXMEMORY*"])?
+    );
+
+    // Each parameter is reported once, even if it matches several patterns
+    let params = config_get(&mut cnxn, &["*", "port"])?;
+    assert_eq!(2 * config::PARAMETERS.len(), params.len());
+    assert!(params
+        .chunks(2)
+        .any(|pair| pair == ["port", &port.to_string()]));
+
+    assert!(config_get(&mut cnxn, &["nosuchparameter"])?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_config_set() -> RedisResult<()> {
+    let port = start_server_with_config(Config::default()).await;
+    let mut cnxn = connect(port);
+
+    let _: () = redis::cmd("CONFIG")
+        .arg(&[
+            "SET",
+            "hz",
+            "100",
+            "maxmemory",
+            "1mb",
+            "maxmemory-policy",
+            "allkeys-lru",
+        ])
+        .query(&mut cnxn)?;
+    assert_eq!(
+        vec![
+            "hz",
+            "100",
+            "maxmemory",
+            "1048576",
+            "maxmemory-policy",
+            "allkeys-lru"
+        ],
+        config_get(&mut cnxn, &["hz", "maxmemory*"])?
+    );
+
+    // Nothing is set, if any of the parameters can't be
+    let err = redis::cmd("CONFIG")
+        .arg(&["SET", "hz", "50", "port", "7000"])
+        .query::<()>(&mut cnxn)
+        .unwrap_err();
+    assert!(err.to_string().contains("can't set immutable config"));
+    assert_eq!(vec!["hz", "100"], config_get(&mut cnxn, &["hz"])?);
+
+    let err = redis::cmd("CONFIG")
+        .arg(&["SET", "hz", "0"])
+        .query::<()>(&mut cnxn)
+        .unwrap_err();
+    assert!(err.to_string().contains("argument 'hz'"));
+
+    let err = redis::cmd("CONFIG")
+        .arg(&["SET", "nosuchparameter", "yes"])
+        .query::<()>(&mut cnxn)
+        .unwrap_err();
+    assert!(err.to_string().contains("Unknown option"));
+
+    // The expiry runs more often right away
+    let _: () = redis::cmd("SET")
+        .arg(&["Temp", "Value", "PX", "20"])
+        .query(&mut cnxn)?;
+    common::wait_until(|| {
+        redis::cmd("OBJECT")
+            .arg(&["ENCODING", "Temp"])
+            .query::<Value>(&mut cnxn)
+            .is_ok_and(|value| value == Value::Nil)
+    })
+    .await;
+
+    // A password set at runtime is needed by new connections
+    let _: () = redis::cmd("CONFIG")
+        .arg(&["SET", "requirepass", "secret"])
+        .query(&mut cnxn)?;
+    let err = redis::cmd("GET")
+        .arg("Key")
+        .query::<Option<String>>(&mut connect(port))
+        .unwrap_err();
+    assert_eq!(Some("NOAUTH"), err.code());
+
+    Ok(())
+}
+
+#[test]
+fn test_config_rewrite() {
+    let text = "# The port\n\
+                port 7000\n\
+                \n\
+                rename-command CLIENT \"\"\n\
+                hz 20\n\
+                # A duplicate, which is removed\n\
+                hz 30\n";
+
+    let mut config = Config::default();
+    config.load(text).unwrap();
+    config.set("hz", &["50".to_string()]).unwrap();
+    config
+        .set("requirepass", &["with space".to_string()])
+        .unwrap();
+
+    let expected = "# The port\n\
+                    port 7000\n\
+                    \n\
+                    rename-command CLIENT \"\"\n\
+                    hz 50\n\
+                    # A duplicate, which is removed\n\
+                    # Generated by CONFIG REWRITE\n\
+                    requirepass \"with space\"\n";
+    let rewritten = config.rewrite(text);
+    assert_eq!(expected, rewritten);
+
+    // The rewritten file is read back as the same configuration
+    let mut reloaded = Config::default();
+    reloaded.load(&rewritten).unwrap();
+    assert_eq!(config, reloaded);
+
+    // Rewriting is idempotent
+    assert_eq!(rewritten, config.rewrite(&rewritten));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_config_rewrite_file() -> RedisResult<()> {
+    let path = std::env::temp_dir().join(format!("redust-rewrite-{}.conf", std::process::id()));
+    std::fs::write(&path, "# Tuned live\nhz 20\n").unwrap();
+
+    let port = start_server_with_config(Config::from_file(&path).unwrap()).await;
+    let mut cnxn = connect(port);
+
+    let _: () = redis::cmd("CONFIG")
+        .arg(&["SET", "hz", "40", "maxmemory", "100"])
+        .query(&mut cnxn)?;
+    let _: () = redis::cmd("CONFIG").arg("REWRITE").query(&mut cnxn)?;
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    let expected = format!(
+        "# Tuned live\nhz 40\n# Generated by CONFIG REWRITE\nport {}\nmaxmemory 100\n",
+        port
+    );
+    assert_eq!(expected, text);
+    let _ = std::fs::remove_file(&path);
+
+    // Without a config file, there is nothing to rewrite
+    let mut cnxn = connect(start_server_with_config(Config::default()).await);
+    let err = redis::cmd("CONFIG")
+        .arg("REWRITE")
+        .query::<()>(&mut cnxn)
+        .unwrap_err();
+    assert!(err.to_string().contains("without a config file"));
+
+    let _: () = redis::cmd("CONFIG").arg("RESETSTAT").query(&mut cnxn)?;
+
+    Ok(())
+}
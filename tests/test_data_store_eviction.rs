@@ -0,0 +1,102 @@
+use chrono::Duration;
+use redust::{DataType, EvictionPolicy, SharedStore, SharedStoreBase};
+use rstest::rstest;
+
+/// Insert `count` keys with a 100 byte value, and an optional expiry
+fn insert_keys(store: &SharedStore, prefix: &str, count: usize, duration: Option<Duration>) {
+    for i in 0..count {
+        store
+            .set(
+                format!("{}{}", prefix, i),
+                DataType::String("x".repeat(100)),
+                duration,
+                false,
+                false,
+            )
+            .unwrap();
+    }
+}
+
+#[rstest]
+#[case(EvictionPolicy::AllKeysLru)]
+#[case(EvictionPolicy::AllKeysLfu)]
+#[case(EvictionPolicy::AllKeysRandom)]
+#[tokio::test]
+async fn test_allkeys_eviction_frees_memory(#[case] policy: EvictionPolicy) {
+    let store = SharedStore::new();
+    insert_keys(&store, "Key", 100, None);
+
+    let maxmemory = store.used_memory() / 2;
+    store.set_maxmemory(maxmemory);
+    store.set_eviction_policy(policy);
+
+    assert!(store.evict_keys_if_needed().is_ok());
+    assert!(store.used_memory() <= maxmemory);
+
+    let keys: Vec<String> = (0..100).map(|i| format!("Key{}", i)).collect();
+    let remaining = store.exists(keys);
+    assert!(remaining > 0 && remaining < 100);
+}
+
+#[rstest]
+#[case(EvictionPolicy::VolatileLru)]
+#[case(EvictionPolicy::VolatileLfu)]
+#[case(EvictionPolicy::VolatileRandom)]
+#[case(EvictionPolicy::VolatileTtl)]
+#[tokio::test]
+async fn test_volatile_eviction_keeps_persistent_keys(#[case] policy: EvictionPolicy) {
+    let store = SharedStore::new();
+    insert_keys(&store, "Persistent", 50, None);
+    insert_keys(&store, "Volatile", 50, Some(Duration::seconds(60)));
+
+    let maxmemory = store.used_memory() * 3 / 4;
+    store.set_maxmemory(maxmemory);
+    store.set_eviction_policy(policy);
+
+    assert!(store.evict_keys_if_needed().is_ok());
+    assert!(store.used_memory() <= maxmemory);
+
+    let persistent: Vec<String> = (0..50).map(|i| format!("Persistent{}", i)).collect();
+    assert_eq!(50, store.exists(persistent));
+}
+
+#[rstest]
+// No keys are ever evicted
+#[case(EvictionPolicy::NoEviction)]
+// There are no keys with an expiry, to evict
+#[case(EvictionPolicy::VolatileLru)]
+#[tokio::test]
+async fn test_eviction_returns_oom_error(#[case] policy: EvictionPolicy) {
+    let store = SharedStore::new();
+    insert_keys(&store, "Key", 10, None);
+
+    store.set_maxmemory(1);
+    store.set_eviction_policy(policy);
+
+    let result = store.evict_keys_if_needed();
+    assert_eq!(
+        "OOM command not allowed when used memory > 'maxmemory'.",
+        result.err().unwrap().to_string()
+    );
+
+    let keys: Vec<String> = (0..10).map(|i| format!("Key{}", i)).collect();
+    assert_eq!(10, store.exists(keys));
+}
+
+#[tokio::test]
+async fn test_used_memory_is_released_on_delete() {
+    let store = SharedStore::new();
+    let initial = store.used_memory();
+
+    insert_keys(&store, "Key", 10, None);
+    store
+        .rpush("List".to_string(), vec!["a".repeat(100); 10])
+        .unwrap();
+    assert!(store.used_memory() > initial);
+
+    let mut keys: Vec<String> = (0..10).map(|i| format!("Key{}", i)).collect();
+    keys.push("List".to_string());
+    store.del(keys);
+
+    assert_eq!(initial, store.used_memory());
+}
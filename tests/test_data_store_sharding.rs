@@ -0,0 +1,154 @@
+use chrono::Duration;
+use redust::cmd::BitOperation;
+use redust::{DataType, SharedStore, SharedStoreBase};
+use std::sync::Arc;
+use std::thread;
+
+fn set(store: &SharedStore, key: &str, value: &str) {
+    store
+        .set(
+            key.to_string(),
+            DataType::String(value.to_string()),
+            None,
+            false,
+            false,
+        )
+        .unwrap();
+}
+
+fn keys(prefix: &str, count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("{}{}", prefix, i)).collect()
+}
+
+#[tokio::test]
+async fn test_multi_key_commands_across_shards() {
+    let store = SharedStore::with_shards(8);
+    assert_eq!(8, store.num_shards());
+
+    for key in keys("Key", 100) {
+        set(&store, &key, "1");
+    }
+
+    let mut requested = keys("Key", 100);
+    requested.push("Missing".to_string());
+    assert_eq!(100, store.exists(requested.clone()));
+
+    assert_eq!(50, store.del(keys("Key", 50)));
+    assert_eq!(50, store.exists(requested.clone()));
+    assert_eq!(50, store.del(requested.clone()));
+    assert_eq!(0, store.exists(requested));
+
+    assert_eq!(0, store.memory_stats().keys);
+    assert_eq!(0, store.used_memory());
+}
+
+#[tokio::test]
+async fn test_bitop_across_shards() {
+    let store = SharedStore::with_shards(8);
+
+    set(&store, "a", "abc");
+    set(&store, "b", "ABC");
+
+    let length = store
+        .bitop(
+            BitOperation::Xor,
+            "dest".to_string(),
+            vec!["a".to_string(), "b".to_string()],
+        )
+        .unwrap();
+
+    assert_eq!(3, length);
+    assert_eq!(
+        Some(DataType::String("   ".to_string())),
+        store.get("dest".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_snapshot_and_stats_cover_all_shards() {
+    let store = SharedStore::with_shards(4);
+
+    for key in keys("Key", 20) {
+        set(&store, &key, "value");
+    }
+    store
+        .set(
+            "Volatile".to_string(),
+            DataType::String("value".to_string()),
+            Some(Duration::seconds(60)),
+            false,
+            false,
+        )
+        .unwrap();
+
+    let stats = store.memory_stats();
+    assert_eq!(21, stats.keys);
+    assert_eq!(1, stats.expires);
+    assert_eq!(store.used_memory(), stats.used_memory);
+    assert!(stats.peak_memory >= stats.used_memory);
+
+    // A snapshot can be loaded into a store with a different number of shards
+    let copy = SharedStore::with_shards(3);
+    copy.load_snapshot(store.snapshot()).unwrap();
+
+    assert_eq!(21, copy.memory_stats().keys);
+    assert_eq!(store.used_memory(), copy.used_memory());
+    assert_eq!(
+        Some(DataType::String("value".to_string())),
+        copy.get("Key7".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_each_shard_expires_its_keys() {
+    let store = SharedStore::with_shards(4);
+
+    for key in keys("Key", 40) {
+        store
+            .set(
+                key,
+                DataType::String("value".to_string()),
+                Some(Duration::milliseconds(50)),
+                false,
+                false,
+            )
+            .unwrap();
+    }
+
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let stats = store.memory_stats();
+    assert_eq!(0, stats.keys);
+    assert_eq!(0, stats.expires);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_concurrent_multi_key_commands_dont_deadlock() {
+    let store = Arc::new(SharedStore::with_shards(4));
+
+    let handles: Vec<_> = (0..8)
+        .map(|thread_id| {
+            let store = store.clone();
+
+            thread::spawn(move || {
+                for round in 0..500 {
+                    let mut batch = keys("Key", 16);
+                    // Lock the shards in a different order of keys, in each thread
+                    batch.rotate_left((thread_id + round) % 16);
+
+                    for key in batch.iter().take(4) {
+                        set(&store, key, "1");
+                    }
+                    store.exists(batch.clone());
+                    store.del(batch);
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(0, store.exists(keys("Key", 16)));
+}
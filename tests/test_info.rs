@@ -0,0 +1,157 @@
+mod common;
+
+use common::{connect, start_server, start_server_with_config, wait_until};
+use redis::RedisResult;
+use redust::Config;
+use std::collections::HashMap;
+
+/// The fields of the INFO `section`, by name
+fn info(cnxn: &mut redis::Connection, section: &str) -> HashMap<String, String> {
+    let text: String = redis::cmd("INFO").arg(section).query(cnxn).unwrap();
+
+    text.lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+fn field(cnxn: &mut redis::Connection, section: &str, name: &str) -> String {
+    info(cnxn, section)
+        .remove(name)
+        .unwrap_or_else(|| panic!("No field {} in section {}", name, section))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_info_sections() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    let text: String = redis::cmd("INFO").query(&mut cnxn)?;
+    let headers: Vec<&str> = text.lines().filter(|line| line.starts_with('#')).collect();
+    assert_eq!(
+        vec![
+            "# Server",
+            "# Clients",
+            "# Memory",
+            "# Stats",
+            "# Replication",
+            "# Cluster",
+            "# Keyspace"
+        ],
+        headers
+    );
+
+    let server = info(&mut cnxn, "server");
+    assert_eq!(Some(&port.to_string()), server.get("tcp_port"));
+    assert_eq!(Some(&"standalone".to_string()), server.get("redust_mode"));
+    assert!(server.contains_key("uptime_in_seconds"));
+    assert!(server.contains_key("redust_version"));
+
+    // Only the requested sections are reported
+    let text: String = redis::cmd("INFO").arg("memory").query(&mut cnxn)?;
+    assert!(text.starts_with("# Memory\r\n"));
+    assert!(!text.contains("# Stats"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_info_clients_and_commands() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    let other = connect(port);
+    assert_eq!("2", field(&mut cnxn, "clients", "connected_clients"));
+
+    drop(other);
+    wait_until(|| field(&mut cnxn, "clients", "connected_clients") == "1").await;
+
+    let before: u64 = field(&mut cnxn, "stats", "total_commands_processed")
+        .parse()
+        .unwrap();
+    let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut cnxn)?;
+    let after: u64 = field(&mut cnxn, "stats", "total_commands_processed")
+        .parse()
+        .unwrap();
+
+    // The SET, and the INFO itself
+    assert_eq!(before + 2, after);
+    assert_eq!("2", field(&mut cnxn, "stats", "total_connections_received"));
+
+    // CONFIG RESETSTAT resets the totals, but not the connected clients
+    let _: () = redis::cmd("CONFIG").arg("RESETSTAT").query(&mut cnxn)?;
+    assert_eq!("0", field(&mut cnxn, "stats", "total_connections_received"));
+    assert_eq!("1", field(&mut cnxn, "clients", "connected_clients"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_info_keyspace() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    // The empty database isn't reported
+    assert!(info(&mut cnxn, "keyspace").is_empty());
+
+    let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut cnxn)?;
+    let _: () = redis::cmd("SET")
+        .arg(&["Temp", "Value", "EX", "100"])
+        .query(&mut cnxn)?;
+    let _: () = redis::cmd("SET")
+        .arg(&["Short", "Value", "PX", "1"])
+        .query(&mut cnxn)?;
+
+    let _: Option<String> = redis::cmd("GET").arg("Key").query(&mut cnxn)?;
+    let _: Option<String> = redis::cmd("GET").arg("Missing").query(&mut cnxn)?;
+    let _: Vec<String> = redis::cmd("LRANGE")
+        .arg(&["Missing", "0", "-1"])
+        .query(&mut cnxn)?;
+
+    // An expired key is removed on access, if the expiry task didn't already
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    let _: Option<String> = redis::cmd("GET").arg("Short").query(&mut cnxn)?;
+
+    let stats = info(&mut cnxn, "stats");
+    assert_eq!(Some(&"1".to_string()), stats.get("keyspace_hits"));
+    assert_eq!(Some(&"3".to_string()), stats.get("keyspace_misses"));
+    assert_eq!(Some(&"1".to_string()), stats.get("expired_keys"));
+
+    let db = field(&mut cnxn, "keyspace", "db0");
+    assert!(db.starts_with("keys=2,expires=1,avg_ttl="), "{}", db);
+    let avg_ttl: i64 = db.rsplit('=').next().unwrap().parse().unwrap();
+    assert!(avg_ttl > 90_000 && avg_ttl <= 100_000);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_info_memory_and_evictions() -> RedisResult<()> {
+    let mut config = Config::default();
+    config
+        .load("maxmemory 2kb\nmaxmemory-policy allkeys-lru\n")
+        .unwrap();
+
+    let port = start_server_with_config(config).await;
+    let mut cnxn = connect(port);
+
+    for i in 0..100 {
+        let _: () = redis::cmd("SET")
+            .arg(format!("Key{}", i))
+            .arg("x".repeat(100))
+            .query(&mut cnxn)?;
+    }
+
+    let memory = info(&mut cnxn, "memory");
+    assert_eq!(Some(&"2048".to_string()), memory.get("maxmemory"));
+    assert_eq!(Some(&"2.00K".to_string()), memory.get("maxmemory_human"));
+    assert_eq!(
+        Some(&"allkeys-lru".to_string()),
+        memory.get("maxmemory_policy")
+    );
+
+    let evicted: u64 = field(&mut cnxn, "stats", "evicted_keys").parse().unwrap();
+    assert!(evicted > 0);
+
+    Ok(())
+}
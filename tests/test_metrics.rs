@@ -0,0 +1,163 @@
+mod common;
+
+use common::connect;
+use redis::{RedisResult, Value};
+use redust::server::{self, Listeners};
+use redust::Config;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Start a server with a metrics listener, and return the client and metrics ports
+async fn start_server_with_metrics() -> (u16, u16) {
+    start_server_with_metrics_config(Config::default()).await
+}
+
+async fn start_server_with_metrics_config(config: Config) -> (u16, u16) {
+    let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let metrics = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let ports = (
+        tcp.local_addr().unwrap().port(),
+        metrics.local_addr().unwrap().port(),
+    );
+
+    let listeners = Listeners {
+        tcp: vec![tcp],
+        metrics: vec![metrics],
+        ..Listeners::default()
+    };
+
+    tokio::spawn(async move {
+        let _ = server::run_with_listeners(listeners, config).await;
+    });
+
+    ports
+}
+
+/// Send an HTTP request for `path`, and return the status line and the body
+async fn http_get(port: u16, method: &str, path: &str) -> (String, String) {
+    let mut socket = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let request = format!("{} {} HTTP/1.1\r\nHost: localhost\r\n\r\n", method, path);
+    socket.write_all(request.as_bytes()).await.unwrap();
+
+    // The server closes the connection after the response
+    let mut response = String::new();
+    socket.read_to_string(&mut response).await.unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.lines().next().unwrap().to_string();
+
+    (status, body.to_string())
+}
+
+/// The value of the sample `name`, with its labels
+fn sample(body: &str, name: &str) -> Option<f64> {
+    body.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.rsplit_once(' '))
+        .find(|(sample, _)| *sample == name)
+        .map(|(_, value)| value.parse().unwrap())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_metrics() -> RedisResult<()> {
+    let (port, metrics_port) = start_server_with_metrics().await;
+    let mut cnxn = connect(port);
+
+    let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut cnxn)?;
+    let _: () = redis::cmd("SET")
+        .arg(&["Other", "Value", "EX", "100"])
+        .query(&mut cnxn)?;
+    let _: String = redis::cmd("GET").arg("Key").query(&mut cnxn)?;
+    let _: Value = redis::cmd("INCR")
+        .arg("Key")
+        .query(&mut cnxn)
+        .unwrap_or(Value::Nil);
+    let _: Value = redis::cmd("NOSUCHCOMMAND")
+        .query(&mut cnxn)
+        .unwrap_or(Value::Nil);
+
+    let (status, body) = http_get(metrics_port, "GET", "/metrics").await;
+    assert_eq!("HTTP/1.1 200 OK", status);
+
+    assert!(body.contains("# TYPE redust_commands_total counter\n"));
+    assert_eq!(Some(1.0), sample(&body, "redust_connected_clients"));
+    assert_eq!(
+        Some(1.0),
+        sample(&body, "redust_connections_received_total")
+    );
+    assert_eq!(
+        Some(2.0),
+        sample(&body, "redust_commands_total{cmd=\"set\"}")
+    );
+    assert_eq!(
+        Some(1.0),
+        sample(&body, "redust_commands_total{cmd=\"get\"}")
+    );
+    assert_eq!(
+        Some(1.0),
+        sample(&body, "redust_commands_failed_calls_total{cmd=\"incr\"}")
+    );
+    assert!(sample(&body, "redust_commands_duration_seconds_total{cmd=\"set\"}").is_some());
+
+    // The unknown command and the failed INCR are both errors of type ERR,
+    // along with those of the commands which the client sends on connecting
+    assert!(sample(&body, "redust_errors_total{err=\"ERR\"}").unwrap() >= 2.0);
+
+    assert_eq!(Some(2.0), sample(&body, "redust_db_keys{db=\"db0\"}"));
+    assert_eq!(
+        Some(1.0),
+        sample(&body, "redust_db_keys_expiring{db=\"db0\"}")
+    );
+    assert_eq!(Some(1.0), sample(&body, "redust_keyspace_hits_total"));
+    assert_eq!(Some(0.0), sample(&body, "redust_expired_keys_total"));
+    assert_eq!(Some(0.0), sample(&body, "redust_evicted_keys_total"));
+    assert!(sample(&body, "redust_memory_used_bytes").unwrap() > 0.0);
+    assert!(sample(&body, "redust_expire_cycles_total").is_some());
+    assert!(sample(&body, "redust_expire_cycles_duration_seconds_total").is_some());
+
+    // The query string is ignored
+    let (status, _) = http_get(metrics_port, "GET", "/metrics?format=text").await;
+    assert_eq!("HTTP/1.1 200 OK", status);
+
+    let (status, _) = http_get(metrics_port, "GET", "/").await;
+    assert_eq!("HTTP/1.1 404 Not Found", status);
+
+    let (status, _) = http_get(metrics_port, "POST", "/metrics").await;
+    assert_eq!("HTTP/1.1 405 Method Not Allowed", status);
+
+    // The metrics port is reported by CONFIG GET
+    let reply: Vec<String> = redis::cmd("CONFIG")
+        .arg(&["GET", "metrics-port"])
+        .query(&mut cnxn)?;
+    assert_eq!(metrics_port.to_string(), reply[1]);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_metrics_clients_are_limited() {
+    let config = Config {
+        maxclients: 1,
+        ..Config::default()
+    };
+    let (_, metrics_port) = start_server_with_metrics_config(config).await;
+
+    // An idle scraper holds the only slot, until its request times out
+    let mut idle = TcpStream::connect(("127.0.0.1", metrics_port))
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    let (status, _) = http_get(metrics_port, "GET", "/metrics").await;
+    assert_eq!("HTTP/1.1 503 Service Unavailable", status);
+
+    let mut response = Vec::new();
+    let closed = idle.read_to_end(&mut response);
+    tokio::time::timeout(std::time::Duration::from_secs(10), closed)
+        .await
+        .expect("The idle connection wasn't closed")
+        .unwrap();
+
+    let (status, _) = http_get(metrics_port, "GET", "/metrics").await;
+    assert_eq!("HTTP/1.1 200 OK", status);
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use common::{connect, start_server};
+use redis::RedisResult;
+use redust::cli::command_frame;
+use redust::monitor::quote;
+use redust::{Connection, ConnectionBase, RESPType};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+/// Connect a monitor, once MONITOR was acknowledged
+async fn start_monitor(port: u16) -> Connection {
+    let socket = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let mut monitor = Connection::new(socket);
+
+    monitor
+        .write_frame(&command_frame(&["MONITOR".to_string()]))
+        .await
+        .unwrap();
+    assert_eq!(
+        Some(RESPType::SimpleString("OK".to_string())),
+        monitor.read_frame().await.unwrap()
+    );
+
+    monitor
+}
+
+/// The next line of the `monitor`, without its timestamp
+async fn next_line(monitor: &mut Connection) -> String {
+    let frame = timeout(Duration::from_secs(5), monitor.read_frame())
+        .await
+        .expect("No line was received in time")
+        .unwrap();
+
+    match frame {
+        Some(RESPType::SimpleString(line)) => {
+            let (timestamp, line) = line.split_once(' ').unwrap();
+            let (seconds, micros) = timestamp.split_once('.').unwrap();
+            assert!(seconds.parse::<i64>().is_ok());
+            assert_eq!(6, micros.len());
+
+            line.to_string()
+        }
+        other => panic!("Unexpected frame {:?}", other),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_monitor() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut monitor = start_monitor(port).await;
+
+    let mut cnxn = connect(port);
+    let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut cnxn)?;
+
+    // Skip the commands which the client sends on connecting
+    let mut line = next_line(&mut monitor).await;
+    while !line.ends_with("\"SET\" \"Key\" \"Value\"") {
+        line = next_line(&mut monitor).await;
+    }
+
+    // The address of the client, as seen by the server
+    let addr = line[3..line.find(']').unwrap()].to_string();
+    assert!(addr.starts_with("127.0.0.1:"));
+
+    // The arguments are quoted and escaped
+    let _: () = redis::cmd("SET")
+        .arg(&["Key", "a \"quoted\"\nvalue"])
+        .query(&mut cnxn)?;
+    assert_eq!(
+        format!("[0 {}] \"SET\" \"Key\" \"a \\\"quoted\\\"\\nvalue\"", addr),
+        next_line(&mut monitor).await
+    );
+
+    // AUTH and the administrative commands aren't fed to the monitors
+    let _: () = redis::cmd("AUTH")
+        .arg(&["default", "secret"])
+        .query(&mut cnxn)?;
+    let _: Vec<String> = redis::cmd("CONFIG")
+        .arg(&["GET", "port"])
+        .query(&mut cnxn)?;
+    let _: Option<String> = redis::cmd("GET").arg("Key").query(&mut cnxn)?;
+    assert_eq!(
+        format!("[0 {}] \"GET\" \"Key\"", addr),
+        next_line(&mut monitor).await
+    );
+
+    // Monitors can't run other commands
+    monitor
+        .write_frame(&command_frame(&["GET".to_string(), "Key".to_string()]))
+        .await
+        .unwrap();
+    assert!(matches!(
+        monitor.read_frame().await.unwrap(),
+        Some(RESPType::Error(_))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_slow_monitor_doesnt_block() -> RedisResult<()> {
+    let port = start_server().await;
+
+    // This monitor never reads its lines
+    let _monitor = start_monitor(port).await;
+
+    let value = "x".repeat(1024);
+    let mut cnxn = connect(port);
+    let mut pipe = redis::pipe();
+    for i in 0..10000 {
+        pipe.cmd("SET")
+            .arg(format!("key:{}", i))
+            .arg(&value)
+            .ignore();
+    }
+
+    let result = tokio::task::spawn_blocking(move || pipe.query::<()>(&mut cnxn));
+    timeout(Duration::from_secs(10), result)
+        .await
+        .expect("The writes were blocked by the monitor")
+        .unwrap()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_quote() {
+    assert_eq!("\"value\"", quote("value"));
+    assert_eq!("\"a b\"", quote("a b"));
+    assert_eq!("\"\\\"\\\\\"", quote("\"\\"));
+    assert_eq!("\"\\r\\n\\t\\x00\\xc3\\xa9\"", quote("\r\n\t\0é"));
+}
@@ -1,4 +1,8 @@
-use redust::protocol_handler::{deserialize_buffer, serialize_data, BulkStringData, RESPType};
+use redust::protocol_handler::{
+    deserialize_buffer, parse_frame, serialize_data, BulkStringData, ProtocolError, ProtocolLimits,
+    RESPType,
+};
+use redust::PROTO_MAX_NESTING;
 use rstest::rstest;
 
 #[rstest]
@@ -20,9 +24,11 @@ use rstest::rstest;
 #[case(b"$-1\r\n", (Some(RESPType::BulkString(None)), 5usize))]
 #[case(b"$4\r\ntest\r\n", (Some(RESPType::BulkString(Some(BulkStringData{text: "test".to_string(), prefix_length: 4}))), 10usize))]
 #[case(b"$4\r\ntest\r\n+Next", (Some(RESPType::BulkString(Some(BulkStringData{text: "test".to_string(), prefix_length: 4}))), 10usize))]
+#[case(b"$2\r\n\xff\xfe\r\n", (Some(RESPType::BulkBytes(vec![0xff, 0xfe])), 8usize))]
 // Array Test Cases
 #[case(b"*0", (None, 0usize))]
 #[case(b"*0\r\n", (Some(RESPType::Array(vec![])),4usize))]
+#[case(b"*-1\r\n", (Some(RESPType::Array(vec![])), 5usize))]
 #[case(b"*2\r\n$5\r\nhello\r\n$5\r\nworld\r\n", (Some(RESPType::Array(
 vec![
     RESPType::BulkString(Some(BulkStringData{text: "hello".to_string(), prefix_length: 5})),
@@ -68,6 +74,7 @@ fn deserialize_test(#[case] input: &[u8], #[case] expected: (Option<RESPType>, u
 #[case(RESPType::BulkString(Some(BulkStringData{text: "".to_string(), prefix_length: 0})), Some(b"$0\r\n\r\n".to_vec()))]
 #[case(RESPType::BulkString(None), Some(b"$-1\r\n".to_vec()))]
 #[case(RESPType::BulkString(Some(BulkStringData{text: "test".to_string(), prefix_length: 4})), Some(b"$4\r\ntest\r\n".to_vec()))]
+#[case(RESPType::BulkBytes(vec![0xff, 0x00]), Some(b"$2\r\n\xff\x00\r\n".to_vec()))]
 // Array Test Cases
 #[case(RESPType::Array(
     vec![
@@ -97,3 +104,74 @@ fn serialize_test(#[case] data: RESPType, #[case] expected: Option<Vec<u8>>) {
     let actual: Option<Vec<u8>> = serialize_data(&data);
     assert_eq!(expected, actual);
 }
+
+#[rstest]
+#[case(b"$-2\r\n", ProtocolError::InvalidBulkLength)]
+#[case(b"$4294967296\r\n", ProtocolError::InvalidBulkLength)]
+#[case(b"$abc\r\n", ProtocolError::InvalidBulkLength)]
+#[case(b"*2147483647\r\n", ProtocolError::InvalidMultibulkLength)]
+#[case(b"*-2\r\n", ProtocolError::InvalidMultibulkLength)]
+#[case(b"*1\r\n$4294967296\r\n", ProtocolError::InvalidBulkLength)]
+#[case(b":abc\r\n", ProtocolError::InvalidInteger)]
+#[case(b"PING\r\n", ProtocolError::UnexpectedByte(b'P'))]
+fn parse_frame_error_test(#[case] input: &[u8], #[case] expected: ProtocolError) {
+    assert_eq!(
+        Err(expected),
+        parse_frame(input, &ProtocolLimits::default())
+    );
+}
+
+#[test]
+fn parse_frame_limits_test() {
+    let limits = ProtocolLimits {
+        max_bulk_len: 4,
+        max_multibulk_len: 2,
+        max_inline_size: 8,
+    };
+
+    // The frames at the limits are parsed
+    assert!(parse_frame(b"$4\r\ntest\r\n", &limits).unwrap().0.is_some());
+    assert!(parse_frame(b"*2\r\n:1\r\n:2\r\n", &limits)
+        .unwrap()
+        .0
+        .is_some());
+    assert!(parse_frame(b"+1234567\r\n", &limits).unwrap().0.is_some());
+
+    // The lengths over them are refused before the data is received
+    assert_eq!(
+        Err(ProtocolError::InvalidBulkLength),
+        parse_frame(b"$5\r\n", &limits)
+    );
+    assert_eq!(
+        Err(ProtocolError::InvalidMultibulkLength),
+        parse_frame(b"*3\r\n", &limits)
+    );
+
+    // Lines, with their type, may end within the limit, but not after it
+    assert_eq!(Ok((None, 0)), parse_frame(b"+1234567\r", &limits));
+    assert_eq!(
+        Err(ProtocolError::TooBigInline),
+        parse_frame(b"+12345678\r", &limits)
+    );
+}
+
+#[test]
+fn parse_frame_nesting_test() {
+    let nested = |depth: usize| [b"*1\r\n".repeat(depth), b":1\r\n".to_vec()].concat();
+
+    // The arrays may be nested up to the limit
+    let input = nested(PROTO_MAX_NESTING);
+    let (frame, size) = parse_frame(&input, &ProtocolLimits::default()).unwrap();
+    assert!(frame.is_some());
+    assert_eq!(input.len(), size);
+
+    // Deeper arrays are refused, rather than overflowing the stack
+    assert_eq!(
+        Err(ProtocolError::TooDeepNesting),
+        parse_frame(&nested(PROTO_MAX_NESTING + 1), &ProtocolLimits::default())
+    );
+    assert_eq!(
+        Err(ProtocolError::TooDeepNesting),
+        parse_frame(&b"*1\r\n".repeat(200000), &ProtocolLimits::default())
+    );
+}
@@ -0,0 +1,337 @@
+mod common;
+
+use common::{connect, start_server, wait_until};
+use redis::{Connection, RedisResult};
+use redust::protocol_handler::BulkStringData;
+use redust::{ConnectionBase, RESPType};
+use tokio::net::TcpStream;
+
+fn info_field(cnxn: &mut Connection, name: &str) -> Option<String> {
+    let info: String = redis::cmd("INFO").arg("replication").query(cnxn).unwrap();
+
+    info.lines()
+        .find_map(|line| line.strip_prefix(&format!("{}:", name)))
+        .map(|value| value.to_string())
+}
+
+fn command(parts: &[&str]) -> RESPType {
+    RESPType::Array(
+        parts
+            .iter()
+            .map(|part| {
+                RESPType::BulkString(Some(BulkStringData {
+                    text: part.to_string(),
+                    prefix_length: part.len(),
+                }))
+            })
+            .collect(),
+    )
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_full_sync_followed_by_the_write_stream() -> RedisResult<()> {
+    let master_port = start_server().await;
+    let replica_port = start_server().await;
+
+    let mut master = connect(master_port);
+    let mut replica = connect(replica_port);
+
+    // Data written before the replica connects is part of the snapshot
+    let _: () = redis::cmd("SET")
+        .arg("Key")
+        .arg("Value")
+        .query(&mut master)?;
+    let _: () = redis::cmd("RPUSH")
+        .arg("List")
+        .arg(&["a", "b", "c"])
+        .query(&mut master)?;
+    let _: () = redis::cmd("GEOADD")
+        .arg("Sicily")
+        .arg(&["13.361389", "38.115556", "Palermo"])
+        .query(&mut master)?;
+    let _: i64 = redis::cmd("SETBIT")
+        .arg(&["Bitmap", "0", "1"])
+        .query(&mut master)?;
+
+    let reply: String = redis::cmd("REPLICAOF")
+        .arg("127.0.0.1")
+        .arg(master_port)
+        .query(&mut replica)?;
+    assert_eq!("OK", reply);
+
+    wait_until(|| info_field(&mut replica, "master_link_status") == Some("up".to_string())).await;
+
+    let value: Option<String> = redis::cmd("GET").arg("Key").query(&mut replica)?;
+    assert_eq!(Some("Value".to_string()), value);
+
+    let list: Vec<String> = redis::cmd("LRANGE")
+        .arg("List")
+        .arg(0)
+        .arg(-1)
+        .query(&mut replica)?;
+    assert_eq!(vec!["a", "b", "c"], list);
+
+    let hash: Vec<String> = redis::cmd("GEOHASH")
+        .arg("Sicily")
+        .arg("Palermo")
+        .query(&mut replica)?;
+    assert_eq!(vec!["sqc8b49rny0"], hash);
+
+    let bitmap: Vec<u8> = redis::cmd("GET").arg("Bitmap").query(&mut replica)?;
+    assert_eq!(vec![0x80], bitmap);
+
+    // Writes after the full sync are streamed, in order
+    for _ in 0..3 {
+        let _: i64 = redis::cmd("INCR").arg("Counter").query(&mut master)?;
+    }
+    let _: () = redis::cmd("DEL").arg("Key").query(&mut master)?;
+
+    wait_until(|| {
+        redis::cmd("GET")
+            .arg("Counter")
+            .query::<Option<String>>(&mut replica)
+            == Ok(Some("3".to_string()))
+    })
+    .await;
+
+    let value: Option<String> = redis::cmd("GET").arg("Key").query(&mut replica)?;
+    assert_eq!(None, value);
+
+    // Replicas are read-only
+    let result: RedisResult<()> = redis::cmd("SET").arg("Key").arg("1").query(&mut replica);
+    assert_eq!(redis::ErrorKind::ReadOnly, result.unwrap_err().kind());
+
+    // Both servers report the same offset, once the replica has acknowledged it
+    wait_until(|| {
+        let role: redis::Value = redis::cmd("ROLE").query(&mut master).unwrap();
+        let replicas: Vec<Vec<String>> = match role {
+            redis::Value::Bulk(parts) => redis::from_redis_value(&parts[2]).unwrap(),
+            _ => vec![],
+        };
+
+        replicas.len() == 1
+            && replicas[0][1] == replica_port.to_string()
+            && Some(replicas[0][2].clone()) == info_field(&mut master, "master_repl_offset")
+    })
+    .await;
+
+    let role: (String, String, i64, String, i64) = redis::cmd("ROLE").query(&mut replica)?;
+    assert_eq!("slave", role.0);
+    assert_eq!(master_port as i64, role.2);
+    assert_eq!("connected", role.3);
+    assert_eq!(
+        info_field(&mut master, "master_repl_offset"),
+        Some(role.4.to_string())
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_partial_resync_from_the_backlog() {
+    let master_port = start_server().await;
+    let mut master = connect(master_port);
+
+    let _: () = redis::cmd("SET")
+        .arg("Key")
+        .arg("Value")
+        .query(&mut master)
+        .unwrap();
+
+    // An unknown replication ID requires a full sync
+    let socket = TcpStream::connect(("127.0.0.1", master_port))
+        .await
+        .unwrap();
+    let mut link = redust::Connection::new(socket);
+    link.write_frame(&command(&["PSYNC", "?", "-1"]))
+        .await
+        .unwrap();
+
+    let reply = link.read_frame().await.unwrap().unwrap().to_string();
+    let parts: Vec<&str> = reply.split(' ').collect();
+    assert_eq!("FULLRESYNC", parts[0]);
+
+    let (replid, offset) = (parts[1].to_string(), parts[2].parse::<i64>().unwrap());
+
+    assert_eq!(Some(RESPType::Integer(1)), link.read_frame().await.unwrap());
+    let _entry = link.read_frame().await.unwrap();
+    drop(link);
+
+    // Writes while the replica is disconnected are kept in the backlog
+    let _: () = redis::cmd("SET")
+        .arg("Missed")
+        .arg("Write")
+        .query(&mut master)
+        .unwrap();
+
+    let socket = TcpStream::connect(("127.0.0.1", master_port))
+        .await
+        .unwrap();
+    let mut link = redust::Connection::new(socket);
+    link.write_frame(&command(&["PSYNC", &replid, &(offset + 1).to_string()]))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        Some(RESPType::SimpleString(format!("CONTINUE {}", replid))),
+        link.read_frame().await.unwrap()
+    );
+    assert_eq!(
+        Some(command(&["SET", "Missed", "Write"])),
+        link.read_frame().await.unwrap()
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_promoted_replica_accepts_writes() -> RedisResult<()> {
+    let master_port = start_server().await;
+    let replica_port = start_server().await;
+
+    let mut master = connect(master_port);
+    let mut replica = connect(replica_port);
+
+    let _: () = redis::cmd("REPLICAOF")
+        .arg("127.0.0.1")
+        .arg(master_port)
+        .query(&mut replica)?;
+
+    wait_until(|| info_field(&mut replica, "master_link_status") == Some("up".to_string())).await;
+
+    let reply: String = redis::cmd("REPLICAOF")
+        .arg("127.0.0.1")
+        .arg(master_port)
+        .query(&mut replica)?;
+    assert_eq!("OK Already connected to specified master", reply);
+
+    let _: () = redis::cmd("REPLICAOF")
+        .arg("NO")
+        .arg("ONE")
+        .query(&mut replica)?;
+
+    assert_eq!(Some("master".to_string()), info_field(&mut replica, "role"));
+
+    // The stream of the old master can still be continued, with the previous ID
+    assert_eq!(
+        info_field(&mut master, "master_replid"),
+        info_field(&mut replica, "master_replid2")
+    );
+
+    let _: () = redis::cmd("SET")
+        .arg("Key")
+        .arg("Value")
+        .query(&mut replica)?;
+    let value: Option<String> = redis::cmd("GET").arg("Key").query(&mut replica)?;
+    assert_eq!(Some("Value".to_string()), value);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_write_locks_of_other_keys_dont_block() {
+    use redust::{cluster::key_hash_slot, Replication, DEFAULT_NUM_SHARDS};
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    let replication = Replication::new();
+    let lock_index = |key: &str| key_hash_slot(key) as usize % DEFAULT_NUM_SHARDS;
+    assert_ne!(lock_index("a"), lock_index("b"));
+
+    let held = replication.lock_writes(&["a"]).await;
+
+    // A write on a key of another lock goes on, while writes on the same key,
+    // and the snapshots, wait for the held lock
+    let other = timeout(Duration::from_millis(100), replication.lock_writes(&["b"])).await;
+    assert!(other.is_ok());
+    drop(other);
+
+    let same = timeout(
+        Duration::from_millis(100),
+        replication.lock_writes(&["a", "b"]),
+    )
+    .await;
+    assert!(same.is_err());
+    let all = timeout(Duration::from_millis(100), replication.lock_all_writes()).await;
+    assert!(all.is_err());
+
+    drop(held);
+    let all = timeout(Duration::from_millis(100), replication.lock_all_writes()).await;
+    assert!(all.is_ok());
+}
+
+#[tokio::test]
+async fn test_write_locks_follow_the_number_of_shards() {
+    use redust::Replication;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    // With a single shard, all the keys share its write lock
+    let replication = Replication::with_shards(1);
+    let held = replication.lock_writes(&["a"]).await;
+
+    let other = timeout(Duration::from_millis(100), replication.lock_writes(&["b"])).await;
+    assert!(other.is_err());
+
+    drop(held);
+    let other = timeout(Duration::from_millis(100), replication.lock_writes(&["b"])).await;
+    assert!(other.is_ok());
+}
+
+/// The number of keys of the server, from INFO keyspace, which doesn't expire them
+fn key_count(cnxn: &mut Connection) -> usize {
+    let info: String = redis::cmd("INFO").arg("keyspace").query(cnxn).unwrap();
+
+    info.lines()
+        .find_map(|line| line.strip_prefix("db0:keys="))
+        .and_then(|fields| fields.split(',').next())
+        .map(|keys| keys.parse().unwrap())
+        .unwrap_or(0)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_expired_and_evicted_keys_are_deleted_on_replicas() -> RedisResult<()> {
+    let master_port = start_server().await;
+    let replica_port = start_server().await;
+
+    let mut master = connect(master_port);
+    let mut replica = connect(replica_port);
+
+    let _: () = redis::cmd("REPLICAOF")
+        .arg("127.0.0.1")
+        .arg(master_port)
+        .query(&mut replica)?;
+    wait_until(|| info_field(&mut replica, "master_link_status") == Some("up".to_string())).await;
+
+    // The key expires on the master, and its deletion is streamed, while the
+    // replica doesn't expire keys by itself
+    let _: () = redis::cmd("SET")
+        .arg(&["Temporary", "Value", "PX", "200"])
+        .query(&mut master)?;
+    let _: () = redis::cmd("SET")
+        .arg(&["Kept", "Value"])
+        .query(&mut master)?;
+    wait_until(|| key_count(&mut replica) == 2).await;
+
+    wait_until(|| key_count(&mut replica) == 1).await;
+    let stats: String = redis::cmd("INFO").arg("stats").query(&mut replica)?;
+    assert!(stats.contains("expired_keys:0\r\n"));
+
+    // So are the evictions of the master
+    let _: () = redis::cmd("CONFIG")
+        .arg(&["SET", "maxmemory-policy", "allkeys-random"])
+        .query(&mut master)?;
+    let _: () = redis::cmd("CONFIG")
+        .arg(&["SET", "maxmemory", "2000"])
+        .query(&mut master)?;
+    for i in 0..100 {
+        let _: () = redis::cmd("SET")
+            .arg(&[format!("Key:{}", i), "x".repeat(50)])
+            .query(&mut master)?;
+    }
+
+    let stats: String = redis::cmd("INFO").arg("stats").query(&mut master)?;
+    assert!(!stats.contains("evicted_keys:0\r\n"));
+    let count = key_count(&mut master);
+    wait_until(|| key_count(&mut replica) == count).await;
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use common::connect;
+use redis::RedisResult;
+use redust::cli::command_frame;
+use redust::server::{self, Listeners};
+use redust::{Config, Connection, ConnectionBase};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout};
+
+type ServerHandle = JoinHandle<Result<(), String>>;
+
+/// Start a server, and return its port along with the task which runs it
+async fn start_server() -> (u16, ServerHandle) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let listeners = Listeners {
+        tcp: vec![listener],
+        ..Listeners::default()
+    };
+    let handle = tokio::spawn(async move {
+        server::run_with_listeners(listeners, Config::default())
+            .await
+            .map_err(|err| err.to_string())
+    });
+
+    (port, handle)
+}
+
+/// Wait for the server to stop, after a shutdown
+async fn stopped(handle: ServerHandle) {
+    let result = timeout(Duration::from_secs(5), handle)
+        .await
+        .expect("The server didn't stop in time");
+    assert_eq!(Ok(()), result.unwrap());
+}
+
+fn command(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|part| part.to_string()).collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_shutdown() -> RedisResult<()> {
+    let (port, handle) = start_server().await;
+    let mut cnxn = connect(port);
+    let mut other = connect(port);
+
+    // There is no reply, the connections are closed
+    let result: RedisResult<String> = redis::cmd("SHUTDOWN")
+        .arg(&["NOSAVE", "NOW"])
+        .query(&mut cnxn);
+    assert!(result.is_err());
+
+    stopped(handle).await;
+
+    let result: RedisResult<String> = redis::cmd("PING").query(&mut other);
+    assert!(result.is_err());
+    assert!(TcpStream::connect(("127.0.0.1", port)).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_shutdown_save_fails() -> RedisResult<()> {
+    let (port, handle) = start_server().await;
+    let mut cnxn = connect(port);
+
+    // The data store isn't persisted, so it can't be saved
+    let err = redis::cmd("SHUTDOWN")
+        .arg(&["SAVE", "NOW"])
+        .query::<String>(&mut cnxn)
+        .unwrap_err();
+    assert_eq!(
+        "Errors trying to SHUTDOWN. Check logs.",
+        err.detail().unwrap()
+    );
+
+    let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut cnxn)?;
+    assert!(!handle.is_finished());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_shutdown_finishes_current_commands() -> RedisResult<()> {
+    let (port, handle) = start_server().await;
+    let mut cnxn = connect(port);
+
+    // The write is held by the pause, while the server shuts down
+    let _: () = redis::cmd("CLIENT")
+        .arg(&["PAUSE", "500", "WRITE"])
+        .query(&mut cnxn)?;
+
+    let mut writer = connect(port);
+    let write = tokio::task::spawn_blocking(move || {
+        redis::cmd("SET")
+            .arg(&["Key", "Value"])
+            .query::<()>(&mut writer)
+    });
+    sleep(Duration::from_millis(100)).await;
+
+    let _ = redis::cmd("SHUTDOWN").arg("NOW").query::<String>(&mut cnxn);
+
+    // The write is still applied and replied to, before its connection is closed
+    write.await.unwrap()?;
+    stopped(handle).await;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_shutdown_abort() -> RedisResult<()> {
+    let (port, handle) = start_server().await;
+    let mut cnxn = connect(port);
+
+    let result: RedisResult<String> = redis::cmd("SHUTDOWN").arg("ABORT").query(&mut cnxn);
+    assert!(result.is_err());
+
+    let result: RedisResult<String> = redis::cmd("SHUTDOWN")
+        .arg(&["ABORT", "NOW"])
+        .query(&mut cnxn);
+    assert!(result.is_err());
+
+    // This replica never acknowledges the write, so the shutdown waits for it
+    let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut cnxn)?;
+    let socket = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let mut replica = Connection::new(socket);
+    replica
+        .write_frame(&command_frame(&command(&["PSYNC", "?", "-1"])))
+        .await
+        .unwrap();
+    let _fullresync = replica.read_frame().await.unwrap();
+
+    let mut shutdown_cnxn = connect(port);
+    let shutdown = tokio::task::spawn_blocking(move || {
+        redis::cmd("SHUTDOWN").query::<String>(&mut shutdown_cnxn)
+    });
+
+    // Until it is aborted
+    let mut aborted = false;
+    for _ in 0..100 {
+        let result: RedisResult<String> = redis::cmd("SHUTDOWN").arg("ABORT").query(&mut cnxn);
+        if result.is_ok() {
+            aborted = true;
+            break;
+        }
+        sleep(Duration::from_millis(50)).await;
+    }
+    assert!(aborted);
+
+    let err = shutdown.await.unwrap().unwrap_err();
+    assert_eq!(
+        "Errors trying to SHUTDOWN. Check logs.",
+        err.detail().unwrap()
+    );
+
+    // The server is still running
+    let value: String = redis::cmd("GET").arg("Key").query(&mut cnxn)?;
+    assert_eq!("Value", value);
+    assert!(!handle.is_finished());
+
+    // The replica is dropped, and the shutdown goes on without waiting
+    drop(replica);
+    let _ = redis::cmd("SHUTDOWN").query::<String>(&mut cnxn);
+    stopped(handle).await;
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_shutdown() {
+    use redust::cmd::Shutdown;
+
+    assert!(Shutdown::parse(command(&["SHUTDOWN"])).is_ok());
+    assert_eq!(
+        Shutdown::new(Some(true), true, false),
+        Shutdown::parse(command(&["SHUTDOWN", "save", "NOW", "FORCE"])).unwrap()
+    );
+    assert!(Shutdown::parse(command(&["SHUTDOWN", "SAVE", "NOSAVE"])).is_err());
+    assert!(Shutdown::parse(command(&["SHUTDOWN", "ABORT", "FORCE"])).is_err());
+    assert!(Shutdown::parse(command(&["SHUTDOWN", "LATER"])).is_err());
+}
@@ -0,0 +1,177 @@
+mod common;
+
+use common::{connect, start_server};
+use redis::{RedisResult, Value};
+
+/// The arguments of a SLOWLOG GET entry
+fn entry_args(entry: &Value) -> Vec<String> {
+    match entry {
+        Value::Bulk(fields) => match &fields[3] {
+            Value::Bulk(args) => args
+                .iter()
+                .map(|arg| redis::from_redis_value(arg).unwrap())
+                .collect(),
+            other => panic!("Unexpected arguments {:?}", other),
+        },
+        other => panic!("Unexpected entry {:?}", other),
+    }
+}
+
+fn config_set(cnxn: &mut redis::Connection, name: &str, value: &str) -> RedisResult<()> {
+    redis::cmd("CONFIG").arg(&["SET", name, value]).query(cnxn)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_slowlog() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    // Commands are only logged past the threshold of 10ms, by default
+    let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut cnxn)?;
+    let len: i64 = redis::cmd("SLOWLOG").arg("LEN").query(&mut cnxn)?;
+    assert_eq!(0, len);
+
+    // Every command is logged with a threshold of 0
+    config_set(&mut cnxn, "slowlog-log-slower-than", "0")?;
+    let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut cnxn)?;
+    let _: String = redis::cmd("GET").arg("Key").query(&mut cnxn)?;
+
+    let entries: Vec<Value> = redis::cmd("SLOWLOG").arg("GET").query(&mut cnxn)?;
+    assert_eq!(3, entries.len());
+
+    // From the newest entry
+    assert_eq!(vec!["GET", "Key"], entry_args(&entries[0]));
+    assert_eq!(vec!["SET", "Key", "Value"], entry_args(&entries[1]));
+    assert_eq!(
+        vec!["CONFIG", "SET", "slowlog-log-slower-than", "0"],
+        entry_args(&entries[2])
+    );
+
+    let fields = match &entries[0] {
+        Value::Bulk(fields) => fields,
+        other => panic!("Unexpected entry {:?}", other),
+    };
+    assert_eq!(6, fields.len());
+    let id: i64 = redis::from_redis_value(&fields[0])?;
+    let timestamp: i64 = redis::from_redis_value(&fields[1])?;
+    let duration: i64 = redis::from_redis_value(&fields[2])?;
+    let addr: String = redis::from_redis_value(&fields[4])?;
+    let name: String = redis::from_redis_value(&fields[5])?;
+    assert_eq!(2, id);
+    assert!((timestamp - chrono::Utc::now().timestamp()).abs() <= 1);
+    assert!(duration >= 0);
+    assert!(addr.starts_with("127.0.0.1:"));
+    assert_eq!("", name);
+
+    let entries: Vec<Value> = redis::cmd("SLOWLOG").arg(&["GET", "1"]).query(&mut cnxn)?;
+    assert_eq!(1, entries.len());
+
+    // The ids keep increasing after a reset
+    let _: () = redis::cmd("SLOWLOG").arg("RESET").query(&mut cnxn)?;
+    let entries: Vec<Value> = redis::cmd("SLOWLOG").arg(&["GET", "-1"]).query(&mut cnxn)?;
+    assert_eq!(1, entries.len());
+    assert_eq!(vec!["SLOWLOG", "RESET"], entry_args(&entries[0]));
+    let id: i64 = match &entries[0] {
+        Value::Bulk(fields) => redis::from_redis_value(&fields[0])?,
+        other => panic!("Unexpected entry {:?}", other),
+    };
+    assert!(id > 2);
+
+    // AUTH isn't logged
+    let _: () = redis::cmd("AUTH")
+        .arg(&["default", "secret"])
+        .query(&mut cnxn)?;
+    let len: i64 = redis::cmd("SLOWLOG").arg("LEN").query(&mut cnxn)?;
+    assert_eq!(2, len);
+
+    // Nothing is logged with a negative threshold
+    config_set(&mut cnxn, "slowlog-log-slower-than", "-1")?;
+    let _: () = redis::cmd("SLOWLOG").arg("RESET").query(&mut cnxn)?;
+    let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut cnxn)?;
+    let len: i64 = redis::cmd("SLOWLOG").arg("LEN").query(&mut cnxn)?;
+    assert_eq!(0, len);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_slowlog_max_len_and_truncation() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    config_set(&mut cnxn, "slowlog-max-len", "2")?;
+    config_set(&mut cnxn, "slowlog-log-slower-than", "0")?;
+
+    for _ in 0..5 {
+        let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut cnxn)?;
+    }
+    let len: i64 = redis::cmd("SLOWLOG").arg("LEN").query(&mut cnxn)?;
+    assert_eq!(2, len);
+
+    // Long arguments, and commands with many arguments, are truncated
+    let long_value = "x".repeat(200);
+    let _: () = redis::cmd("SET")
+        .arg(&["Key", long_value.as_str()])
+        .query(&mut cnxn)?;
+    let keys: Vec<String> = (0..40).map(|i| format!("key:{}", i)).collect();
+    let _: i64 = redis::cmd("EXISTS").arg(&keys).query(&mut cnxn)?;
+
+    let entries: Vec<Value> = redis::cmd("SLOWLOG").arg("GET").query(&mut cnxn)?;
+    assert_eq!(2, entries.len());
+
+    let args = entry_args(&entries[0]);
+    assert_eq!(32, args.len());
+    assert_eq!("EXISTS", args[0]);
+    assert_eq!("key:29", args[30]);
+    assert_eq!("... (10 more arguments)", args[31]);
+
+    let args = entry_args(&entries[1]);
+    assert_eq!(format!("{}... (72 more bytes)", "x".repeat(128)), args[2]);
+
+    let reply: Vec<String> = redis::cmd("CONFIG")
+        .arg(&["GET", "slowlog-*"])
+        .query(&mut cnxn)?;
+    assert_eq!(
+        vec!["slowlog-log-slower-than", "0", "slowlog-max-len", "2"],
+        reply
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_slowlog_redacts_passwords() -> RedisResult<()> {
+    let port = start_server().await;
+    let mut cnxn = connect(port);
+
+    config_set(&mut cnxn, "slowlog-log-slower-than", "0")?;
+    let hash = format!("#{}", "a".repeat(64));
+    let _: () = redis::cmd("ACL")
+        .arg(&[
+            "SETUSER", "alice", "on", ">secret", &hash, "<secret", "~*", "+@all",
+        ])
+        .query(&mut cnxn)?;
+    config_set(&mut cnxn, "requirepass", "secret")?;
+
+    let entries: Vec<Value> = redis::cmd("SLOWLOG").arg("GET").query(&mut cnxn)?;
+    assert_eq!(
+        vec!["CONFIG", "SET", "requirepass", "(redacted)"],
+        entry_args(&entries[0])
+    );
+    assert_eq!(
+        vec![
+            "ACL",
+            "SETUSER",
+            "alice",
+            "on",
+            "(redacted)",
+            "(redacted)",
+            "(redacted)",
+            "~*",
+            "+@all"
+        ],
+        entry_args(&entries[1])
+    );
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+mod common;
+
+use common::connect;
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+};
+use redis::RedisResult;
+use redust::cli::command_frame;
+use redust::server::{self, Listeners};
+use redust::tls::{self, TlsAuthClients};
+use redust::{Config, Connection, ConnectionBase, RESPType};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+/// A CA, with a server and a client certificate signed by it
+struct Certs {
+    dir: PathBuf,
+    ca: Certificate,
+    client: Certificate,
+    client_key: KeyPair,
+}
+
+impl Certs {
+    /// Generate the certificates, and write the CA and server ones in a new directory
+    fn generate(name: &str) -> Certs {
+        let dir = std::env::temp_dir().join(format!("redust-tls-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca_key = KeyPair::generate().unwrap();
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+
+        let mut server_params = CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+        server_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        let server_key = KeyPair::generate().unwrap();
+        let server = server_params.signed_by(&server_key, &ca, &ca_key).unwrap();
+
+        let mut client_params = CertificateParams::new(vec!["client".to_string()]).unwrap();
+        client_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+        let client_key = KeyPair::generate().unwrap();
+        let client = client_params.signed_by(&client_key, &ca, &ca_key).unwrap();
+
+        std::fs::write(dir.join("ca.crt"), ca.pem()).unwrap();
+        std::fs::write(dir.join("redust.crt"), server.pem()).unwrap();
+        std::fs::write(dir.join("redust.key"), server_key.serialize_pem()).unwrap();
+
+        Certs {
+            dir,
+            ca,
+            client,
+            client_key,
+        }
+    }
+
+    fn config(&self, auth_clients: TlsAuthClients) -> Config {
+        Config {
+            tls_cert_file: Some(self.dir.join("redust.crt")),
+            tls_key_file: Some(self.dir.join("redust.key")),
+            tls_ca_cert_file: Some(self.dir.join("ca.crt")),
+            tls_auth_clients: auth_clients,
+            ..Config::default()
+        }
+    }
+
+    /// Connect to the TLS port, with the client certificate if `with_cert`
+    async fn connect(
+        &self,
+        port: u16,
+        with_cert: bool,
+    ) -> std::io::Result<Connection<TlsStream<TcpStream>>> {
+        let mut roots = RootCertStore::empty();
+        roots.add(self.ca.der().clone()).unwrap();
+
+        let builder = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots);
+
+        let client_config = if with_cert {
+            let certs: Vec<CertificateDer<'static>> = vec![self.client.der().clone()];
+            let key = PrivateKeyDer::try_from(self.client_key.serialize_der()).unwrap();
+            builder.with_client_auth_cert(certs, key).unwrap()
+        } else {
+            builder.with_no_client_auth()
+        };
+
+        let socket = TcpStream::connect(("127.0.0.1", port)).await?;
+        let stream = TlsConnector::from(Arc::new(client_config))
+            .connect(ServerName::try_from("localhost").unwrap(), socket)
+            .await?;
+
+        Ok(Connection::from_stream(stream, None))
+    }
+}
+
+impl Drop for Certs {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Start a server with both a plain and a TLS listener, and return their ports
+async fn start_tls_server(config: Config) -> (u16, u16) {
+    let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let tls = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let ports = (
+        tcp.local_addr().unwrap().port(),
+        tls.local_addr().unwrap().port(),
+    );
+
+    let listeners = Listeners {
+        tcp: vec![tcp],
+        tls: vec![tls],
+        ..Listeners::default()
+    };
+
+    tokio::spawn(async move {
+        let _ = server::run_with_listeners(listeners, config).await;
+    });
+
+    ports
+}
+
+/// Send a command, and return its reply, or `None` if the server closed the connection
+async fn request(
+    cnxn: &mut dyn ConnectionBase,
+    args: &[&str],
+) -> Result<Option<RESPType>, Box<dyn std::error::Error>> {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    cnxn.write_frame(&command_frame(&args)).await?;
+    cnxn.read_frame().await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_tls_alongside_plain_tcp() -> RedisResult<()> {
+    let certs = Certs::generate("plain");
+    let (port, tls_port) = start_tls_server(certs.config(TlsAuthClients::No)).await;
+
+    let mut cnxn = certs.connect(tls_port, false).await.unwrap();
+    let reply = request(&mut cnxn, &["SET", "Key", "Secret"]).await.unwrap();
+    assert!(matches!(reply, Some(RESPType::SimpleString(_))));
+
+    // Both listeners share the same data store
+    let value: String = redis::cmd("GET").arg("Key").query(&mut connect(port))?;
+    assert_eq!("Secret", value);
+
+    // Plaintext isn't accepted on the TLS port
+    let mut plain = Connection::new(TcpStream::connect(("127.0.0.1", tls_port)).await.unwrap());
+    assert!(!matches!(request(&mut plain, &["PING"]).await, Ok(Some(_))));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_mutual_tls() {
+    let certs = Certs::generate("mutual");
+    let (_, tls_port) = start_tls_server(certs.config(TlsAuthClients::Yes)).await;
+
+    let mut cnxn = certs.connect(tls_port, true).await.unwrap();
+    let reply = request(&mut cnxn, &["PING"]).await.unwrap();
+    assert_eq!(Some(RESPType::SimpleString("\"PONG\"".to_string())), reply);
+
+    // Clients without a certificate are refused, during or right after the handshake
+    let refused = match certs.connect(tls_port, false).await {
+        Ok(mut cnxn) => !matches!(request(&mut cnxn, &["PING"]).await, Ok(Some(_))),
+        Err(_) => true,
+    };
+    assert!(refused);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_optional_client_certificates() {
+    let certs = Certs::generate("optional");
+    let (_, tls_port) = start_tls_server(certs.config(TlsAuthClients::Optional)).await;
+
+    for with_cert in [true, false] {
+        let mut cnxn = certs.connect(tls_port, with_cert).await.unwrap();
+        let reply = request(&mut cnxn, &["PING"]).await.unwrap();
+        assert_eq!(Some(RESPType::SimpleString("\"PONG\"".to_string())), reply);
+    }
+}
+
+#[test]
+fn test_acceptor_errors() {
+    let certs = Certs::generate("errors");
+
+    assert!(tls::acceptor(&Config::default()).is_err());
+
+    // Clients can't be authenticated without a CA
+    let config = Config {
+        tls_ca_cert_file: None,
+        ..certs.config(TlsAuthClients::Yes)
+    };
+    let err = tls::acceptor(&config).err().unwrap();
+    assert!(err.to_string().contains("tls-ca-cert-file"));
+
+    let config = Config {
+        tls_cert_file: Some(certs.dir.join("missing.crt")),
+        ..certs.config(TlsAuthClients::No)
+    };
+    let err = tls::acceptor(&config).err().unwrap();
+    assert!(err.to_string().contains("missing.crt"));
+
+    assert!(tls::acceptor(&certs.config(TlsAuthClients::Yes)).is_ok());
+}
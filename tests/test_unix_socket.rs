@@ -0,0 +1,77 @@
+use redis::{Client, RedisResult};
+use redust::server::{self, Listeners};
+use redust::Config;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tokio::net::TcpListener;
+
+fn socket_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("redust-{}-{}.sock", std::process::id(), name))
+}
+
+fn connect(path: &Path) -> RedisResult<redis::Connection> {
+    Client::open(format!("redis+unix://{}", path.display()))?.get_connection()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_unix_socket_instead_of_tcp() -> RedisResult<()> {
+    let path = socket_path("only");
+    let listeners = Listeners {
+        unix: Some(server::bind_unix(&path, Some(0o700)).unwrap()),
+        ..Listeners::default()
+    };
+
+    tokio::spawn(async move {
+        let _ = server::run_with_listeners(listeners, Config::default()).await;
+    });
+
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(0o700, mode & 0o777);
+
+    let mut cnxn = connect(&path)?;
+    let _: () = redis::cmd("SET").arg(&["Key", "Value"]).query(&mut cnxn)?;
+    let value: String = redis::cmd("GET").arg("Key").query(&mut cnxn)?;
+    assert_eq!("Value", value);
+
+    let _ = std::fs::remove_file(&path);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_unix_socket_alongside_tcp() -> RedisResult<()> {
+    let path = socket_path("both");
+
+    // A stale socket file of a previous server is replaced
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+    let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = tcp.local_addr().unwrap().port();
+    let listeners = Listeners {
+        tcp: vec![tcp],
+        unix: Some(server::bind_unix(&path, None).unwrap()),
+        ..Listeners::default()
+    };
+
+    tokio::spawn(async move {
+        let _ = server::run_with_listeners(listeners, Config::default()).await;
+    });
+
+    // Unix socket clients are local, so protected mode doesn't refuse them
+    let mut local = connect(&path)?;
+    let _: () = redis::cmd("SET").arg(&["Key", "Local"]).query(&mut local)?;
+
+    let mut remote = Client::open(format!("redis://127.0.0.1:{}/", port))?.get_connection()?;
+    let value: String = redis::cmd("GET").arg("Key").query(&mut remote)?;
+    assert_eq!("Local", value);
+
+    let _ = std::fs::remove_file(&path);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_no_listener() {
+    let result = server::run_with_listeners(Listeners::default(), Config::default()).await;
+    assert!(result.is_err());
+}